@@ -0,0 +1,143 @@
+//! Write path throughput: indexing single blocks as the ExEx does per
+//! notification, and 1000-block backfill batches with and without bulk ingest.
+//!
+//! Run with `cargo bench --bench ingest`. Every iteration writes into a fresh
+//! in-memory database, so runs are comparable across machines' disks.
+
+use alloy_consensus::{Header, Transaction as _, TxEip4844};
+use alloy_eips::eip4844::DATA_GAS_PER_BLOB;
+use alloy_primitives::{Address, B256};
+use blob_exex::{indexer, BlobSchedule, Database};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use reth_execution_types::{Chain, ExecutionOutcome};
+use reth_primitives::{
+    Block, BlockBody, RecoveredBlock, SealedBlock, Transaction, TransactionSigned,
+};
+use reth_testing_utils::generators::{self, sign_tx_with_random_key_pair};
+
+/// A timestamp after BPO2 activation on mainnet.
+const TIMESTAMP: u64 = 1_767_747_671 + 12;
+
+/// Blocks per backfill batch, as in `blob-exex backfill`.
+const BACKFILL_BLOCKS: u64 = 1000;
+
+/// Blob txs per block of the backfill batches, about a busy mainnet block.
+const BACKFILL_TXS_PER_BLOCK: usize = 5;
+
+/// Synthetic chain of `blocks` consecutive blocks from `first_block`, each
+/// with `txs_per_block` blob txs of 1 to 6 blobs from distinct senders.
+fn synthetic_chain(first_block: u64, blocks: u64, txs_per_block: usize) -> Chain {
+    let mut rng = generators::rng();
+    let blocks = (first_block..first_block + blocks)
+        .map(|number| {
+            let transactions = (0..txs_per_block)
+                .map(|i| {
+                    let tx = TxEip4844 {
+                        chain_id: 1,
+                        nonce: number,
+                        gas_limit: 21_000,
+                        max_fee_per_gas: 100,
+                        max_priority_fee_per_gas: 2,
+                        to: Address::repeat_byte(0x42),
+                        blob_versioned_hashes: (0..i % 6 + 1)
+                            .map(|blob| B256::with_last_byte((i * 6 + blob) as u8))
+                            .collect(),
+                        max_fee_per_blob_gas: 1_000,
+                        ..Default::default()
+                    };
+                    sign_tx_with_random_key_pair(&mut rng, Transaction::Eip4844(tx))
+                })
+                .collect();
+            block(number, transactions)
+        })
+        .collect();
+    Chain::new(blocks, ExecutionOutcome::default(), None)
+}
+
+fn block(number: u64, transactions: Vec<TransactionSigned>) -> RecoveredBlock<Block> {
+    let blob_gas_used = transactions
+        .iter()
+        .filter_map(|tx| tx.blob_versioned_hashes())
+        .map(|hashes| hashes.len() as u64 * DATA_GAS_PER_BLOB)
+        .sum();
+    let header = Header {
+        number,
+        timestamp: TIMESTAMP + number * 12,
+        base_fee_per_gas: Some(7),
+        excess_blob_gas: Some(0),
+        blob_gas_used: Some(blob_gas_used),
+        ..Default::default()
+    };
+    let body = BlockBody {
+        transactions,
+        ..Default::default()
+    };
+    SealedBlock::seal_slow(Block::new(header, body))
+        .try_recover()
+        .expect("failed to recover block senders")
+}
+
+fn fresh_db() -> Database {
+    Database::new(":memory:").expect("failed to create database")
+}
+
+fn process_chain(c: &mut Criterion) {
+    let schedule = BlobSchedule::mainnet();
+    let mut group = c.benchmark_group("process_chain");
+    group.throughput(Throughput::Elements(1));
+
+    for txs_per_block in [0, 5, 15] {
+        let chain = synthetic_chain(1, 1, txs_per_block);
+        group.bench_with_input(
+            BenchmarkId::new("blob_txs", txs_per_block),
+            &chain,
+            |b, chain| {
+                b.iter_batched(
+                    fresh_db,
+                    |db| {
+                        db.transaction(|| indexer::process_chain(&db, &schedule, chain, |_| None))
+                            .expect("failed to index chain")
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn backfill_batch(c: &mut Criterion) {
+    let schedule = BlobSchedule::mainnet();
+    let chain = synthetic_chain(1, BACKFILL_BLOCKS, BACKFILL_TXS_PER_BLOCK);
+    let mut group = c.benchmark_group("backfill_batch");
+    group.throughput(Throughput::Elements(BACKFILL_BLOCKS));
+    group.sample_size(10);
+
+    for bulk in [false, true] {
+        let mode = if bulk { "bulk" } else { "indexed" };
+        group.bench_function(mode, |b| {
+            b.iter_batched(
+                || {
+                    let db = fresh_db();
+                    if bulk {
+                        db.begin_bulk_ingest().expect("failed to begin bulk ingest");
+                    }
+                    db
+                },
+                |db| {
+                    db.transaction(|| indexer::process_chain(&db, &schedule, &chain, |_| None))
+                        .expect("failed to index batch");
+                    // Rebuilding indexes and rollups is part of a bulk ingest's cost
+                    if bulk {
+                        db.end_bulk_ingest().expect("failed to end bulk ingest");
+                    }
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, process_chain, backfill_batch);
+criterion_main!(benches);
@@ -0,0 +1,537 @@
+//! Operator endpoints under `/admin`, authenticated with a bearer token.
+//!
+//! Writes to `/api/annotations` are authenticated the same way, while reading
+//! them is public. Every action is recorded in the `admin_audit_log` table.
+//!
+//! There is no action reloading the chain registry: it's compiled into the
+//! ExEx, so a new one ships with an upgrade, after which `/admin/relabel`
+//! re-attributes the blocks indexed under the old one.
+
+use crate::{
+    alerts::{self, Condition},
+    api::{checksum, ApiError},
+    config::Verbosity,
+    db::{
+        AlertKeyData, AlertRuleData, DbError, NewAnnotation, RelabelJobData, SenderLabelData,
+        MAX_LABEL_LEN,
+    },
+    params::Counts,
+    telemetry::BlockLog,
+    Database,
+};
+use alloy_primitives::{hex, Address, B256};
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, Serialize)]
+struct PruneRequest {
+    keep_blocks: u64, // Mempool snapshots of this many latest blocks are kept
+}
+
+#[derive(Serialize)]
+struct PruneResult {
+    deleted: usize,
+}
+
+#[derive(Serialize)]
+struct RebuildSendersResult {
+    senders: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ReprocessRequest {
+    from_block: u64,
+    to_block: u64,
+}
+
+#[derive(Serialize)]
+struct ReprocessResult {
+    request_id: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RelabelRequest {
+    from_block: u64,
+    to_block: u64,
+}
+
+#[derive(Serialize)]
+struct RelabelResult {
+    job_id: u64,
+}
+
+#[derive(Serialize)]
+struct RelabelJob {
+    id: u64,
+    from_block: u64,
+    to_block: u64,
+    next_block: u64,
+    relabeled: u64,
+    registry_version: Option<u64>, // Of the ExEx running the job, null until it starts
+    requested_at: u64,
+    completed_at: Option<u64>,
+}
+
+impl From<RelabelJobData> for RelabelJob {
+    fn from(job: RelabelJobData) -> Self {
+        Self {
+            id: job.id,
+            from_block: job.from_block,
+            to_block: job.to_block,
+            next_block: job.next_block,
+            relabeled: job.relabeled,
+            registry_version: job.registry_version,
+            requested_at: job.requested_at,
+            completed_at: job.completed_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct LabelRequest {
+    label: String, // Chain or entity, overriding every other attribution of the address
+}
+
+#[derive(Serialize)]
+struct SenderLabel {
+    address: String,
+    label: String,
+    updated_at: u64,
+}
+
+impl From<SenderLabelData> for SenderLabel {
+    fn from(label: SenderLabelData) -> Self {
+        Self {
+            address: checksum(&label.address),
+            label: label.label,
+            updated_at: label.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct AnnotationRequest {
+    timestamp: u64,
+    kind: String, // e.g. release, incident or fork
+    title: String,
+    description: Option<String>,
+}
+
+impl AnnotationRequest {
+    fn as_new(&self) -> NewAnnotation<'_> {
+        NewAnnotation {
+            timestamp: self.timestamp,
+            kind: &self.kind,
+            title: &self.title,
+            description: self.description.as_deref(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AnnotationCreated {
+    id: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct LogVerbosity {
+    verbosity: String, // summary:<blocks>, block or tx
+}
+
+#[derive(Deserialize, Serialize)]
+struct AlertKeyRequest {
+    name: String, // Who the key is for
+}
+
+#[derive(Serialize)]
+struct AlertKeyIssued {
+    key: String, // Only ever returned here
+    owner: String,
+}
+
+#[derive(Serialize)]
+struct AlertKey {
+    owner: String, // keccak256 of the key, as stored with its rules
+    name: String,
+    created_at: u64,
+}
+
+impl From<AlertKeyData> for AlertKey {
+    fn from(key: AlertKeyData) -> Self {
+        Self {
+            owner: key.owner,
+            name: key.name,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AlertRule {
+    id: u64,
+    owner: String, // keccak256 of the API key that created it
+    condition: serde_json::Value,
+    delivery: serde_json::Value,
+    created_at: u64,
+    firing: bool,
+    last_fired_at: Option<u64>,
+}
+
+impl From<AlertRuleData> for AlertRule {
+    fn from(rule: AlertRuleData) -> Self {
+        Self {
+            id: rule.id,
+            owner: rule.owner,
+            condition: serde_json::from_str(&rule.condition).unwrap_or_default(),
+            delivery: serde_json::from_str(&rule.delivery).unwrap_or_default(),
+            created_at: rule.created_at,
+            firing: rule.firing,
+            last_fired_at: rule.last_fired_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AdminAction {
+    id: u64,
+    action: String,
+    details: serde_json::Value,
+    performed_at: u64,
+}
+
+/// Reject requests without an `Authorization: Bearer <token>` header matching `token`.
+pub(crate) async fn require_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => ApiError::new(StatusCode::UNAUTHORIZED, "missing or invalid token").into_response(),
+    }
+}
+
+/// Compare secrets without returning early on the first mismatching byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn audit(db: &Database, action: &str, details: &impl Serialize) -> Result<(), DbError> {
+    let details = serde_json::to_string(details).expect("Failed to encode audit details");
+    db.record_admin_action(action, &details)
+}
+
+/// Run `f` off the async workers, for actions scanning whole tables.
+async fn blocking<T: Send + 'static>(
+    db: &Database,
+    f: impl FnOnce(&Database) -> Result<T, DbError> + Send + 'static,
+) -> Result<T, DbError> {
+    let db = db.clone();
+    tokio::task::spawn_blocking(move || f(&db))
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+async fn recompute_rollups(State(db): State<Database>) -> Result<StatusCode, DbError> {
+    blocking(&db, Database::recompute_hourly_stats).await?;
+    audit(&db, "recompute_rollups", &serde_json::json!({}))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn prune(
+    State(db): State<Database>,
+    Json(request): Json<PruneRequest>,
+) -> Result<Json<PruneResult>, DbError> {
+    let latest = db.get_latest_block()?.unwrap_or(0);
+    let deleted = db.prune_pending_blob_transactions(latest.saturating_sub(request.keep_blocks))?;
+    audit(&db, "prune", &request)?;
+    Ok(Json(PruneResult { deleted }))
+}
+
+async fn rebuild_senders(
+    State(db): State<Database>,
+) -> Result<Json<RebuildSendersResult>, DbError> {
+    let senders = blocking(&db, Database::rebuild_sender_stats).await?;
+    audit(&db, "rebuild_senders", &serde_json::json!({}))?;
+    Ok(Json(RebuildSendersResult { senders }))
+}
+
+async fn reprocess(
+    State(db): State<Database>,
+    Json(request): Json<ReprocessRequest>,
+) -> Result<Json<ReprocessResult>, DbError> {
+    // An inverted range is rejected as `DbError::InvalidInput`, i.e. a 400
+    let request_id = db.request_reprocess(request.from_block, request.to_block)?;
+    audit(&db, "reprocess", &request)?;
+    Ok(Json(ReprocessResult { request_id }))
+}
+
+async fn relabel(
+    State(db): State<Database>,
+    Json(request): Json<RelabelRequest>,
+) -> Result<Json<RelabelResult>, DbError> {
+    // An inverted range is rejected as `DbError::InvalidInput`, i.e. a 400
+    let job_id = db.request_relabel(request.from_block, request.to_block)?;
+    audit(&db, "relabel", &request)?;
+    Ok(Json(RelabelResult { job_id }))
+}
+
+async fn get_relabel_jobs(State(db): State<Database>) -> Result<Json<Vec<RelabelJob>>, DbError> {
+    let jobs = db.get_relabel_jobs(20)?;
+    Ok(Json(jobs.into_iter().map(RelabelJob::from).collect()))
+}
+
+async fn get_log_verbosity(Extension(log): Extension<BlockLog>) -> Json<LogVerbosity> {
+    Json(LogVerbosity {
+        verbosity: log.verbosity().to_string(),
+    })
+}
+
+async fn put_log_verbosity(
+    State(db): State<Database>,
+    Extension(log): Extension<BlockLog>,
+    Json(request): Json<LogVerbosity>,
+) -> Result<Json<LogVerbosity>, DbError> {
+    let verbosity: Verbosity = request
+        .verbosity
+        .parse()
+        .map_err(|err: eyre::Report| DbError::InvalidInput(err.to_string()))?;
+    log.set_verbosity(verbosity);
+    audit(&db, "log_verbosity", &request)?;
+    Ok(Json(LogVerbosity {
+        verbosity: verbosity.to_string(),
+    }))
+}
+
+async fn issue_alert_key(
+    State(db): State<Database>,
+    Json(request): Json<AlertKeyRequest>,
+) -> Result<(StatusCode, Json<AlertKeyIssued>), DbError> {
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > MAX_LABEL_LEN {
+        return Err(DbError::InvalidInput(format!(
+            "name must be 1 to {MAX_LABEL_LEN} bytes"
+        )));
+    }
+    let key = hex::encode(B256::random());
+    let owner = alerts::owner_of(&key);
+    db.insert_alert_key(&owner, name)?;
+    audit(
+        &db,
+        "issue_alert_key",
+        &serde_json::json!({ "owner": owner, "name": name }),
+    )?;
+    Ok((StatusCode::CREATED, Json(AlertKeyIssued { key, owner })))
+}
+
+async fn get_alert_keys(State(db): State<Database>) -> Result<Json<Vec<AlertKey>>, DbError> {
+    let keys = db.get_alert_keys()?;
+    Ok(Json(keys.into_iter().map(AlertKey::from).collect()))
+}
+
+async fn revoke_alert_key(
+    State(db): State<Database>,
+    Path(owner): Path<String>,
+) -> Result<StatusCode, DbError> {
+    if !db.delete_alert_key(&owner)? {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    audit(
+        &db,
+        "revoke_alert_key",
+        &serde_json::json!({ "owner": owner }),
+    )?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_alert_rules(State(db): State<Database>) -> Result<Json<Vec<AlertRule>>, DbError> {
+    let rules = db.get_alert_rules(None)?;
+    Ok(Json(rules.into_iter().map(AlertRule::from).collect()))
+}
+
+async fn put_alert_condition(
+    State(db): State<Database>,
+    Path(id): Path<u64>,
+    Json(condition): Json<Condition>,
+) -> Result<StatusCode, DbError> {
+    alerts::validate_condition(&condition).map_err(DbError::InvalidInput)?;
+    let encoded = serde_json::to_string(&condition).expect("Failed to encode condition");
+    if !db.set_alert_rule_condition(id, &encoded)? {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    audit(
+        &db,
+        "put_alert_condition",
+        &serde_json::json!({ "id": id, "condition": condition }),
+    )?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn parse_address(address: &str) -> Result<Address, DbError> {
+    address
+        .parse()
+        .map_err(|_| DbError::InvalidInput(format!("invalid address: {address}")))
+}
+
+async fn get_labels(State(db): State<Database>) -> Result<Json<Vec<SenderLabel>>, DbError> {
+    let labels = db.get_sender_labels()?;
+    Ok(Json(labels.into_iter().map(SenderLabel::from).collect()))
+}
+
+async fn put_label(
+    State(db): State<Database>,
+    Path(address): Path<String>,
+    Json(request): Json<LabelRequest>,
+) -> Result<StatusCode, DbError> {
+    let address = parse_address(&address)?;
+    // An empty or overlong label is rejected as `DbError::InvalidInput`, i.e. a 400
+    db.set_sender_label(&address, &request.label)?;
+    audit(
+        &db,
+        "put_label",
+        &serde_json::json!({ "address": address.to_checksum(None), "label": request.label }),
+    )?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_label(
+    State(db): State<Database>,
+    Path(address): Path<String>,
+) -> Result<StatusCode, DbError> {
+    let address = parse_address(&address)?;
+    if !db.delete_sender_label(&address)? {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    audit(
+        &db,
+        "delete_label",
+        &serde_json::json!({ "address": address.to_checksum(None) }),
+    )?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn post_annotation(
+    State(db): State<Database>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<(StatusCode, Json<AnnotationCreated>), DbError> {
+    // An empty or overlong kind or title is rejected as `DbError::InvalidInput`, i.e. a 400
+    let id = db.insert_annotation(&request.as_new())?;
+    audit(
+        &db,
+        "post_annotation",
+        &serde_json::json!({ "id": id, "annotation": request }),
+    )?;
+    Ok((StatusCode::CREATED, Json(AnnotationCreated { id })))
+}
+
+async fn put_annotation(
+    State(db): State<Database>,
+    Path(id): Path<u64>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<StatusCode, DbError> {
+    if !db.update_annotation(id, &request.as_new())? {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    audit(
+        &db,
+        "put_annotation",
+        &serde_json::json!({ "id": id, "annotation": request }),
+    )?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_annotation(
+    State(db): State<Database>,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, DbError> {
+    if !db.delete_annotation(id)? {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    audit(&db, "delete_annotation", &serde_json::json!({ "id": id }))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_audit_log(
+    State(db): State<Database>,
+    counts: Counts,
+) -> Result<Json<Vec<AdminAction>>, DbError> {
+    let actions = db.get_admin_audit_log(counts.limit.unwrap_or(100).min(1000))?;
+
+    Ok(Json(
+        actions
+            .into_iter()
+            .map(|a| AdminAction {
+                id: a.id,
+                action: a.action,
+                details: serde_json::from_str(&a.details).unwrap_or_default(),
+                performed_at: a.performed_at,
+            })
+            .collect(),
+    ))
+}
+
+/// Admin routes, only reachable with `token` as bearer token.
+///
+/// `/admin/log-verbosity` is only served with the `log` of an indexer running
+/// in the same process.
+pub fn router(db: Database, token: &str, log: Option<BlockLog>) -> Router {
+    let mut router = Router::new()
+        .route("/admin/rollups/recompute", post(recompute_rollups))
+        .route("/admin/prune", post(prune))
+        .route("/admin/senders/rebuild", post(rebuild_senders))
+        .route("/admin/reprocess", post(reprocess))
+        .route("/admin/relabel", get(get_relabel_jobs).post(relabel))
+        .route("/admin/labels", get(get_labels))
+        .route(
+            "/admin/labels/{address}",
+            put(put_label).delete(delete_label),
+        )
+        .route(
+            "/admin/alerts/keys",
+            get(get_alert_keys).post(issue_alert_key),
+        )
+        .route("/admin/alerts/keys/{owner}", delete(revoke_alert_key))
+        .route("/admin/alerts/rules", get(get_alert_rules))
+        .route(
+            "/admin/alerts/rules/{id}/condition",
+            put(put_alert_condition),
+        )
+        .route("/admin/audit-log", get(get_audit_log))
+        // Merged with the public `GET` of the API router
+        .route("/api/annotations", post(post_annotation))
+        .route(
+            "/api/annotations/{id}",
+            put(put_annotation).delete(delete_annotation),
+        );
+    if let Some(log) = log {
+        router = router.route(
+            "/admin/log-verbosity",
+            get(get_log_verbosity)
+                .put(put_log_verbosity)
+                .layer(Extension(log)),
+        );
+    }
+
+    router
+        .route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_token,
+        ))
+        .with_state(db)
+}
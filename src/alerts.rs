@@ -0,0 +1,555 @@
+//! Alert rules registered by API users under `/api/alerts`.
+//!
+//! A rule belongs to the API key it was created with, sent as
+//! `Authorization: Bearer <key>`. Keys are issued by operators through
+//! `/admin/alerts/keys`, which also revokes them, and only their keccak256 hash
+//! is stored, so nobody can list the rules of another key. The routes are only
+//! served with admin routes to issue keys.
+//!
+//! Webhooks are only delivered to public addresses, checked when a rule is
+//! created and again on every delivery, and redirects aren't followed, so rules
+//! can't reach the server's own network.
+//!
+//! [`run`] evaluates every rule periodically and on every indexed block, and
+//! fires it when its condition starts to hold. Each firing is recorded in
+//! `alert_events`, announced on the event bus, streamed to the key's
+//! `/api/alerts/stream` connections and, for webhook rules, POSTed to the
+//! rule's URL.
+
+use crate::{
+    api::{ApiError, STREAM_HEARTBEAT_INTERVAL, STREAM_RETRY_INTERVAL},
+    chains::chain_of,
+    db::{AlertEventData, AlertRuleData, DbError, ForkEventData},
+    events::{self, EventBus},
+    params::Query,
+    types::Heartbeat,
+    Database,
+};
+use alloy_primitives::keccak256;
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, Path, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, warn};
+
+/// How often [`run`] evaluates the rules when no block is indexed.
+pub const EVALUATION_INTERVAL: Duration = Duration::from_secs(30);
+
+// Rules a single API key may register
+const MAX_RULES_PER_KEY: usize = 50;
+
+// Longest silence a chain_silent rule may watch for, one week
+const MAX_SILENT_MINUTES: u64 = 7 * 24 * 60;
+
+// Events sent per /api/alerts/stream chunk
+const STREAM_MAX_EVENTS: u64 = 100;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long after its block a fork activation keeps a fork_activated rule
+/// firing, one hour.
+pub const FORK_ALERT_WINDOW: u64 = 3600;
+
+/// When a rule fires.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// `chain` hasn't posted a blob for `minutes`.
+    ChainSilent { chain: String, minutes: u64 },
+    /// The blob base fee of the latest block is below `gwei`.
+    BlobFeeBelow { gwei: f64 },
+    /// The blob base fee of the latest block is above `gwei`.
+    BlobFeeAbove { gwei: f64 },
+    /// A block changing the blob target or max, e.g. a BPO fork activating,
+    /// was indexed within the last [`FORK_ALERT_WINDOW`] seconds.
+    ForkActivated,
+}
+
+/// Where a firing is delivered besides `/api/alerts/stream`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Delivery {
+    /// POST the event as JSON to `url`.
+    Webhook { url: String },
+    /// Only `/api/alerts/stream`.
+    Stream,
+}
+
+#[derive(Deserialize)]
+struct NewRule {
+    condition: Condition,
+    delivery: Delivery,
+}
+
+#[derive(Serialize)]
+struct Rule {
+    id: u64,
+    condition: Condition,
+    delivery: Delivery,
+    created_at: u64,
+    firing: bool,
+    last_fired_at: Option<u64>,
+}
+
+impl Rule {
+    /// `None` for rows that no longer decode.
+    fn new(rule: AlertRuleData) -> Option<Self> {
+        Some(Self {
+            id: rule.id,
+            condition: serde_json::from_str(&rule.condition).ok()?,
+            delivery: serde_json::from_str(&rule.delivery).ok()?,
+            created_at: rule.created_at,
+            firing: rule.firing,
+            last_fired_at: rule.last_fired_at,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Event {
+    id: u64,
+    rule_id: u64,
+    message: String,
+    fired_at: u64,
+}
+
+impl From<AlertEventData> for Event {
+    fn from(event: AlertEventData) -> Self {
+        Self {
+            id: event.id,
+            rule_id: event.rule_id,
+            message: event.message,
+            fired_at: event.fired_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    after: Option<u64>, // Resume after this event id instead of at the latest event
+}
+
+/// The caller's API key, as the owner string stored with its rules.
+struct Owner(String);
+
+impl FromRequestParts<Database> for Owner {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, db: &Database) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "missing API key, expected Authorization: Bearer <key>",
+                )
+            })?;
+        let owner = owner_of(key);
+        if !db.alert_key_exists(&owner)? {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "unknown API key"));
+        }
+        Ok(Self(owner))
+    }
+}
+
+/// Owner string of an API key, as stored with its rules.
+pub(crate) fn owner_of(key: &str) -> String {
+    keccak256(key.as_bytes()).to_string()
+}
+
+/// Check that `condition` watches something sensible, returning why not.
+pub(crate) fn validate_condition(condition: &Condition) -> Result<(), String> {
+    match condition {
+        Condition::ChainSilent { chain, minutes } => {
+            if chain.is_empty() {
+                return Err("chain must not be empty".to_string());
+            }
+            if !(1..=MAX_SILENT_MINUTES).contains(minutes) {
+                return Err(format!(
+                    "minutes must be between 1 and {MAX_SILENT_MINUTES}"
+                ));
+            }
+        }
+        Condition::BlobFeeBelow { gwei } | Condition::BlobFeeAbove { gwei } => {
+            if !gwei.is_finite() || *gwei < 0.0 {
+                return Err("gwei must be a non-negative number".to_string());
+            }
+        }
+        Condition::ForkActivated => {}
+    }
+    Ok(())
+}
+
+fn validate(rule: &NewRule) -> Result<(), String> {
+    validate_condition(&rule.condition)?;
+    if let Delivery::Webhook { url } = &rule.delivery {
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return Err(format!("invalid webhook url: {url}, expected http(s)://"));
+        }
+    }
+    Ok(())
+}
+
+/// The address to deliver webhooks for `url` to, as long as every address its
+/// host resolves to is public, so a host can't pass with one public address
+/// and be reached on another.
+async fn webhook_address(url: &str) -> Result<(reqwest::Url, SocketAddr), String> {
+    let url = reqwest::Url::parse(url).map_err(|err| format!("invalid webhook url: {err}"))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("invalid webhook url: {url}, expected a host"))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let addresses: Vec<SocketAddr> =
+        match host.trim_start_matches('[').trim_end_matches(']').parse() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|err| format!("failed to resolve webhook host {host}: {err}"))?
+                .collect(),
+        };
+    match addresses.first() {
+        Some(&address) if addresses.iter().all(|address| is_public(address.ip())) => {
+            Ok((url, address))
+        }
+        Some(_) => Err(format!(
+            "webhook host {host} resolves to a private, loopback or link-local address"
+        )),
+        None => Err(format!("webhook host {host} doesn't resolve")),
+    }
+}
+
+/// Whether `ip` is reachable on the internet, i.e. not loopback, private,
+/// link-local (including cloud metadata endpoints), shared or reserved.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// POST `event` to a webhook, only to a public address and without following
+/// redirects.
+async fn deliver(url: &str, event: &Event) -> Result<(), String> {
+    let (url, address) = webhook_address(url).await?;
+    let host = url.host_str().unwrap_or_default();
+    // Pinned to the checked address, so the host can't resolve elsewhere now
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve(host, address)
+        .build()
+        .map_err(|err| err.to_string())?;
+    client
+        .post(url)
+        .json(event)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+async fn create_rule(
+    State(db): State<Database>,
+    Owner(owner): Owner,
+    Json(rule): Json<NewRule>,
+) -> Result<(StatusCode, Json<Rule>), ApiError> {
+    validate(&rule).map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, err))?;
+    if let Delivery::Webhook { url } = &rule.delivery {
+        webhook_address(url)
+            .await
+            .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, err))?;
+    }
+
+    if db.get_alert_rules(Some(&owner))?.len() >= MAX_RULES_PER_KEY {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("at most {MAX_RULES_PER_KEY} rules per API key"),
+        ));
+    }
+
+    let condition = serde_json::to_string(&rule.condition).expect("Failed to encode condition");
+    let delivery = serde_json::to_string(&rule.delivery).expect("Failed to encode delivery");
+    let id = db.insert_alert_rule(&owner, &condition, &delivery)?;
+
+    let created = db
+        .get_alert_rules(Some(&owner))?
+        .into_iter()
+        .find(|rule| rule.id == id)
+        .and_then(Rule::new)
+        .ok_or_else(|| DbError::NotFound(format!("alert rule {id}")))?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+async fn list_rules(
+    State(db): State<Database>,
+    Owner(owner): Owner,
+) -> Result<Json<Vec<Rule>>, ApiError> {
+    let rules = db.get_alert_rules(Some(&owner))?;
+    Ok(Json(rules.into_iter().filter_map(Rule::new).collect()))
+}
+
+async fn delete_rule(
+    State(db): State<Database>,
+    Owner(owner): Owner,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, ApiError> {
+    if !db.delete_alert_rule(&owner, id)? {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("no alert rule {id}"),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Stream the key's alert events, one JSON object per line
+async fn stream_events(
+    State(db): State<Database>,
+    Extension(bus): Extension<EventBus>,
+    Owner(owner): Owner,
+    Query(params): Query<StreamQuery>,
+) -> Result<Response, ApiError> {
+    // Subscribe first so events fired from now on can't be missed
+    let fired = bus.subscribe();
+    let cursor = match params.after {
+        Some(after) => after,
+        None => db.get_latest_alert_event_id()?,
+    };
+
+    let heartbeat = Heartbeat::load(&db)?.line();
+
+    let events = futures::stream::unfold(
+        (db, owner, fired, cursor, Instant::now()),
+        |(db, owner, mut fired, mut cursor, mut last_sent)| async move {
+            loop {
+                // Keep retrying through lock contention, stop the stream on anything else
+                let events = match db.get_alert_events(&owner, cursor, STREAM_MAX_EVENTS) {
+                    Ok(events) => events,
+                    Err(err) if err.is_transient() => {
+                        tokio::time::sleep(STREAM_RETRY_INTERVAL).await;
+                        continue;
+                    }
+                    Err(_) => return None,
+                };
+                if let Some(last) = events.last() {
+                    cursor = last.id;
+
+                    let chunk: String = events
+                        .into_iter()
+                        .filter_map(|event| serde_json::to_string(&Event::from(event)).ok())
+                        .map(|line| line + "\n")
+                        .collect();
+                    return Some((Ok(chunk), (db, owner, fired, cursor, Instant::now())));
+                }
+
+                // Wait for the next alert of any key, or until a heartbeat is due
+                loop {
+                    let idle = STREAM_HEARTBEAT_INTERVAL.saturating_sub(last_sent.elapsed());
+                    match tokio::time::timeout(idle, fired.recv()).await {
+                        Ok(Ok(events::Event::AlertFired { id })) if id > cursor => break,
+                        Ok(Ok(_)) => {}
+                        Ok(Err(RecvError::Lagged(_))) => break,
+                        Ok(Err(RecvError::Closed)) => return None,
+                        Err(_) => match Heartbeat::load(&db) {
+                            Ok(heartbeat) => {
+                                last_sent = Instant::now();
+                                let state = (db, owner, fired, cursor, last_sent);
+                                return Some((Ok(heartbeat.line()), state));
+                            }
+                            // Skip it, the next one is due soon enough
+                            Err(err) if err.is_transient() => last_sent = Instant::now(),
+                            Err(_) => return None,
+                        },
+                    }
+                }
+            }
+        },
+    );
+    let lines = futures::stream::once(async { Ok::<_, Infallible>(heartbeat) }).chain(events);
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+/// Alert routes, authenticated per API key.
+pub fn router(db: Database, events: EventBus) -> Router {
+    Router::new()
+        .route("/api/alerts/rules", get(list_rules).post(create_rule))
+        .route("/api/alerts/rules/{id}", delete(delete_rule))
+        .route("/api/alerts/stream", get(stream_events))
+        .layer(Extension(events))
+        .with_state(db)
+}
+
+/// What the rules are evaluated against, read once per evaluation.
+struct Snapshot {
+    now: u64,
+    last_posts: HashMap<String, u64>, // Latest post per chain within the longest watched silence
+    blob_fee_gwei: Option<f64>,       // Blob base fee of the latest indexed block
+    latest_fork: Option<ForkEventData>,
+}
+
+impl Snapshot {
+    fn read(db: &Database, now: u64) -> Result<Self, DbError> {
+        let mut last_posts = HashMap::new();
+        for post in db.get_sender_last_posts(now.saturating_sub(MAX_SILENT_MINUTES * 60))? {
+            let chain = chain_of(&post.sender, post.attributed_entity.as_deref());
+            let last = last_posts.entry(chain).or_insert(0);
+            *last = post.last_post.max(*last);
+        }
+        let blob_fee_gwei = db
+            .get_fee_history(None, 1)?
+            .last()
+            .map(|block| block.gas_price as f64 / 1e9);
+        let latest_fork = db.get_fork_events(1)?.pop();
+        Ok(Self {
+            now,
+            last_posts,
+            blob_fee_gwei,
+            latest_fork,
+        })
+    }
+
+    /// Why `condition` holds, or `None` if it doesn't.
+    fn check(&self, condition: &Condition) -> Option<String> {
+        match condition {
+            Condition::ChainSilent { chain, minutes } => {
+                let since = self.now.saturating_sub(minutes * 60);
+                match self.last_posts.get(chain) {
+                    Some(&last) if last >= since => None,
+                    Some(&last) => Some(format!(
+                        "{chain} hasn't posted for {} minutes",
+                        (self.now - last) / 60
+                    )),
+                    None => Some(format!("{chain} hasn't posted for {minutes} minutes")),
+                }
+            }
+            Condition::BlobFeeBelow { gwei } => {
+                let fee = self.blob_fee_gwei?;
+                (fee < *gwei).then(|| format!("blob fee {fee} gwei is below {gwei} gwei"))
+            }
+            Condition::BlobFeeAbove { gwei } => {
+                let fee = self.blob_fee_gwei?;
+                (fee > *gwei).then(|| format!("blob fee {fee} gwei is above {gwei} gwei"))
+            }
+            Condition::ForkActivated => {
+                let fork = self.latest_fork.as_ref()?;
+                (fork.block_timestamp + FORK_ALERT_WINDOW >= self.now).then(|| {
+                    format!(
+                        "blob schedule changed at block {}: target {} -> {}, max {} -> {}",
+                        fork.block_number,
+                        fork.previous_target,
+                        fork.target,
+                        fork.previous_max,
+                        fork.max
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Evaluate every rule once, firing those whose condition started to hold and
+/// announcing each firing on `events`.
+pub async fn evaluate(db: &Database, events: &EventBus) -> Result<(), DbError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let snapshot = Snapshot::read(db, now)?;
+
+    for rule in db.get_alert_rules(None)? {
+        let (id, owner) = (rule.id, rule.owner.clone());
+        let Some(rule) = Rule::new(rule) else {
+            continue;
+        };
+
+        let Some(message) = snapshot.check(&rule.condition) else {
+            if rule.firing {
+                db.set_alert_rule_firing(id, false)?;
+            }
+            continue;
+        };
+
+        // Only the transition fires, and only for the first evaluator to make it
+        if rule.firing || !db.set_alert_rule_firing(id, true)? {
+            continue;
+        }
+        let event = Event::from(db.record_alert_event(id, &owner, &message)?);
+        events.publish(events::Event::AlertFired { id: event.id });
+
+        if let Delivery::Webhook { url } = rule.delivery {
+            tokio::spawn(async move {
+                if let Err(err) = deliver(&url, &event).await {
+                    warn!(%err, id = event.id, url, "Failed to deliver alert");
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate the rules on every block indexed, and every [`EVALUATION_INTERVAL`]
+/// for conditions that hold without new blocks, forever.
+pub async fn run(db: Database, events: EventBus) {
+    let mut indexed = events.subscribe();
+    let mut interval = tokio::time::interval(EVALUATION_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            event = indexed.recv() => match event {
+                Ok(events::Event::BlockIndexed { .. }) | Err(RecvError::Lagged(_)) => {
+                    interval.reset();
+                }
+                Ok(_) => continue,
+                Err(RecvError::Closed) => return,
+            },
+        }
+        if let Err(err) = evaluate(&db, &events).await {
+            error!(%err, "Failed to evaluate alert rules");
+        }
+    }
+}
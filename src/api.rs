@@ -0,0 +1,3201 @@
+use crate::{
+    chains::{chain_of, identify_chain, LabelSource},
+    config,
+    db::{
+        self, AnnotationData, BlobTransactionData, BlockData, BlockIntervalData, DbError,
+        Downsample, GapFill, PriorityFees, BLOB_SIZE_BYTES, SECONDS_PER_DAY, SECONDS_PER_HOUR,
+        SECONDS_PER_SLOT,
+    },
+    encoding::{Encoded, Format},
+    events::{Event, EventBus},
+    forecast::{self, CadenceModel, GrowthTrend},
+    lease::LEASE_TIMEOUT,
+    params::{Counts, Query},
+    regime::{RegimeThresholds, REGIMES},
+    sensitivity::{self, PriceSensitivity},
+    types::{
+        AllTimeChartData, Annotation, BlobCountBucket, BlobFeeHistory, BlobFeePercentiles,
+        BlobSavings, BlobScheduleEntry, BlobTransaction, BlobsPerTx, Block, BlockChainTotals,
+        BlockConsistency, BlockIntervals, BlockRange, BlockRecord, BlockTransaction, BuildUp,
+        Builder, Builders, CanonicalCheck, Capacity, ChainCalendar, ChainComparison,
+        ChainCostBreakdown, ChainDailyUptime, ChainDemand, ChainGrowth, ChainHealth,
+        ChainHealthStatus, ChainOpBatches, ChainProfile, ChainResubmissions, ChainUptime,
+        ChartData, ComparePeriods, ComparedChain, Concentration, ConcentrationMetrics,
+        DailyBlobHashes, DemandBucket, DemandForecast, ExcessBlobGas, ExcessBlobGasPoint,
+        FeeDoubling, ForkEvent, Health, Heartbeat, Heatmap, HeatmapCell, Inbox, InboxSender,
+        InclusionLatency, InclusionLatencyBucket, InclusionMarketBlock, IngestError,
+        IngestLogEntry, LatencyPercentiles, PeriodDeltas, PeriodStats, PriorityFeeLevels,
+        ProtocolSummary, Records, Regime, Reorg, Resubmissions, Sender, SenderBlob, SenderBlobs,
+        SenderCheck, SenderConsistency, SenderDayRecord, SenderDrift, SenderTotals, Stats,
+        StreakRecord, SyncPage, ThroughputWindow, Writer,
+    },
+    Database,
+};
+use alloy_eips::eip4844::DATA_GAS_PER_BLOB;
+use alloy_primitives::{Address, B256};
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fmt::Display,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, warn};
+
+impl From<BlockIntervalData> for BlockIntervals {
+    fn from(intervals: BlockIntervalData) -> Self {
+        Self {
+            avg_secs: intervals.avg_secs,
+            p50_secs: intervals.p50_secs,
+            p90_secs: intervals.p90_secs,
+            p99_secs: intervals.p99_secs,
+            max_secs: intervals.max_secs,
+        }
+    }
+}
+
+const THROUGHPUT_WINDOWS: [(&str, u64); 3] = [("1h", 3600), ("24h", 86400), ("7d", 604800)];
+
+// Most throughput windows one /api/stats request may ask for
+const MAX_THROUGHPUT_WINDOWS: u64 = 8;
+
+impl Block {
+    fn new(b: BlockData, include_txs: bool, thresholds: &RegimeThresholds) -> Self {
+        let transactions: Vec<BlockTransaction> = b
+            .transactions
+            .into_iter()
+            .map(|tx| {
+                let chain = chain_of(&tx.sender, tx.attributed_entity.as_deref());
+                BlockTransaction {
+                    url: tx_url(&tx.tx_hash),
+                    tx_hash: tx.tx_hash,
+                    sender: checksum(&tx.sender),
+                    blob_count: tx.blob_count,
+                    blob_size: tx.blob_size,
+                    chain,
+                    attributed_entity: tx.attributed_entity,
+                    label_source: tx.label_source,
+                }
+            })
+            .collect();
+
+        let total_blob_size = transactions.iter().map(|tx| tx.blob_size).sum();
+        let mut by_chain: Vec<BlockChainTotals> = Vec::new();
+        for tx in &transactions {
+            let index = match by_chain.iter().position(|totals| totals.chain == tx.chain) {
+                Some(index) => index,
+                None => {
+                    by_chain.push(BlockChainTotals {
+                        chain: tx.chain.clone(),
+                        tx_count: 0,
+                        blobs: 0,
+                        blob_size: 0,
+                    });
+                    by_chain.len() - 1
+                }
+            };
+            let totals = &mut by_chain[index];
+            totals.tx_count += 1;
+            totals.blobs += tx.blob_count;
+            totals.blob_size += tx.blob_size;
+        }
+        by_chain.sort_by(|a, b| b.blobs.cmp(&a.blobs).then_with(|| a.chain.cmp(&b.chain)));
+        let target_utilization = (b.total_blobs as f64 / b.blob_target as f64) * 100.0;
+        let saturation_index = (b.total_blobs as f64 / b.blob_max as f64) * 100.0;
+        let blob_gas_used = b.header_blob_gas_used.unwrap_or(b.gas_used);
+        let blob_gas_used_ratio = blob_gas_used as f64 / (b.blob_max * DATA_GAS_PER_BLOB) as f64;
+
+        Self {
+            block_number: b.block_number,
+            url: block_url(b.block_number, b.block_hash.as_deref()),
+            block_hash: b.block_hash,
+            block_timestamp: b.block_timestamp,
+            tx_count: b.tx_count,
+            total_blobs: b.total_blobs,
+            total_blob_size,
+            gas_used: b.gas_used,
+            gas_price: b.gas_price,
+            excess_blob_gas: b.excess_blob_gas,
+            base_fee_per_gas: b.base_fee_per_gas,
+            non_blob_tx_count: b.execution.map(|e| e.non_blob_tx_count),
+            non_blob_gas_used: b.execution.map(|e| e.non_blob_gas_used),
+            blob_target: b.blob_target,
+            blob_max: b.blob_max,
+            block_interval: b.block_interval,
+            beneficiary: b.beneficiary.as_deref().map(checksum),
+            transactions: include_txs.then_some(transactions),
+            by_chain,
+            target_utilization,
+            regime: thresholds.regime(target_utilization).to_string(),
+            saturation_index,
+            blob_gas_used_ratio,
+            consistency: BlockConsistency {
+                header_blob_gas_used: b.header_blob_gas_used,
+                computed_blob_gas_used: b.gas_used,
+                matches: b.header_blob_gas_used.map(|used| used == b.gas_used),
+            },
+        }
+    }
+}
+
+// How often live streams send a heartbeat while there's nothing else to send
+pub(crate) const STREAM_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+// How long live streams wait before retrying a read that hit lock contention
+pub(crate) const STREAM_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+impl Heartbeat {
+    pub(crate) fn load(db: &Database) -> Result<Self, DbError> {
+        let latest_block = db.get_latest_block()?;
+        let lease = db.get_writer_lease()?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let node_tip = lease.as_ref().and_then(|lease| lease.node_tip);
+
+        Ok(Self {
+            r#type: "heartbeat".to_string(),
+            timestamp,
+            latest_block,
+            node_tip,
+            blocks_behind: blocks_behind(latest_block, node_tip),
+            writer_active: lease.is_some_and(|lease| {
+                timestamp.saturating_sub(lease.heartbeat_at) <= LEASE_TIMEOUT.as_secs()
+            }),
+        })
+    }
+
+    /// The heartbeat as an NDJSON line.
+    pub(crate) fn line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default() + "\n"
+    }
+}
+
+fn blocks_behind(latest_block: Option<u64>, node_tip: Option<u64>) -> Option<u64> {
+    node_tip
+        .zip(latest_block)
+        .map(|(tip, latest)| tip.saturating_sub(latest))
+}
+
+#[derive(Deserialize)]
+struct StatsQuery {
+    windows: Option<String>, // Comma separated durations, e.g. "1h,6h,30d"
+}
+
+#[derive(Deserialize)]
+struct ChartQuery {
+    gap_fill: Option<GapFill>, // previous (default), excess_blob_gas or null
+}
+
+impl From<AnnotationData> for Annotation {
+    fn from(annotation: AnnotationData) -> Self {
+        Self {
+            id: annotation.id,
+            timestamp: annotation.timestamp,
+            kind: annotation.kind,
+            title: annotation.title,
+            description: annotation.description,
+            block_number: annotation.block_number,
+            updated_at: annotation.updated_at,
+        }
+    }
+}
+
+impl From<BlobTransactionData> for BlobTransaction {
+    fn from(tx: BlobTransactionData) -> Self {
+        let chain = chain_of(&tx.sender, tx.attributed_entity.as_deref());
+        let blob_size = tx.blob_size();
+        Self {
+            url: tx_url(&tx.tx_hash),
+            tx_hash: tx.tx_hash,
+            block_number: tx.block_number,
+            sender: checksum(&tx.sender),
+            blob_count: tx.blob_count,
+            blob_size,
+            gas_price: tx.gas_price,
+            chain,
+            attributed_entity: tx.attributed_entity,
+            label_source: tx.label_source,
+            tx_type: tx.tx_type,
+            blob_hashes: tx.blob_hashes,
+            blob_sizes: tx.blob_sizes,
+        }
+    }
+}
+
+/// Permalink of a block, by hash when it was recorded since numbers can be
+/// reorged to another block.
+fn block_url(block_number: u64, block_hash: Option<&str>) -> String {
+    match block_hash {
+        Some(block_hash) => format!("/api/blocks/{block_hash}"),
+        None => format!("/api/blocks/{block_number}"),
+    }
+}
+
+fn tx_url(tx_hash: &str) -> String {
+    format!("/api/txs/{tx_hash}")
+}
+
+/// Parse a block or tx hash from a path, as stored: lowercase `0x` hex.
+fn parse_hash(hash: &str) -> Result<String, ApiError> {
+    hash.parse::<B256>()
+        .map(|hash| hash.to_string())
+        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("invalid hash: {hash}")))
+}
+
+#[derive(Deserialize)]
+struct TailQuery {
+    format: Option<String>, // Only "ndjson" (the default) for now
+}
+
+// Blocks sent per /api/tail chunk, so a client connected during a sync isn't flooded
+const TAIL_MAX_BLOCKS: u64 = 100;
+
+#[derive(Deserialize)]
+struct BlockQuery {
+    block_number: u64,
+}
+
+#[derive(Deserialize)]
+struct BlockRangeQuery {
+    from: u64,
+    to: u64,
+    include: Option<String>, // "txs" to include per-transaction detail
+}
+
+#[derive(Deserialize)]
+struct AllTimeChartQuery {
+    strategy: Option<Downsample>, // mean (default), max or last
+}
+
+#[derive(Deserialize)]
+struct FeeHistoryQuery {
+    block_count: Option<u64>,
+    newest_block: Option<String>, // "latest", decimal or 0x-prefixed hex
+    reward_percentiles: Option<String>, // Comma separated, e.g. "25,50,75"
+}
+
+// Same cap as eth_feeHistory
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+#[derive(Deserialize)]
+struct HeatmapQuery {
+    tz_offset: Option<i64>, // Whole hours east of UTC, e.g. -5 or 9
+    metric: Option<String>, // utilization (default), fee, blobs or tx_count
+    group: Option<String>,  // both (default), hour_of_day or day_of_week
+}
+
+const MAX_HEATMAP_DAYS: u64 = 365;
+
+const MAX_EXCESS_BLOB_GAS_HOURS: u64 = 24 * 30;
+
+const MAX_PROTOCOL_SUMMARY_DAYS: u64 = 90;
+
+#[derive(Deserialize)]
+struct CapacityQuery {
+    target: Option<f64>, // Blobs per block, the current target by default
+    max: Option<f64>,    // Blobs per block, the current max by default
+}
+
+const MAX_CAPACITY_DAYS: u64 = 180;
+
+#[derive(Deserialize)]
+struct AnnotationsQuery {
+    from: Option<u64>, // Unix timestamp, inclusive
+    to: Option<u64>,   // Likewise
+}
+
+#[derive(Deserialize)]
+struct SenderBlobsQuery {
+    cursor: Option<String>, // next_cursor of the previous page
+    from: Option<u64>,      // First block, inclusive
+    to: Option<u64>,        // Last block, inclusive
+}
+
+#[derive(Deserialize)]
+struct SyncQuery {
+    since_block: Option<u64>, // Last block the mirror has, 0 by default
+    cursor: Option<String>,   // cursor of the previous page, takes precedence over since_block
+}
+
+#[derive(Deserialize)]
+struct IngestLogQuery {
+    before: Option<u64>, // Entry id to page back from, exclusive
+}
+
+impl From<db::SenderTotals> for SenderTotals {
+    fn from(totals: db::SenderTotals) -> Self {
+        Self {
+            tx_count: totals.tx_count,
+            total_blobs: totals.total_blobs,
+            total_blob_size: totals.total_blob_size,
+        }
+    }
+}
+
+const MAX_DRIFT_ROWS: usize = 100;
+
+#[derive(Deserialize)]
+struct ConcentrationQuery {
+    window: Option<String>, // e.g. "24h", "7d" (default) or "4w"
+    top: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct WindowQuery {
+    window: Option<String>, // e.g. "24h", "7d" (default) or "4w"
+}
+
+// EIP-7623 floor: 10 gas per token, 4 tokens per non-zero byte. Rollup batches
+// are compressed, so every payload byte is priced as non-zero.
+const CALLDATA_FLOOR_GAS_PER_BYTE: u64 = 40;
+
+const MAX_UPTIME_DAYS: u64 = 90;
+
+// A chain counts as down once it hasn't posted for this many typical gaps
+const UPTIME_GAP_FACTOR: u64 = 3;
+
+// Lower bound of the down threshold, so chains posting every block aren't
+// marked down for a few missed slots
+const MIN_UPTIME_GAP_SECS: u64 = 300;
+
+#[derive(Deserialize)]
+struct ComparePeriodsQuery {
+    a_from: u64,
+    a_to: u64,
+    b_from: u64,
+    b_to: u64,
+}
+
+// Width of the intervals in /api/demand-forecast
+const FORECAST_BUCKET_SECS: u64 = 300;
+
+#[derive(Deserialize)]
+struct BlobsPerTxQuery {
+    chain: String,
+}
+
+#[derive(Deserialize)]
+struct CompareChainsQuery {
+    chains: String, // Comma separated, e.g. "Base,Arbitrum,Linea"
+}
+
+const MAX_COMPARED_CHAINS: u64 = 10;
+
+/// Days of a chain calendar or daily blob hash series, enough for a year-long
+/// heatmap. Read from rollups, so the limit doesn't depend on the profile.
+const MAX_CALENDAR_DAYS: u64 = 366;
+
+#[derive(Deserialize)]
+struct InclusionLatencyQuery {
+    window: Option<String>, // e.g. "1h", "24h" (default) or "7d"
+    chain: Option<String>,
+}
+
+// Lower bounds of the priority fee buckets of /api/inclusion-latency, in wei
+const LATENCY_FEE_BUCKETS: [u64; 6] = [
+    0,
+    100_000_000,
+    1_000_000_000,
+    2_000_000_000,
+    5_000_000_000,
+    10_000_000_000,
+];
+
+/// An error response, answered as JSON: `{"error": ..., "param": ...}`,
+/// `param` naming the query parameter at fault, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub error: String,
+    pub param: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    param: Option<&'a str>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, error: impl Into<String>) -> Self {
+        Self {
+            status,
+            error: error.into(),
+            param: None,
+        }
+    }
+
+    /// A 400 for the query parameter `param`.
+    pub fn invalid_param(param: &'static str, error: impl Into<String>) -> Self {
+        Self {
+            param: Some(param),
+            ..Self::new(StatusCode::BAD_REQUEST, error)
+        }
+    }
+
+    /// A 500 whose details, e.g. of a failed query or a panicked task, are only
+    /// logged, as they may include SQL, paths or other internals.
+    pub fn internal(err: impl Display) -> Self {
+        error!(%err, "Request failed");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: &self.error,
+            param: self.param,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Database errors answer with a status per error class. Only the caller's
+/// own mistakes are described, the other classes get a generic message.
+impl From<DbError> for ApiError {
+    fn from(err: DbError) -> Self {
+        match err {
+            DbError::NotFound(_) => Self::new(StatusCode::NOT_FOUND, err.to_string()),
+            DbError::InvalidInput(_) => Self::new(StatusCode::BAD_REQUEST, err.to_string()),
+            DbError::Busy(_) => {
+                warn!(%err, "Request failed");
+                Self::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "database is busy, retry later",
+                )
+            }
+            DbError::Corrupt(_) | DbError::Schema(_) | DbError::Io(_) => Self::internal(err),
+        }
+    }
+}
+
+impl IntoResponse for DbError {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
+}
+
+async fn get_stats(
+    State(db): State<Database>,
+    Query(params): Query<StatsQuery>,
+) -> Result<Json<Stats>, ApiError> {
+    let windows = match params.windows.as_deref() {
+        Some(windows) => windows
+            .split(',')
+            .map(|window| {
+                config::parse_duration(window)
+                    .map(|seconds| (window, seconds))
+                    .ok_or_else(|| {
+                        ApiError::invalid_param("window", format!("invalid window: {window}"))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => THROUGHPUT_WINDOWS.to_vec(),
+    };
+    check_limit("windows", windows.len() as u64, MAX_THROUGHPUT_WINDOWS)?;
+    let stats = db.get_stats()?;
+
+    let mut throughput_windows = Vec::new();
+    if let Some(all_time) = db.get_slot_throughput(0)? {
+        let latest_timestamp = all_time.last_timestamp;
+        for (window, seconds) in windows {
+            let since = (latest_timestamp + SECONDS_PER_SLOT).saturating_sub(seconds);
+            if let Some(throughput) = db.get_slot_throughput(since)? {
+                throughput_windows.push(ThroughputWindow {
+                    window: window.to_string(),
+                    blocks: throughput.produced_blocks(),
+                    slots: throughput.slots(),
+                    missed_slots: throughput.missed_slots(),
+                    blobs_per_block: throughput.blobs_per_block(),
+                    blobs_per_slot: throughput.blobs_per_slot(),
+                    block_intervals: db.get_block_intervals(since)?.map(BlockIntervals::from),
+                    sustained_bytes_per_sec: throughput.blobs_per_slot() * BLOB_SIZE_BYTES as f64
+                        / SECONDS_PER_SLOT as f64,
+                    peak_bytes_per_sec: db.get_peak_bandwidth(since)?,
+                });
+            }
+        }
+    }
+
+    Ok(Json(Stats {
+        total_blocks: stats.total_blocks,
+        total_blobs: stats.total_blobs,
+        total_transactions: stats.total_transactions,
+        avg_blobs_per_block: stats.avg_blobs_per_block,
+        latest_block: stats.latest_block,
+        earliest_block: stats.earliest_block,
+        latest_gas_price: stats.latest_gas_price,
+        total_slots: stats.total_slots,
+        missed_slots: stats.missed_slots,
+        avg_blobs_per_slot: stats.avg_blobs_per_slot,
+        throughput_windows,
+        total_el_bytes: stats.total_el_bytes,
+        total_da_bytes: stats.total_da_bytes,
+        total_payload_bytes: stats.total_payload_bytes,
+        total_blob_refs: stats.total_blob_refs,
+        distinct_daily_blobs: stats.distinct_daily_blobs,
+        regimes: regimes(&db.get_regime_thresholds()?),
+    }))
+}
+
+async fn get_recent_blocks(State(db): State<Database>) -> Result<Json<Vec<Block>>, DbError> {
+    let block_data = db.get_recent_blocks(50)?;
+    let thresholds = db.get_regime_thresholds()?;
+
+    let blocks: Vec<Block> = block_data
+        .into_iter()
+        .map(|b| Block::new(b, true, &thresholds))
+        .collect();
+
+    Ok(Json(blocks))
+}
+
+async fn get_top_senders(State(db): State<Database>) -> Result<Json<Vec<Sender>>, DbError> {
+    let sender_data = db.get_top_senders(20)?;
+    let labels: HashMap<String, String> = db
+        .get_sender_labels()?
+        .into_iter()
+        .map(|label| (label.address, label.label))
+        .collect();
+
+    let senders: Vec<Sender> = sender_data
+        .into_iter()
+        .map(|s| {
+            let (chain, label_source) = match labels.get(&s.address) {
+                Some(label) => (label.clone(), Some(LabelSource::Manual.as_str())),
+                None => {
+                    let chain = identify_chain(&s.address);
+                    let known = chain != "Other";
+                    (chain, known.then_some(LabelSource::Registry.as_str()))
+                }
+            };
+            Sender {
+                address: checksum(&s.address),
+                tx_count: s.tx_count,
+                total_blobs: s.total_blobs,
+                total_blob_size: s.total_blob_size,
+                chain,
+                label_source: label_source.map(str::to_string),
+                first_seen_block: s.first_seen_block,
+                last_seen_block: s.last_seen_block,
+                last_seen_timestamp: s.last_seen_timestamp,
+            }
+        })
+        .collect();
+
+    Ok(Json(senders))
+}
+
+async fn get_chart_data(
+    format: Format,
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+    Query(params): Query<ChartQuery>,
+) -> Result<Encoded<ChartData>, ApiError> {
+    let num_blocks = check_limit(
+        "blocks",
+        counts.blocks.unwrap_or(100),
+        limits.max_chart_blocks,
+    )?;
+    let schedule = db.get_blob_schedule()?;
+    let chart_data =
+        db.get_chart_data(num_blocks, params.gap_fill.unwrap_or_default(), &schedule)?;
+    let annotations = match (chart_data.labels.first(), chart_data.labels.last()) {
+        (Some(&first), Some(&last)) => db.get_block_annotations(first, last)?,
+        _ => Vec::new(),
+    };
+    let bandwidth_bytes_per_sec = chart_data
+        .blobs
+        .iter()
+        .zip(&chart_data.block_intervals)
+        .map(|(&blobs, interval)| match interval {
+            Some(seconds) if *seconds > 0 => {
+                Some((blobs * BLOB_SIZE_BYTES) as f64 / *seconds as f64)
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok(Encoded(
+        format,
+        ChartData {
+            labels: chart_data.labels,
+            blobs: chart_data.blobs,
+            gas_prices: chart_data.gas_prices,
+            gas_prices_log10: chart_data
+                .gas_prices_wei
+                .iter()
+                .map(|wei| wei.map(|wei| log10_wei(wei as f64)))
+                .collect(),
+            gas_prices_wei: chart_data
+                .gas_prices_wei
+                .iter()
+                .map(|wei| wei.map(|wei| wei.to_string()))
+                .collect(),
+            non_blob_tx_counts: chart_data.non_blob_tx_counts,
+            non_blob_gas_used: chart_data.non_blob_gas_used,
+            base_fees: chart_data.base_fees,
+            block_intervals: chart_data.block_intervals,
+            bandwidth_bytes_per_sec,
+            annotations: annotations.into_iter().map(Annotation::from).collect(),
+        },
+    ))
+}
+
+/// Base 10 logarithm of a fee in wei, 0 for fees below the 1 wei minimum so
+/// log scale charts never get a non-finite point.
+fn log10_wei(wei: f64) -> f64 {
+    wei.max(1.0).log10()
+}
+
+async fn get_blob_transactions(
+    State(db): State<Database>,
+) -> Result<Json<Vec<BlobTransaction>>, DbError> {
+    let tx_data = db.get_blob_transactions(50)?;
+
+    Ok(Json(
+        tx_data.into_iter().map(BlobTransaction::from).collect(),
+    ))
+}
+
+// Stream newly indexed blob transactions, one JSON object per line, with a
+// heartbeat line on connect and whenever the stream has been idle for a while,
+// and a reorg line when streamed blocks were reverted
+async fn get_tail(
+    State(db): State<Database>,
+    Extension(bus): Extension<EventBus>,
+    Query(params): Query<TailQuery>,
+) -> Result<Response, ApiError> {
+    match params.format.as_deref() {
+        None | Some("ndjson") => {}
+        Some(format) => {
+            return Err(ApiError::invalid_param(
+                "format",
+                format!("unsupported format: {format}, expected ndjson"),
+            ))
+        }
+    }
+
+    // Subscribe first so blocks indexed from now on can't be missed
+    let events = bus.subscribe();
+    let cursor = db.get_latest_block()?.unwrap_or(0);
+    let heartbeat = Heartbeat::load(&db)?.line();
+
+    // Blocks up to the target were announced but not streamed yet
+    let updates = futures::stream::unfold(
+        (db, events, cursor, cursor, Instant::now()),
+        |(db, mut events, mut cursor, mut target, mut last_sent)| async move {
+            loop {
+                if target > cursor {
+                    let to_block = target.min(cursor + TAIL_MAX_BLOCKS);
+                    // Keep retrying through lock contention, stop the stream on anything else
+                    let txs = match db.get_blob_transactions_in_range(cursor + 1, to_block) {
+                        Ok(txs) => txs,
+                        Err(err) if err.is_transient() => {
+                            tokio::time::sleep(STREAM_RETRY_INTERVAL).await;
+                            continue;
+                        }
+                        Err(_) => return None,
+                    };
+                    cursor = to_block;
+
+                    let chunk: String = txs
+                        .into_iter()
+                        .filter_map(|tx| serde_json::to_string(&BlobTransaction::from(tx)).ok())
+                        .map(|line| line + "\n")
+                        .collect();
+                    if !chunk.is_empty() {
+                        let state = (db, events, cursor, target, Instant::now());
+                        return Some((Ok(chunk), state));
+                    }
+                    continue;
+                }
+
+                // Wait for the next block, or until a heartbeat is due
+                let idle = STREAM_HEARTBEAT_INTERVAL.saturating_sub(last_sent.elapsed());
+                match tokio::time::timeout(idle, events.recv()).await {
+                    Ok(Ok(Event::BlockIndexed { block_number })) => {
+                        target = target.max(block_number);
+                    }
+                    Ok(Ok(Event::ReorgDetected { first_block, .. })) => {
+                        target = target.min(first_block.saturating_sub(1));
+                    }
+                    Ok(Ok(Event::AlertFired { .. })) => {}
+                    // Missed events, catch up from the database
+                    Ok(Err(RecvError::Lagged(_))) => match db.get_latest_block() {
+                        Ok(latest) => target = latest.unwrap_or(0),
+                        Err(err) if err.is_transient() => {}
+                        Err(_) => return None,
+                    },
+                    Ok(Err(RecvError::Closed)) => return None,
+                    Err(_) => match Heartbeat::load(&db) {
+                        Ok(heartbeat) => {
+                            last_sent = Instant::now();
+                            let state = (db, events, cursor, target, last_sent);
+                            return Some((Ok(heartbeat.line()), state));
+                        }
+                        // Skip it, the next one is due soon enough
+                        Err(err) if err.is_transient() => last_sent = Instant::now(),
+                        Err(_) => return None,
+                    },
+                }
+
+                // Blocks already streamed were reverted
+                if target < cursor {
+                    let reorg = Reorg {
+                        r#type: "reorg".to_string(),
+                        first_block: target + 1,
+                        last_block: cursor,
+                    };
+                    cursor = target;
+                    let line = serde_json::to_string(&reorg).unwrap_or_default() + "\n";
+                    return Some((Ok(line), (db, events, cursor, target, last_sent)));
+                }
+            }
+        },
+    );
+    let lines = futures::stream::once(async { Ok::<_, Infallible>(heartbeat) }).chain(updates);
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+async fn get_block(
+    State(db): State<Database>,
+    Query(params): Query<BlockQuery>,
+) -> Result<Json<Option<Block>>, DbError> {
+    let thresholds = db.get_regime_thresholds()?;
+    let block = db
+        .get_block(params.block_number)?
+        .map(|b| Block::new(b, true, &thresholds));
+
+    Ok(Json(block))
+}
+
+// A block by number or hash, for permalinks
+async fn get_block_by_id(
+    State(db): State<Database>,
+    Path(id): Path<String>,
+) -> Result<Json<Block>, ApiError> {
+    let block = if id.starts_with("0x") {
+        db.get_block_by_hash(&parse_hash(&id)?)?
+    } else {
+        let block_number = id.parse().map_err(|_| {
+            ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("invalid block number or hash: {id}"),
+            )
+        })?;
+        db.get_block(block_number)?
+    };
+
+    match block {
+        Some(block) => Ok(Json(Block::new(block, true, &db.get_regime_thresholds()?))),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("block {id} is not indexed"),
+        )),
+    }
+}
+
+async fn get_tx(
+    State(db): State<Database>,
+    Path(tx_hash): Path<String>,
+) -> Result<Json<BlobTransaction>, ApiError> {
+    let hash = parse_hash(&tx_hash)?;
+    let tx = match db.get_blob_transaction(&hash)? {
+        Some(tx) => Some(tx),
+        None => db.get_archived_blob_transaction(&hash)?,
+    };
+    match tx {
+        Some(tx) => Ok(Json(BlobTransaction::from(tx))),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("blob transaction {tx_hash} is not indexed"),
+        )),
+    }
+}
+
+async fn get_inbox(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    Path(address): Path<String>,
+    counts: Counts,
+) -> Result<Json<Inbox>, ApiError> {
+    let inbox: Address = address.parse().map_err(|_| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("invalid address: {address}"),
+        )
+    })?;
+    let hours = check_limit(
+        "hours",
+        counts.hours.unwrap_or(24),
+        limits.max_profile_hours,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let senders: Vec<InboxSender> = db
+        .get_inbox_senders(&inbox, now.saturating_sub(hours * SECONDS_PER_HOUR))?
+        .into_iter()
+        .map(|sender| InboxSender {
+            address: checksum(&sender.sender),
+            chain: sender.chain,
+            tx_count: sender.tx_count,
+            total_blobs: sender.total_blobs,
+            total_blob_size: sender.total_blob_size,
+            first_block: sender.first_block,
+            last_block: sender.last_block,
+            reverted_txs: sender.reverted_txs,
+        })
+        .collect();
+
+    Ok(Json(Inbox {
+        address: inbox.to_checksum(None),
+        hours,
+        total_transactions: senders.iter().map(|sender| sender.tx_count).sum(),
+        total_blobs: senders.iter().map(|sender| sender.total_blobs).sum(),
+        total_blob_size: senders.iter().map(|sender| sender.total_blob_size).sum(),
+        senders,
+    }))
+}
+
+async fn get_sender_blobs(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    Path(address): Path<String>,
+    counts: Counts,
+    Query(params): Query<SenderBlobsQuery>,
+) -> Result<Json<SenderBlobs>, ApiError> {
+    let sender: Address = address.parse().map_err(|_| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("invalid address: {address}"),
+        )
+    })?;
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            parse_blob_cursor(cursor).ok_or_else(|| {
+                ApiError::invalid_param("cursor", format!("invalid cursor: {cursor}"))
+            })
+        })
+        .transpose()?;
+    let limit = counts.limit.unwrap_or(100).min(limits.max_rows);
+
+    let blobs = db.get_sender_blobs(
+        &sender,
+        params.from.unwrap_or(0),
+        params.to.unwrap_or(i64::MAX as u64),
+        after,
+        limit,
+    )?;
+    let next_cursor = match blobs.last() {
+        Some(last) if blobs.len() as u64 == limit => {
+            Some(format!("{}-{}", last.position.0, last.position.1))
+        }
+        _ => None,
+    };
+
+    Ok(Json(SenderBlobs {
+        sender: sender.to_checksum(None),
+        blobs: blobs
+            .into_iter()
+            .map(|blob| SenderBlob {
+                block_number: blob.position.0,
+                block_timestamp: blob.block_timestamp,
+                tx_url: tx_url(&blob.tx_hash),
+                tx_hash: blob.tx_hash,
+                blob_index: blob.blob_index,
+                blob_hash: blob.blob_hash,
+                blob_size: blob.blob_size,
+            })
+            .collect(),
+        next_cursor,
+    }))
+}
+
+/// Parse a `/api/sender/{address}/blobs` cursor, `<block number>-<blob id>`.
+fn parse_blob_cursor(cursor: &str) -> Option<(u64, u64)> {
+    let (block_number, id) = cursor.split_once('-')?;
+    Some((block_number.parse().ok()?, id.parse().ok()?))
+}
+
+// Cursors are `{block_number}-{block_hash}`, or just the block number when
+// the block ending the page wasn't indexed or has no recorded hash
+fn parse_sync_cursor(cursor: &str) -> Option<(u64, Option<&str>)> {
+    match cursor.split_once('-') {
+        Some((block_number, block_hash)) => Some((block_number.parse().ok()?, Some(block_hash))),
+        None => Some((cursor.parse().ok()?, None)),
+    }
+}
+
+// Blocks and blob transactions indexed after a height, `limit` blocks at a
+// time, for mirrors replicating the index incrementally. Blocks backfilled
+// below a mirror's cursor later on aren't sent, resync from since_block for those
+async fn get_sync(
+    format: Format,
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+    Query(params): Query<SyncQuery>,
+) -> Result<Encoded<SyncPage>, ApiError> {
+    let after = match params.cursor.as_deref() {
+        Some(cursor) => {
+            let (block_number, block_hash) = parse_sync_cursor(cursor).ok_or_else(|| {
+                ApiError::invalid_param("cursor", format!("invalid cursor: {cursor}"))
+            })?;
+            // The mirror has rows of a block that was reverted since
+            if let Some(block_hash) = block_hash {
+                let current = db.get_block(block_number)?.and_then(|b| b.block_hash);
+                if current.as_deref() != Some(block_hash) {
+                    return Err(ApiError::new(
+                        StatusCode::CONFLICT,
+                        format!(
+                            "block {block_number} was reorged out, resync from an earlier since_block"
+                        ),
+                    ));
+                }
+            }
+            block_number
+        }
+        None => params.since_block.unwrap_or(0),
+    };
+    let limit = counts.limit.unwrap_or(100).min(limits.max_rows).max(1);
+
+    let latest = db.get_latest_block()?.unwrap_or(0);
+    let thresholds = db.get_regime_thresholds()?;
+    let to_block = after.saturating_add(limit).min(latest).max(after);
+    let (blocks, transactions) = if to_block > after {
+        (
+            db.get_blocks_in_range(after + 1, to_block)?,
+            db.get_blob_transactions_in_range(after + 1, to_block)?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let block_hash = match blocks.last() {
+        Some(last) if last.block_number == to_block => last.block_hash.clone(),
+        _ => db.get_block(to_block)?.and_then(|b| b.block_hash),
+    };
+    let cursor = match block_hash {
+        Some(block_hash) => format!("{to_block}-{block_hash}"),
+        None => to_block.to_string(),
+    };
+
+    Ok(Encoded(
+        format,
+        SyncPage {
+            blocks: blocks
+                .into_iter()
+                .map(|b| Block::new(b, false, &thresholds))
+                .collect(),
+            transactions: transactions
+                .into_iter()
+                .map(BlobTransaction::from)
+                .collect(),
+            cursor,
+            has_more: to_block < latest,
+        },
+    ))
+}
+
+async fn get_blocks_range(
+    format: Format,
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    Query(params): Query<BlockRangeQuery>,
+) -> Result<Encoded<Vec<Block>>, ApiError> {
+    if params.from > params.to {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "from ({}) must not be above to ({})",
+                params.from, params.to
+            ),
+        ));
+    }
+    check_limit(
+        "blocks",
+        params.to - params.from + 1,
+        limits.max_chart_blocks,
+    )?;
+    let include_txs = match params.include.as_deref() {
+        None => false,
+        Some("txs") => true,
+        Some(other) => {
+            return Err(ApiError::invalid_param(
+                "include",
+                format!("unknown include: {other}, expected txs"),
+            ))
+        }
+    };
+
+    let thresholds = db.get_regime_thresholds()?;
+    let blocks = db
+        .get_blocks_in_range(params.from, params.to)?
+        .into_iter()
+        .map(|b| Block::new(b, include_txs, &thresholds))
+        .collect();
+
+    Ok(Encoded(format, blocks))
+}
+
+async fn get_all_time_chart(
+    format: Format,
+    State(db): State<Database>,
+    Query(params): Query<AllTimeChartQuery>,
+) -> Result<Encoded<AllTimeChartData>, DbError> {
+    let schedule = db.get_blob_schedule()?;
+
+    // Target ~500 data points for smooth visualization
+    let chart_data =
+        db.get_all_time_chart_data(500, &schedule, params.strategy.unwrap_or_default())?;
+    // Snapped to the sampled labels, so they can be drawn at one
+    let annotations = match (chart_data.labels.first(), chart_data.labels.last()) {
+        (Some(&first), Some(&last)) => db.get_block_annotations(first, last)?,
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .map(|annotation| {
+        let block_number = annotation.block_number.and_then(|block| {
+            let index = chart_data.labels.partition_point(|&label| label < block);
+            chart_data.labels.get(index).copied()
+        });
+        Annotation {
+            block_number,
+            ..annotation.into()
+        }
+    })
+    .collect();
+    let bandwidth_bytes_per_sec = chart_data
+        .blobs
+        .iter()
+        .zip(&chart_data.block_intervals)
+        .map(|(&blobs, interval)| {
+            interval
+                .filter(|&seconds| seconds > 0.0)
+                .map(|seconds| blobs * BLOB_SIZE_BYTES as f64 / seconds)
+        })
+        .collect();
+
+    Ok(Encoded(
+        format,
+        AllTimeChartData {
+            labels: chart_data.labels,
+            blobs: chart_data.blobs,
+            gas_prices: chart_data.gas_prices,
+            gas_prices_log10: chart_data
+                .gas_prices_wei
+                .iter()
+                .map(|&wei| log10_wei(wei))
+                .collect(),
+            gas_prices_wei: chart_data
+                .gas_prices_wei
+                .iter()
+                .map(|wei| format!("{wei:.0}"))
+                .collect(),
+            non_blob_tx_counts: chart_data.non_blob_tx_counts,
+            non_blob_gas_used: chart_data.non_blob_gas_used,
+            base_fees: chart_data.base_fees,
+            block_intervals: chart_data.block_intervals,
+            bandwidth_bytes_per_sec,
+            timestamps: chart_data.timestamps,
+            targets: chart_data.targets,
+            maxes: chart_data.maxes,
+            bpo2_block: chart_data.bpo2_block,
+            annotations,
+        },
+    ))
+}
+
+async fn get_annotations(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+    Query(params): Query<AnnotationsQuery>,
+) -> Result<Json<Vec<Annotation>>, DbError> {
+    let limit = counts.limit.unwrap_or(100).min(limits.max_rows);
+    let annotations = db.get_annotations(
+        params.from.unwrap_or(0),
+        params.to.unwrap_or(i64::MAX as u64),
+        limit,
+    )?;
+    Ok(Json(
+        annotations.into_iter().map(Annotation::from).collect(),
+    ))
+}
+
+async fn get_blob_schedule(
+    State(db): State<Database>,
+) -> Result<Json<Vec<BlobScheduleEntry>>, DbError> {
+    let schedule = db.get_blob_schedule()?;
+
+    Ok(Json(
+        schedule
+            .entries()
+            .iter()
+            .map(|entry| BlobScheduleEntry {
+                activation_timestamp: entry.activation_timestamp,
+                target: entry.target,
+                max: entry.max,
+                base_fee_update_fraction: entry.base_fee_update_fraction,
+            })
+            .collect(),
+    ))
+}
+
+async fn get_regimes(State(db): State<Database>) -> Result<Json<Vec<Regime>>, DbError> {
+    Ok(Json(regimes(&db.get_regime_thresholds()?)))
+}
+
+fn regimes(thresholds: &RegimeThresholds) -> Vec<Regime> {
+    let bounds = thresholds.lower_bounds();
+    REGIMES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Regime {
+            name: name.to_string(),
+            from_pct: bounds[i],
+            to_pct: bounds.get(i + 1).copied(),
+        })
+        .collect()
+}
+
+async fn get_fork_events(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+) -> Result<Json<Vec<ForkEvent>>, DbError> {
+    let limit = counts.limit.unwrap_or(20).min(limits.max_rows);
+    let events = db.get_fork_events(limit)?;
+
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|event| ForkEvent {
+                block_number: event.block_number,
+                block_timestamp: event.block_timestamp,
+                previous_target: event.previous_target,
+                previous_max: event.previous_max,
+                target: event.target,
+                max: event.max,
+            })
+            .collect(),
+    ))
+}
+
+async fn get_inclusion_market(
+    format: Format,
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+) -> Result<Encoded<Vec<InclusionMarketBlock>>, ApiError> {
+    let num_blocks = check_limit(
+        "blocks",
+        counts.blocks.unwrap_or(100),
+        limits.max_chart_blocks,
+    )?;
+    let market = db.get_inclusion_market(num_blocks)?;
+
+    let to_levels = |fees: PriorityFees| PriorityFeeLevels {
+        min: fees.min,
+        median: fees.median,
+        max: fees.max,
+    };
+
+    Ok(Encoded(
+        format,
+        market
+            .into_iter()
+            .map(|m| InclusionMarketBlock {
+                block_number: m.block_number,
+                tx_count: m.tx_count,
+                included: m.included.map(to_levels),
+                excluded_count: m.excluded_count,
+                excluded: m.excluded.map(to_levels),
+            })
+            .collect(),
+    ))
+}
+
+async fn get_heatmap(
+    State(db): State<Database>,
+    counts: Counts,
+    Query(params): Query<HeatmapQuery>,
+) -> Result<Json<Heatmap>, ApiError> {
+    let days = counts.days.unwrap_or(30).min(MAX_HEATMAP_DAYS);
+    let tz_offset = params.tz_offset.unwrap_or(0);
+    if !(-12..=14).contains(&tz_offset) {
+        return Err(ApiError::invalid_param(
+            "tz_offset",
+            format!("invalid tz_offset: {tz_offset}, expected whole hours between -12 and 14"),
+        ));
+    }
+    let metric = params.metric.as_deref().unwrap_or("utilization");
+    if !["utilization", "fee", "blobs", "tx_count"].contains(&metric) {
+        return Err(ApiError::invalid_param(
+            "metric",
+            format!("unknown metric: {metric}, expected utilization, fee, blobs or tx_count"),
+        ));
+    }
+    // Whether cells split by weekday and by hour
+    let group = params.group.as_deref().unwrap_or("both");
+    let (by_weekday, by_hour) = match group {
+        "both" => (true, true),
+        "hour_of_day" => (false, true),
+        "day_of_week" => (true, false),
+        _ => {
+            return Err(ApiError::invalid_param(
+                "group",
+                format!("unknown group: {group}, expected hour_of_day, day_of_week or both"),
+            ))
+        }
+    };
+    let hours_per_cell = if by_hour { 24 } else { 1 };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let hours = db.get_hourly_stats(now.saturating_sub(days * 86400))?;
+    let schedule = db.get_blob_schedule()?;
+
+    // (blocks, blobs, blob txs, blob target, gas price sum) per local weekday
+    // and/or hour
+    let cell_count = if by_weekday { 7 } else { 1 } * hours_per_cell;
+    let mut buckets = vec![(0u64, 0u64, 0u64, 0u64, 0f64); cell_count];
+    let utc_offset = tz_offset * SECONDS_PER_HOUR as i64;
+    for h in hours {
+        let hour = if by_hour {
+            db::hour_of_day(h.hour_start, utc_offset)
+        } else {
+            0
+        };
+        let weekday = if by_weekday {
+            db::weekday(h.hour_start, utc_offset)
+        } else {
+            0
+        };
+
+        let bucket = &mut buckets[weekday * hours_per_cell + hour];
+        bucket.0 += h.block_count;
+        bucket.1 += h.total_blobs;
+        bucket.2 += h.tx_count;
+        bucket.3 += h.block_count * schedule.params_at(h.hour_start).target;
+        bucket.4 += h.gas_price_sum;
+    }
+
+    let cells = buckets
+        .iter()
+        .enumerate()
+        .map(
+            |(i, &(blocks, total_blobs, tx_count, target, gas_price_sum))| {
+                let per_block = |value: f64| {
+                    if blocks > 0 {
+                        value / blocks as f64
+                    } else {
+                        0.0
+                    }
+                };
+                let target_utilization = if target > 0 {
+                    total_blobs as f64 / target as f64 * 100.0
+                } else {
+                    0.0
+                };
+                let avg_gas_price = per_block(gas_price_sum) / 1e9;
+                HeatmapCell {
+                    weekday: by_weekday.then_some((i / hours_per_cell) as u8),
+                    hour: by_hour.then_some((i % hours_per_cell) as u8),
+                    blocks,
+                    total_blobs,
+                    tx_count,
+                    avg_blobs_per_block: per_block(total_blobs as f64),
+                    target_utilization,
+                    avg_gas_price,
+                    value: match metric {
+                        "fee" => avg_gas_price,
+                        "blobs" => per_block(total_blobs as f64),
+                        "tx_count" => per_block(tx_count as f64),
+                        _ => target_utilization,
+                    },
+                }
+            },
+        )
+        .collect();
+
+    Ok(Json(Heatmap {
+        days,
+        tz_offset,
+        metric: metric.to_string(),
+        group: group.to_string(),
+        cells,
+    }))
+}
+
+async fn get_health(State(db): State<Database>) -> Result<Json<Health>, DbError> {
+    let latest_block = db.get_latest_block()?;
+    let lease = db.get_writer_lease()?;
+    let sizes = db.file_sizes()?;
+    let canonical = db.get_canonical_check()?;
+    let senders = db.get_sender_check()?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("System clock before UNIX epoch")
+        .as_secs();
+    let writer = lease.map(|lease| {
+        let heartbeat_age_secs = now.saturating_sub(lease.heartbeat_at);
+        Writer {
+            holder: lease.holder,
+            acquired_at: lease.acquired_at,
+            heartbeat_at: lease.heartbeat_at,
+            heartbeat_age_secs,
+            active: heartbeat_age_secs <= LEASE_TIMEOUT.as_secs(),
+            node_tip: lease.node_tip,
+        }
+    });
+
+    let status = if writer.as_ref().is_some_and(|writer| writer.active) {
+        "ok"
+    } else {
+        "no_writer"
+    };
+
+    Ok(Json(Health {
+        status: status.to_string(),
+        latest_block,
+        blocks_behind: blocks_behind(latest_block, writer.as_ref().and_then(|w| w.node_tip)),
+        writer,
+        db_size_bytes: sizes.map(|sizes| sizes.db_bytes),
+        wal_size_bytes: sizes.map(|sizes| sizes.wal_bytes),
+        canonical: canonical.map(|check| CanonicalCheck {
+            checked_at: check.checked_at,
+            blocks_checked: check.blocks_checked,
+            mismatches: check.mismatches,
+            total_mismatches: check.total_mismatches,
+            last_mismatch_block: check.last_mismatch_block,
+            last_mismatch_at: check.last_mismatch_at,
+        }),
+        senders: senders.map(|check| SenderCheck {
+            checked_at: check.checked_at,
+            drifted_senders: check.drifted_senders,
+            tx_count_drift: check.tx_count_drift,
+            blob_drift: check.blob_drift,
+            repaired: check.repaired,
+            total_repairs: check.total_repairs,
+            last_repair_at: check.last_repair_at,
+        }),
+    }))
+}
+
+async fn get_excess_blob_gas(
+    format: Format,
+    State(db): State<Database>,
+    counts: Counts,
+) -> Result<Encoded<ExcessBlobGas>, DbError> {
+    let hours = counts.hours.unwrap_or(48).min(MAX_EXCESS_BLOB_GAS_HOURS);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let blocks = db.get_excess_blob_gas(now.saturating_sub(hours * 3600))?;
+    let schedule = db.get_blob_schedule()?;
+
+    let (mut above, mut at, mut below) = (0, 0, 0);
+    for b in &blocks {
+        match b
+            .total_blobs
+            .cmp(&schedule.params_at(b.block_timestamp).target)
+        {
+            std::cmp::Ordering::Greater => above += 1,
+            std::cmp::Ordering::Equal => at += 1,
+            std::cmp::Ordering::Less => below += 1,
+        }
+    }
+
+    // A build-up continues while each block's excess grows over its parent's
+    let mut largest_build_up: Option<BuildUp> = None;
+    let mut current: Option<BuildUp> = None;
+    for b in &blocks {
+        current = match (current.take(), b.delta) {
+            (Some(mut run), Some(delta)) if delta > 0 && run.to_block + 1 == b.block_number => {
+                run.to_block = b.block_number;
+                run.blocks += 1;
+                run.excess_blob_gas_increase += delta as u64;
+                Some(run)
+            }
+            (_, Some(delta)) if delta > 0 => Some(BuildUp {
+                from_block: b.block_number,
+                to_block: b.block_number,
+                blocks: 1,
+                excess_blob_gas_increase: delta as u64,
+            }),
+            _ => None,
+        };
+        if let Some(run) = &current {
+            if largest_build_up
+                .as_ref()
+                .is_none_or(|largest| run.blocks > largest.blocks)
+            {
+                largest_build_up = Some(run.clone());
+            }
+        }
+    }
+
+    let fee_doublings = fee_doublings(&blocks);
+
+    Ok(Encoded(
+        format,
+        ExcessBlobGas {
+            series: blocks
+                .iter()
+                .map(|b| ExcessBlobGasPoint {
+                    block_number: b.block_number,
+                    timestamp: b.block_timestamp,
+                    excess_blob_gas: b.excess_blob_gas,
+                    delta: b.delta,
+                    blob_gas_price: b.gas_price,
+                })
+                .collect(),
+            blocks_above_target: above,
+            blocks_at_target: at,
+            blocks_below_target: below,
+            secs_above_target: above * SECONDS_PER_SLOT,
+            secs_below_target: below * SECONDS_PER_SLOT,
+            largest_build_up,
+            fee_doublings,
+        },
+    ))
+}
+
+/// Every time the blob gas price reaches twice its lowest value since the
+/// previous doubling. `blocks` must be in ascending order.
+fn fee_doublings(blocks: &[db::ExcessBlobGasData]) -> Vec<FeeDoubling> {
+    let mut fee_doublings = Vec::new();
+    let mut trough: Option<(u64, u128)> = None; // (block number, price)
+    for b in blocks {
+        match trough {
+            Some((from_block, from_price)) if b.gas_price >= from_price.saturating_mul(2) => {
+                fee_doublings.push(FeeDoubling {
+                    from_block,
+                    to_block: b.block_number,
+                    timestamp: b.block_timestamp,
+                    from_price,
+                    to_price: b.gas_price,
+                });
+                trough = Some((b.block_number, b.gas_price));
+            }
+            Some((_, price)) if b.gas_price >= price => {}
+            _ => trough = Some((b.block_number, b.gas_price)),
+        }
+    }
+    fee_doublings
+}
+
+// Answers whether the blob target is sized right: how blob usage compares to
+// the target and max in force for each block, and how the fee reacted
+async fn get_capacity(
+    State(db): State<Database>,
+    counts: Counts,
+    Query(params): Query<CapacityQuery>,
+) -> Result<Json<Capacity>, ApiError> {
+    let days = counts.days.unwrap_or(30).min(MAX_CAPACITY_DAYS);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let schedule = db.get_blob_schedule()?;
+    let current = schedule.params_at(now);
+    let target = params.target.unwrap_or(current.target as f64);
+    let max = params.max.unwrap_or(current.max as f64);
+    if !(target > 0.0 && max > 0.0) {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "target and max must be positive",
+        ));
+    }
+
+    // Whole days only, a partial day would read as a drop in demand
+    let today = db::group_by_day(now, 0);
+    let from = today - days * SECONDS_PER_DAY;
+    let day_of = |timestamp: u64| ((timestamp - from) / SECONDS_PER_DAY) as usize;
+    let mut blocks = vec![0; days as usize];
+    let mut blobs = vec![0; days as usize];
+    for bucket in db.get_block_series(from, today - 1, SECONDS_PER_DAY)? {
+        blocks[day_of(bucket.bucket_start)] = bucket.block_count;
+        blobs[day_of(bucket.bucket_start)] = bucket.total_blobs;
+    }
+    let mut chain_blobs: HashMap<String, Vec<u64>> = HashMap::new();
+    for hour in db.get_chain_activity(from)? {
+        if hour.hour_start < today {
+            chain_blobs
+                .entry(hour.chain)
+                .or_insert_with(|| vec![0; days as usize])[day_of(hour.hour_start)] +=
+                hour.total_blobs;
+        }
+    }
+
+    // Blobs per block of each day with blocks
+    let per_block = |blobs: &[u64]| -> Vec<(f64, f64)> {
+        (0..days as usize)
+            .filter(|&day| blocks[day] > 0)
+            .map(|day| (day as f64, blobs[day] as f64 / blocks[day] as f64))
+            .collect()
+    };
+    let mean = |series: &[(f64, f64)]| match series.len() {
+        0 => 0.0,
+        n => series.iter().map(|(_, value)| value).sum::<f64>() / n as f64,
+    };
+
+    let total = per_block(&blobs);
+    let trend = GrowthTrend::fit(&total);
+    // Measured from the end of the last whole day
+    let reached_at = |threshold: f64| {
+        trend?
+            .days_until(threshold)
+            .map(|days| today + (days * SECONDS_PER_DAY as f64) as u64)
+    };
+
+    let mut chains: Vec<ChainGrowth> = chain_blobs
+        .into_iter()
+        .map(|(chain, blobs)| {
+            let series = per_block(&blobs);
+            ChainGrowth {
+                chain,
+                avg_blobs_per_block: mean(&series),
+                trend_per_day: forecast::linear_slope(&series),
+                daily_growth: GrowthTrend::fit(&series).map(|trend| trend.daily_growth),
+            }
+        })
+        .collect();
+    chains.sort_by(|a, b| b.trend_per_day.total_cmp(&a.trend_per_day));
+
+    Ok(Json(Capacity {
+        days,
+        target,
+        max,
+        avg_blobs_per_block: mean(&total),
+        trend_blobs_per_block: trend.map(|trend| trend.level),
+        daily_growth: trend.map(|trend| trend.daily_growth),
+        trend_per_day: forecast::linear_slope(&total),
+        target_reached_at: reached_at(target),
+        max_reached_at: reached_at(max),
+        chains,
+    }))
+}
+
+async fn get_protocol_summary(
+    State(db): State<Database>,
+    counts: Counts,
+) -> Result<Json<ProtocolSummary>, DbError> {
+    let days = counts.days.unwrap_or(30).min(MAX_PROTOCOL_SUMMARY_DAYS);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let blocks = db.get_excess_blob_gas(now.saturating_sub(days * 86400))?;
+    let schedule = db.get_blob_schedule()?;
+
+    let (mut total_blobs, mut total_target, mut blocks_at_max) = (0, 0, 0);
+    for b in &blocks {
+        let params = schedule.params_at(b.block_timestamp);
+        total_blobs += b.total_blobs;
+        total_target += params.target;
+        if b.total_blobs >= params.max {
+            blocks_at_max += 1;
+        }
+    }
+    let block_count = blocks.len() as u64;
+    let per_block = |total: u64| match block_count {
+        0 => 0.0,
+        n => total as f64 / n as f64,
+    };
+
+    let mut prices: Vec<u128> = blocks.iter().map(|b| b.gas_price).collect();
+    prices.sort_unstable();
+    // Nearest rank
+    let percentile = |p: f64| {
+        let rank = ((prices.len() as f64 * p).ceil() as usize).max(1);
+        prices[rank - 1]
+    };
+    let fee_percentiles = (!prices.is_empty()).then(|| BlobFeePercentiles {
+        p10: percentile(0.1),
+        p25: percentile(0.25),
+        p50: percentile(0.5),
+        p75: percentile(0.75),
+        p90: percentile(0.9),
+        p99: percentile(0.99),
+    });
+
+    Ok(Json(ProtocolSummary {
+        days,
+        blocks: block_count,
+        avg_blobs_per_block: per_block(total_blobs),
+        avg_target: per_block(total_target),
+        target_utilization: match total_target {
+            0 => 0.0,
+            target => total_blobs as f64 / target as f64 * 100.0,
+        },
+        pct_blocks_at_max: match block_count {
+            0 => 0.0,
+            n => blocks_at_max as f64 / n as f64 * 100.0,
+        },
+        equilibrium_deviation: per_block(total_blobs) - per_block(total_target),
+        fee_percentiles,
+        fee_doublings: fee_doublings(&blocks).len() as u64,
+    }))
+}
+
+async fn get_ingest_errors(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+) -> Result<Json<Vec<IngestError>>, DbError> {
+    let limit = counts.limit.unwrap_or(100).min(limits.max_rows);
+    let errors = db.get_ingest_errors(limit)?;
+
+    Ok(Json(
+        errors
+            .into_iter()
+            .map(|e| IngestError {
+                block_number: e.block_number,
+                stage: e.stage,
+                error: e.error,
+                attempts: e.attempts,
+                failed_at: e.failed_at,
+            })
+            .collect(),
+    ))
+}
+
+async fn get_ingest_log(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+    Query(params): Query<IngestLogQuery>,
+) -> Result<Json<Vec<IngestLogEntry>>, DbError> {
+    let limit = counts.limit.unwrap_or(100).min(limits.max_rows);
+    let entries = db.get_ingest_log(params.before, limit)?;
+
+    let range = |range: Option<RangeInclusive<u64>>| {
+        range.map(|range| BlockRange {
+            from: *range.start(),
+            to: *range.end(),
+        })
+    };
+    Ok(Json(
+        entries
+            .into_iter()
+            .map(|e| IngestLogEntry {
+                id: e.id,
+                processed_at: e.processed_at,
+                kind: e.kind,
+                reverted: range(e.reverted),
+                committed: range(e.committed),
+                reverted_txs: e.reverted_txs,
+                committed_txs: e.committed_txs,
+                duration_ms: e.duration_ms,
+                outcome: e.outcome,
+                error: e.error,
+            })
+            .collect(),
+    ))
+}
+
+async fn get_sender_consistency(
+    State(db): State<Database>,
+) -> Result<Json<SenderConsistency>, DbError> {
+    let drift = db.get_sender_drift()?;
+
+    Ok(Json(SenderConsistency {
+        drifted_senders: drift.len(),
+        drift: drift
+            .into_iter()
+            .take(MAX_DRIFT_ROWS)
+            .map(|d| SenderDrift {
+                address: checksum(&d.address),
+                stored: d.stored.into(),
+                derived: d.derived.into(),
+            })
+            .collect(),
+    }))
+}
+
+async fn get_concentration(
+    State(db): State<Database>,
+    Query(params): Query<ConcentrationQuery>,
+) -> Result<Json<Concentration>, ApiError> {
+    let window = params.window.as_deref().unwrap_or("7d");
+    let window_secs = config::parse_duration(window)
+        .ok_or_else(|| ApiError::invalid_param("window", format!("invalid window: {window}")))?;
+    let top = params.top.unwrap_or(5).max(1);
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let totals = db.get_sender_blob_totals(now.saturating_sub(window_secs))?;
+
+    let mut by_chain: HashMap<String, u64> = HashMap::new();
+    for (sender, blobs) in &totals {
+        let chain = match identify_chain(sender).as_str() {
+            "Other" => sender.clone(),
+            chain => chain.to_string(),
+        };
+        *by_chain.entry(chain).or_default() += blobs;
+    }
+
+    let sender_blobs: Vec<u64> = totals.iter().map(|(_, blobs)| *blobs).collect();
+    let chain_blobs: Vec<u64> = by_chain.into_values().collect();
+
+    Ok(Json(Concentration {
+        window_secs,
+        total_blobs: sender_blobs.iter().sum(),
+        top,
+        senders: concentration(sender_blobs, top),
+        chains: concentration(chain_blobs, top),
+    }))
+}
+
+async fn get_blob_savings(
+    State(db): State<Database>,
+    Query(params): Query<WindowQuery>,
+) -> Result<Json<Vec<BlobSavings>>, ApiError> {
+    let window = params.window.as_deref().unwrap_or("7d");
+    let window_secs = config::parse_duration(window)
+        .ok_or_else(|| ApiError::invalid_param("window", format!("invalid window: {window}")))?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let costs =
+        db.get_sender_blob_costs(now.saturating_sub(window_secs), CALLDATA_FLOOR_GAS_PER_BYTE)?;
+
+    let mut by_chain: HashMap<String, BlobSavings> = HashMap::new();
+    for cost in costs {
+        let chain = identify_chain(&cost.sender);
+        let savings = by_chain.entry(chain.clone()).or_insert(BlobSavings {
+            chain,
+            tx_count: 0,
+            total_blobs: 0,
+            payload_bytes: 0,
+            blob_fees_eth: 0.0,
+            calldata_cost_eth: 0.0,
+            savings_eth: 0.0,
+            savings_pct: 0.0,
+            unpriced_tx_count: 0,
+        });
+        savings.tx_count += cost.tx_count;
+        savings.total_blobs += cost.total_blobs;
+        savings.payload_bytes += cost.payload_bytes;
+        savings.blob_fees_eth += cost.blob_fees_wei / 1e18;
+        savings.calldata_cost_eth += cost.calldata_fees_wei / 1e18;
+        savings.unpriced_tx_count += cost.unpriced_tx_count;
+    }
+
+    let mut savings: Vec<BlobSavings> = by_chain
+        .into_values()
+        .map(|mut s| {
+            s.savings_eth = s.calldata_cost_eth - s.blob_fees_eth;
+            s.savings_pct = if s.calldata_cost_eth > 0.0 {
+                s.savings_eth / s.calldata_cost_eth * 100.0
+            } else {
+                0.0
+            };
+            s
+        })
+        .collect();
+    savings.sort_by(|a, b| b.savings_eth.total_cmp(&a.savings_eth));
+
+    Ok(Json(savings))
+}
+
+async fn get_chain_cost_breakdown(
+    State(db): State<Database>,
+    Query(params): Query<WindowQuery>,
+) -> Result<Json<Vec<ChainCostBreakdown>>, ApiError> {
+    let window = params.window.as_deref().unwrap_or("7d");
+    let window_secs = config::parse_duration(window)
+        .ok_or_else(|| ApiError::invalid_param("window", format!("invalid window: {window}")))?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let costs = db.get_sender_cost_breakdown(now.saturating_sub(window_secs))?;
+
+    let mut by_chain: HashMap<String, ChainCostBreakdown> = HashMap::new();
+    for cost in costs {
+        let chain = chain_of(&cost.sender, cost.attributed_entity.as_deref());
+        let breakdown = by_chain.entry(chain.clone()).or_insert(ChainCostBreakdown {
+            chain,
+            tx_count: 0,
+            priced_blobs: 0,
+            blob_fees_eth: 0.0,
+            execution_fees_eth: 0.0,
+            total_cost_eth: 0.0,
+            blob_fee_share_pct: 0.0,
+            execution_gas_used: 0,
+            unpriced_tx_count: 0,
+        });
+        breakdown.tx_count += cost.tx_count;
+        breakdown.priced_blobs += cost.priced_blobs;
+        breakdown.blob_fees_eth += cost.blob_fees_wei / 1e18;
+        breakdown.execution_fees_eth += cost.execution_fees_wei / 1e18;
+        breakdown.execution_gas_used += cost.execution_gas_used;
+        breakdown.unpriced_tx_count += cost.unpriced_tx_count;
+    }
+
+    let mut breakdowns: Vec<ChainCostBreakdown> = by_chain
+        .into_values()
+        .map(|mut b| {
+            b.total_cost_eth = b.blob_fees_eth + b.execution_fees_eth;
+            b.blob_fee_share_pct = if b.total_cost_eth > 0.0 {
+                b.blob_fees_eth / b.total_cost_eth * 100.0
+            } else {
+                0.0
+            };
+            b
+        })
+        .collect();
+    breakdowns.sort_by(|a, b| b.total_cost_eth.total_cmp(&a.total_cost_eth));
+
+    Ok(Json(breakdowns))
+}
+
+async fn get_resubmissions(
+    State(db): State<Database>,
+    Query(params): Query<WindowQuery>,
+) -> Result<Json<Resubmissions>, ApiError> {
+    let window = params.window.as_deref().unwrap_or("7d");
+    let window_secs = config::parse_duration(window)
+        .ok_or_else(|| ApiError::invalid_param("window", format!("invalid window: {window}")))?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let senders = db.get_resubmissions(now.saturating_sub(window_secs))?;
+
+    // (tx_count, resubmitted_tx_count, replaced_attempts) per chain
+    let mut by_chain: HashMap<String, (u64, u64, u64)> = HashMap::new();
+    let mut reincluded_tx_count = 0;
+    for sender in senders {
+        let totals = by_chain.entry(identify_chain(&sender.sender)).or_default();
+        totals.0 += sender.tx_count;
+        totals.1 += sender.resubmitted_tx_count;
+        totals.2 += sender.replaced_attempts;
+        reincluded_tx_count += sender.reincluded_tx_count;
+    }
+
+    let rate = |part: u64, total: u64| {
+        if total > 0 {
+            part as f64 / total as f64
+        } else {
+            0.0
+        }
+    };
+
+    let (tx_count, resubmitted_tx_count, replaced_attempts) = by_chain
+        .values()
+        .fold((0, 0, 0), |acc, t| (acc.0 + t.0, acc.1 + t.1, acc.2 + t.2));
+
+    let mut chains: Vec<ChainResubmissions> = by_chain
+        .into_iter()
+        .map(
+            |(chain, (tx_count, resubmitted, replaced))| ChainResubmissions {
+                chain,
+                tx_count,
+                resubmitted_tx_count: resubmitted,
+                resubmission_rate: rate(resubmitted, tx_count),
+                avg_attempts: rate(tx_count + replaced, tx_count),
+            },
+        )
+        .collect();
+    chains.sort_by_key(|c| std::cmp::Reverse(c.resubmitted_tx_count));
+
+    Ok(Json(Resubmissions {
+        tx_count,
+        resubmitted_tx_count,
+        resubmission_rate: rate(resubmitted_tx_count, tx_count),
+        avg_attempts: rate(tx_count + replaced_attempts, tx_count),
+        reincluded_tx_count,
+        chains,
+    }))
+}
+
+/// Gini coefficient, HHI and top-N share of the given blob counts.
+fn concentration(mut blobs: Vec<u64>, top: usize) -> ConcentrationMetrics {
+    let total: u64 = blobs.iter().sum();
+    if total == 0 {
+        return ConcentrationMetrics {
+            participants: 0,
+            gini: 0.0,
+            hhi: 0.0,
+            top_n_share: 0.0,
+        };
+    }
+
+    blobs.sort_unstable();
+    let n = blobs.len() as f64;
+    let total = total as f64;
+
+    let weighted: f64 = blobs
+        .iter()
+        .enumerate()
+        .map(|(i, blobs)| (i + 1) as f64 * *blobs as f64)
+        .sum();
+    let gini = 2.0 * weighted / (n * total) - (n + 1.0) / n;
+
+    let hhi = blobs
+        .iter()
+        .map(|blobs| (*blobs as f64 / total * 100.0).powi(2))
+        .sum();
+
+    let top_blobs: u64 = blobs.iter().rev().take(top).sum();
+
+    ConcentrationMetrics {
+        participants: blobs.len(),
+        gini,
+        hhi,
+        top_n_share: top_blobs as f64 / total * 100.0,
+    }
+}
+
+/// EIP-55 checksummed form of an address stored in lowercase.
+pub(crate) fn checksum(address: &str) -> String {
+    address
+        .parse::<Address>()
+        .map_or_else(|_| address.to_string(), |address| address.to_checksum(None))
+}
+
+fn parse_block_number(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_percentiles(value: &str) -> Option<Vec<f64>> {
+    let percentiles: Vec<f64> = value
+        .split(',')
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+
+    let in_range = percentiles.iter().all(|p| (0.0..=100.0).contains(p));
+    let ascending = percentiles.windows(2).all(|w| w[0] <= w[1]);
+    (in_range && ascending).then_some(percentiles)
+}
+
+/// Priority fee at each percentile, weighting every tx by its blob count.
+/// `fees` must be sorted ascending by fee.
+fn reward_percentiles(fees: &[(u64, u64)], percentiles: &[f64]) -> Vec<u64> {
+    let total_blobs: u64 = fees.iter().map(|(_, blobs)| blobs).sum();
+    if total_blobs == 0 {
+        return vec![0; percentiles.len()];
+    }
+
+    percentiles
+        .iter()
+        .map(|p| {
+            let threshold = total_blobs as f64 * p / 100.0;
+            let mut cumulative = 0;
+            fees.iter()
+                .find(|(_, blobs)| {
+                    cumulative += blobs;
+                    cumulative as f64 >= threshold
+                })
+                .or(fees.last())
+                .map_or(0, |(fee, _)| *fee)
+        })
+        .collect()
+}
+
+async fn get_blob_fee_history(
+    format: Format,
+    State(db): State<Database>,
+    Query(params): Query<FeeHistoryQuery>,
+) -> Result<Encoded<BlobFeeHistory>, ApiError> {
+    let block_count = params
+        .block_count
+        .unwrap_or(100)
+        .clamp(1, MAX_FEE_HISTORY_BLOCKS);
+
+    let newest_block = match params.newest_block.as_deref() {
+        None | Some("latest") => None,
+        Some(value) => Some(parse_block_number(value).ok_or_else(|| {
+            ApiError::invalid_param("newest_block", format!("invalid newest_block: {value}"))
+        })?),
+    };
+
+    let percentiles = params
+        .reward_percentiles
+        .as_deref()
+        .map(|value| {
+            parse_percentiles(value).ok_or_else(|| {
+                ApiError::invalid_param(
+                    "reward_percentiles",
+                    format!("invalid reward_percentiles: {value}"),
+                )
+            })
+        })
+        .transpose()?;
+
+    let history = db.get_fee_history(newest_block, block_count)?;
+    let schedule = db.get_blob_schedule()?;
+
+    let oldest_block = history
+        .first()
+        .map_or(newest_block.unwrap_or(0), |b| b.block_number);
+
+    // Each block's max as it was stored when indexed, not the current schedule's
+    let blob_gas_used_ratio = history
+        .iter()
+        .map(|b| b.gas_used as f64 / (b.blob_max * DATA_GAS_PER_BLOB) as f64)
+        .collect();
+
+    // Like eth_feeHistory, the fee of the block after the newest one comes last
+    let mut base_fee_per_blob_gas: Vec<String> = history
+        .iter()
+        .map(|b| format!("{:#x}", b.gas_price))
+        .collect();
+    if let Some(newest) = history.last() {
+        let params = schedule
+            .params_at(newest.block_timestamp + SECONDS_PER_SLOT)
+            .blob_params();
+        let excess_blob_gas = params.next_block_excess_blob_gas_osaka(
+            newest.excess_blob_gas,
+            newest.gas_used,
+            newest.base_fee_per_gas.unwrap_or(0),
+        );
+        base_fee_per_blob_gas.push(format!("{:#x}", params.calc_blob_fee(excess_blob_gas)));
+    }
+
+    let reward = percentiles.map(|percentiles| {
+        history
+            .iter()
+            .map(|b| {
+                reward_percentiles(&b.priority_fees, &percentiles)
+                    .into_iter()
+                    .map(|fee| format!("{fee:#x}"))
+                    .collect()
+            })
+            .collect()
+    });
+
+    Ok(Encoded(
+        format,
+        BlobFeeHistory {
+            oldest_block: format!("{oldest_block:#x}"),
+            base_fee_per_blob_gas,
+            blob_gas_used_ratio,
+            reward,
+        },
+    ))
+}
+
+// Totals come from hourly rollups merged per request, price sensitivity from
+// a cache refreshed every SENSITIVITY_REFRESH
+async fn get_chain_profiles(
+    format: Format,
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    Extension(cache): Extension<SensitivityCache>,
+    counts: Counts,
+) -> Result<Encoded<Vec<ChainProfile>>, ApiError> {
+    let hours = check_limit(
+        "hours",
+        counts.hours.unwrap_or(24),
+        limits.max_profile_hours,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let time_limit = now.saturating_sub(hours * 3600);
+
+    // Merge hours per chain
+    let mut chain_data: HashMap<String, ChainActivity> = HashMap::new();
+    let mut grand_total_blobs = 0u64;
+    for hour in db.get_chain_activity(time_limit)? {
+        let activity = chain_data.entry(hour.chain).or_default();
+        activity.total_transactions += hour.tx_count;
+        activity.total_blobs += hour.total_blobs;
+        activity.first_tx_at = activity.first_tx_at.min(hour.first_tx_at);
+        activity.last_tx_at = activity.last_tx_at.max(hour.last_tx_at);
+        activity.hourly_counts[db::hour_of_day(hour.hour_start, 0)] += hour.tx_count;
+        grand_total_blobs += hour.total_blobs;
+    }
+
+    let sensitivities = cache.get(&db, hours, now)?;
+
+    let mut profiles: Vec<ChainProfile> = chain_data
+        .into_iter()
+        .map(|(chain, activity)| {
+            let price_sensitivity = sensitivities
+                .get(&chain)
+                .cloned()
+                .unwrap_or_else(|| sensitivity::price_sensitivity(&[], &[]));
+
+            let total_transactions = activity.total_transactions;
+            let total_blobs = activity.total_blobs;
+            let avg_blobs_per_tx = if total_transactions > 0 {
+                total_blobs as f64 / total_transactions as f64
+            } else {
+                0.0
+            };
+
+            let percentage = if grand_total_blobs > 0 {
+                (total_blobs as f64 / grand_total_blobs as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            // Average of the gaps between consecutive posts, which add up to
+            // the span from the first to the last one
+            let avg_posting_interval_secs = if total_transactions > 1 {
+                (activity.last_tx_at - activity.first_tx_at) as f64
+                    / (total_transactions - 1) as f64
+            } else {
+                0.0
+            };
+
+            // Hourly activity distribution (24 hours)
+            let max_count = *activity.hourly_counts.iter().max().unwrap_or(&1) as f64;
+            let hourly_activity: Vec<f64> = activity
+                .hourly_counts
+                .iter()
+                .map(|&c| {
+                    if max_count > 0.0 {
+                        c as f64 / max_count
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+
+            ChainProfile {
+                chain,
+                total_transactions,
+                total_blobs,
+                percentage,
+                avg_blobs_per_tx,
+                avg_posting_interval_secs,
+                hourly_activity,
+                price_sensitivity,
+            }
+        })
+        .collect();
+
+    profiles.sort_by_key(|p| std::cmp::Reverse(p.total_blobs));
+    Ok(Encoded(format, profiles))
+}
+
+// A chain's posts in a chain profile window
+struct ChainActivity {
+    total_transactions: u64,
+    total_blobs: u64,
+    first_tx_at: u64,
+    last_tx_at: u64,
+    hourly_counts: [u64; 24],
+}
+
+impl Default for ChainActivity {
+    fn default() -> Self {
+        Self {
+            total_transactions: 0,
+            total_blobs: 0,
+            first_tx_at: u64::MAX,
+            last_tx_at: 0,
+            hourly_counts: [0; 24],
+        }
+    }
+}
+
+// How long chain profiles reuse a window's price sensitivities
+const SENSITIVITY_REFRESH: Duration = Duration::from_secs(300);
+
+/// Price sensitivity of every chain per chain profile window (in hours),
+/// recomputed once older than [`SENSITIVITY_REFRESH`].
+#[derive(Clone, Default)]
+struct SensitivityCache(Arc<Mutex<HashMap<u64, CachedSensitivities>>>);
+
+struct CachedSensitivities {
+    computed_at: Instant,
+    by_chain: Arc<HashMap<String, PriceSensitivity>>,
+}
+
+impl SensitivityCache {
+    fn get(
+        &self,
+        db: &Database,
+        hours: u64,
+        now: u64,
+    ) -> Result<Arc<HashMap<String, PriceSensitivity>>, DbError> {
+        if let Some(cached) = self.lock().get(&hours) {
+            if cached.computed_at.elapsed() < SENSITIVITY_REFRESH {
+                return Ok(cached.by_chain.clone());
+            }
+        }
+
+        let by_chain = Arc::new(price_sensitivities(
+            db,
+            now.saturating_sub(hours * 3600),
+            now,
+        )?);
+        self.lock().insert(
+            hours,
+            CachedSensitivities {
+                computed_at: Instant::now(),
+                by_chain: by_chain.clone(),
+            },
+        );
+        Ok(by_chain)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, CachedSensitivities>> {
+        self.0
+            .lock()
+            .expect("failed to acquire sensitivity cache lock")
+    }
+}
+
+/// Correlate the blobs each chain posted in `time_limit..=now` with the
+/// block-level blob base fee, in aligned buckets.
+fn price_sensitivities(
+    db: &Database,
+    time_limit: u64,
+    now: u64,
+) -> Result<HashMap<String, PriceSensitivity>, DbError> {
+    let from = time_limit / sensitivity::BUCKET_SECS * sensitivity::BUCKET_SECS;
+    let buckets = ((now - from) / sensitivity::BUCKET_SECS + 1) as usize;
+    let bucket_index = |start: u64| ((start - from) / sensitivity::BUCKET_SECS) as usize;
+
+    let mut fees = vec![None; buckets];
+    for bucket in db.get_block_series(from, now, sensitivity::BUCKET_SECS)? {
+        fees[bucket_index(bucket.bucket_start)] = Some(bucket.avg_gas_price);
+    }
+    let mut chain_blobs: HashMap<String, Vec<f64>> = HashMap::new();
+    for bucket in db.get_sender_blob_series(from, now, sensitivity::BUCKET_SECS)? {
+        chain_blobs
+            .entry(chain_of(
+                &bucket.sender,
+                bucket.attributed_entity.as_deref(),
+            ))
+            .or_insert_with(|| vec![0.0; buckets])[bucket_index(bucket.bucket_start)] +=
+            bucket.blobs as f64;
+    }
+
+    Ok(chain_blobs
+        .into_iter()
+        .map(|(chain, blobs)| (chain, sensitivity::price_sensitivity(&fees, &blobs)))
+        .collect())
+}
+
+async fn get_chain_uptime(
+    State(db): State<Database>,
+    counts: Counts,
+) -> Result<Json<ChainUptime>, DbError> {
+    let days = counts.days.unwrap_or(30).min(MAX_UPTIME_DAYS);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let first_day = db::group_by_day(now, 0) - (days - 1) * SECONDS_PER_DAY;
+    let day_starts: Vec<u64> = (0..days)
+        .map(|day| first_day + day * SECONDS_PER_DAY)
+        .collect();
+
+    // One extra day so the gap running into the first day is known
+    let mut posts: HashMap<String, Vec<u64>> = HashMap::new();
+    for tx in db.get_transactions_in_time_range(first_day.saturating_sub(SECONDS_PER_DAY) as i64)? {
+        let chain = chain_of(&tx.sender, tx.attributed_entity.as_deref());
+        // Unknown senders are unrelated to each other, so they have no cadence
+        if chain != "Other" {
+            posts.entry(chain).or_default().push(tx.created_at as u64);
+        }
+    }
+
+    let mut chains: Vec<ChainDailyUptime> = posts
+        .into_iter()
+        .filter_map(|(chain, mut posts)| {
+            posts.sort_unstable();
+            posts.dedup();
+            let mut gaps: Vec<u64> = posts.windows(2).map(|w| w[1] - w[0]).collect();
+            if gaps.len() < 2 {
+                return None;
+            }
+            gaps.sort_unstable();
+            let gap_threshold_secs =
+                (gaps[gaps.len() / 2] * UPTIME_GAP_FACTOR).max(MIN_UPTIME_GAP_SECS);
+
+            let uptime: Vec<Option<f64>> = day_starts
+                .iter()
+                .map(|&day_start| {
+                    daily_uptime(
+                        &posts,
+                        gap_threshold_secs,
+                        day_start,
+                        now.min(day_start + SECONDS_PER_DAY),
+                    )
+                })
+                .collect();
+            let measured: Vec<f64> = uptime.iter().flatten().copied().collect();
+            let avg_uptime = measured.iter().sum::<f64>() / measured.len().max(1) as f64;
+
+            Some(ChainDailyUptime {
+                chain,
+                gap_threshold_secs,
+                uptime,
+                avg_uptime,
+            })
+        })
+        .collect();
+
+    chains.sort_by(|a, b| a.chain.cmp(&b.chain));
+    Ok(Json(ChainUptime {
+        days: day_starts,
+        chains,
+    }))
+}
+
+// Per chain posting and failed batches, blob txs that reverted but still paid
+// their blob fees
+async fn get_chain_health(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+) -> Result<Json<ChainHealth>, ApiError> {
+    let hours = check_limit(
+        "hours",
+        counts.hours.unwrap_or(24),
+        limits.max_profile_hours,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut chains: HashMap<String, ChainHealthStatus> = HashMap::new();
+    for tx in db.get_transactions_in_time_range(now.saturating_sub(hours * 3600) as i64)? {
+        let chain = chain_of(&tx.sender, tx.attributed_entity.as_deref());
+        // Unknown senders are unrelated to each other, so they have no health
+        if chain == "Other" {
+            continue;
+        }
+        let status = chains
+            .entry(chain.clone())
+            .or_insert_with(|| ChainHealthStatus {
+                chain,
+                txs: 0,
+                failed_txs: 0,
+                failure_rate: 0.0,
+                failed_blobs: 0,
+                failed_blob_fees_wei: 0,
+                last_post_timestamp: 0,
+                secs_since_last_post: 0,
+            });
+        status.txs += 1;
+        if tx.reverted {
+            status.failed_txs += 1;
+            status.failed_blobs += tx.blob_count;
+            status.failed_blob_fees_wei = status
+                .failed_blob_fees_wei
+                .saturating_add((tx.blob_count * DATA_GAS_PER_BLOB) as u128 * tx.gas_price);
+        }
+        status.last_post_timestamp = status.last_post_timestamp.max(tx.created_at as u64);
+    }
+
+    let mut chains: Vec<ChainHealthStatus> = chains
+        .into_values()
+        .map(|mut status| {
+            status.failure_rate = status.failed_txs as f64 / status.txs as f64;
+            status.secs_since_last_post = now.saturating_sub(status.last_post_timestamp);
+            status
+        })
+        .collect();
+    chains.sort_by(|a, b| {
+        b.failed_txs
+            .cmp(&a.failed_txs)
+            .then_with(|| a.chain.cmp(&b.chain))
+    });
+    Ok(Json(ChainHealth { hours, chains }))
+}
+
+async fn get_records(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+) -> Result<Json<Records>, DbError> {
+    let mut records = Records {
+        most_blobs: None,
+        highest_blob_fee: None,
+        longest_saturated_streak: None,
+        largest_sender_day: None,
+    };
+    let range_link = |from: u64, to: u64| format!("/api/blocks/range?from={from}&to={to}");
+
+    for record in db.get_records()? {
+        let block_record = || BlockRecord {
+            block_number: record.from_block,
+            value: record.value,
+            link: format!("/api/block?block_number={}", record.from_block),
+        };
+        match record.kind.as_str() {
+            db::RECORD_MOST_BLOBS => records.most_blobs = Some(block_record()),
+            db::RECORD_HIGHEST_BLOB_FEE => records.highest_blob_fee = Some(block_record()),
+            db::RECORD_LONGEST_SATURATED_STREAK => {
+                records.longest_saturated_streak = Some(StreakRecord {
+                    from_block: record.from_block,
+                    to_block: record.to_block,
+                    blocks: record.value as u64,
+                    link: range_link(record.from_block, record.to_block),
+                })
+            }
+            db::RECORD_LARGEST_SENDER_DAY => {
+                let sender = record.sender.clone().unwrap_or_default();
+                let fits = record.to_block - record.from_block < limits.max_chart_blocks;
+                records.largest_sender_day = Some(SenderDayRecord {
+                    chain: identify_chain(&sender),
+                    sender,
+                    day: record.day.unwrap_or_default(),
+                    blobs: record.value as u64,
+                    from_block: record.from_block,
+                    to_block: record.to_block,
+                    link: fits
+                        .then(|| range_link(record.from_block, record.to_block) + "&include=txs"),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Json(records))
+}
+
+// Fee recipients by blocks built, to compare how many blobs each includes
+async fn get_builders(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+) -> Result<Json<Builders>, ApiError> {
+    let hours = check_limit(
+        "hours",
+        counts.hours.unwrap_or(24 * 7),
+        limits.max_profile_hours,
+    )?;
+    let limit = counts.limit.unwrap_or(20).min(limits.max_rows);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let stats = db.get_builder_stats(now.saturating_sub(hours * 3600))?;
+    let blocks: u64 = stats.iter().map(|b| b.blocks).sum();
+    let total_blobs: u64 = stats.iter().map(|b| b.total_blobs).sum();
+    let ratio = |a: u64, b: u64| if b > 0 { a as f64 / b as f64 } else { 0.0 };
+    let avg_blobs_per_block = ratio(total_blobs, blocks);
+
+    Ok(Json(Builders {
+        hours,
+        blocks,
+        avg_blobs_per_block,
+        builders: stats
+            .into_iter()
+            .take(limit as usize)
+            .map(|b| Builder {
+                beneficiary: checksum(&b.beneficiary),
+                blocks: b.blocks,
+                share_pct: ratio(b.blocks, blocks) * 100.0,
+                total_blobs: b.total_blobs,
+                avg_blobs_per_block: ratio(b.total_blobs, b.blocks),
+                blobs_vs_avg: ratio(b.total_blobs, b.blocks) - avg_blobs_per_block,
+                avg_target_utilization: b.avg_target_utilization,
+                empty_blocks: b.empty_blocks,
+                full_blocks: b.full_blocks,
+            })
+            .collect(),
+    }))
+}
+
+async fn get_op_batches(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+) -> Result<Json<Vec<ChainOpBatches>>, ApiError> {
+    let hours = check_limit(
+        "hours",
+        counts.hours.unwrap_or(24),
+        limits.max_profile_hours,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut chains: HashMap<String, db::OpBatchStatsData> = HashMap::new();
+    for stats in db.get_op_batch_stats(now.saturating_sub(hours * 3600))? {
+        let chain = chain_of(&stats.sender, stats.attributed_entity.as_deref());
+        match chains.get_mut(&chain) {
+            Some(total) => {
+                total.blobs += stats.blobs;
+                total.frames += stats.frames;
+                total.frame_bytes += stats.frame_bytes;
+                total.channels += stats.channels;
+                total.channel_frames += stats.channel_frames;
+                total.compressed_size += stats.compressed_size;
+                total.decompressed_size += stats.decompressed_size;
+                total.decompressed_from += stats.decompressed_from;
+            }
+            None => {
+                chains.insert(chain, stats);
+            }
+        }
+    }
+
+    let ratio = |a: u64, b: u64| if b > 0 { a as f64 / b as f64 } else { 0.0 };
+    let mut batches: Vec<ChainOpBatches> = chains
+        .into_iter()
+        .map(|(chain, total)| ChainOpBatches {
+            chain,
+            blobs: total.blobs,
+            frames: total.frames,
+            avg_frame_size: ratio(total.frame_bytes, total.frames),
+            channels: total.channels,
+            avg_frames_per_channel: ratio(total.channel_frames, total.channels),
+            avg_channel_size: ratio(total.compressed_size, total.channels),
+            compression_ratio: (total.decompressed_from > 0)
+                .then(|| ratio(total.decompressed_size, total.decompressed_from)),
+        })
+        .collect();
+
+    batches.sort_by_key(|b| std::cmp::Reverse(b.blobs));
+    Ok(Json(batches))
+}
+
+async fn get_compare_periods(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    Query(params): Query<ComparePeriodsQuery>,
+) -> Result<Json<ComparePeriods>, ApiError> {
+    let mut periods = Vec::with_capacity(2);
+    for (name, from, to) in [
+        ("a", params.a_from, params.a_to),
+        ("b", params.b_from, params.b_to),
+    ] {
+        if from >= to {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("{name}_from must be before {name}_to"),
+            ));
+        }
+        check_limit(
+            &format!("{name} hours"),
+            (to - from).div_ceil(3600),
+            limits.max_profile_hours,
+        )?;
+        periods.push(period_stats(&db, from, to)?);
+    }
+    let b = periods.pop().unwrap();
+    let a = periods.pop().unwrap();
+
+    let pct = |a: f64, b: f64| (a != 0.0).then(|| (b - a) / a * 100.0);
+    let delta_pct = PeriodDeltas {
+        blocks: pct(a.blocks as f64, b.blocks as f64),
+        missed_slots: pct(a.missed_slots as f64, b.missed_slots as f64),
+        total_blobs: pct(a.total_blobs as f64, b.total_blobs as f64),
+        blobs_per_block: pct(a.blobs_per_block, b.blobs_per_block),
+        blobs_per_slot: pct(a.blobs_per_slot, b.blobs_per_slot),
+        blob_transactions: pct(a.blob_transactions as f64, b.blob_transactions as f64),
+        unique_senders: pct(a.unique_senders as f64, b.unique_senders as f64),
+        avg_blob_base_fee: pct(a.avg_blob_base_fee, b.avg_blob_base_fee),
+        blob_fees_eth: pct(a.blob_fees_eth, b.blob_fees_eth),
+        saturated_blocks_pct: pct(a.saturated_blocks_pct, b.saturated_blocks_pct),
+        target_utilization_pct: pct(a.target_utilization_pct, b.target_utilization_pct),
+    };
+
+    Ok(Json(ComparePeriods { a, b, delta_pct }))
+}
+
+async fn get_blobs_per_tx(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+    Query(params): Query<BlobsPerTxQuery>,
+) -> Result<Json<BlobsPerTx>, ApiError> {
+    let days = check_limit(
+        "days",
+        counts.days.unwrap_or(7),
+        limits.max_profile_hours / 24,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut chain = params.chain;
+    let mut tx_counts: Vec<u64> = Vec::new();
+    for count in db.get_sender_blob_counts(now.saturating_sub(days * 86400))? {
+        let sender_chain = chain_of(&count.sender, count.attributed_entity.as_deref());
+        if !sender_chain.eq_ignore_ascii_case(&chain) {
+            continue;
+        }
+        chain = sender_chain;
+        let index = count.blob_count.saturating_sub(1) as usize;
+        if tx_counts.len() <= index {
+            tx_counts.resize(index + 1, 0);
+        }
+        tx_counts[index] += count.tx_count;
+    }
+
+    let tx_count: u64 = tx_counts.iter().sum();
+    let total_blobs: u64 = (1..).zip(&tx_counts).map(|(blobs, txs)| blobs * txs).sum();
+    let buckets = (1..)
+        .zip(tx_counts)
+        .map(|(blob_count, txs)| BlobCountBucket {
+            blob_count,
+            tx_count: txs,
+            share: txs as f64 / tx_count as f64,
+        })
+        .collect();
+
+    Ok(Json(BlobsPerTx {
+        chain,
+        days,
+        tx_count,
+        avg_blobs_per_tx: (tx_count > 0).then(|| total_blobs as f64 / tx_count as f64),
+        buckets,
+    }))
+}
+
+async fn get_compare_chains(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    counts: Counts,
+    Query(params): Query<CompareChainsQuery>,
+) -> Result<Json<ChainComparison>, ApiError> {
+    let days = check_limit(
+        "days",
+        counts.days.unwrap_or(7),
+        limits.max_profile_hours / 24,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let first_day = db::group_by_day(now, 0) - (days - 1) * SECONDS_PER_DAY;
+    let day_starts: Vec<u64> = (0..days)
+        .map(|day| first_day + day * SECONDS_PER_DAY)
+        .collect();
+
+    let mut chains: Vec<ComparedChain> = Vec::new();
+    for name in params.chains.split(',').map(str::trim) {
+        if name.is_empty() || chains.iter().any(|c| c.chain.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        chains.push(ComparedChain {
+            chain: name.to_string(),
+            total_transactions: 0,
+            total_blobs: 0,
+            blob_fees_eth: 0.0,
+            avg_posting_interval_secs: None,
+            avg_fill: None,
+            daily_transactions: vec![0; days as usize],
+            daily_blobs: vec![0; days as usize],
+            daily_blob_fees_eth: vec![0.0; days as usize],
+        });
+    }
+    if chains.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "chains must name at least one chain",
+        ));
+    }
+    check_limit("chains", chains.len() as u64, MAX_COMPARED_CHAINS)?;
+
+    // (first post, last post, payload bytes, blobs of known payload) per chain
+    let mut spans = vec![(u64::MAX, 0u64, 0u64, 0u64); chains.len()];
+    for day in db.get_chain_days(first_day)? {
+        let Some(index) = chains
+            .iter()
+            .position(|c| c.chain.eq_ignore_ascii_case(&day.chain))
+        else {
+            continue;
+        };
+        let offset = ((day.day_start - first_day) / SECONDS_PER_DAY) as usize;
+        let compared = &mut chains[index];
+        compared.daily_transactions[offset] += day.tx_count;
+        compared.daily_blobs[offset] += day.total_blobs;
+        compared.daily_blob_fees_eth[offset] += day.blob_fees_wei / 1e18;
+        // As labeled rather than as requested
+        compared.chain = day.chain;
+
+        let span = &mut spans[index];
+        span.0 = span.0.min(day.first_tx_at);
+        span.1 = span.1.max(day.last_tx_at);
+        span.2 += day.payload_bytes;
+        span.3 += day.sized_blobs;
+    }
+
+    for (compared, (first_tx_at, last_tx_at, payload_bytes, sized_blobs)) in
+        chains.iter_mut().zip(spans)
+    {
+        compared.total_transactions = compared.daily_transactions.iter().sum();
+        compared.total_blobs = compared.daily_blobs.iter().sum();
+        compared.blob_fees_eth = compared.daily_blob_fees_eth.iter().sum();
+        // The gaps between consecutive posts add up to the span from the first to the last one
+        compared.avg_posting_interval_secs = (compared.total_transactions > 1)
+            .then(|| (last_tx_at - first_tx_at) as f64 / (compared.total_transactions - 1) as f64);
+        compared.avg_fill = (sized_blobs > 0)
+            .then(|| payload_bytes as f64 / (sized_blobs * BLOB_SIZE_BYTES) as f64);
+    }
+
+    Ok(Json(ChainComparison {
+        days: day_starts,
+        chains,
+    }))
+}
+
+async fn get_chain_calendar(
+    State(db): State<Database>,
+    Path(name): Path<String>,
+    counts: Counts,
+) -> Result<Json<ChainCalendar>, ApiError> {
+    let days = check_limit("days", counts.days.unwrap_or(90), MAX_CALENDAR_DAYS)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let first_day = db::group_by_day(now, 0) - (days - 1) * SECONDS_PER_DAY;
+
+    let mut calendar = ChainCalendar {
+        chain: name,
+        first_day,
+        blobs: vec![0; days as usize],
+        blob_fees_eth: vec![0.0; days as usize],
+    };
+    for day in db.get_chain_calendar(&calendar.chain, first_day)? {
+        let offset = ((day.day_start - first_day) / SECONDS_PER_DAY) as usize;
+        calendar.blobs[offset] = day.total_blobs;
+        calendar.blob_fees_eth[offset] = day.blob_fees_wei / 1e18;
+        // As labeled rather than as requested
+        calendar.chain = day.chain;
+    }
+
+    Ok(Json(calendar))
+}
+
+async fn get_daily_blob_hashes(
+    State(db): State<Database>,
+    counts: Counts,
+) -> Result<Json<DailyBlobHashes>, ApiError> {
+    let days = check_limit("days", counts.days.unwrap_or(30), MAX_CALENDAR_DAYS)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let first_day = db::group_by_day(now, 0) - (days - 1) * SECONDS_PER_DAY;
+
+    let mut daily = DailyBlobHashes {
+        first_day,
+        blob_refs: vec![0; days as usize],
+        distinct_blobs: vec![0; days as usize],
+    };
+    for day in db.get_daily_blob_hashes(first_day)? {
+        let offset = ((day.day_start - first_day) / SECONDS_PER_DAY) as usize;
+        daily.blob_refs[offset] = day.blob_refs;
+        daily.distinct_blobs[offset] = day.distinct_blobs;
+    }
+
+    Ok(Json(daily))
+}
+
+async fn get_inclusion_latency(
+    State(db): State<Database>,
+    Extension(limits): Extension<Limits>,
+    Query(params): Query<InclusionLatencyQuery>,
+) -> Result<Json<InclusionLatency>, ApiError> {
+    let window = params.window.as_deref().unwrap_or("24h");
+    let window_secs = config::parse_duration(window)
+        .ok_or_else(|| ApiError::invalid_param("window", format!("invalid window: {window}")))?;
+    check_limit(
+        "window hours",
+        window_secs.div_ceil(3600),
+        limits.max_profile_hours,
+    )?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut chain = params.chain;
+    let mut tx_count = 0;
+    // (blocks, secs) of tracked txs per fee bucket
+    let mut buckets: Vec<Vec<(u64, u64)>> = vec![Vec::new(); LATENCY_FEE_BUCKETS.len()];
+    for latency in db.get_inclusion_latencies(now.saturating_sub(window_secs))? {
+        if let Some(chain) = &mut chain {
+            let tx_chain = chain_of(&latency.sender, latency.attributed_entity.as_deref());
+            if !tx_chain.eq_ignore_ascii_case(chain) {
+                continue;
+            }
+            *chain = tx_chain;
+        }
+        tx_count += 1;
+        if let Some((blocks, secs)) = latency.blocks.zip(latency.secs) {
+            let bucket = LATENCY_FEE_BUCKETS
+                .iter()
+                .rposition(|&min| latency.priority_fee >= min)
+                .unwrap_or(0);
+            buckets[bucket].push((blocks, secs));
+        }
+    }
+
+    let all: Vec<(u64, u64)> = buckets.iter().flatten().copied().collect();
+    let (latency, first_eligible_share) = latency_summary(&all);
+    let fee_buckets = buckets
+        .iter()
+        .enumerate()
+        .map(|(i, latencies)| {
+            let (latency, first_eligible_share) = latency_summary(latencies);
+            InclusionLatencyBucket {
+                min_priority_fee_gwei: LATENCY_FEE_BUCKETS[i] as f64 / 1e9,
+                max_priority_fee_gwei: LATENCY_FEE_BUCKETS.get(i + 1).map(|&max| max as f64 / 1e9),
+                tracked_tx_count: latencies.len() as u64,
+                latency,
+                first_eligible_share,
+            }
+        })
+        .collect();
+
+    Ok(Json(InclusionLatency {
+        window_secs,
+        chain,
+        tx_count,
+        tracked_tx_count: all.len() as u64,
+        latency,
+        first_eligible_share,
+        fee_buckets,
+    }))
+}
+
+/// Percentiles of `(blocks, secs)` waits and the share included after one
+/// block, `None` without any.
+fn latency_summary(latencies: &[(u64, u64)]) -> (Option<LatencyPercentiles>, Option<f64>) {
+    if latencies.is_empty() {
+        return (None, None);
+    }
+    let mut blocks: Vec<u64> = latencies.iter().map(|(blocks, _)| *blocks).collect();
+    let mut secs: Vec<u64> = latencies.iter().map(|(_, secs)| *secs).collect();
+    blocks.sort_unstable();
+    secs.sort_unstable();
+    // Nearest rank
+    let percentile = |sorted: &[u64], p: f64| {
+        let rank = ((sorted.len() as f64 * p).ceil() as usize).max(1);
+        sorted[rank - 1]
+    };
+    let first_eligible = blocks.iter().filter(|&&blocks| blocks <= 1).count();
+
+    (
+        Some(LatencyPercentiles {
+            p50_blocks: percentile(&blocks, 0.5),
+            p90_blocks: percentile(&blocks, 0.9),
+            p99_blocks: percentile(&blocks, 0.99),
+            p50_secs: percentile(&secs, 0.5),
+            p90_secs: percentile(&secs, 0.9),
+            p99_secs: percentile(&secs, 0.99),
+        }),
+        Some(first_eligible as f64 / latencies.len() as f64),
+    )
+}
+
+fn period_stats(db: &Database, from: u64, to: u64) -> Result<PeriodStats, DbError> {
+    let stats = db.get_period_stats(from, to)?;
+    let throughput = stats.throughput.as_ref();
+    let blocks = throughput.map_or(0, |t| t.indexed_blocks);
+
+    Ok(PeriodStats {
+        from,
+        to,
+        blocks,
+        slots: throughput.map_or(0, db::SlotThroughputData::slots),
+        missed_slots: throughput.map_or(0, db::SlotThroughputData::missed_slots),
+        total_blobs: throughput.map_or(0, |t| t.total_blobs),
+        blobs_per_block: throughput.map_or(0.0, db::SlotThroughputData::blobs_per_block),
+        blobs_per_slot: throughput.map_or(0.0, db::SlotThroughputData::blobs_per_slot),
+        blob_transactions: stats.blob_transactions,
+        unique_senders: stats.unique_senders,
+        avg_blob_base_fee: stats.avg_gas_price,
+        blob_fees_eth: stats.blob_fees_wei / 1e18,
+        saturated_blocks_pct: if blocks > 0 {
+            stats.saturated_blocks as f64 / blocks as f64 * 100.0
+        } else {
+            0.0
+        },
+        target_utilization_pct: if stats.target_blobs > 0 {
+            throughput.map_or(0, |t| t.total_blobs) as f64 / stats.target_blobs as f64 * 100.0
+        } else {
+            0.0
+        },
+    })
+}
+
+async fn get_demand_forecast(State(db): State<Database>) -> Result<Json<DemandForecast>, DbError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let until = now + forecast::HORIZON_SECS;
+    let since = now.saturating_sub(forecast::LOOKBACK_SECS);
+
+    let mut posts: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
+    let mut background_blobs = 0;
+    for tx in db.get_transactions_in_time_range(since as i64)? {
+        let chain = chain_of(&tx.sender, tx.attributed_entity.as_deref());
+        // Unknown senders are unrelated to each other, so they have no cadence
+        if chain == "Other" {
+            background_blobs += tx.blob_count;
+        } else {
+            posts
+                .entry(chain)
+                .or_default()
+                .push((tx.created_at as u64, tx.blob_count));
+        }
+    }
+
+    let slots_per_bucket = FORECAST_BUCKET_SECS / SECONDS_PER_SLOT;
+    let background_blobs_per_block =
+        background_blobs as f64 * SECONDS_PER_SLOT as f64 / forecast::LOOKBACK_SECS as f64;
+    let mut buckets: Vec<DemandBucket> = (now..until)
+        .step_by(FORECAST_BUCKET_SECS as usize)
+        .map(|start| DemandBucket {
+            start,
+            expected_blobs_per_block: background_blobs_per_block,
+        })
+        .collect();
+
+    let mut chains: Vec<ChainDemand> = posts
+        .into_iter()
+        .filter_map(|(chain, mut posts)| {
+            // Transactions of a chain in the same block are one post
+            posts.sort_unstable();
+            posts.dedup_by(|post, prev| {
+                let same_block = post.0 == prev.0;
+                if same_block {
+                    prev.1 += post.1;
+                }
+                same_block
+            });
+            let model = CadenceModel::fit(&posts)?;
+
+            let next_posts: Vec<u64> = model.next_posts(now, until).collect();
+            for post in &next_posts {
+                let bucket = ((post - now) / FORECAST_BUCKET_SECS) as usize;
+                buckets[bucket].expected_blobs_per_block +=
+                    model.blobs_per_post / slots_per_bucket as f64;
+            }
+
+            Some(ChainDemand {
+                chain,
+                cadence_secs: model.cadence_secs,
+                blobs_per_post: model.blobs_per_post,
+                last_post: model.last_post,
+                next_post: next_posts.first().copied(),
+                expected_blobs: model.blobs_per_post * next_posts.len() as f64,
+            })
+        })
+        .collect();
+    chains.sort_by(|a, b| b.expected_blobs.total_cmp(&a.expected_blobs));
+
+    let expected_blobs_per_block = buckets
+        .iter()
+        .map(|bucket| bucket.expected_blobs_per_block)
+        .sum::<f64>()
+        / buckets.len() as f64;
+    let params = *db.get_blob_schedule()?.params_at(now);
+
+    Ok(Json(DemandForecast {
+        generated_at: now,
+        horizon_secs: forecast::HORIZON_SECS,
+        target_blobs_per_block: params.target,
+        max_blobs_per_block: params.max,
+        expected_blobs_per_block,
+        background_blobs_per_block,
+        buckets,
+        chains,
+    }))
+}
+
+/// Fraction of `from..to` in which the gap since the previous post in `posts`
+/// (sorted) stayed within `threshold`, or `None` if the chain hadn't posted yet.
+fn daily_uptime(posts: &[u64], threshold: u64, from: u64, to: u64) -> Option<f64> {
+    let from = from.max(*posts.first()?);
+    if from >= to {
+        return None;
+    }
+
+    // Downtime runs from `threshold` after a post until the next one (or `to`)
+    let next_posts = posts.iter().skip(1).copied().chain(std::iter::once(to));
+    let downtime: u64 = posts
+        .iter()
+        .zip(next_posts)
+        .map(|(&post, next)| {
+            let down_from = (post + threshold).max(from);
+            next.min(to).saturating_sub(down_from)
+        })
+        .sum();
+
+    Some(1.0 - downtime as f64 / (to - from) as f64)
+}
+
+/// Upper bounds on how much data a single request can ask for, so a query like
+/// `?blocks=10000000` is rejected instead of loading the whole database.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Blocks per request on `/api/chart` and `/api/inclusion-market`.
+    pub max_chart_blocks: u64,
+    /// Hours of transactions scanned by `/api/chain-profiles`.
+    pub max_profile_hours: u64,
+    /// Rows returned by endpoints paginated with `limit`, which are clamped
+    /// rather than rejected.
+    pub max_rows: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_chart_blocks: 10_000,
+            max_profile_hours: 24 * 30,
+            max_rows: 1000,
+        }
+    }
+}
+
+impl Limits {
+    /// Caps of the public deployment profile, whatever the environment says.
+    pub const PUBLIC: Self = Self {
+        max_chart_blocks: 2000,
+        max_profile_hours: 24 * 7,
+        max_rows: 200,
+    };
+
+    /// These limits, lowered to `caps` where they exceed them.
+    pub fn capped(self, caps: Self) -> Self {
+        Self {
+            max_chart_blocks: self.max_chart_blocks.min(caps.max_chart_blocks),
+            max_profile_hours: self.max_profile_hours.min(caps.max_profile_hours),
+            max_rows: self.max_rows.min(caps.max_rows),
+        }
+    }
+
+    /// Read overrides from `BLOB_MAX_CHART_BLOCKS`, `BLOB_MAX_PROFILE_HOURS` and
+    /// `BLOB_MAX_ROWS`, keeping the defaults for unset variables.
+    pub fn from_env() -> eyre::Result<Self> {
+        let var = |name: &str, default: u64| -> eyre::Result<u64> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .parse()
+                    .map_err(|err| eyre::eyre!("invalid {name}: {value}: {err}")),
+                Err(_) => Ok(default),
+            }
+        };
+        let defaults = Self::default();
+        Ok(Self {
+            max_chart_blocks: var("BLOB_MAX_CHART_BLOCKS", defaults.max_chart_blocks)?,
+            max_profile_hours: var("BLOB_MAX_PROFILE_HOURS", defaults.max_profile_hours)?,
+            max_rows: var("BLOB_MAX_ROWS", defaults.max_rows)?.max(1),
+        })
+    }
+}
+
+/// Reject a requested amount of data above its cap with a 413.
+fn check_limit(name: &str, value: u64, max: u64) -> Result<u64, ApiError> {
+    if value > max {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("{name} must be at most {max}, got {value}"),
+        ));
+    }
+    Ok(value)
+}
+
+/// Build the JSON API router over the given database.
+///
+/// Static assets and the dashboard page are served by the `blob-web` binary.
+pub fn router(db: Database, limits: Limits, events: EventBus) -> Router {
+    Router::new()
+        .route("/api/stats", get(get_stats))
+        .route("/api/blocks", get(get_recent_blocks))
+        .route("/api/block", get(get_block))
+        .route("/api/blocks/range", get(get_blocks_range))
+        .route("/api/sync", get(get_sync))
+        .route("/api/blocks/{id}", get(get_block_by_id))
+        .route("/api/txs/{tx_hash}", get(get_tx))
+        .route("/api/senders", get(get_top_senders))
+        .route("/api/builders", get(get_builders))
+        .route("/api/sender/{address}/blobs", get(get_sender_blobs))
+        .route("/api/inbox/{address}", get(get_inbox))
+        .route("/api/chart", get(get_chart_data))
+        .route("/api/all-time-chart", get(get_all_time_chart))
+        .route("/api/blob-transactions", get(get_blob_transactions))
+        .route("/api/chain-profiles", get(get_chain_profiles))
+        .route("/api/chain-uptime", get(get_chain_uptime))
+        .route("/api/chain-health", get(get_chain_health))
+        .route("/api/demand-forecast", get(get_demand_forecast))
+        .route("/api/records", get(get_records))
+        .route("/api/op-batches", get(get_op_batches))
+        .route("/api/compare-periods", get(get_compare_periods))
+        .route("/api/blobs-per-tx", get(get_blobs_per_tx))
+        .route("/api/compare-chains", get(get_compare_chains))
+        .route("/api/chain/{name}/calendar", get(get_chain_calendar))
+        .route("/api/blob-hashes/daily", get(get_daily_blob_hashes))
+        .route("/api/inclusion-latency", get(get_inclusion_latency))
+        .route("/api/inclusion-market", get(get_inclusion_market))
+        .route("/api/blob-schedule", get(get_blob_schedule))
+        .route("/api/regimes", get(get_regimes))
+        .route("/api/fork-events", get(get_fork_events))
+        .route("/api/annotations", get(get_annotations))
+        .route("/api/blob-fee-history", get(get_blob_fee_history))
+        .route("/api/heatmap", get(get_heatmap))
+        .route("/api/excess-blob-gas", get(get_excess_blob_gas))
+        .route("/api/protocol-summary", get(get_protocol_summary))
+        .route("/api/capacity", get(get_capacity))
+        .route("/api/health", get(get_health))
+        .route("/api/ingest-errors", get(get_ingest_errors))
+        .route("/api/ingest-log", get(get_ingest_log))
+        .route("/api/consistency/senders", get(get_sender_consistency))
+        .route("/api/tail", get(get_tail))
+        .route("/api/concentration", get(get_concentration))
+        .route("/api/blob-savings", get(get_blob_savings))
+        .route("/api/chain-cost-breakdown", get(get_chain_cost_breakdown))
+        .route("/api/resubmissions", get(get_resubmissions))
+        .layer(Extension(limits))
+        .layer(Extension(events))
+        .layer(Extension(SensitivityCache::default()))
+        .with_state(db)
+}
@@ -0,0 +1,197 @@
+//! Backfill from a reth datadir.
+//!
+//! Blocks are read straight from the node's database and static files through
+//! a read-only provider, without executing anything, and indexed like blocks
+//! from ExEx notifications. The node may keep running meanwhile. Progress is
+//! checkpointed in `backfill_progress` after every batch, so an interrupted
+//! backfill resumes where it stopped.
+//!
+//! Sidecars of old blocks are gone, so blob payload sizes stay unknown, and
+//! secondary processors don't run. Blocks already indexed are skipped.
+//!
+//! With `--bulk`, the database runs in bulk ingest mode (see
+//! [`Database::begin_bulk_ingest`]) and rebuilds its indexes, rollups and
+//! records once at the end, which is much faster for long ranges but slows
+//! down the API until the backfill completes.
+
+use crate::{indexer, telemetry::BlockLog, Database};
+use alloy_primitives::TxHash;
+use reth::{
+    chainspec::{ChainSpec, HOLESKY, HOODI, MAINNET, SEPOLIA},
+    providers::{providers::ReadOnlyConfig, BlockNumReader, BlockReader, TransactionVariant},
+};
+use reth_node_ethereum::EthereumNode;
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+/// Blocks indexed per database transaction and checkpoint. Each batch also
+/// gets a fresh read transaction on the reth database, which aborts long-lived
+/// ones.
+const BATCH_SIZE: u64 = 1000;
+
+pub const USAGE: &str = "usage: blob-exex backfill --datadir <dir> --from <block> [--to <block>]
+           [--chain mainnet|sepolia|holesky|hoodi] [--max-blocks-per-sec <n>] [--bulk]";
+
+/// What to backfill, from `blob-exex backfill` arguments.
+#[derive(Debug)]
+pub struct Options {
+    /// The reth datadir, containing `db/` and `static_files/`.
+    pub datadir: PathBuf,
+    pub chain: Arc<ChainSpec>,
+    pub from_block: u64,
+    /// The datadir's latest block if `None`.
+    pub to_block: Option<u64>,
+    /// Throttle, so a backfill next to a running node doesn't starve it of IO.
+    pub max_blocks_per_sec: Option<u64>,
+    /// Ingest in bulk mode, deferring index and derived table maintenance.
+    pub bulk: bool,
+}
+
+impl Options {
+    pub fn from_args(args: &[String]) -> eyre::Result<Self> {
+        let mut datadir = None;
+        let mut chain = MAINNET.clone();
+        let mut from_block = None;
+        let mut to_block = None;
+        let mut max_blocks_per_sec = None;
+        let mut bulk = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--bulk" {
+                bulk = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| eyre::eyre!("{arg} requires a value\n{USAGE}"))?;
+            match arg.as_str() {
+                "--datadir" => datadir = Some(PathBuf::from(value)),
+                "--chain" => chain = chain_spec(value, USAGE)?,
+                "--from" => from_block = Some(value.parse()?),
+                "--to" => to_block = Some(value.parse()?),
+                "--max-blocks-per-sec" => {
+                    let rate: u64 = value.parse()?;
+                    eyre::ensure!(rate > 0, "--max-blocks-per-sec must be at least 1");
+                    max_blocks_per_sec = Some(rate);
+                }
+                _ => eyre::bail!("unknown argument: {arg}\n{USAGE}"),
+            }
+        }
+
+        let options = Self {
+            datadir: datadir.ok_or_else(|| eyre::eyre!("--datadir is required\n{USAGE}"))?,
+            chain,
+            from_block: from_block.ok_or_else(|| eyre::eyre!("--from is required\n{USAGE}"))?,
+            to_block,
+            max_blocks_per_sec,
+            bulk,
+        };
+        eyre::ensure!(
+            options.datadir.is_dir(),
+            "--datadir {}: directory does not exist",
+            options.datadir.display()
+        );
+        Ok(options)
+    }
+}
+
+/// The spec of a chain named by a `--chain` argument, failing with `usage`.
+pub(crate) fn chain_spec(name: &str, usage: &str) -> eyre::Result<Arc<ChainSpec>> {
+    Ok(match name {
+        "mainnet" => MAINNET.clone(),
+        "sepolia" => SEPOLIA.clone(),
+        "holesky" => HOLESKY.clone(),
+        "hoodi" => HOODI.clone(),
+        _ => eyre::bail!("unknown chain: {name}\n{usage}"),
+    })
+}
+
+/// Index `options.from_block..=options.to_block` from the datadir, resuming
+/// from the last checkpoint. Indexed blocks are logged through `log`.
+///
+/// The chain's blob schedule and regime thresholds are stored first, see
+/// [`indexer::seed_chain_params`].
+pub async fn run(db: Database, options: Options, log: BlockLog) -> eyre::Result<()> {
+    let schedule = indexer::seed_chain_params(&db, &options.chain)?;
+    let factory = EthereumNode::provider_factory_builder().open_read_only(
+        options.chain.clone(),
+        ReadOnlyConfig::from_datadir(&options.datadir),
+    )?;
+    let to_block = match options.to_block {
+        Some(to_block) => to_block,
+        None => factory.provider()?.best_block_number()?,
+    };
+
+    let first_block = db
+        .get_backfill_progress(options.from_block)?
+        .unwrap_or(options.from_block);
+    if first_block > options.from_block {
+        info!(
+            from = options.from_block,
+            next = first_block,
+            "Resuming backfill"
+        );
+    }
+
+    if options.bulk {
+        info!("Dropping indexes for bulk ingest");
+        db.begin_bulk_ingest()?;
+    }
+
+    let started = Instant::now();
+    let mut next_block = first_block;
+    while next_block <= to_block {
+        let last_block = to_block.min(next_block + BATCH_SIZE - 1);
+
+        // Reads and SQLite writes block, so the lease heartbeat runs meanwhile
+        let (db, schedule, factory, log) =
+            (db.clone(), schedule.clone(), factory.clone(), log.clone());
+        let from_block = options.from_block;
+        tokio::task::spawn_blocking(move || {
+            let provider = factory.provider()?;
+            db.transaction(|| -> eyre::Result<()> {
+                for block_number in next_block..=last_block {
+                    if db.get_block(block_number)?.is_some() {
+                        continue;
+                    }
+                    let block = provider
+                        .recovered_block(block_number.into(), TransactionVariant::WithHash)?
+                        .ok_or_else(|| eyre::eyre!("block {block_number} not in the datadir"))?;
+                    indexer::process_block(&db, &schedule, &block, |_: TxHash| None, &log)?;
+                }
+                db.set_backfill_progress(from_block, to_block, last_block + 1)?;
+                Ok(())
+            })
+        })
+        .await??;
+        next_block = last_block + 1;
+
+        let done = next_block - first_block;
+        let elapsed = started.elapsed();
+        info!(
+            block = last_block,
+            to_block,
+            blocks_per_sec = (done as f64 / elapsed.as_secs_f64()) as u64,
+            "Backfilled blocks"
+        );
+
+        if let Some(rate) = options.max_blocks_per_sec {
+            let due = Duration::from_secs_f64(done as f64 / rate as f64);
+            tokio::time::sleep(due.saturating_sub(elapsed)).await;
+        }
+    }
+
+    if options.bulk {
+        info!("Rebuilding indexes, rollups and records");
+        let db = db.clone();
+        tokio::task::spawn_blocking(move || db.end_bulk_ingest()).await??;
+    }
+
+    info!(from = options.from_block, to_block, "Backfill complete");
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Per-chain blob totals for Prometheus.
+//!
+//! `blob_exex_chain_blobs{chain="Base"}` and
+//! `blob_exex_chain_blob_fees_gwei{chain="Base"}` are every chain's blobs and
+//! the blob fees it paid, so alerts can fire when a chain stops posting, e.g.
+//! on `delta(blob_exex_chain_blobs[1h]) == 0`. They're read from the hourly
+//! rollups rather than counted per transaction, so a web process serving
+//! `/metrics` next to a separate ExEx exports them too, and restarts don't
+//! reset them.
+//!
+//! They're gauges rather than counters: reverted blocks and re-attributed
+//! senders lower a chain's totals in the rollups, which `rate()` and
+//! `increase()` would read as counter resets.
+//!
+//! Operators can attribute senders to any number of entities, so only the
+//! first [`MAX_CHAIN_LABELS`] chains by blobs get a label of their own; the
+//! rest are counted as `Other`, like senders no chain is known for.
+
+use crate::{
+    db::{ChainTotalsData, DbError},
+    Database,
+};
+use std::{sync::Mutex, time::Duration};
+use tracing::warn;
+
+/// Most `chain` label values exported, `Other` included.
+pub const MAX_CHAIN_LABELS: usize = 20;
+
+/// How often the ExEx process refreshes the totals, see [`run`].
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Label of senders without a known chain, and of chains past the limit.
+const OTHER: &str = "Other";
+
+/// Chains with a label of their own. Only ever grows, so a chain's series
+/// doesn't jump into `Other` and back as its rank changes.
+static LABELED_CHAINS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Refresh the per-chain totals every [`REFRESH_INTERVAL`]. Never returns;
+/// failures are logged and retried on the next round.
+pub async fn run(db: &Database) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = refresh(db) {
+            warn!(%err, "Failed to export chain totals");
+        }
+    }
+}
+
+/// Set the per-chain gauges to the totals stored in `db`.
+pub fn refresh(db: &Database) -> Result<(), DbError> {
+    set_totals(&db.get_chain_totals()?);
+    Ok(())
+}
+
+/// Set the per-chain gauges to `totals`, which are ordered by blobs
+/// descending so the busiest chains get the labels.
+pub fn set_totals(totals: &[ChainTotalsData]) {
+    let mut labeled = LABELED_CHAINS
+        .lock()
+        .expect("failed to acquire chain labels");
+    let (mut other_blobs, mut other_fees_wei) = (0, 0.0);
+    for total in totals {
+        if total.chain != OTHER
+            && !labeled.contains(&total.chain)
+            && labeled.len() < MAX_CHAIN_LABELS - 1
+        {
+            labeled.push(total.chain.clone());
+        }
+        if labeled.contains(&total.chain) {
+            set_chain_totals(total.chain.clone(), total.total_blobs, total.blob_fees_wei);
+        } else {
+            other_blobs += total.total_blobs;
+            other_fees_wei += total.blob_fees_wei;
+        }
+    }
+    set_chain_totals(OTHER.to_string(), other_blobs, other_fees_wei);
+}
+
+fn set_chain_totals(chain: String, blobs: u64, fees_wei: f64) {
+    metrics::gauge!("blob_exex_chain_blobs", "chain" => chain.clone()).set(blobs as f64);
+    metrics::gauge!("blob_exex_chain_blob_fees_gwei", "chain" => chain).set(fees_wei / 1e9);
+}
@@ -0,0 +1,219 @@
+use alloy_primitives::Address;
+
+/// Version of the registry in [`identify_chain`]. Bump it whenever an address
+/// is added, removed or moved to another chain, then queue a relabel job (see
+/// [`crate::Database::request_relabel`]) so stored transactions attributed with
+/// an older version follow.
+pub const REGISTRY_VERSION: u64 = 1;
+
+/// Identify the rollup (or other chain) posting blobs from a sender address.
+///
+/// Returns "Other" for senders not in the registry.
+pub fn identify_chain(address: &str) -> String {
+    let addr = address.to_lowercase();
+
+    match addr.as_str() {
+        // Base
+        "0x5050f69a9786f081509234f1a7f4684b5e5b76c9" => "Base".to_string(),
+        "0xff00000000000000000000000000000000008453" => "Base".to_string(),
+
+        // Optimism
+        "0x6887246668a3b87f54deb3b94ba47a6f63f32985" => "Optimism".to_string(),
+
+        // Arbitrum
+        "0xc1b634853cb333d3ad8663715b08f41a3aec47cc" => "Arbitrum".to_string(),
+        "0xa4b10ac61e79ea1e150df70b8dda53391928fd14" => "Arbitrum".to_string(),
+        "0xa4b1e63cb4901e327597bc35d36fe8a23e4c253f" => "Arbitrum".to_string(),
+
+        // Scroll
+        "0xa1e4380a3b1f749673e270229993ee55f35663b4" => "Scroll".to_string(),
+        "0xcf2898225ed05be911d3709d9417e86e0b4cfc8f" => "Scroll".to_string(),
+        "0x4f250b05262240c787a1ee222687c6ec395c628a" => "Scroll".to_string(),
+        "0xb4a04505a487fcf16232d74ebb76429e232b1f21" => "Scroll".to_string(),
+        "0x054a47b9e2a22af6c0ce55020238c8fecd7d334b" => "Scroll".to_string(),
+
+        // Starknet
+        "0x415c8893d514f9bc5211d36eeda4183226b84aa7" => "Starknet".to_string(),
+        "0x2c169dfe5fbba12957bdd0ba47d9cedbfe260ca7" => "Starknet".to_string(),
+
+        // Swell Chain
+        "0xeb18ea5dedee42e7af378991dfeb719d21c17b4c" => "Swell Chain".to_string(),
+
+        // Zircuit
+        "0xaf1e4f6a47af647f87c0ec814d8032c4a4bff145" => "Zircuit".to_string(),
+
+        // zkSync Era
+        "0xa9268341831efa4937537bc3e9eb36dbece83c7e" => "zkSync Era".to_string(),
+        "0x3db52ce065f728011ac6732222270b3f2360d919" => "zkSync Era".to_string(),
+
+        // Linea
+        "0xd19d4b5d358258f05d7b411e21a1460d11b0876f" => "Linea".to_string(),
+        "0xc70ae19b5feaa5c19f576e621d2bad9771864fe2" => "Linea".to_string(),
+
+        // Hemi
+        "0x65115c6d23274e0a29a63b69130efe901aa52e7a" => "Hemi".to_string(),
+
+        // Taiko
+        "0x77b064f418b27167bd8c6f263a16455e628b56cb" => "Taiko".to_string(),
+        "0xfc3756dc89ee98b049c1f2b0c8e69f0649e5c3e3" => "Taiko".to_string(),
+
+        // Abstract
+        "0x4b2d036d2c27192549ad5a2f2d9875e1843833de" => "Abstract".to_string(),
+
+        // World
+        "0xdbbe3d8c2d2b22a2611c5a94a9a12c2fcd49eb29" => "World".to_string(),
+
+        // Ink
+        "0x500d7ea63cf2e501dadaa5feec1fc19fe2aa72ac" => "Ink".to_string(),
+
+        // Blast
+        "0x98a986ee08bf67c9cfc4de2aaaff2d7f56c0bc47" => "Blast".to_string(),
+
+        // Zora
+        "0x625726c858dbf78c0125436c943bf4b4be9d9033" => "Zora".to_string(),
+
+        // Mode
+        "0x99199a22125034c808ff20f377d91187e8050f2e" => "Mode".to_string(),
+
+        // Mantle
+        "0xd1328c9167e0693b689b5aa5a024379d4e437858" => "Mantle".to_string(),
+
+        // Metal
+        "0xc94c243f8fb37223f3eb77f1e6d55e0f8f9caef4" => "Metal".to_string(),
+        "0xc94c243f8fb37223f3eb2f7961f7072602a51b8b" => "Metal".to_string(),
+
+        // Cyber
+        "0x3c11c3025ce387d76c2eddf1493ec55a8cc2a0f7" => "Cyber".to_string(),
+
+        // Kroma
+        "0x41b8cd6791de4d8f9e0eda9f185ce1898f0b5b3b" => "Kroma".to_string(),
+
+        // Redstone
+        "0xa8cd7f4c94eb0f15a5d8f5e9f9b4eb9b2e3eb60d" => "Redstone".to_string(),
+
+        // Fraxtal
+        "0x7f9d9c1bce1062e1077845ea39a0303429600a06" => "Fraxtal".to_string(),
+
+        // Mint
+        "0xd6c24e78cc77e48c87c246a2e0b7d21ffb7c1c0a" => "Mint".to_string(),
+
+        // Soneium
+        "0x6776be80dbada6a02b5f2095cf13734ac303b8d1" => "Soneium".to_string(),
+
+        // Lighter
+        "0xfbc0dcd6c3518cb529bc1b585db992a7d40005fa" => "Lighter".to_string(),
+
+        // UniChain
+        "0x2f60a5184c63ca94f82a27100643dbabe4f3f7fd" => "UniChain".to_string(),
+
+        // Katana
+        "0x1ffda89c755f6d4af069897d77ccabb580fd412a" => "Katana".to_string(),
+
+        // Codex
+        "0xb5bd290ef8ef3840cb866c7a8b7cc9e45fde3ab9" => "Codex".to_string(),
+
+        // Retired keys keep their latest chain when no block is given
+        _ => DATED_ADDRESSES
+            .iter()
+            .rev()
+            .find(|dated| dated.address == addr)
+            .map_or("Other", |dated| dated.chain)
+            .to_string(),
+    }
+}
+
+/// A registry address only attributed to its chain for a range of blocks,
+/// e.g. a batcher key rotated out, or a key reused by another chain later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatedAddress {
+    /// Lowercase with `0x` prefix, like the arms of [`identify_chain`].
+    pub address: &'static str,
+    pub chain: &'static str,
+    pub from_block: u64,
+    /// Inclusive, `None` if still in use.
+    pub to_block: Option<u64>,
+}
+
+impl DatedAddress {
+    pub fn contains(&self, block_number: u64) -> bool {
+        block_number >= self.from_block && self.to_block.is_none_or(|to| block_number <= to)
+    }
+}
+
+/// Registry addresses with validity ranges, taking precedence over
+/// [`identify_chain`] when attributing a transaction of a known block. Keys
+/// with an entry here should not also be listed there. Like the registry,
+/// changes need a [`REGISTRY_VERSION`] bump.
+pub const DATED_ADDRESSES: &[DatedAddress] = &[];
+
+/// Entries of [`DATED_ADDRESSES`] that can't match as intended: addresses
+/// that aren't lowercase `0x` hex, empty block ranges, and ranges of the same
+/// address overlapping.
+pub fn registry_problems() -> Vec<String> {
+    let mut problems = Vec::new();
+    for (i, dated) in DATED_ADDRESSES.iter().enumerate() {
+        let address = dated.address;
+        if address.parse::<Address>().is_err() || address != address.to_lowercase() {
+            problems.push(format!("{address} isn't a lowercase 0x-prefixed address"));
+        }
+        if dated.to_block.is_some_and(|to| to < dated.from_block) {
+            problems.push(format!("{address} has an empty block range"));
+        }
+        let overlapping = DATED_ADDRESSES[..i].iter().any(|earlier| {
+            earlier.address == address
+                && earlier.to_block.is_none_or(|to| to >= dated.from_block)
+                && dated.to_block.is_none_or(|to| to >= earlier.from_block)
+        });
+        if overlapping {
+            problems.push(format!("{address} has overlapping block ranges"));
+        }
+    }
+    problems
+}
+
+/// Identify the chain posting blobs from a sender address at a block, honoring
+/// [`DATED_ADDRESSES`]. Returns "Other" outside the ranges of a dated address.
+pub fn identify_chain_at(address: &str, block_number: u64) -> String {
+    let addr = address.to_lowercase();
+    let mut dated = DATED_ADDRESSES
+        .iter()
+        .filter(|dated| dated.address == addr)
+        .peekable();
+    if dated.peek().is_none() {
+        return identify_chain(&addr);
+    }
+    dated
+        .find(|dated| dated.contains(block_number))
+        .map_or("Other", |dated| dated.chain)
+        .to_string()
+}
+
+/// Where the entity a sender is attributed to comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelSource {
+    /// Set by an analyst through `PUT /admin/labels/{address}`.
+    Manual,
+    /// The chain registry above or the operator's `BLOB_ENTITY_MAP`.
+    Registry,
+    /// Inherited from an attributed address that funded the sender.
+    Heuristic,
+}
+
+impl LabelSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Registry => "registry",
+            Self::Heuristic => "heuristic",
+        }
+    }
+}
+
+/// Chain a blob transaction is attributed to: the entity recorded for it at
+/// indexing time, falling back to the registry for rows indexed before that.
+pub fn chain_of(sender: &str, attributed_entity: Option<&str>) -> String {
+    match attributed_entity {
+        Some(entity) => entity.to_string(),
+        None => identify_chain(sender),
+    }
+}
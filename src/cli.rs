@@ -0,0 +1,164 @@
+use blob_exex::{config, db::SCHEMA_VERSION, lease, snapshot, Database};
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const USAGE: &str = "usage: blob-cli <command>
+
+commands:
+  reprocess <from> <to>   queue blocks <from>..=<to> to be re-fetched from the node
+                          and re-indexed by the running ExEx
+  reprocess-status        list recent re-process requests
+  relabel <from> <to>     queue the blob transactions of blocks <from>..=<to> to
+                          be re-attributed by the running ExEx, after the chain
+                          registry or labels changed
+  relabel-status          list recent relabel jobs
+  reaggregate --from <from> --to <to>
+                          rebuild the hourly rollups and sender stats covering
+                          blocks <from>..=<to> from the indexed rows, e.g. after
+                          a reorg repair or an aggregation fix
+  snapshot <path>         write a consistent copy of the database to <path>,
+                          safe while the ExEx is writing
+  snapshot <dir> --every <interval> --keep <n>
+                          snapshot into <dir> every <interval> (e.g. 6h),
+                          keeping the newest <n> snapshots
+  restore <path>          replace the database with the snapshot at <path>,
+                          refused while an ExEx holds the writer lease
+  archive <dir> --older-than <days> [--vacuum]
+                          move blob transactions older than <days> into monthly
+                          archive databases in <dir>, then optionally shrink
+                          the database file
+  archive-status          list the monthly archives
+  archived-tx <tx_hash>   look up a blob transaction in the archives
+  raw-tx <tx_hash>        print the stored EIP-2718 envelope of a blob
+                          transaction as hex, see BLOB_STORE_RAW_TXS
+  verify-schema           migrate the database and check its tables, columns
+                          and indexes against the ones this build expects";
+
+fn main() -> eyre::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let db_path = config::db_path()?;
+    let db = match Database::new(&db_path) {
+        // The schema problems are the report
+        Err(err) if args[..] == ["verify-schema"] => {
+            eprintln!("{db_path}: {err}");
+            std::process::exit(1);
+        }
+        db => db?,
+    };
+
+    match args[..] {
+        ["reprocess", from, to] => {
+            let id = db.request_reprocess(from.parse()?, to.parse()?)?;
+            println!("Queued re-process request {id} for blocks {from}..={to}");
+        }
+        ["reprocess-status"] => {
+            for request in db.get_reprocess_requests(20)? {
+                let status = match request.completed_at {
+                    Some(_) => "completed".to_string(),
+                    None => format!("next block {}", request.next_block),
+                };
+                println!(
+                    "#{} blocks {}..={}: {}",
+                    request.id, request.from_block, request.to_block, status
+                );
+            }
+        }
+        ["relabel", from, to] => {
+            let id = db.request_relabel(from.parse()?, to.parse()?)?;
+            println!("Queued relabel job {id} for blocks {from}..={to}");
+        }
+        ["relabel-status"] => {
+            for job in db.get_relabel_jobs(20)? {
+                let status = match job.completed_at {
+                    Some(_) => format!("completed, {} relabeled", job.relabeled),
+                    None => format!("next block {}, {} relabeled", job.next_block, job.relabeled),
+                };
+                let version = job
+                    .registry_version
+                    .map_or_else(|| "-".to_string(), |version| version.to_string());
+                println!(
+                    "#{} blocks {}..={} (registry v{version}): {status}",
+                    job.id, job.from_block, job.to_block
+                );
+            }
+        }
+        ["reaggregate", "--from", from, "--to", to] => {
+            let rebuilt = db.reaggregate(from.parse()?, to.parse()?)?;
+            println!(
+                "Rebuilt {} hourly rollups and {} senders for blocks {from}..={to}",
+                rebuilt.hours, rebuilt.senders
+            );
+        }
+        ["snapshot", path] => {
+            db.snapshot(Path::new(path))?;
+            println!("Wrote snapshot {path}");
+        }
+        ["snapshot", dir, "--every", interval, "--keep", keep] => {
+            let interval = config::parse_duration(interval)
+                .ok_or_else(|| eyre::eyre!("invalid interval: {interval}"))?;
+            let keep: usize = keep.parse()?;
+            eyre::ensure!(keep > 0, "--keep must be at least 1");
+            snapshot::run_schedule(&db, Path::new(dir), Duration::from_secs(interval), keep)?;
+        }
+        ["restore", path] => {
+            if let Some(holder) = lease::active_writer(&db)? {
+                eyre::bail!("database is being written by {holder}, stop it before restoring");
+            }
+            db.restore(Path::new(path))?;
+            println!("Restored database from {path}");
+        }
+        ["archive", dir, "--older-than", days]
+        | ["archive", dir, "--older-than", days, "--vacuum"] => {
+            let vacuum = args.len() == 5;
+            let days: u64 = days.parse()?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            for archive in db.archive(now.saturating_sub(days * 86400), Path::new(dir))? {
+                println!(
+                    "{}: {} txs, {} blobs in {}",
+                    archive.month, archive.tx_count, archive.blob_count, archive.path
+                );
+            }
+            if vacuum {
+                db.vacuum()?;
+                println!("Vacuumed {db_path}");
+            }
+        }
+        ["archive-status"] => {
+            for archive in db.get_archives()? {
+                println!(
+                    "{}: {} txs, {} blobs in {}",
+                    archive.month, archive.tx_count, archive.blob_count, archive.path
+                );
+            }
+        }
+        ["archived-tx", tx_hash] => match db.get_archived_blob_transaction(tx_hash)? {
+            Some(tx) => println!(
+                "{} block {} from {}: {} blobs {}",
+                tx.tx_hash,
+                tx.block_number,
+                tx.sender,
+                tx.blob_count,
+                tx.blob_hashes.join(",")
+            ),
+            None => eyre::bail!("{tx_hash} is not archived"),
+        },
+        ["raw-tx", tx_hash] => match db.get_raw_transaction(tx_hash)? {
+            Some(raw) => println!("{}", alloy_primitives::hex::encode_prefixed(raw)),
+            None => eyre::bail!("no envelope stored for {tx_hash}"),
+        },
+        ["verify-schema"] => {
+            // Opening the database already refused any problem
+            println!("{db_path}: schema version {SCHEMA_VERSION}, no problems found");
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,507 @@
+//! Settings read from the environment.
+//!
+//! Everything is validated up front so a misconfigured deployment fails at
+//! startup with an error naming the offending variable, instead of once the
+//! node is already syncing.
+
+use crate::{
+    api::Limits,
+    db::{DEFAULT_READERS, SECONDS_PER_SLOT},
+};
+use alloy_primitives::Address;
+use axum::http::HeaderValue;
+use eyre::WrapErr;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+/// Services a `blob-exex` process runs, from `--role` or `BLOB_ROLE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    /// Only the reth node with the indexing ExEx.
+    #[default]
+    Exex,
+    /// Only the web server, reading a database written by another process.
+    Web,
+    /// The node and the web server in one process.
+    All,
+}
+
+impl FromStr for Role {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "exex" => Ok(Self::Exex),
+            "web" => Ok(Self::Web),
+            "all" => Ok(Self::All),
+            _ => eyre::bail!("invalid role: {value}, expected exex, web or all"),
+        }
+    }
+}
+
+impl Role {
+    /// Take `--role <role>` or `--role=<role>` out of `args`, falling back to
+    /// `BLOB_ROLE` and then [`Role::Exex`]. The remaining args are returned so
+    /// they can be handed to the reth CLI.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> eyre::Result<(Self, Vec<String>)> {
+        let mut role = None;
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--role" {
+                let value = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("--role requires a value"))?;
+                role = Some(value.parse()?);
+            } else if let Some(value) = arg.strip_prefix("--role=") {
+                role = Some(value.parse()?);
+            } else {
+                rest.push(arg);
+            }
+        }
+
+        let role = match role {
+            Some(role) => role,
+            None => match std::env::var("BLOB_ROLE") {
+                Ok(value) => value.parse().wrap_err("invalid BLOB_ROLE")?,
+                Err(_) => Self::default(),
+            },
+        };
+        Ok((role, rest))
+    }
+
+    pub fn runs_exex(self) -> bool {
+        matches!(self, Self::Exex | Self::All)
+    }
+
+    pub fn runs_web(self) -> bool {
+        matches!(self, Self::Web | Self::All)
+    }
+}
+
+/// How much of the web server is exposed, from `BLOB_PROFILE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Every route, permissive CORS and the configured limits.
+    #[default]
+    Internal,
+    /// Safe to expose publicly: read-only routes only, CORS restricted to
+    /// `BLOB_CORS_ORIGINS`, limits capped at [`Limits::PUBLIC`] and responses
+    /// cacheable by browsers and CDNs.
+    Public,
+}
+
+impl FromStr for Profile {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "internal" => Ok(Self::Internal),
+            "public" => Ok(Self::Public),
+            _ => eyre::bail!("invalid profile: {value}, expected internal or public"),
+        }
+    }
+}
+
+/// How much the indexer logs about the blocks it indexes, from
+/// `BLOB_LOG_VERBOSITY`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// `summary:<every>`: one line per `every` blocks, totalling them.
+    Summary { every: u64 },
+    /// `block`: one line per block.
+    #[default]
+    Block,
+    /// `tx`: one line per block plus one per blob tx, for debugging.
+    Tx,
+}
+
+impl FromStr for Verbosity {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "block" => Ok(Self::Block),
+            "tx" => Ok(Self::Tx),
+            _ => match value
+                .strip_prefix("summary:")
+                .and_then(|every| every.parse().ok())
+            {
+                Some(every) if every > 0 => Ok(Self::Summary { every }),
+                _ => eyre::bail!(
+                    "invalid verbosity: {value}, expected summary:<blocks>, block or tx"
+                ),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Summary { every } => write!(f, "summary:{every}"),
+            Self::Block => f.write_str("block"),
+            Self::Tx => f.write_str("tx"),
+        }
+    }
+}
+
+/// Indexer log verbosity from `BLOB_LOG_VERBOSITY`, [`Verbosity::Block`] if
+/// unset.
+pub fn log_verbosity() -> eyre::Result<Verbosity> {
+    match std::env::var("BLOB_LOG_VERBOSITY") {
+        Ok(value) => value
+            .parse()
+            .wrap_err_with(|| format!("invalid BLOB_LOG_VERBOSITY={value}")),
+        Err(_) => Ok(Verbosity::default()),
+    }
+}
+
+/// Whether the ExEx runs as a standby writer from `BLOB_STANDBY=true`: it
+/// follows its node without indexing until the active writer's lease expires,
+/// then takes over, see [`crate::lease::wait`]. It must run on the same host
+/// as the active writer, with the same database file.
+pub fn standby() -> bool {
+    std::env::var("BLOB_STANDBY").is_ok_and(|value| value == "true")
+}
+
+/// Threshold of the slow query log from `BLOB_SLOW_QUERY_MS`, off if unset,
+/// see [`crate::Database::log_slow_queries`].
+pub fn slow_query_threshold() -> eyre::Result<Option<Duration>> {
+    match std::env::var("BLOB_SLOW_QUERY_MS") {
+        Ok(value) => {
+            let millis: u64 = value
+                .parse()
+                .wrap_err_with(|| format!("invalid BLOB_SLOW_QUERY_MS={value}"))?;
+            Ok(Some(Duration::from_millis(millis)))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Read-only connections each process opens for queries, from
+/// `BLOB_DB_READERS`, [`DEFAULT_READERS`] by default. 0 runs queries on the
+/// write connection, see [`crate::Database::with_readers`].
+pub fn db_readers() -> eyre::Result<usize> {
+    match std::env::var("BLOB_DB_READERS") {
+        Ok(value) => value
+            .parse()
+            .wrap_err_with(|| format!("invalid BLOB_DB_READERS={value}")),
+        Err(_) => Ok(DEFAULT_READERS),
+    }
+}
+
+/// How long the writer keeps the `ingest_log` table's entries, from
+/// `BLOB_INGEST_LOG_RETENTION_DAYS`, 30 days by default. 0 keeps them forever.
+pub fn ingest_log_retention() -> eyre::Result<Option<Duration>> {
+    let days: u64 = match std::env::var("BLOB_INGEST_LOG_RETENTION_DAYS") {
+        Ok(value) => value
+            .parse()
+            .wrap_err_with(|| format!("invalid BLOB_INGEST_LOG_RETENTION_DAYS={value}"))?,
+        Err(_) => 30,
+    };
+    Ok((days > 0).then(|| Duration::from_secs(days * 86400)))
+}
+
+/// Drifted senders above which the writer rebuilds sender stats, from
+/// `BLOB_SENDER_REPAIR_THRESHOLD`. Off if unset, drift is then only reported,
+/// see [`crate::maintenance::check_senders`].
+pub fn sender_repair_threshold() -> eyre::Result<Option<u64>> {
+    match std::env::var("BLOB_SENDER_REPAIR_THRESHOLD") {
+        Ok(value) => {
+            let senders = value
+                .parse()
+                .wrap_err_with(|| format!("invalid BLOB_SENDER_REPAIR_THRESHOLD={value}"))?;
+            Ok(Some(senders))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Path of the SQLite database from `BLOB_DB_PATH`, defaulting to
+/// `blob_stats.db`. Fails if the directory it would live in doesn't exist.
+pub fn db_path() -> eyre::Result<String> {
+    let path = std::env::var("BLOB_DB_PATH").unwrap_or_else(|_| "blob_stats.db".to_string());
+    if path != ":memory:" {
+        let dir = std::path::Path::new(&path)
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty());
+        if let Some(dir) = dir {
+            eyre::ensure!(
+                dir.is_dir(),
+                "BLOB_DB_PATH={path}: directory {} does not exist",
+                dir.display()
+            );
+        }
+    }
+    Ok(path)
+}
+
+/// Operator-assigned entity of batcher and treasury addresses, from the JSON
+/// object `{"<address>": "<entity>"}` in the file at `BLOB_ENTITY_MAP`. Empty if
+/// unset.
+///
+/// An entity can be limited to a range of blocks, for rotated keys:
+/// `{"<address>": {"entity": "<entity>", "from_block": 1, "to_block": 2}}`,
+/// either bound optional and both inclusive.
+pub fn entity_addresses() -> eyre::Result<HashMap<Address, EntityAddress>> {
+    let Ok(path) = std::env::var("BLOB_ENTITY_MAP") else {
+        return Ok(HashMap::new());
+    };
+    let contents = std::fs::read_to_string(&path)
+        .wrap_err_with(|| format!("failed to read BLOB_ENTITY_MAP={path}"))?;
+    let entities: HashMap<String, EntityAddress> =
+        serde_json::from_str(&contents).wrap_err_with(|| {
+            format!("invalid BLOB_ENTITY_MAP={path}, expected {{\"<address>\": \"<entity>\"}}")
+        })?;
+    entities
+        .into_iter()
+        .map(|(address, entity)| {
+            let parsed = address
+                .parse()
+                .wrap_err_with(|| format!("invalid address {address} in BLOB_ENTITY_MAP={path}"))?;
+            if let (Some(from), Some(to)) = (entity.from_block, entity.to_block) {
+                eyre::ensure!(
+                    from <= to,
+                    "invalid block range {from}..={to} of {address} in BLOB_ENTITY_MAP={path}"
+                );
+            }
+            Ok((parsed, entity))
+        })
+        .collect()
+}
+
+/// Entity of an address in `BLOB_ENTITY_MAP`, see [`entity_addresses`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityAddress {
+    pub entity: String,
+    pub from_block: Option<u64>,
+    /// Inclusive.
+    pub to_block: Option<u64>,
+}
+
+impl<'de> Deserialize<'de> for EntityAddress {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            Entity(String),
+            Dated {
+                entity: String,
+                from_block: Option<u64>,
+                to_block: Option<u64>,
+            },
+        }
+        Ok(match Entry::deserialize(deserializer)? {
+            Entry::Entity(entity) => Self {
+                entity,
+                from_block: None,
+                to_block: None,
+            },
+            Entry::Dated {
+                entity,
+                from_block,
+                to_block,
+            } => Self {
+                entity,
+                from_block,
+                to_block,
+            },
+        })
+    }
+}
+
+/// Database file sizes above which the writer logs warnings.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeWarnings {
+    /// `BLOB_DB_WARN_MB`, unset by default.
+    pub db_bytes: Option<u64>,
+    /// `BLOB_WAL_WARN_MB`, 1024 by default. A WAL this large usually means
+    /// long-lived readers keep checkpoints from completing.
+    pub wal_bytes: Option<u64>,
+}
+
+impl SizeWarnings {
+    /// Read the thresholds, in MiB. 0 disables a warning.
+    pub fn from_env() -> eyre::Result<Self> {
+        let var = |name: &str, default: Option<u64>| -> eyre::Result<Option<u64>> {
+            let mib = match std::env::var(name) {
+                Ok(value) => Some(
+                    value
+                        .parse::<u64>()
+                        .wrap_err_with(|| format!("invalid {name}={value}, expected MiB"))?,
+                ),
+                Err(_) => default,
+            };
+            Ok(mib
+                .filter(|mib| *mib > 0)
+                .map(|mib| mib.saturating_mul(1 << 20)))
+        };
+        Ok(Self {
+            db_bytes: var("BLOB_DB_WARN_MB", None)?,
+            wal_bytes: var("BLOB_WAL_WARN_MB", Some(1024))?,
+        })
+    }
+}
+
+/// Settings of the web server.
+#[derive(Debug, Clone)]
+pub struct WebConfig {
+    /// `BLOB_WEB_ADDR`, `0.0.0.0:3500` by default.
+    pub addr: SocketAddr,
+    /// `BLOB_STATIC_DIR`, the built dashboard assets, `web/dist` by default.
+    pub static_dir: PathBuf,
+    /// `BLOB_ADMIN_TOKEN`. Admin routes are only served when it is set.
+    pub admin_token: Option<String>,
+    /// `BLOB_QUERY_TOKEN`. `/api/query` is only served when it is set.
+    pub query_token: Option<String>,
+    pub limits: Limits,
+    /// `BLOB_PROFILE`, `internal` by default.
+    pub profile: Profile,
+    /// `BLOB_CORS_ORIGINS`, comma-separated origins allowed cross-origin
+    /// requests in the public profile. None are by default.
+    pub cors_origins: Vec<HeaderValue>,
+    /// `BLOB_CACHE_MAX_AGE`, seconds responses may be cached for in the public
+    /// profile, one slot by default.
+    pub cache_max_age: u64,
+    /// `BLOB_TLS_CERT` and `BLOB_TLS_KEY`. HTTPS is served when both are set.
+    pub tls: Option<TlsConfig>,
+    /// `BLOB_TRUSTED_PROXIES`, comma-separated addresses of reverse proxies
+    /// whose `X-Forwarded-For` and `X-Forwarded-Proto` headers are believed.
+    /// None are by default.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// PEM files of the web server's certificate and private key.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// The certificate chain, leaf first.
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl WebConfig {
+    pub fn from_env() -> eyre::Result<Self> {
+        let addr = std::env::var("BLOB_WEB_ADDR").unwrap_or_else(|_| "0.0.0.0:3500".to_string());
+        let addr = addr
+            .parse()
+            .wrap_err_with(|| format!("invalid BLOB_WEB_ADDR={addr}, expected <ip>:<port>"))?;
+
+        let static_dir = PathBuf::from(
+            std::env::var("BLOB_STATIC_DIR").unwrap_or_else(|_| "web/dist".to_string()),
+        );
+        eyre::ensure!(
+            static_dir.is_dir(),
+            "BLOB_STATIC_DIR={}: directory does not exist, build the dashboard with `npm run build` in web/",
+            static_dir.display()
+        );
+
+        let admin_token = std::env::var("BLOB_ADMIN_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+        let query_token = std::env::var("BLOB_QUERY_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+
+        let profile = match std::env::var("BLOB_PROFILE") {
+            Ok(value) => value.parse().wrap_err("invalid BLOB_PROFILE")?,
+            Err(_) => Profile::default(),
+        };
+        let mut limits = Limits::from_env()?;
+        if profile == Profile::Public {
+            // Refuse rather than ignore it, so nobody expects admin routes that aren't there
+            eyre::ensure!(
+                admin_token.is_none(),
+                "BLOB_ADMIN_TOKEN must not be set with BLOB_PROFILE=public"
+            );
+            eyre::ensure!(
+                query_token.is_none(),
+                "BLOB_QUERY_TOKEN must not be set with BLOB_PROFILE=public"
+            );
+            limits = limits.capped(Limits::PUBLIC);
+        }
+
+        let cors_origins = std::env::var("BLOB_CORS_ORIGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| {
+                eyre::ensure!(
+                    origin.starts_with("http://") || origin.starts_with("https://"),
+                    "invalid origin {origin} in BLOB_CORS_ORIGINS, expected http(s)://<host>"
+                );
+                HeaderValue::from_str(origin)
+                    .wrap_err_with(|| format!("invalid origin {origin} in BLOB_CORS_ORIGINS"))
+            })
+            .collect::<eyre::Result<_>>()?;
+
+        let cache_max_age = match std::env::var("BLOB_CACHE_MAX_AGE") {
+            Ok(value) => value
+                .parse()
+                .wrap_err_with(|| format!("invalid BLOB_CACHE_MAX_AGE={value}"))?,
+            Err(_) => SECONDS_PER_SLOT,
+        };
+
+        let tls = match (
+            std::env::var("BLOB_TLS_CERT"),
+            std::env::var("BLOB_TLS_KEY"),
+        ) {
+            (Ok(cert), Ok(key)) => Some(TlsConfig {
+                cert: cert.into(),
+                key: key.into(),
+            }),
+            (Err(_), Err(_)) => None,
+            _ => eyre::bail!("BLOB_TLS_CERT and BLOB_TLS_KEY must be set together"),
+        };
+
+        let trusted_proxies = std::env::var("BLOB_TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| {
+                proxy.parse().wrap_err_with(|| {
+                    format!("invalid address {proxy} in BLOB_TRUSTED_PROXIES, expected an IP")
+                })
+            })
+            .collect::<eyre::Result<_>>()?;
+
+        Ok(Self {
+            addr,
+            static_dir,
+            admin_token,
+            query_token,
+            limits,
+            profile,
+            cors_origins,
+            cache_max_age,
+            tls,
+            trusted_proxies,
+        })
+    }
+}
+
+/// Parse a duration like "90m", "24h", "7d" or "4w" into seconds.
+pub fn parse_duration(value: &str) -> Option<u64> {
+    let unit = value.chars().last()?;
+    let amount = &value[..value.len() - unit.len_utf8()];
+    let unit_secs = match unit {
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        _ => return None,
+    };
+    amount
+        .parse::<u64>()
+        .ok()
+        .filter(|amount| *amount > 0)?
+        .checked_mul(unit_secs)
+}
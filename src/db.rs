@@ -1,21 +1,126 @@
+//! SQLite storage of indexed blocks and blob transactions, and of everything
+//! derived from them.
+//!
+//! Every schema change, i.e. a table, column or index created, altered or
+//! dropped by [`Database::new`], must bump [`SCHEMA_VERSION`] so older builds
+//! refuse the migrated database instead of failing on its writes. A test in
+//! `tests/db.rs` pins the schema to the version and fails until both are
+//! updated.
+
+use crate::{
+    chains::{chain_of, identify_chain_at, LabelSource, DATED_ADDRESSES, REGISTRY_VERSION},
+    config::EntityAddress,
+    regime::RegimeThresholds,
+    schedule::{BlobSchedule, BlobScheduleEntry},
+};
+use alloy_eips::eip4844::DATA_GAS_PER_BLOB;
 use alloy_primitives::Address;
-use rusqlite::Connection;
+use rusqlite::{
+    backup::{Backup, StepResult},
+    ffi,
+    functions::FunctionFlags,
+    hooks::{AuthAction, AuthContext, Authorization},
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value, ValueRef},
+    Connection, ErrorCode, OpenFlags, OptionalExtension, ToSql,
+};
 use std::{
-    fmt::{Debug, Formatter},
-    sync::{Arc, Mutex, MutexGuard},
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ffi::{c_int, c_uint, c_void, CStr},
+    fmt::{Debug, Display, Formatter},
+    ops::{Deref, DerefMut, RangeInclusive},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
+    },
+    time::{Duration, Instant},
 };
 
-/// Thread-safe database wrapper using Arc<Mutex<Connection>>.
+/// Each blob is 128KB (131072 bytes) per EIP-4844. Used as the size of a blob
+/// whenever its real payload length is unknown.
+pub const BLOB_SIZE_BYTES: u64 = 131072;
+
+/// Beacon chain slot length. Every slot can hold at most one execution block.
+pub const SECONDS_PER_SLOT: u64 = 12;
+
+/// Lengths of the buckets time series are grouped by, see [`group_by_hour`]
+/// and [`group_by_day`].
+pub const SECONDS_PER_HOUR: u64 = 3600;
+pub const SECONDS_PER_DAY: u64 = 86400;
+
+/// Page cache of a bulk ingest, in KiB.
+const BULK_CACHE_KIB: u64 = 1024 * 1024;
+
+/// Indexes the insert path reads through, kept during a bulk ingest.
+const BULK_KEPT_INDEXES: [&str; 1] = ["idx_funding_transfers_recipient"];
+
+/// Tables ad-hoc queries may not read (see [`Database::query_read_only`]), as
+/// they hold other users' alert keys and rules and the operators' actions.
+pub const PRIVATE_TABLES: [&str; 4] = [
+    "alert_keys",
+    "alert_rules",
+    "alert_events",
+    "admin_audit_log",
+];
+
+/// Longest bound value kept in a slow query log line, in characters. Long
+/// enough for a tx hash.
+const MAX_LOGGED_VALUE_LEN: usize = 66;
+
+/// Statements running longer are logged, see [`Database::log_slow_queries`].
+/// Process-wide, so the profiling callback needs no state.
+static SLOW_QUERY_NANOS: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Longest manual sender label, in bytes.
+pub const MAX_LABEL_LEN: usize = 64;
+
+/// Longest annotation kind and title, and description, in bytes.
+pub const MAX_ANNOTATION_KIND_LEN: usize = 32;
+pub const MAX_ANNOTATION_TITLE_LEN: usize = 200;
+pub const MAX_ANNOTATION_DESCRIPTION_LEN: usize = 2000;
+
+/// Version of the schema [`Database::new`] creates and migrates to, stored as
+/// the `user_version` pragma. Bump it with every schema change so that older
+/// builds refuse databases they'd misread.
+pub const SCHEMA_VERSION: u32 = 28;
+
+/// Read-only connections [`Database::new`] opens for queries.
+pub const DEFAULT_READERS: usize = 4;
+
+thread_local! {
+    /// Set on the thread running [`Database::transaction`], which holds the
+    /// transaction lock already, and whose reads have to see the writes it
+    /// hasn't committed yet.
+    static IN_TRANSACTION: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Thread-safe database wrapper with one serialized write connection and a
+/// pool of read-only connections.
 ///
 /// This pattern allows the database to be safely shared between:
 /// - Multiple async tasks in the web server
 /// - The ExEx notification handler
 ///
-/// Since we use separate binaries, each process gets its own Database instance,
-/// but SQLite WAL mode allows concurrent reads across processes.
+/// Writes go through a single mutex, while queries run on the readers (see
+/// [`Database::reader`]), so the web server doesn't wait on the ExEx when they
+/// share a process. SQLite WAL mode lets readers run next to the writer,
+/// within and across processes.
 #[derive(Clone)]
 pub struct Database {
     connection: Arc<Mutex<Connection>>,
+    /// Held for the whole of a [`Database::transaction`], and by every other
+    /// thread using the write connection meanwhile, so a web server sharing the
+    /// ExEx's process can't write into its open transaction.
+    transaction_lock: Arc<Mutex<()>>,
+    readers: Arc<[Mutex<Connection>]>,
+    /// Reader the next query tries first, so queries spread over the pool.
+    next_reader: Arc<AtomicUsize>,
+    /// Set between [`Database::begin_bulk_ingest`] and [`Database::end_bulk_ingest`].
+    bulk: Arc<AtomicBool>,
+    /// Writer lease holder every transaction checks for, see
+    /// [`Database::fence_writes`].
+    fence: Arc<OnceLock<String>>,
 }
 
 impl Debug for Database {
@@ -24,27 +129,360 @@ impl Debug for Database {
     }
 }
 
+/// A locked connection, see [`Database::connection`].
+struct ConnectionGuard<'a> {
+    connection: MutexGuard<'a, Connection>,
+    // Released after the connection
+    _transaction: Option<MutexGuard<'a, ()>>,
+}
+
+impl Deref for ConnectionGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl DerefMut for ConnectionGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.connection
+    }
+}
+
+/// A [`Database::transaction`] in progress on this thread, rolled back when
+/// dropped before [`Transaction::commit`], e.g. by a panic.
+struct Transaction<'a> {
+    db: &'a Database,
+    /// Inside another transaction on this thread, as a savepoint of it.
+    nested: bool,
+    committed: bool,
+    // Released after the rollback
+    _lock: Option<MutexGuard<'a, ()>>,
+}
+
+impl<'a> Transaction<'a> {
+    fn begin(db: &'a Database) -> Result<Self> {
+        let nested = IN_TRANSACTION.get();
+        // Not poisoned by a panicking `f`, which holds no connection lock
+        let lock = (!nested).then(|| {
+            db.transaction_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+        });
+        IN_TRANSACTION.set(true);
+        let transaction = Self {
+            db,
+            nested,
+            committed: false,
+            _lock: lock,
+        };
+        if nested {
+            db.connection().execute_batch("SAVEPOINT nested")?;
+        } else {
+            // Takes the write lock up front, so the lease can't change hands
+            // between the check and the writes
+            db.connection().execute_batch("BEGIN IMMEDIATE")?;
+            db.check_fence()?;
+        }
+        Ok(transaction)
+    }
+
+    fn commit(mut self) -> Result<()> {
+        if self.nested {
+            self.db.connection().execute_batch("RELEASE nested")?;
+        } else {
+            let _commit = tracing::info_span!("db.commit").entered();
+            self.db.connection().execute_batch("COMMIT")?;
+        }
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            // Locked directly, as a panic in `f` may have poisoned it
+            let conn = self
+                .db
+                .connection
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let rollback = if self.nested {
+                "ROLLBACK TO nested; RELEASE nested"
+            } else {
+                "ROLLBACK"
+            };
+            if !conn.is_autocommit() {
+                if let Err(err) = conn.execute_batch(rollback) {
+                    tracing::error!(%err, "Failed to roll back transaction");
+                }
+            }
+        }
+        if !self.nested {
+            IN_TRANSACTION.set(false);
+        }
+    }
+}
+
+/// Errors returned by [`Database`], classified so callers can react per class
+/// (retry on [`DbError::Busy`], map [`DbError::NotFound`] to a 404, ...)
+/// instead of matching on messages.
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    /// The database is locked by another connection or writer. Retrying may succeed.
+    #[error("database is busy: {0}")]
+    Busy(String),
+    /// The database file is damaged or isn't a SQLite database.
+    #[error("database is corrupt: {0}")]
+    Corrupt(String),
+    /// A row the caller asked for doesn't exist.
+    #[error("not found: {0}")]
+    NotFound(String),
+    /// A query doesn't match the stored schema or data, e.g. after a failed
+    /// migration or a constraint violation.
+    #[error("schema error: {0}")]
+    Schema(String),
+    /// The database file couldn't be opened, read or written.
+    #[error("database I/O error: {0}")]
+    Io(String),
+    /// The caller passed arguments the database can't act on.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+}
+
+impl DbError {
+    /// Whether retrying the same operation may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Busy(_) | Self::Io(_))
+    }
+}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(err: rusqlite::Error) -> Self {
+        let message = err.to_string();
+        match &err {
+            rusqlite::Error::QueryReturnedNoRows => Self::NotFound(message),
+            rusqlite::Error::SqliteFailure(failure, _) => match failure.code {
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => Self::Busy(message),
+                ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => Self::Corrupt(message),
+                ErrorCode::SystemIoFailure
+                | ErrorCode::CannotOpen
+                | ErrorCode::DiskFull
+                | ErrorCode::ReadOnly
+                | ErrorCode::PermissionDenied
+                | ErrorCode::FileLockingProtocolFailed
+                | ErrorCode::OutOfMemory => Self::Io(message),
+                ErrorCode::NotFound => Self::NotFound(message),
+                _ => Self::Schema(message),
+            },
+            _ => Self::Schema(message),
+        }
+    }
+}
+
+type Result<T, E = DbError> = std::result::Result<T, E>;
+
 impl Database {
     /// Create new database with the provided path.
-    pub fn new(path: &str) -> eyre::Result<Self> {
+    ///
+    /// Existing databases are migrated, then their schema is verified (see
+    /// [`Database::schema_problems`]) so a database this build can't work with
+    /// is refused up front rather than failing on some later query.
+    pub fn new(path: &str) -> Result<Self> {
+        Self::with_readers(path, DEFAULT_READERS)
+    }
+
+    /// Create a database like [`Database::new`], with `readers` read-only
+    /// connections for queries. With none, or for an in-memory database,
+    /// queries share the write connection.
+    pub fn with_readers(path: &str, readers: usize) -> Result<Self> {
+        let mut database = Self::open(path)?;
+        database.prepare_schema()?;
+        if !path.is_empty() && path != ":memory:" {
+            database.readers = (0..readers)
+                .map(|_| open_reader(path).map(Mutex::new))
+                .collect::<Result<Vec<_>>>()?
+                .into();
+        }
+        Ok(database)
+    }
+
+    fn open(path: &str) -> Result<Self> {
         let connection = Connection::open(path)?;
         connection.pragma_update(None, "journal_mode", "WAL")?;
-        let database = Self {
+        register_functions(&connection)?;
+        Ok(Self {
             connection: Arc::new(Mutex::new(connection)),
-        };
-        database.create_tables()?;
-        Ok(database)
+            transaction_lock: Arc::new(Mutex::new(())),
+            readers: Arc::new([]),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+            bulk: Arc::new(AtomicBool::new(false)),
+            fence: Arc::new(OnceLock::new()),
+        })
+    }
+
+    /// Log statements running longer than `threshold` as warnings, with their
+    /// bound values (long ones cut short), and count them in
+    /// `blob_db_slow_queries_total`. The threshold is shared by every
+    /// database logging slow queries in the process.
+    pub fn log_slow_queries(&self, threshold: Duration) -> Result<()> {
+        let nanos = u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX);
+        SLOW_QUERY_NANOS.store(nanos, Ordering::Relaxed);
+        // rusqlite only wraps the legacy profiler, which gets the SQL before
+        // values are bound
+        for connection in std::iter::once(&*self.connection).chain(self.readers.iter()) {
+            let conn = connection.lock().expect("failed to acquire database lock");
+            // SAFETY: the handle stays valid while the lock is held, and the
+            // callback only reads what SQLite passes for profile events
+            let code = unsafe {
+                ffi::sqlite3_trace_v2(
+                    conn.handle(),
+                    ffi::SQLITE_TRACE_PROFILE as c_uint,
+                    Some(profile_statement),
+                    std::ptr::null_mut(),
+                )
+            };
+            if code != ffi::SQLITE_OK {
+                return Err(DbError::from(rusqlite::Error::SqliteFailure(
+                    ffi::Error::new(code),
+                    Some("failed to install the slow query profiler".to_string()),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Create and migrate the schema, refusing it if it doesn't end up as expected.
+    fn prepare_schema(&self) -> Result<()> {
+        check_schema_version(&self.connection())?;
+        // A mismatching schema usually breaks the migration first, with an
+        // error naming just one statement, so list everything that's off
+        let migrated = self.create_tables().and_then(|()| self.migrate());
+        let problems = self.schema_problems()?;
+        match migrated {
+            Err(err) if !problems.is_empty() => {
+                return Err(DbError::Schema(format!(
+                    "migration failed ({err}), database schema doesn't match this build:\n  {}",
+                    problems.join("\n  ")
+                )));
+            }
+            Err(err) => return Err(err),
+            Ok(()) if !problems.is_empty() => {
+                return Err(DbError::Schema(format!(
+                    "database schema doesn't match this build:\n  {}",
+                    problems.join("\n  ")
+                )));
+            }
+            Ok(()) => {}
+        }
+        self.connection()
+            .pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(())
+    }
+
+    /// Differences between this database's schema and the one a fresh database
+    /// gets: missing tables, columns and indexes, and columns or indexes defined
+    /// differently. Extra tables, columns and indexes are fine.
+    pub fn schema_problems(&self) -> Result<Vec<String>> {
+        let reference = Self::open(":memory:")?;
+        reference.create_tables()?;
+        reference.migrate()?;
+        let expected = read_schema(&reference.connection())?;
+        let actual = read_schema(&self.connection())?;
+
+        let mut problems = Vec::new();
+        for (table, columns) in &expected.tables {
+            let Some(actual_columns) = actual.tables.get(table) else {
+                problems.push(format!("missing table {table}"));
+                continue;
+            };
+            for column in columns {
+                match actual_columns.iter().find(|c| c.name == column.name) {
+                    None => problems.push(format!("missing column {table}.{}", column.name)),
+                    Some(actual) if actual != column => problems.push(format!(
+                        "column {table}.{} is {}, expected {}",
+                        column.name, actual, column
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+        for (name, index) in &expected.indexes {
+            match actual.indexes.get(name) {
+                None => problems.push(format!("missing index {name}")),
+                Some(actual) if actual != index => {
+                    problems.push(format!("index {name} is on {}, expected {}", actual, index))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Write a row to a scratch table, read it back and drop the table again,
+    /// to check the database takes writes end to end.
+    pub fn round_trip(&self) -> Result<()> {
+        let written = unix_timestamp()?;
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        tx.execute("CREATE TABLE self_test (value INTEGER NOT NULL)", ())?;
+        tx.execute("INSERT INTO self_test (value) VALUES (?)", [written])?;
+        let read: u64 = tx.query_row("SELECT value FROM self_test", [], |row| row.get(0))?;
+        tx.execute("DROP TABLE self_test", ())?;
+        tx.commit()?;
+
+        if read != written {
+            return Err(DbError::Corrupt(format!(
+                "wrote {written} to a scratch table, read back {read}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Acquire a lock on the database connection. Outside of
+    /// [`Database::transaction`] this waits for another thread's transaction to
+    /// end first.
+    fn connection(&self) -> ConnectionGuard<'_> {
+        let transaction = (!IN_TRANSACTION.get()).then(|| {
+            self.transaction_lock
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+        });
+        ConnectionGuard {
+            connection: self
+                .connection
+                .lock()
+                .expect("failed to acquire database lock"),
+            _transaction: transaction,
+        }
     }
 
-    /// Acquire a lock on the database connection.
-    fn connection(&self) -> MutexGuard<'_, Connection> {
-        self.connection
-            .lock()
-            .expect("failed to acquire database lock")
+    /// Acquire a read-only connection, the first idle reader or else the next
+    /// one in turn. Falls back to the write connection without readers, and
+    /// inside [`Database::transaction`] so its reads see what it wrote.
+    fn reader(&self) -> ConnectionGuard<'_> {
+        if self.readers.is_empty() || IN_TRANSACTION.get() {
+            return self.connection();
+        }
+        let first = self.next_reader.fetch_add(1, Ordering::Relaxed);
+        let count = self.readers.len();
+        let connection = (0..count)
+            .find_map(|i| self.readers[(first + i) % count].try_lock().ok())
+            .unwrap_or_else(|| {
+                self.readers[first % count]
+                    .lock()
+                    .expect("failed to acquire database lock")
+            });
+        ConnectionGuard {
+            connection,
+            _transaction: None,
+        }
     }
 
     /// Create all required tables if they don't exist.
-    fn create_tables(&self) -> eyre::Result<()> {
+    fn create_tables(&self) -> Result<()> {
         let conn = self.connection();
         conn.execute(
             r#"
@@ -55,7 +493,20 @@ impl Database {
                 total_blobs INTEGER NOT NULL,
                 gas_used INTEGER NOT NULL,
                 gas_price INTEGER NOT NULL,
-                excess_blob_gas INTEGER NOT NULL DEFAULT 0
+                excess_blob_gas INTEGER NOT NULL DEFAULT 0,
+                min_priority_fee INTEGER,
+                median_priority_fee INTEGER,
+                max_priority_fee INTEGER,
+                non_blob_tx_count INTEGER,
+                non_blob_gas_used INTEGER,
+                base_fee_per_gas INTEGER,
+                excess_blob_gas_delta INTEGER,
+                blob_target INTEGER,
+                blob_max INTEGER,
+                block_interval INTEGER,
+                header_blob_gas_used INTEGER,
+                block_hash TEXT,
+                beneficiary TEXT
             )
             "#,
             (),
@@ -66,7 +517,11 @@ impl Database {
             CREATE TABLE IF NOT EXISTS senders (
                 address TEXT PRIMARY KEY,
                 tx_count INTEGER NOT NULL DEFAULT 0,
-                total_blobs INTEGER NOT NULL DEFAULT 0
+                total_blobs INTEGER NOT NULL DEFAULT 0,
+                total_blob_size INTEGER NOT NULL DEFAULT 0,
+                first_seen_block INTEGER,
+                last_seen_block INTEGER,
+                last_seen_timestamp INTEGER
             )
             "#,
             (),
@@ -80,7 +535,12 @@ impl Database {
                 sender TEXT NOT NULL,
                 blob_count INTEGER NOT NULL,
                 gas_price INTEGER NOT NULL,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                priority_fee INTEGER,
+                nonce INTEGER,
+                el_size INTEGER,
+                payload_size INTEGER,
+                attributed_entity TEXT
             )
             "#,
             (),
@@ -92,153 +552,2954 @@ impl Database {
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 tx_hash TEXT NOT NULL,
                 blob_hash TEXT NOT NULL,
-                blob_index INTEGER NOT NULL
+                blob_index INTEGER NOT NULL,
+                blob_size INTEGER
             )
             "#,
             (),
         )?;
 
         conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_blob_txs_block ON blob_transactions(block_number)",
+            r#"
+            CREATE TABLE IF NOT EXISTS pending_blob_transactions (
+                block_number INTEGER NOT NULL,
+                tx_hash TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                blob_count INTEGER NOT NULL,
+                priority_fee INTEGER NOT NULL,
+                max_fee_per_blob_gas INTEGER NOT NULL,
+                nonce INTEGER,
+                PRIMARY KEY (block_number, tx_hash)
+            )
+            "#,
             (),
         )?;
 
         conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_blob_txs_sender ON blob_transactions(sender)",
+            r#"
+            CREATE TABLE IF NOT EXISTS reverted_blob_transactions (
+                tx_hash TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                sender TEXT NOT NULL,
+                nonce INTEGER,
+                blob_count INTEGER NOT NULL,
+                gas_price INTEGER NOT NULL,
+                priority_fee INTEGER,
+                created_at INTEGER NOT NULL,
+                reverted_at INTEGER NOT NULL,
+                PRIMARY KEY (tx_hash, block_number)
+            )
+            "#,
             (),
         )?;
 
         conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_blob_txs_created ON blob_transactions(created_at)",
+            r#"
+            CREATE TABLE IF NOT EXISTS blob_schedule (
+                activation_timestamp INTEGER PRIMARY KEY,
+                target INTEGER NOT NULL,
+                max INTEGER NOT NULL,
+                base_fee_update_fraction INTEGER NOT NULL
+            )
+            "#,
             (),
         )?;
 
-        Ok(())
-    }
-
-    /// Insert a block with blob statistics.
-    pub fn insert_block(
-        &self,
-        block_number: u64,
-        block_timestamp: u64,
-        tx_count: u64,
-        total_blobs: u64,
-        gas_used: i64,
-        gas_price: i64,
-        excess_blob_gas: i64,
-    ) -> eyre::Result<()> {
-        self.connection().execute(
-            "INSERT OR REPLACE INTO blocks VALUES (?, ?, ?, ?, ?, ?, ?)",
-            (
-                block_number,
-                block_timestamp,
-                tx_count,
-                total_blobs,
-                gas_used,
-                gas_price,
-                excess_blob_gas,
-            ),
+        // A single row, seeded by the ExEx
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS regime_thresholds (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                normal REAL NOT NULL,
+                pressured REAL NOT NULL,
+                congested REAL NOT NULL,
+                saturated REAL NOT NULL
+            )
+            "#,
+            (),
         )?;
-        Ok(())
-    }
 
-    /// Insert a blob transaction.
-    pub fn insert_blob_transaction(
-        &self,
-        tx_hash: &str,
-        block_number: u64,
-        sender: &str,
-        blob_count: i64,
-        gas_price: i64,
-        created_at: u64,
-    ) -> eyre::Result<()> {
-        self.connection().execute(
-            "INSERT OR REPLACE INTO blob_transactions VALUES (?, ?, ?, ?, ?, ?)",
-            (
-                tx_hash,
-                block_number,
-                sender,
-                blob_count,
-                gas_price,
-                created_at,
-            ),
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS hourly_blob_stats (
+                hour_start INTEGER PRIMARY KEY,
+                block_count INTEGER NOT NULL,
+                tx_count INTEGER NOT NULL,
+                total_blobs INTEGER NOT NULL,
+                gas_used INTEGER NOT NULL,
+                gas_price_sum REAL NOT NULL
+            )
+            "#,
+            (),
         )?;
-        Ok(())
-    }
 
-    /// Insert a blob hash for a transaction.
-    pub fn insert_blob_hash(
-        &self,
-        tx_hash: &str,
-        blob_hash: &str,
-        blob_index: i64,
-    ) -> eyre::Result<()> {
-        self.connection().execute(
-            "INSERT INTO blob_hashes (tx_hash, blob_hash, blob_index) VALUES (?, ?, ?)",
-            (tx_hash, blob_hash, blob_index),
+        // Blob transactions per chain and hour, for chain profiles and calendars
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS hourly_chain_stats (
+                hour_start INTEGER NOT NULL,
+                chain TEXT NOT NULL,
+                tx_count INTEGER NOT NULL,
+                total_blobs INTEGER NOT NULL,
+                first_tx_at INTEGER NOT NULL,
+                last_tx_at INTEGER NOT NULL,
+                blob_fees REAL NOT NULL DEFAULT 0,
+                PRIMARY KEY (hour_start, chain)
+            )
+            "#,
+            (),
         )?;
-        Ok(())
-    }
 
-    /// Update sender statistics (upsert).
-    pub fn update_sender(&self, sender: &Address, num_blobs: u64) -> eyre::Result<()> {
-        self.connection().execute(
+        // References to blob versioned hashes per UTC day, and how many
+        // distinct hashes they are, telling unique data from re-posts
+        conn.execute(
             r#"
-            INSERT INTO senders (address, tx_count, total_blobs)
-            VALUES (?, 1, ?)
-            ON CONFLICT(address) DO UPDATE SET
-                tx_count = tx_count + 1,
-                total_blobs = total_blobs + ?
+            CREATE TABLE IF NOT EXISTS daily_blob_hashes (
+                day_start INTEGER PRIMARY KEY,
+                blob_refs INTEGER NOT NULL,
+                distinct_blobs INTEGER NOT NULL
+            )
             "#,
-            (sender.to_string(), num_blobs, num_blobs),
+            (),
         )?;
-        Ok(())
-    }
-
-    /// Delete a block and its associated data (for reverts).
-    pub fn delete_block(&self, block_number: u64) -> eyre::Result<()> {
-        self.connection()
-            .execute("DELETE FROM blocks WHERE block_number = ?", (block_number,))?;
-        Ok(())
-    }
-
-    /// Get overall statistics.
-    pub fn get_stats(&self) -> eyre::Result<Stats> {
-        let conn = self.connection();
 
-        let total_blocks: u64 = conn
-            .query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))
-            .unwrap_or(0);
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS ingest_errors (
+                block_number INTEGER NOT NULL,
+                stage TEXT NOT NULL,
+                error TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                failed_at INTEGER NOT NULL,
+                PRIMARY KEY (block_number, stage)
+            )
+            "#,
+            (),
+        )?;
 
-        let total_blobs: u64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(blob_count), 0) FROM blob_transactions",
-                [],
-                |row| row.get(0),
+        // Append-only record of the notifications the ExEx processed, pruned
+        // by age only
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS ingest_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                processed_at INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                reverted_from INTEGER,
+                reverted_to INTEGER,
+                committed_from INTEGER,
+                committed_to INTEGER,
+                reverted_txs INTEGER NOT NULL,
+                committed_txs INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                error TEXT
             )
-            .unwrap_or(0);
+            "#,
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_ingest_log_processed ON ingest_log(processed_at)",
+            (),
+        )?;
 
-        let total_transactions: u64 = conn
-            .query_row("SELECT COALESCE(SUM(tx_count), 0) FROM blocks", [], |row| {
-                row.get(0)
-            })
-            .unwrap_or(0);
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS reprocess_requests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                from_block INTEGER NOT NULL,
+                to_block INTEGER NOT NULL,
+                next_block INTEGER NOT NULL,
+                requested_at INTEGER NOT NULL,
+                completed_at INTEGER
+            )
+            "#,
+            (),
+        )?;
 
-        let latest_block: Option<u64> = conn
-            .query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))
-            .ok();
+        // Re-attribution of stored blob transactions, see `request_relabel`
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS relabel_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                from_block INTEGER NOT NULL,
+                to_block INTEGER NOT NULL,
+                next_block INTEGER NOT NULL,
+                relabeled INTEGER NOT NULL DEFAULT 0,
+                registry_version INTEGER,
+                requested_at INTEGER NOT NULL,
+                completed_at INTEGER
+            )
+            "#,
+            (),
+        )?;
 
-        let earliest_block: Option<u64> = conn
-            .query_row("SELECT MIN(block_number) FROM blocks", [], |row| row.get(0))
-            .ok();
+        // All-time extremes, kept up to date as blocks are inserted and reverted.
+        // `value` is a count or a wei amount stored like fee columns.
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS records (
+                kind TEXT PRIMARY KEY,
+                value INTEGER NOT NULL,
+                from_block INTEGER NOT NULL,
+                to_block INTEGER NOT NULL,
+                sender TEXT,
+                day INTEGER
+            )
+            "#,
+            (),
+        )?;
 
-        let latest_gas_price: u64 = conn
-            .query_row(
-                "SELECT gas_price FROM blocks ORDER BY block_number DESC LIMIT 1",
-                [],
-                |row| row.get(0),
+        // Blocks whose blob target or max differ from their parent's, i.e. where
+        // a fork changing the blob schedule was observed to activate
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS fork_events (
+                block_number INTEGER PRIMARY KEY,
+                block_timestamp INTEGER NOT NULL,
+                previous_target INTEGER NOT NULL,
+                previous_max INTEGER NOT NULL,
+                target INTEGER NOT NULL,
+                max INTEGER NOT NULL
             )
-            .unwrap_or(0);
+            "#,
+            (),
+        )?;
+
+        // Checkpoints of backfills read from a reth datadir, keyed by first block
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS backfill_progress (
+                from_block INTEGER PRIMARY KEY,
+                to_block INTEGER NOT NULL,
+                next_block INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS admin_audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                action TEXT NOT NULL,
+                details TEXT NOT NULL,
+                performed_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS writer_lease (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                holder TEXT NOT NULL,
+                acquired_at INTEGER NOT NULL,
+                heartbeat_at INTEGER NOT NULL,
+                node_tip INTEGER
+            )
+            "#,
+            (),
+        )?;
+
+        // Outcome of the writer's canonical chain verification, see
+        // `crate::indexer::verify_canonical`
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS canonical_checks (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                checked_at INTEGER NOT NULL,
+                blocks_checked INTEGER NOT NULL,
+                mismatches INTEGER NOT NULL,
+                total_mismatches INTEGER NOT NULL,
+                last_mismatch_block INTEGER,
+                last_mismatch_at INTEGER
+            )
+            "#,
+            (),
+        )?;
+
+        // Outcome of the writer's sender stats check, see
+        // `crate::maintenance::check_senders`
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS sender_checks (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                checked_at INTEGER NOT NULL,
+                drifted_senders INTEGER NOT NULL,
+                tx_count_drift INTEGER NOT NULL,
+                blob_drift INTEGER NOT NULL,
+                repaired INTEGER NOT NULL,
+                total_repairs INTEGER NOT NULL,
+                last_repair_at INTEGER
+            )
+            "#,
+            (),
+        )?;
+
+        // Monthly archive databases of old blob transactions, see
+        // `Database::archive`
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS archives (
+                month TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                tx_count INTEGER NOT NULL,
+                blob_count INTEGER NOT NULL,
+                first_tx_at INTEGER NOT NULL,
+                last_tx_at INTEGER NOT NULL,
+                archived_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+        // Sender stats of the archived transactions, which rebuilding sender
+        // stats adds to those of the indexed ones
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS archived_senders (
+                address TEXT PRIMARY KEY,
+                tx_count INTEGER NOT NULL,
+                total_blobs INTEGER NOT NULL,
+                total_blob_size INTEGER NOT NULL,
+                first_seen_block INTEGER NOT NULL,
+                last_seen_block INTEGER NOT NULL,
+                last_seen_timestamp INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        // API keys issued by operators for alert rules, see `crate::alerts`
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS alert_keys (
+                owner TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        // Alert rules registered by API users, see `crate::alerts`
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS alert_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                owner TEXT NOT NULL,
+                condition TEXT NOT NULL,
+                delivery TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                firing INTEGER NOT NULL DEFAULT 0,
+                last_fired_at INTEGER
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS alert_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule_id INTEGER NOT NULL,
+                owner TEXT NOT NULL,
+                message TEXT NOT NULL,
+                fired_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_alert_rules_owner ON alert_rules(owner)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_alert_events_owner ON alert_events(owner, id)",
+            (),
+        )?;
+
+        // Operator-configured owners of batcher and treasury addresses
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS entity_addresses (
+                address TEXT PRIMARY KEY,
+                entity TEXT NOT NULL,
+                from_block INTEGER,
+                to_block INTEGER
+            )
+            "#,
+            (),
+        )?;
+
+        // Labels set by analysts, overriding every other attribution
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS sender_labels (
+                address TEXT PRIMARY KEY,
+                label TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        // Events marked by operators (client releases, incidents, forks),
+        // drawn on charts
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS annotations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                title TEXT NOT NULL,
+                description TEXT,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_annotations_timestamp ON annotations(timestamp)",
+            (),
+        )?;
+
+        // ETH sent from an attributed address, so fresh batcher keys funded
+        // from a known treasury inherit its entity
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS funding_transfers (
+                tx_hash TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL,
+                funder TEXT NOT NULL,
+                recipient TEXT NOT NULL,
+                entity TEXT NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_funding_transfers_recipient
+             ON funding_transfers(recipient, block_number)",
+            (),
+        )?;
+
+        // Secondary indexes dropped for a bulk ingest, restored when it ends
+        // or, if it was interrupted, on the next start
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS bulk_deferred_indexes (
+                name TEXT PRIMARY KEY,
+                sql TEXT NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        // Frames decoded from OP Stack batcher blobs, per blob
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS op_frames (
+                tx_hash TEXT NOT NULL,
+                blob_index INTEGER NOT NULL,
+                channel_id TEXT NOT NULL,
+                frame_number INTEGER NOT NULL,
+                block_number INTEGER NOT NULL,
+                frame_size INTEGER NOT NULL,
+                is_last INTEGER NOT NULL,
+                PRIMARY KEY (tx_hash, blob_index, channel_id, frame_number)
+            )
+            "#,
+            (),
+        )?;
+
+        // OP Stack channels completed within one transaction
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS op_channels (
+                channel_id TEXT NOT NULL,
+                tx_hash TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                frame_count INTEGER NOT NULL,
+                compressed_size INTEGER NOT NULL,
+                decompressed_size INTEGER,
+                compression TEXT,
+                PRIMARY KEY (channel_id, tx_hash)
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_op_frames_block ON op_frames(block_number)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_op_channels_block ON op_channels(block_number)",
+            (),
+        )?;
+
+        // Blob transactions as EIP-2718 envelopes without their sidecars, for
+        // decoding later without a node
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS tx_raw (
+                tx_hash TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL,
+                raw BLOB NOT NULL
+            )
+            "#,
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tx_raw_block ON tx_raw(block_number)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blocks_timestamp ON blocks(block_timestamp)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_txs_block ON blob_transactions(block_number)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_txs_sender ON blob_transactions(sender)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_txs_created ON blob_transactions(created_at)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_txs_sender_block
+             ON blob_transactions(sender, block_number)",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_hashes_tx ON blob_hashes(tx_hash)",
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_hashes_hash ON blob_hashes(blob_hash)",
+            (),
+        )?;
+
+        Ok(())
+    }
+
+    /// Bring databases created by older versions up to the current schema.
+    fn migrate(&self) -> Result<()> {
+        let conn = self.connection();
+
+        add_column_if_missing(&conn, "blob_hashes", "blob_size", "INTEGER")?;
+        add_column_if_missing(&conn, "blob_transactions", "priority_fee", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "min_priority_fee", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "median_priority_fee", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "max_priority_fee", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "non_blob_tx_count", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "non_blob_gas_used", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "base_fee_per_gas", "INTEGER")?;
+        // Unknown for blocks indexed before they were recorded
+        add_column_if_missing(&conn, "blocks", "header_blob_gas_used", "INTEGER")?;
+        add_column_if_missing(&conn, "blocks", "block_hash", "TEXT")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blocks_block_hash ON blocks(block_hash)",
+            (),
+        )?;
+        // Fee recipient, unknown for blocks indexed before it was recorded
+        add_column_if_missing(&conn, "blocks", "beneficiary", "TEXT")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blocks_beneficiary ON blocks(beneficiary)",
+            (),
+        )?;
+        add_column_if_missing(&conn, "blob_transactions", "nonce", "INTEGER")?;
+        if add_column_if_missing(&conn, "blocks", "excess_blob_gas_delta", "INTEGER")? {
+            conn.execute(
+                "UPDATE blocks SET excess_blob_gas_delta = excess_blob_gas -
+                     (SELECT parent.excess_blob_gas FROM blocks parent
+                      WHERE parent.block_number = blocks.block_number - 1)",
+                (),
+            )?;
+        }
+        if add_column_if_missing(&conn, "blocks", "block_interval", "INTEGER")? {
+            conn.execute(
+                "UPDATE blocks SET block_interval = block_timestamp -
+                     (SELECT parent.block_timestamp FROM blocks parent
+                      WHERE parent.block_number = blocks.block_number - 1)",
+                (),
+            )?;
+        }
+        let added_blob_target = add_column_if_missing(&conn, "blocks", "blob_target", "INTEGER")?;
+        let added_blob_max = add_column_if_missing(&conn, "blocks", "blob_max", "INTEGER")?;
+        if added_blob_target || added_blob_max {
+            fill_block_blob_params(&conn, &blob_schedule(&conn)?)?;
+        }
+        add_column_if_missing(&conn, "pending_blob_transactions", "nonce", "INTEGER")?;
+        add_column_if_missing(&conn, "writer_lease", "node_tip", "INTEGER")?;
+        add_column_if_missing(&conn, "blob_transactions", "el_size", "INTEGER")?;
+        if add_column_if_missing(&conn, "blob_transactions", "payload_size", "INTEGER")? {
+            // Only txs whose every blob size is known have a payload size
+            conn.execute(
+                "UPDATE blob_transactions SET payload_size =
+                     (SELECT SUM(h.blob_size) FROM blob_hashes h
+                      WHERE h.tx_hash = blob_transactions.tx_hash)
+                 WHERE NOT EXISTS (SELECT 1 FROM blob_hashes h
+                                   WHERE h.tx_hash = blob_transactions.tx_hash
+                                     AND h.blob_size IS NULL)",
+                (),
+            )?;
+        }
+        dedupe_blob_hashes(&conn)?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_transactions_sender_nonce
+             ON blob_transactions(sender, nonce)",
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_pending_blob_transactions_sender_nonce
+             ON pending_blob_transactions(sender, nonce)",
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_pending_blob_transactions_tx_hash
+             ON pending_blob_transactions(tx_hash)",
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_reverted_blob_transactions_sender_nonce
+             ON reverted_blob_transactions(sender, nonce)",
+            (),
+        )?;
+
+        if add_column_if_missing(
+            &conn,
+            "senders",
+            "total_blob_size",
+            "INTEGER NOT NULL DEFAULT 0",
+        )? {
+            conn.execute(
+                "UPDATE senders SET total_blob_size = total_blobs * ?",
+                (BLOB_SIZE_BYTES,),
+            )?;
+        }
+
+        let mut seen_added = false;
+        for column in ["first_seen_block", "last_seen_block", "last_seen_timestamp"] {
+            seen_added |= add_column_if_missing(&conn, "senders", column, "INTEGER")?;
+        }
+        if seen_added {
+            conn.execute(
+                "UPDATE senders SET
+                     first_seen_block = seen.first_seen_block,
+                     last_seen_block = seen.last_seen_block,
+                     last_seen_timestamp = seen.last_seen_timestamp
+                 FROM (
+                     SELECT sender,
+                            MIN(block_number) AS first_seen_block,
+                            MAX(block_number) AS last_seen_block,
+                            MAX(created_at) AS last_seen_timestamp
+                     FROM blob_transactions GROUP BY sender
+                 ) AS seen
+                 WHERE senders.address = seen.sender",
+                (),
+            )?;
+        }
+
+        // Inclusive block range an operator-configured entity applies to
+        add_column_if_missing(&conn, "entity_addresses", "from_block", "INTEGER")?;
+        add_column_if_missing(&conn, "entity_addresses", "to_block", "INTEGER")?;
+
+        // Fees in wei, stored like `gas_price`. The execution side is only
+        // recorded with execution tracking enabled
+        if add_column_if_missing(&conn, "blob_transactions", "blob_fee", "INTEGER")? {
+            let txs: Vec<(String, u64, Wei)> = conn
+                .prepare("SELECT tx_hash, blob_count, gas_price FROM blob_transactions")?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .filter_map(|r| r.ok())
+                .collect();
+            conn.execute_batch("BEGIN")?;
+            let mut update =
+                conn.prepare("UPDATE blob_transactions SET blob_fee = ? WHERE tx_hash = ?")?;
+            for (tx_hash, blob_count, gas_price) in txs {
+                update.execute((Wei(blob_fee(blob_count, gas_price.0)), tx_hash))?;
+            }
+            drop(update);
+            conn.execute_batch("COMMIT")?;
+        }
+        // Blob fees in wei, summed like `hourly_blob_stats.gas_price_sum`.
+        // Filled in once transactions are attributed, see below
+        let added_chain_fees = add_column_if_missing(
+            &conn,
+            "hourly_chain_stats",
+            "blob_fees",
+            "REAL NOT NULL DEFAULT 0",
+        )?;
+
+        let added_entity =
+            add_column_if_missing(&conn, "blob_transactions", "attributed_entity", "TEXT")?;
+        let added_source =
+            add_column_if_missing(&conn, "blob_transactions", "label_source", "TEXT")?;
+        // NULL for rows attributed before registry versions were recorded
+        add_column_if_missing(&conn, "blob_transactions", "registry_version", "INTEGER")?;
+        if added_entity || added_source {
+            reattribute_senders(&conn)?;
+        }
+        if added_chain_fees {
+            rebuild_hourly_chain_stats(&conn)?;
+        }
+
+        add_column_if_missing(&conn, "blob_transactions", "execution_gas_used", "INTEGER")?;
+        add_column_if_missing(&conn, "blob_transactions", "execution_fee", "INTEGER")?;
+        add_column_if_missing(&conn, "blob_transactions", "total_fee", "INTEGER")?;
+        // Receipt status: 1 succeeded, 0 reverted, null before execution tracking saw it
+        add_column_if_missing(&conn, "blob_transactions", "status", "INTEGER")?;
+        // The inbox contract of rollups, null for txs indexed before it was recorded
+        add_column_if_missing(&conn, "blob_transactions", "to_address", "TEXT")?;
+        // EIP-2718 type. Every blob tx indexed before it was recorded was EIP-4844
+        if add_column_if_missing(&conn, "blob_transactions", "tx_type", "INTEGER")? {
+            conn.execute("UPDATE blob_transactions SET tx_type = 3", ())?;
+        }
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_transactions_to_created
+             ON blob_transactions(to_address, created_at)",
+            (),
+        )?;
+
+        normalize_addresses(&conn)?;
+
+        let has_rollups: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM hourly_blob_stats)",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_rollups {
+            rebuild_hourly_stats(&conn)?;
+        }
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_transactions_sender_created
+             ON blob_transactions(sender, created_at)",
+            (),
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_blob_transactions_created
+             ON blob_transactions(created_at)",
+            (),
+        )?;
+        let has_chain_rollups: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM hourly_chain_stats)
+                 OR NOT EXISTS(SELECT 1 FROM blob_transactions)",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_chain_rollups {
+            rebuild_hourly_chain_stats(&conn)?;
+        }
+        let has_hash_rollups: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM daily_blob_hashes)
+                 OR NOT EXISTS(SELECT 1 FROM blob_hashes)",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_hash_rollups {
+            rebuild_daily_blob_hashes(&conn)?;
+        }
+        // A bulk ingest was interrupted
+        if restore_deferred_indexes(&conn)? {
+            rebuild_derived_tables(&conn)?;
+        }
+
+        let has_records: bool =
+            conn.query_row("SELECT EXISTS(SELECT 1 FROM records)", [], |row| row.get(0))?;
+        if !has_records {
+            for kind in RECORD_KINDS {
+                rebuild_record(&conn, kind)?;
+            }
+            rebuild_current_streak(&conn)?;
+        }
+
+        let has_fork_events: bool =
+            conn.query_row("SELECT EXISTS(SELECT 1 FROM fork_events)", [], |row| {
+                row.get(0)
+            })?;
+        if !has_fork_events {
+            rebuild_fork_events(&conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Run `f` inside a transaction, rolling back everything it wrote if it
+    /// fails or panics.
+    ///
+    /// The connection lock isn't held while `f` runs so it can call other
+    /// `Database` methods on this thread, including `transaction` itself, which
+    /// nests as a savepoint. Other threads using the connection meanwhile wait
+    /// for the transaction to end.
+    #[tracing::instrument(name = "db.transaction", skip_all)]
+    pub fn transaction<T, E: From<DbError>>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let transaction = Transaction::begin(self)?;
+        let value = f()?;
+        transaction.commit()?;
+        Ok(value)
+    }
+
+    /// Refuse [`Database::transaction`]s from now on unless `holder` holds the
+    /// writer lease (see [`crate::lease`]). It's checked inside each
+    /// transaction, so a writer that lost the lease can't write after the new
+    /// holder took over. Only the first holder is kept.
+    pub fn fence_writes(&self, holder: &str) {
+        let _ = self.fence.set(holder.to_string());
+    }
+
+    fn check_fence(&self) -> Result<()> {
+        let Some(holder) = self.fence.get() else {
+            return Ok(());
+        };
+        let current: Option<String> = self
+            .connection()
+            .query_row("SELECT holder FROM writer_lease WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        if current.as_ref() != Some(holder) {
+            return Err(DbError::Busy(format!(
+                "writer lease lost to {}",
+                current.as_deref().unwrap_or("nobody")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Switch to bulk ingest for a backfill: non-unique indexes are dropped,
+    /// writes aren't synced and hourly rollups and records stop being
+    /// maintained per insert, until [`Database::end_bulk_ingest`].
+    ///
+    /// Queries other than the insert path get slow meanwhile. If the process
+    /// dies first, the indexes and derived tables are restored on the next
+    /// [`Database::new`].
+    pub fn begin_bulk_ingest(&self) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        let indexes: Vec<(String, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT name, sql FROM sqlite_master
+                 WHERE type = 'index' AND sql LIKE 'CREATE INDEX %'",
+            )?;
+            let indexes = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .filter_map(|r| r.ok())
+                .collect();
+            indexes
+        };
+        for (name, sql) in indexes {
+            if BULK_KEPT_INDEXES.contains(&name.as_str()) {
+                continue;
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO bulk_deferred_indexes (name, sql) VALUES (?, ?)",
+                (&name, &sql),
+            )?;
+            tx.execute(&format!("DROP INDEX {name}"), ())?;
+        }
+        tx.commit()?;
+
+        conn.pragma_update(None, "synchronous", "OFF")?;
+        conn.pragma_update(None, "cache_size", -(BULK_CACHE_KIB as i64))?;
+        self.bulk.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Leave bulk ingest: recreate the dropped indexes, rebuild hourly rollups
+    /// and records, and restore durable writes.
+    pub fn end_bulk_ingest(&self) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        restore_deferred_indexes(&tx)?;
+        rebuild_derived_tables(&tx)?;
+        tx.commit()?;
+        conn.pragma_update(None, "synchronous", "FULL")?;
+        conn.pragma_update(None, "cache_size", -2000)?;
+        self.bulk.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Insert a block with blob statistics.
+    pub fn insert_block(&self, block: &NewBlock) -> Result<()> {
+        let conn = self.connection();
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO blocks (
+                block_number, block_timestamp, tx_count, total_blobs, gas_used, gas_price,
+                excess_blob_gas, base_fee_per_gas,
+                min_priority_fee, median_priority_fee, max_priority_fee,
+                blob_target, blob_max, header_blob_gas_used, block_hash, beneficiary
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            (
+                block.block_number,
+                block.block_timestamp,
+                block.tx_count,
+                block.total_blobs,
+                block.gas_used,
+                Wei(block.gas_price),
+                block.excess_blob_gas,
+                block.base_fee_per_gas,
+                block.priority_fees.map(|fees| fees.min),
+                block.priority_fees.map(|fees| fees.median),
+                block.priority_fees.map(|fees| fees.max),
+                block.blob_target,
+                block.blob_max,
+                block.header_blob_gas_used,
+                &block.block_hash,
+                address_key(&block.beneficiary),
+            ),
+        )?;
+        // Deltas against the parent, and of the child if it arrived first
+        conn.execute(
+            "UPDATE blocks SET
+                 excess_blob_gas_delta = excess_blob_gas -
+                     (SELECT parent.excess_blob_gas FROM blocks parent
+                      WHERE parent.block_number = blocks.block_number - 1),
+                 block_interval = block_timestamp -
+                     (SELECT parent.block_timestamp FROM blocks parent
+                      WHERE parent.block_number = blocks.block_number - 1)
+             WHERE block_number IN (?1, ?1 + 1)",
+            (block.block_number,),
+        )?;
+        // Rebuilt once at the end of a bulk ingest instead
+        if !self.bulk.load(Ordering::Relaxed) {
+            refresh_hourly_stats(&conn, block.block_timestamp)?;
+            update_block_records(&conn, block)?;
+            refresh_fork_events(&conn, block.block_number)?;
+        }
+        Ok(())
+    }
+
+    /// Record execution layer context for an already inserted block.
+    pub fn update_block_execution(
+        &self,
+        block_number: u64,
+        execution: &ExecutionContext,
+    ) -> Result<()> {
+        self.connection().execute(
+            "UPDATE blocks
+             SET non_blob_tx_count = ?2, non_blob_gas_used = ?3
+             WHERE block_number = ?1",
+            (
+                block_number,
+                execution.non_blob_tx_count,
+                execution.non_blob_gas_used,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Insert a blob transaction that was pending in the mempool but not
+    /// included in the given block.
+    pub fn insert_pending_blob_transaction(
+        &self,
+        tx: &NewPendingBlobTransaction<'_>,
+    ) -> Result<()> {
+        self.connection().execute(
+            r#"
+            INSERT OR REPLACE INTO pending_blob_transactions (
+                block_number, tx_hash, sender, blob_count, priority_fee, max_fee_per_blob_gas, nonce
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            (
+                tx.block_number,
+                tx.tx_hash,
+                address_key(&tx.sender),
+                tx.blob_count,
+                tx.priority_fee,
+                tx.max_fee_per_blob_gas,
+                tx.nonce,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Insert a blob transaction.
+    ///
+    /// The transaction is attributed to the entity owning its sender, see
+    /// [`Database::set_sender_label`], [`Database::replace_entity_addresses`]
+    /// and [`Database::record_funding_transfer`].
+    pub fn insert_blob_transaction(&self, tx: &NewBlobTransaction<'_>) -> Result<()> {
+        let conn = self.connection();
+        let sender = address_key(&tx.sender);
+        let (attributed_entity, label_source) =
+            attributed_entity(&conn, &sender, tx.block_number)?.unzip();
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO blob_transactions (
+                tx_hash, block_number, sender, nonce, blob_count, gas_price, priority_fee, created_at,
+                el_size, payload_size, attributed_entity, label_source, registry_version,
+                blob_fee, to_address, tx_type
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            (
+                tx.tx_hash,
+                tx.block_number,
+                &sender,
+                tx.nonce,
+                tx.blob_count,
+                Wei(tx.gas_price),
+                tx.priority_fee,
+                tx.created_at,
+                tx.el_size,
+                tx.payload_size,
+                attributed_entity,
+                label_source.map(LabelSource::as_str),
+                REGISTRY_VERSION,
+                Wei(blob_fee(tx.blob_count as u64, tx.gas_price)),
+                tx.to.as_ref().map(address_key),
+                tx.tx_type,
+            ),
+        )?;
+        // Rebuilt once at the end of a bulk ingest instead
+        if !self.bulk.load(Ordering::Relaxed) {
+            update_sender_day_record(&conn, &sender, tx.created_at)?;
+            refresh_hourly_chain_stats(&conn, tx.created_at, tx.created_at)?;
+        }
+        Ok(())
+    }
+
+    /// Insert a blob hash for a transaction.
+    ///
+    /// `blob_size` is the meaningful payload length of the blob (trailing zero
+    /// padding trimmed) when its sidecar was available, `None` otherwise.
+    /// Reprocessing a block overwrites the existing row rather than adding
+    /// another, keeping a known size over an unknown one.
+    pub fn insert_blob_hash(
+        &self,
+        tx_hash: &str,
+        blob_hash: &str,
+        blob_index: i64,
+        blob_size: Option<u64>,
+    ) -> Result<()> {
+        let conn = self.connection();
+        let replaced: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM blob_hashes WHERE tx_hash = ? AND blob_index = ?)",
+            (tx_hash, blob_index),
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "INSERT INTO blob_hashes (tx_hash, blob_hash, blob_index, blob_size) VALUES (?, ?, ?, ?)
+             ON CONFLICT(tx_hash, blob_index) DO UPDATE SET
+                 blob_hash = excluded.blob_hash,
+                 blob_size = COALESCE(excluded.blob_size, blob_size)",
+            (tx_hash, blob_hash, blob_index, blob_size),
+        )?;
+        // Rebuilt once at the end of a bulk ingest instead
+        if self.bulk.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let Some(created_at) = conn
+            .query_row(
+                "SELECT created_at FROM blob_transactions WHERE tx_hash = ?",
+                [tx_hash],
+                |row| row.get::<_, u64>(0),
+            )
+            .optional()?
+        else {
+            return Ok(());
+        };
+        if replaced {
+            refresh_daily_blob_hashes(&conn, created_at, created_at)?;
+        } else {
+            // Counting a new reference is enough, unless its hash was already
+            // posted that day
+            conn.execute(
+                "INSERT INTO daily_blob_hashes (day_start, blob_refs, distinct_blobs)
+                 SELECT ?1, 1, NOT EXISTS(
+                     SELECT 1 FROM blob_hashes h
+                     JOIN blob_transactions t ON t.tx_hash = h.tx_hash
+                     WHERE h.blob_hash = ?2
+                       AND NOT (h.tx_hash = ?3 AND h.blob_index = ?4)
+                       AND t.created_at >= ?1 AND t.created_at < ?1 + 86400
+                 )
+                 ON CONFLICT(day_start) DO UPDATE SET
+                     blob_refs = blob_refs + 1,
+                     distinct_blobs = distinct_blobs + excluded.distinct_blobs",
+                (group_by_day(created_at, 0), blob_hash, tx_hash, blob_index),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Update sender statistics (upsert) with a blob tx included in
+    /// `block_number` at `block_timestamp`.
+    pub fn update_sender(
+        &self,
+        sender: &Address,
+        block_number: u64,
+        block_timestamp: u64,
+        num_blobs: u64,
+        blob_size: u64,
+    ) -> Result<()> {
+        self.connection().execute(
+            r#"
+            INSERT INTO senders (
+                address, tx_count, total_blobs, total_blob_size,
+                first_seen_block, last_seen_block, last_seen_timestamp
+            )
+            VALUES (?1, 1, ?2, ?3, ?4, ?4, ?5)
+            ON CONFLICT(address) DO UPDATE SET
+                tx_count = tx_count + 1,
+                total_blobs = total_blobs + ?2,
+                total_blob_size = total_blob_size + ?3,
+                first_seen_block = MIN(COALESCE(first_seen_block, ?4), ?4),
+                last_seen_block = MAX(COALESCE(last_seen_block, ?4), ?4),
+                last_seen_timestamp = MAX(COALESCE(last_seen_timestamp, ?5), ?5)
+            "#,
+            (
+                address_key(sender),
+                num_blobs,
+                blob_size,
+                block_number,
+                block_timestamp,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Delete a block and its associated data (for reverts).
+    pub fn delete_block(&self, block_number: u64) -> Result<()> {
+        let conn = self.connection();
+        let block_timestamp: Option<u64> = conn
+            .query_row(
+                "SELECT block_timestamp FROM blocks WHERE block_number = ?",
+                (block_number,),
+                |row| row.get(0),
+            )
+            .optional()?;
+        conn.execute("DELETE FROM blocks WHERE block_number = ?", (block_number,))?;
+        if let Some(block_timestamp) = block_timestamp {
+            refresh_hourly_stats(&conn, block_timestamp)?;
+        }
+        // Undo the block's contribution to sender stats before its
+        // transactions are gone, so re-indexing it doesn't double-count
+        conn.execute(
+            &format!(
+                "UPDATE senders SET
+                     tx_count = senders.tx_count - reverted.tx_count,
+                     total_blobs = senders.total_blobs - reverted.total_blobs,
+                     total_blob_size = senders.total_blob_size - reverted.total_blob_size
+                 FROM ({}) AS reverted
+                 WHERE senders.address = reverted.address",
+                sender_totals_sql("WHERE t.block_number = ?1")
+            ),
+            (block_number,),
+        )?;
+        conn.execute("DELETE FROM senders WHERE tx_count <= 0", ())?;
+        conn.execute(
+            "DELETE FROM blob_hashes WHERE tx_hash IN
+                (SELECT tx_hash FROM blob_transactions WHERE block_number = ?)",
+            (block_number,),
+        )?;
+        conn.execute(
+            "DELETE FROM blob_transactions WHERE block_number = ?",
+            (block_number,),
+        )?;
+        if let Some(block_timestamp) = block_timestamp {
+            refresh_hourly_chain_stats(&conn, block_timestamp, block_timestamp)?;
+            refresh_daily_blob_hashes(&conn, block_timestamp, block_timestamp)?;
+        }
+        // Senders last (or first) seen in the block fall back to their
+        // remaining transactions
+        conn.execute(
+            "UPDATE senders SET
+                 first_seen_block = (SELECT MIN(block_number) FROM blob_transactions WHERE sender = senders.address),
+                 last_seen_block = (SELECT MAX(block_number) FROM blob_transactions WHERE sender = senders.address),
+                 last_seen_timestamp = (SELECT MAX(created_at) FROM blob_transactions WHERE sender = senders.address)
+             WHERE last_seen_block >= ?1 OR first_seen_block >= ?1",
+            (block_number,),
+        )?;
+        revert_records(&conn, block_number)?;
+        refresh_fork_events(&conn, block_number)?;
+        Ok(())
+    }
+
+    /// Keep a copy of a block's blob transactions before it is reverted, so
+    /// replacements of reorged transactions can still be linked by nonce.
+    pub fn archive_reverted_block(&self, block_number: u64) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO reverted_blob_transactions
+             SELECT tx_hash, block_number, sender, nonce, blob_count, gas_price, priority_fee,
+                    created_at, ?2
+             FROM blob_transactions WHERE block_number = ?1",
+            (block_number, unix_timestamp()?),
+        )?;
+        Ok(())
+    }
+
+    /// Get the position of the latest blob transaction archived by
+    /// [`Database::archive_reverted_block`], 0 if there is none.
+    ///
+    /// Archived rows are numbered in order, so the position serves as a cursor
+    /// for [`Database::get_reverted_since`].
+    pub fn get_latest_reverted_id(&self) -> Result<u64> {
+        let id = self.reader().query_row(
+            "SELECT COALESCE(MAX(rowid), 0) FROM reverted_blob_transactions",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// Get the blocks of the blob transactions archived as reverted after
+    /// position `after`, or `None` if there are none.
+    pub fn get_reverted_since(&self, after: u64) -> Result<Option<RevertedBlocksData>> {
+        let reverted = self.reader().query_row(
+            "SELECT MIN(block_number), MAX(block_number), MAX(rowid)
+             FROM reverted_blob_transactions WHERE rowid > ?",
+            [after],
+            |row| {
+                Ok(match (row.get(0)?, row.get(1)?, row.get(2)?) {
+                    (Some(first_block), Some(last_block), Some(last_id)) => {
+                        Some(RevertedBlocksData {
+                            first_block,
+                            last_block,
+                            last_id,
+                        })
+                    }
+                    _ => None,
+                })
+            },
+        )?;
+        Ok(reverted)
+    }
+
+    /// Delete the mempool snapshot taken at a block (for reverts).
+    pub fn delete_pending_blob_transactions(&self, block_number: u64) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM pending_blob_transactions WHERE block_number = ?",
+            (block_number,),
+        )?;
+        Ok(())
+    }
+
+    /// Record that an ingest stage gave up on a block, replacing any earlier
+    /// failure of the same stage.
+    pub fn record_ingest_error(
+        &self,
+        block_number: u64,
+        stage: &str,
+        error: &str,
+        attempts: u32,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO ingest_errors VALUES (?, ?, ?, ?, ?)",
+            (block_number, stage, error, attempts, unix_timestamp()?),
+        )?;
+        Ok(())
+    }
+
+    /// Append a processed notification to the ingest log.
+    pub fn append_ingest_log(&self, entry: &NewIngestLogEntry<'_>) -> Result<()> {
+        let range = |range: &Option<RangeInclusive<u64>>| {
+            range
+                .as_ref()
+                .map(|range| (*range.start(), *range.end()))
+                .unzip()
+        };
+        let (reverted_from, reverted_to) = range(&entry.reverted);
+        let (committed_from, committed_to) = range(&entry.committed);
+        self.connection().execute(
+            "INSERT INTO ingest_log (
+                 processed_at, kind, reverted_from, reverted_to, committed_from, committed_to,
+                 reverted_txs, committed_txs, duration_ms, outcome, error
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                unix_timestamp()?,
+                entry.kind,
+                reverted_from,
+                reverted_to,
+                committed_from,
+                committed_to,
+                entry.reverted_txs,
+                entry.committed_txs,
+                entry.duration.as_millis() as u64,
+                entry.outcome,
+                &entry.error,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Delete ingest log entries processed before `before`. Returns how many
+    /// were deleted.
+    pub fn prune_ingest_log(&self, before: u64) -> Result<usize> {
+        Ok(self
+            .connection()
+            .execute("DELETE FROM ingest_log WHERE processed_at < ?", (before,))?)
+    }
+
+    /// Clear recorded failures of a stage for a range of blocks that have since
+    /// been ingested successfully.
+    pub fn resolve_ingest_errors(&self, blocks: &RangeInclusive<u64>, stage: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM ingest_errors WHERE block_number BETWEEN ? AND ? AND stage = ?",
+            (blocks.start(), blocks.end(), stage),
+        )?;
+        Ok(())
+    }
+
+    /// Queue a block range to be re-fetched from the node and re-indexed by the
+    /// ExEx. Returns the request id.
+    pub fn request_reprocess(&self, from_block: u64, to_block: u64) -> Result<u64> {
+        if from_block > to_block {
+            return Err(DbError::InvalidInput(format!(
+                "invalid block range {from_block}..={to_block}"
+            )));
+        }
+        let conn = self.connection();
+        conn.execute(
+            "INSERT INTO reprocess_requests (from_block, to_block, next_block, requested_at)
+             VALUES (?1, ?2, ?1, ?3)",
+            (from_block, to_block, unix_timestamp()?),
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// Get the oldest re-process request that hasn't completed yet.
+    pub fn next_reprocess_request(&self) -> Result<Option<ReprocessRequestData>> {
+        let conn = self.reader();
+        let request = conn
+            .query_row(
+                &format!(
+                    "SELECT {REPROCESS_COLUMNS} FROM reprocess_requests
+                     WHERE completed_at IS NULL ORDER BY id ASC LIMIT 1"
+                ),
+                [],
+                reprocess_request_from_row,
+            )
+            .optional()?;
+        Ok(request)
+    }
+
+    /// Record progress of a re-process request: every block before `next_block`
+    /// has been re-indexed. Marks the request completed once past its range.
+    pub fn advance_reprocess_request(&self, id: u64, next_block: u64) -> Result<()> {
+        self.connection().execute(
+            "UPDATE reprocess_requests
+             SET next_block = ?2,
+                 completed_at = CASE WHEN ?2 > to_block THEN ?3 END
+             WHERE id = ?1",
+            (id, next_block, unix_timestamp()?),
+        )?;
+        Ok(())
+    }
+
+    /// Queue the blob transactions of a block range to be re-attributed by the
+    /// ExEx with its labels, `BLOB_ENTITY_MAP` and chain registry, after any of
+    /// them changed. Returns the job id.
+    pub fn request_relabel(&self, from_block: u64, to_block: u64) -> Result<u64> {
+        if from_block > to_block {
+            return Err(DbError::InvalidInput(format!(
+                "invalid block range {from_block}..={to_block}"
+            )));
+        }
+        let conn = self.connection();
+        conn.execute(
+            "INSERT INTO relabel_jobs (from_block, to_block, next_block, requested_at)
+             VALUES (?1, ?2, ?1, ?3)",
+            (from_block, to_block, unix_timestamp()?),
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// Get the oldest relabel job that hasn't completed yet.
+    pub fn next_relabel_job(&self) -> Result<Option<RelabelJobData>> {
+        let conn = self.reader();
+        let job = conn
+            .query_row(
+                &format!(
+                    "SELECT {RELABEL_COLUMNS} FROM relabel_jobs
+                     WHERE completed_at IS NULL ORDER BY id ASC LIMIT 1"
+                ),
+                [],
+                relabel_job_from_row,
+            )
+            .optional()?;
+        Ok(job)
+    }
+
+    /// Re-attribute the blob transactions of blocks `from_block..=to_block` and
+    /// record the progress of relabel job `id`, in one transaction. Returns how
+    /// many transactions changed entity.
+    pub fn relabel_blocks(&self, id: u64, from_block: u64, to_block: u64) -> Result<usize> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        let rows: Vec<(String, String, u64, Option<String>)> = tx
+            .prepare(
+                "SELECT tx_hash, sender, block_number, attributed_entity FROM blob_transactions
+                 WHERE block_number BETWEEN ? AND ?",
+            )?
+            .query_map((from_block, to_block), |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        // A sender's entity only changes at the bounds of its dated mappings
+        let mut bounds: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut entities: HashMap<(String, u64), Option<(String, LabelSource)>> = HashMap::new();
+        let mut relabeled = 0;
+        for (tx_hash, sender, block_number, previous) in rows {
+            if !bounds.contains_key(&sender) {
+                bounds.insert(sender.clone(), attribution_bounds(&tx, &sender)?);
+            }
+            let segment = bounds[&sender]
+                .iter()
+                .rev()
+                .find(|&&bound| bound <= block_number)
+                .copied()
+                .unwrap_or(0);
+            let key = (sender, segment);
+            if !entities.contains_key(&key) {
+                let entity = attributed_entity(&tx, &key.0, block_number)?;
+                entities.insert(key.clone(), entity);
+            }
+            let (entity, source) = entities[&key].clone().unzip();
+            if entity != previous {
+                relabeled += 1;
+            }
+            tx.execute(
+                "UPDATE blob_transactions
+                 SET attributed_entity = ?, label_source = ?, registry_version = ?
+                 WHERE tx_hash = ?",
+                (
+                    entity,
+                    source.map(LabelSource::as_str),
+                    REGISTRY_VERSION,
+                    tx_hash,
+                ),
+            )?;
+        }
+
+        let hours: (Option<u64>, Option<u64>) = tx.query_row(
+            "SELECT MIN(created_at), MAX(created_at) FROM blob_transactions
+             WHERE block_number BETWEEN ? AND ?",
+            (from_block, to_block),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if let (Some(first), Some(last)) = hours {
+            refresh_hourly_chain_stats(&tx, first, last)?;
+        }
+
+        tx.execute(
+            "UPDATE relabel_jobs
+             SET next_block = ?2,
+                 relabeled = relabeled + ?3,
+                 registry_version = ?4,
+                 completed_at = CASE WHEN ?2 > to_block THEN ?5 END
+             WHERE id = ?1",
+            (
+                id,
+                to_block + 1,
+                relabeled,
+                REGISTRY_VERSION,
+                unix_timestamp()?,
+            ),
+        )?;
+        tx.commit()?;
+        Ok(relabeled)
+    }
+
+    /// Get relabel jobs, most recent first.
+    pub fn get_relabel_jobs(&self, limit: u64) -> Result<Vec<RelabelJobData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RELABEL_COLUMNS} FROM relabel_jobs ORDER BY id DESC LIMIT ?"
+        ))?;
+
+        let jobs = stmt
+            .query_map([limit], relabel_job_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(jobs)
+    }
+
+    /// Get the next block of the backfill starting at `from_block`, if one was
+    /// checkpointed.
+    pub fn get_backfill_progress(&self, from_block: u64) -> Result<Option<u64>> {
+        let next_block = self
+            .reader()
+            .query_row(
+                "SELECT next_block FROM backfill_progress WHERE from_block = ?",
+                [from_block],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(next_block)
+    }
+
+    /// Checkpoint the backfill starting at `from_block`: every block before
+    /// `next_block` has been indexed.
+    pub fn set_backfill_progress(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        next_block: u64,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO backfill_progress (from_block, to_block, next_block, updated_at)
+             VALUES (?, ?, ?, ?)",
+            (from_block, to_block, next_block, unix_timestamp()?),
+        )?;
+        Ok(())
+    }
+
+    /// Get re-process requests, most recent first.
+    pub fn get_reprocess_requests(&self, limit: u64) -> Result<Vec<ReprocessRequestData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {REPROCESS_COLUMNS} FROM reprocess_requests ORDER BY id DESC LIMIT ?"
+        ))?;
+
+        let requests = stmt
+            .query_map([limit], reprocess_request_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(requests)
+    }
+
+    /// Get the known payload sizes of the blobs of each transaction in a block,
+    /// keyed by tx hash. Transactions with any blob of unknown size are left out.
+    pub fn get_block_blob_sizes(&self, block_number: u64) -> Result<HashMap<String, Vec<u64>>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT h.tx_hash, h.blob_size
+             FROM blob_hashes h
+             JOIN blob_transactions t ON t.tx_hash = h.tx_hash
+             WHERE t.block_number = ?
+             ORDER BY h.tx_hash, h.blob_index",
+        )?;
+
+        let mut sizes: HashMap<String, Option<Vec<u64>>> = HashMap::new();
+        let rows = stmt.query_map([block_number], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<u64>>(1)?))
+        })?;
+        for (tx_hash, size) in rows.flatten() {
+            let entry = sizes.entry(tx_hash).or_insert_with(|| Some(Vec::new()));
+            match (entry.as_mut(), size) {
+                (Some(known), Some(size)) => known.push(size),
+                _ => *entry = None,
+            }
+        }
+
+        Ok(sizes
+            .into_iter()
+            .filter_map(|(tx_hash, sizes)| Some((tx_hash, sizes?)))
+            .collect())
+    }
+
+    /// Recompute all hourly rollups from the blocks table.
+    pub fn recompute_hourly_stats(&self) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        rebuild_hourly_stats(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete mempool snapshots taken before `before_block`. Returns the number
+    /// of rows deleted.
+    pub fn prune_pending_blob_transactions(&self, before_block: u64) -> Result<usize> {
+        let deleted = self.connection().execute(
+            "DELETE FROM pending_blob_transactions WHERE block_number < ?",
+            (before_block,),
+        )?;
+        Ok(deleted)
+    }
+
+    /// Append an operator action to the admin audit log.
+    pub fn record_admin_action(&self, action: &str, details: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO admin_audit_log (action, details, performed_at) VALUES (?, ?, ?)",
+            (action, details, unix_timestamp()?),
+        )?;
+        Ok(())
+    }
+
+    /// Get admin actions, most recent first.
+    pub fn get_admin_audit_log(&self, limit: u64) -> Result<Vec<AdminActionData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, action, details, performed_at
+             FROM admin_audit_log
+             ORDER BY id DESC
+             LIMIT ?",
+        )?;
+
+        let actions = stmt
+            .query_map([limit], |row| {
+                Ok(AdminActionData {
+                    id: row.get(0)?,
+                    action: row.get(1)?,
+                    details: row.get(2)?,
+                    performed_at: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(actions)
+    }
+
+    /// Store an issued API key for alert rules, by its `owner` string.
+    pub fn insert_alert_key(&self, owner: &str, name: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO alert_keys (owner, name, created_at) VALUES (?, ?, ?)",
+            (owner, name, unix_timestamp()?),
+        )?;
+        Ok(())
+    }
+
+    /// Whether `owner` is the owner string of an issued API key.
+    pub fn alert_key_exists(&self, owner: &str) -> Result<bool> {
+        let exists = self.reader().query_row(
+            "SELECT EXISTS (SELECT 1 FROM alert_keys WHERE owner = ?)",
+            [owner],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
+    /// Get the issued API keys, oldest first.
+    pub fn get_alert_keys(&self) -> Result<Vec<AlertKeyData>> {
+        let conn = self.reader();
+
+        let mut stmt =
+            conn.prepare("SELECT owner, name, created_at FROM alert_keys ORDER BY created_at")?;
+
+        let keys = stmt
+            .query_map([], |row| {
+                Ok(AlertKeyData {
+                    owner: row.get(0)?,
+                    name: row.get(1)?,
+                    created_at: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(keys)
+    }
+
+    /// Revoke an API key along with its rules. Returns false if there's no
+    /// such key.
+    pub fn delete_alert_key(&self, owner: &str) -> Result<bool> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        let deleted = tx.execute("DELETE FROM alert_keys WHERE owner = ?", [owner])?;
+        tx.execute("DELETE FROM alert_rules WHERE owner = ?", [owner])?;
+        tx.commit()?;
+        Ok(deleted == 1)
+    }
+
+    /// Store an alert rule for `owner`. `condition` and `delivery` are JSON
+    /// encoded. Returns the rule's id.
+    pub fn insert_alert_rule(&self, owner: &str, condition: &str, delivery: &str) -> Result<u64> {
+        let conn = self.connection();
+        conn.execute(
+            "INSERT INTO alert_rules (owner, condition, delivery, created_at) VALUES (?, ?, ?, ?)",
+            (owner, condition, delivery, unix_timestamp()?),
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// Get the alert rules of `owner`, or of everyone if `None`, oldest first.
+    pub fn get_alert_rules(&self, owner: Option<&str>) -> Result<Vec<AlertRuleData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, owner, condition, delivery, created_at, firing, last_fired_at
+             FROM alert_rules
+             WHERE ?1 IS NULL OR owner = ?1
+             ORDER BY id ASC",
+        )?;
+
+        let rules = stmt
+            .query_map([owner], |row| {
+                Ok(AlertRuleData {
+                    id: row.get(0)?,
+                    owner: row.get(1)?,
+                    condition: row.get(2)?,
+                    delivery: row.get(3)?,
+                    created_at: row.get(4)?,
+                    firing: row.get(5)?,
+                    last_fired_at: row.get(6)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(rules)
+    }
+
+    /// Delete an alert rule of `owner`. Returns false if it has no such rule.
+    pub fn delete_alert_rule(&self, owner: &str, id: u64) -> Result<bool> {
+        let deleted = self.connection().execute(
+            "DELETE FROM alert_rules WHERE id = ? AND owner = ?",
+            (id, owner),
+        )?;
+        Ok(deleted == 1)
+    }
+
+    /// Move an alert rule into or out of the firing state. Returns false if it
+    /// already was in that state, so concurrent evaluators fire it only once.
+    pub fn set_alert_rule_firing(&self, id: u64, firing: bool) -> Result<bool> {
+        let changed = self.connection().execute(
+            "UPDATE alert_rules
+             SET firing = ?1, last_fired_at = CASE WHEN ?1 THEN ?3 ELSE last_fired_at END
+             WHERE id = ?2 AND firing != ?1",
+            (firing, id, unix_timestamp()?),
+        )?;
+        Ok(changed == 1)
+    }
+
+    /// Replace the condition of an alert rule, JSON encoded, and take it out
+    /// of the firing state so the new condition is evaluated afresh. Returns
+    /// false if there's no such rule.
+    pub fn set_alert_rule_condition(&self, id: u64, condition: &str) -> Result<bool> {
+        let updated = self.connection().execute(
+            "UPDATE alert_rules SET condition = ?, firing = 0 WHERE id = ?",
+            (condition, id),
+        )?;
+        Ok(updated == 1)
+    }
+
+    /// Record that an alert rule fired. Returns the event.
+    pub fn record_alert_event(
+        &self,
+        rule_id: u64,
+        owner: &str,
+        message: &str,
+    ) -> Result<AlertEventData> {
+        let fired_at = unix_timestamp()?;
+        let conn = self.connection();
+        conn.execute(
+            "INSERT INTO alert_events (rule_id, owner, message, fired_at) VALUES (?, ?, ?, ?)",
+            (rule_id, owner, message, fired_at),
+        )?;
+        Ok(AlertEventData {
+            id: conn.last_insert_rowid() as u64,
+            rule_id,
+            message: message.to_string(),
+            fired_at,
+        })
+    }
+
+    /// Get alert events of `owner` after event `after_id`, oldest first.
+    pub fn get_alert_events(
+        &self,
+        owner: &str,
+        after_id: u64,
+        limit: u64,
+    ) -> Result<Vec<AlertEventData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, rule_id, message, fired_at
+             FROM alert_events
+             WHERE owner = ? AND id > ?
+             ORDER BY id ASC
+             LIMIT ?",
+        )?;
+
+        let events = stmt
+            .query_map((owner, after_id, limit), |row| {
+                Ok(AlertEventData {
+                    id: row.get(0)?,
+                    rule_id: row.get(1)?,
+                    message: row.get(2)?,
+                    fired_at: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Get the id of the latest alert event, 0 if there is none.
+    pub fn get_latest_alert_event_id(&self) -> Result<u64> {
+        let id = self.reader().query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM alert_events",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// Get the latest post of every sender that posted since `since`.
+    pub fn get_sender_last_posts(&self, since: u64) -> Result<Vec<SenderLastPostData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, attributed_entity, MAX(created_at)
+             FROM blob_transactions
+             WHERE created_at >= ?
+             GROUP BY sender, attributed_entity",
+        )?;
+
+        let posts = stmt
+            .query_map([since], |row| {
+                Ok(SenderLastPostData {
+                    sender: row.get(0)?,
+                    attributed_entity: row.get(1)?,
+                    last_post: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(posts)
+    }
+
+    /// Count blob transactions created since `since` per sender and blob count.
+    pub fn get_sender_blob_counts(&self, since: u64) -> Result<Vec<SenderBlobCountData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, attributed_entity, blob_count, COUNT(*)
+             FROM blob_transactions
+             WHERE created_at >= ?
+             GROUP BY sender, attributed_entity, blob_count",
+        )?;
+
+        let counts = stmt
+            .query_map([since], |row| {
+                Ok(SenderBlobCountData {
+                    sender: row.get(0)?,
+                    attributed_entity: row.get(1)?,
+                    blob_count: row.get(2)?,
+                    tx_count: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(counts)
+    }
+
+    /// Claim the single writer lease for `holder`, unless another holder sent a
+    /// heartbeat within the last `timeout_secs`.
+    pub fn acquire_writer_lease(&self, holder: &str, timeout_secs: u64) -> Result<()> {
+        let now = unix_timestamp()?;
+        let conn = self.connection();
+        let acquired = conn.execute(
+            "INSERT INTO writer_lease (id, holder, acquired_at, heartbeat_at) VALUES (1, ?1, ?2, ?2)
+             ON CONFLICT(id) DO UPDATE SET
+                 holder = excluded.holder,
+                 acquired_at = excluded.acquired_at,
+                 heartbeat_at = excluded.heartbeat_at
+             WHERE holder = excluded.holder OR heartbeat_at < ?3",
+            (holder, now, now.saturating_sub(timeout_secs)),
+        )?;
+
+        if acquired == 0 {
+            let current: String =
+                conn.query_row("SELECT holder FROM writer_lease WHERE id = 1", [], |row| {
+                    row.get(0)
+                })?;
+            return Err(DbError::Busy(format!(
+                "database is already being written by {current}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Send a heartbeat for the writer lease. Returns false if `holder` no
+    /// longer holds it.
+    pub fn renew_writer_lease(&self, holder: &str) -> Result<bool> {
+        let renewed = self.connection().execute(
+            "UPDATE writer_lease SET heartbeat_at = ? WHERE id = 1 AND holder = ?",
+            (unix_timestamp()?, holder),
+        )?;
+        Ok(renewed == 1)
+    }
+
+    /// Record the node's canonical tip as seen by the lease holder, so readers
+    /// can tell how far the index is behind.
+    pub fn set_node_tip(&self, block_number: u64) -> Result<()> {
+        self.connection().execute(
+            "UPDATE writer_lease SET node_tip = ? WHERE id = 1",
+            (block_number,),
+        )?;
+        Ok(())
+    }
+
+    /// Get the current (possibly expired) writer lease.
+    pub fn get_writer_lease(&self) -> Result<Option<WriterLeaseData>> {
+        let lease = self
+            .reader()
+            .query_row(
+                "SELECT holder, acquired_at, heartbeat_at, node_tip FROM writer_lease WHERE id = 1",
+                [],
+                |row| {
+                    Ok(WriterLeaseData {
+                        holder: row.get(0)?,
+                        acquired_at: row.get(1)?,
+                        heartbeat_at: row.get(2)?,
+                        node_tip: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(lease)
+    }
+
+    /// Sample indexed blocks to verify against the node: the `recent` latest
+    /// blocks at least `depth` below the latest indexed one, plus `random` blocks
+    /// picked uniformly below those. Blocks without a recorded hash (indexed by
+    /// older versions) are left out.
+    pub fn sample_block_hashes(
+        &self,
+        depth: u64,
+        recent: u64,
+        random: u64,
+    ) -> Result<Vec<(u64, String)>> {
+        let conn = self.reader();
+        let bounds: (Option<u64>, Option<u64>) = conn.query_row(
+            "SELECT MIN(block_number), MAX(block_number) FROM blocks",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let (Some(first), Some(latest)) = bounds else {
+            return Ok(Vec::new());
+        };
+        let Some(last) = latest.checked_sub(depth).filter(|&last| last >= first) else {
+            return Ok(Vec::new());
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT block_number, block_hash FROM blocks
+             WHERE block_number <= ? AND block_hash IS NOT NULL
+             ORDER BY block_number DESC LIMIT ?",
+        )?;
+        let mut samples: Vec<(u64, String)> = stmt
+            .query_map((last, recent), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        // Random samples come from below the recent ones
+        let older = match samples.last() {
+            Some((number, _)) => number.checked_sub(1),
+            None => Some(last),
+        };
+        if let Some(older) = older.filter(|&older| older >= first) {
+            let mut stmt = conn.prepare(
+                "SELECT block_number, block_hash FROM blocks
+                 WHERE block_number >= (SELECT ?1 + ABS(RANDOM()) % (?2 - ?1 + 1))
+                   AND block_number <= ?2 AND block_hash IS NOT NULL
+                 ORDER BY block_number LIMIT 1",
+            )?;
+            for _ in 0..random {
+                if let Some(sample) = stmt
+                    .query_row((first, older), |row| Ok((row.get(0)?, row.get(1)?)))
+                    .optional()?
+                {
+                    samples.push(sample);
+                }
+            }
+        }
+
+        samples.sort_unstable();
+        samples.dedup();
+        Ok(samples)
+    }
+
+    /// Record a round of canonical chain verification, with the blocks found
+    /// off the node's canonical chain (and repaired).
+    pub fn record_canonical_check(&self, blocks_checked: u64, mismatched: &[u64]) -> Result<()> {
+        let now = unix_timestamp()?;
+        let last_mismatch = mismatched.iter().max();
+        self.connection().execute(
+            "INSERT INTO canonical_checks
+                 (id, checked_at, blocks_checked, mismatches, total_mismatches,
+                  last_mismatch_block, last_mismatch_at)
+             VALUES (1, ?1, ?2, ?3, ?3, ?4, CASE WHEN ?4 IS NULL THEN NULL ELSE ?1 END)
+             ON CONFLICT(id) DO UPDATE SET
+                 checked_at = excluded.checked_at,
+                 blocks_checked = excluded.blocks_checked,
+                 mismatches = excluded.mismatches,
+                 total_mismatches = total_mismatches + excluded.mismatches,
+                 last_mismatch_block = COALESCE(excluded.last_mismatch_block, last_mismatch_block),
+                 last_mismatch_at = COALESCE(excluded.last_mismatch_at, last_mismatch_at)",
+            (now, blocks_checked, mismatched.len() as u64, last_mismatch),
+        )?;
+        Ok(())
+    }
+
+    /// Get the outcome of the latest canonical chain verification, if any ran.
+    pub fn get_canonical_check(&self) -> Result<Option<CanonicalCheckData>> {
+        let check = self
+            .reader()
+            .query_row(
+                "SELECT checked_at, blocks_checked, mismatches, total_mismatches,
+                        last_mismatch_block, last_mismatch_at
+                 FROM canonical_checks WHERE id = 1",
+                [],
+                |row| {
+                    Ok(CanonicalCheckData {
+                        checked_at: row.get(0)?,
+                        blocks_checked: row.get(1)?,
+                        mismatches: row.get(2)?,
+                        total_mismatches: row.get(3)?,
+                        last_mismatch_block: row.get(4)?,
+                        last_mismatch_at: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(check)
+    }
+
+    /// Record a round of the sender stats check, with the senders found to
+    /// have drifted and whether their stats were rebuilt.
+    pub fn record_sender_check(&self, drift: &[SenderDriftData], repaired: bool) -> Result<()> {
+        let now = unix_timestamp()?;
+        let (tx_count_drift, blob_drift) = drift.iter().fold((0, 0), |(txs, blobs), d| {
+            (
+                txs + d.stored.tx_count.abs_diff(d.derived.tx_count),
+                blobs + d.stored.total_blobs.abs_diff(d.derived.total_blobs),
+            )
+        });
+        self.connection().execute(
+            "INSERT INTO sender_checks
+                 (id, checked_at, drifted_senders, tx_count_drift, blob_drift,
+                  repaired, total_repairs, last_repair_at)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?5, CASE WHEN ?5 THEN ?1 END)
+             ON CONFLICT(id) DO UPDATE SET
+                 checked_at = excluded.checked_at,
+                 drifted_senders = excluded.drifted_senders,
+                 tx_count_drift = excluded.tx_count_drift,
+                 blob_drift = excluded.blob_drift,
+                 repaired = excluded.repaired,
+                 total_repairs = total_repairs + excluded.repaired,
+                 last_repair_at = COALESCE(excluded.last_repair_at, last_repair_at)",
+            (
+                now,
+                drift.len() as u64,
+                tx_count_drift,
+                blob_drift,
+                repaired,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Get the outcome of the latest sender stats check, if any ran.
+    pub fn get_sender_check(&self) -> Result<Option<SenderCheckData>> {
+        let check = self
+            .reader()
+            .query_row(
+                "SELECT checked_at, drifted_senders, tx_count_drift, blob_drift,
+                        repaired, total_repairs, last_repair_at
+                 FROM sender_checks WHERE id = 1",
+                [],
+                |row| {
+                    Ok(SenderCheckData {
+                        checked_at: row.get(0)?,
+                        drifted_senders: row.get(1)?,
+                        tx_count_drift: row.get(2)?,
+                        blob_drift: row.get(3)?,
+                        repaired: row.get(4)?,
+                        total_repairs: row.get(5)?,
+                        last_repair_at: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(check)
+    }
+
+    /// Run a caller-supplied `SELECT` on a separate read-only connection, so
+    /// ad-hoc queries don't hold up the API or the writer.
+    ///
+    /// An authorizer refuses anything but reads, as well as reads of
+    /// [`PRIVATE_TABLES`]. The query is interrupted once it runs longer than
+    /// `timeout`, and at most `max_rows` rows are returned. Errors in the query
+    /// itself are [`DbError::InvalidInput`].
+    pub fn query_read_only(
+        &self,
+        sql: &str,
+        max_rows: usize,
+        timeout: Duration,
+    ) -> Result<QueryResultData> {
+        let path: String = self.connection().query_row(
+            "SELECT file FROM pragma_database_list WHERE name = 'main'",
+            [],
+            |row| row.get(0),
+        )?;
+        if path.is_empty() {
+            return Err(DbError::InvalidInput(
+                "ad-hoc queries need a database file".to_string(),
+            ));
+        }
+
+        let conn = Connection::open_with_flags(
+            &path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        register_functions(&conn)?;
+        conn.authorizer(Some(|ctx: AuthContext<'_>| match ctx.action {
+            AuthAction::Select | AuthAction::Recursive => Authorization::Allow,
+            AuthAction::Read { table_name, .. } if !PRIVATE_TABLES.contains(&table_name) => {
+                Authorization::Allow
+            }
+            AuthAction::Function { function_name } if function_name != "load_extension" => {
+                Authorization::Allow
+            }
+            _ => Authorization::Deny,
+        }));
+        let started = Instant::now();
+        conn.progress_handler(1000, Some(move || started.elapsed() > timeout));
+
+        let query_error = |err: rusqlite::Error| match err {
+            rusqlite::Error::SqliteFailure(failure, _)
+                if failure.code == ErrorCode::OperationInterrupted =>
+            {
+                DbError::InvalidInput(format!("query ran longer than {}s", timeout.as_secs()))
+            }
+            err => match DbError::from(err) {
+                DbError::Schema(message) => DbError::InvalidInput(message),
+                err => err,
+            },
+        };
+
+        let mut stmt = conn.prepare(sql).map_err(query_error)?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query([]).map_err(query_error)?;
+        let mut result = QueryResultData {
+            columns,
+            rows: Vec::new(),
+            truncated: false,
+        };
+        while let Some(row) = rows.next().map_err(query_error)? {
+            if result.rows.len() == max_rows {
+                result.truncated = true;
+                break;
+            }
+            let values = (0..result.columns.len())
+                .map(|i| row.get::<_, Value>(i))
+                .collect::<rusqlite::Result<_>>()
+                .map_err(query_error)?;
+            result.rows.push(values);
+        }
+        Ok(result)
+    }
+
+    /// Write a consistent copy of the database to `path`, which must not exist.
+    ///
+    /// The copy is taken in a single backup step, i.e. under one read
+    /// transaction, so a writer in another process neither blocks on it nor
+    /// forces it to restart. It's written next to `path` and renamed into
+    /// place, so `path` only ever holds a complete snapshot.
+    pub fn snapshot(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            return Err(DbError::InvalidInput(format!(
+                "{} already exists",
+                path.display()
+            )));
+        }
+
+        let partial = path.with_extension("partial");
+        let copied = Connection::open(&partial)
+            .map_err(DbError::from)
+            .and_then(|mut destination| copy_database(&self.connection(), &mut destination));
+        if let Err(err) = copied {
+            let _ = std::fs::remove_file(&partial);
+            return Err(err);
+        }
+
+        std::fs::rename(&partial, path).map_err(|err| {
+            DbError::Io(format!(
+                "failed to move snapshot into {}: {err}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Replace the contents of the database with the snapshot at `path`.
+    ///
+    /// The snapshot is integrity-checked first, and migrated and verified
+    /// afterwards in case it was taken by an older version. Must not run while the ExEx is
+    /// writing to this database.
+    pub fn restore(&self, path: &Path) -> Result<()> {
+        if !path.is_file() {
+            return Err(DbError::NotFound(format!(
+                "no snapshot at {}",
+                path.display()
+            )));
+        }
+
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let check: String = source.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if check != "ok" {
+            return Err(DbError::Corrupt(format!(
+                "snapshot {} failed integrity check: {check}",
+                path.display()
+            )));
+        }
+        check_schema_version(&source)?;
+
+        copy_database(&source, &mut self.connection())?;
+
+        self.prepare_schema()
+    }
+
+    /// Move blob transactions created before the UTC day of `before` (a UNIX
+    /// timestamp), with their blob hashes, into monthly archive databases in
+    /// `dir` named `blob_archive-<YYYY-MM>.db`, recorded in the `archives`
+    /// manifest. Returns the manifest entries of the months touched.
+    ///
+    /// Blocks, rollups and sender stats stay, so charts keep covering archived
+    /// months, and archived transactions are still found by
+    /// [`Database::get_archived_blob_transaction`]. Rollups of archived days
+    /// are left as they are by rebuilds from the indexed rows, and the sender
+    /// stats of archived transactions are kept in `archived_senders`, which
+    /// rebuilds of sender stats add back.
+    ///
+    /// Each month is moved in its own transaction and rows already archived
+    /// are skipped, so an interrupted run can be repeated. The file only
+    /// shrinks after [`Database::vacuum`].
+    pub fn archive(&self, before: u64, dir: &Path) -> Result<Vec<ArchiveData>> {
+        let dir = dir.canonicalize().map_err(|err| {
+            DbError::InvalidInput(format!("archive directory {}: {err}", dir.display()))
+        })?;
+        // Whole days, so no hourly rollup is left half archived
+        let before = group_by_day(before, 0);
+
+        let conn = self.connection();
+        let months: Vec<String> = conn
+            .prepare(
+                "SELECT DISTINCT strftime('%Y-%m', created_at, 'unixepoch')
+                 FROM blob_transactions
+                 WHERE created_at < ?
+                 ORDER BY 1",
+            )?
+            .query_map([before], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for month in &months {
+            let path = dir.join(format!("blob_archive-{month}.db"));
+            conn.execute("ATTACH DATABASE ? AS archive", [path.to_string_lossy()])?;
+            let archived = archive_month(&conn, month, before, &path);
+            conn.execute_batch("DETACH DATABASE archive")?;
+            archived?;
+        }
+        drop(conn);
+
+        Ok(self
+            .get_archives()?
+            .into_iter()
+            .filter(|archive| months.contains(&archive.month))
+            .collect())
+    }
+
+    /// Get the `archives` manifest, oldest month first.
+    pub fn get_archives(&self) -> Result<Vec<ArchiveData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT month, path, tx_count, blob_count, first_tx_at, last_tx_at, archived_at
+             FROM archives
+             ORDER BY month",
+        )?;
+        let archives = stmt
+            .query_map([], |row| {
+                Ok(ArchiveData {
+                    month: row.get(0)?,
+                    path: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    blob_count: row.get(3)?,
+                    first_tx_at: row.get(4)?,
+                    last_tx_at: row.get(5)?,
+                    archived_at: row.get(6)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(archives)
+    }
+
+    /// Get an archived blob transaction by hash, searching the archives in
+    /// the manifest newest first. Archives missing from disk are skipped.
+    pub fn get_archived_blob_transaction(
+        &self,
+        tx_hash: &str,
+    ) -> Result<Option<BlobTransactionData>> {
+        for archive in self.get_archives()?.into_iter().rev() {
+            let Ok(conn) =
+                Connection::open_with_flags(&archive.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            else {
+                continue;
+            };
+            // Archives created before tx types were recorded lack the column
+            let has_tx_type = conn
+                .prepare(
+                    "SELECT 1 FROM pragma_table_info('blob_transactions') WHERE name = 'tx_type'",
+                )?
+                .exists([])?;
+            let txs = query_blob_transactions(
+                &conn,
+                &format!(
+                    "SELECT tx_hash, block_number, sender, blob_count, gas_price, attributed_entity,
+                            label_source, {}
+                     FROM blob_transactions
+                     WHERE tx_hash = ?",
+                    if has_tx_type { "tx_type" } else { "NULL" }
+                ),
+                [tx_hash],
+            )?;
+            if let Some(tx) = txs.into_iter().next() {
+                return Ok(Some(tx));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Rebuild the database file to release the pages of deleted rows, e.g.
+    /// after [`Database::archive`]. Writers wait until it's done.
+    pub fn vacuum(&self) -> Result<()> {
+        self.connection().execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Copy the WAL into the database file and truncate it.
+    ///
+    /// Readers in other processes still using WAL frames keep the checkpoint
+    /// from completing, it's then retried on the next call.
+    pub fn checkpoint_wal(&self) -> Result<WalCheckpointData> {
+        let (busy, wal_frames, checkpointed_frames): (bool, i64, i64) = self
+            .connection()
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+        Ok(WalCheckpointData {
+            complete: !busy,
+            wal_frames: wal_frames.max(0) as u64,
+            checkpointed_frames: checkpointed_frames.max(0) as u64,
+        })
+    }
+
+    /// Sizes of the database file and its WAL, `None` for in-memory databases.
+    pub fn file_sizes(&self) -> Result<Option<DbFileSizes>> {
+        let conn = self.reader();
+        let path: String = conn.query_row(
+            "SELECT file FROM pragma_database_list WHERE name = 'main'",
+            [],
+            |row| row.get(0),
+        )?;
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        let wal_bytes = match std::fs::metadata(format!("{path}-wal")) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(DbError::Io(format!("failed to read {path}-wal: {err}"))),
+        };
+        Ok(Some(DbFileSizes {
+            db_bytes: page_count * page_size,
+            wal_bytes,
+        }))
+    }
+
+    /// Replace the operator-configured entity of each address and re-attribute
+    /// the stored blob transactions of the addresses whose entity changed.
+    /// Transactions outside the block range of an address's entity aren't
+    /// attributed to it.
+    pub fn replace_entity_addresses(
+        &self,
+        entities: &HashMap<Address, EntityAddress>,
+    ) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        let stored: HashMap<String, (String, Option<u64>, Option<u64>)> = tx
+            .prepare("SELECT address, entity, from_block, to_block FROM entity_addresses")?
+            .query_map([], |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?)))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        let configured: HashMap<String, (String, Option<u64>, Option<u64>)> = entities
+            .iter()
+            .map(|(address, entity)| {
+                (
+                    address_key(address),
+                    (entity.entity.clone(), entity.from_block, entity.to_block),
+                )
+            })
+            .collect();
+
+        let changed: BTreeSet<&String> = stored
+            .keys()
+            .chain(configured.keys())
+            .filter(|address| stored.get(*address) != configured.get(*address))
+            .collect();
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        for address in changed {
+            tx.execute("DELETE FROM entity_addresses WHERE address = ?", [address])?;
+            if let Some((entity, from_block, to_block)) = configured.get(address) {
+                tx.execute(
+                    "INSERT INTO entity_addresses (address, entity, from_block, to_block)
+                     VALUES (?, ?, ?, ?)",
+                    (address, entity, from_block, to_block),
+                )?;
+            }
+            reattribute_sender(&tx, address)?;
+            refresh_sender_chain_stats(&tx, address)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Label `address` as `label`, overriding its entity from the registry,
+    /// `BLOB_ENTITY_MAP` and funding transfers, and re-attribute its stored blob
+    /// transactions.
+    pub fn set_sender_label(&self, address: &Address, label: &str) -> Result<()> {
+        let label = label.trim();
+        if label.is_empty() || label.len() > MAX_LABEL_LEN {
+            return Err(DbError::InvalidInput(format!(
+                "label must be 1 to {MAX_LABEL_LEN} bytes"
+            )));
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        let address = address_key(address);
+        tx.execute(
+            "INSERT OR REPLACE INTO sender_labels (address, label, updated_at) VALUES (?, ?, ?)",
+            (&address, label, now),
+        )?;
+        reattribute_sender(&tx, &address)?;
+        refresh_sender_chain_stats(&tx, &address)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Add an annotation. Returns its id.
+    pub fn insert_annotation(&self, annotation: &NewAnnotation<'_>) -> Result<u64> {
+        annotation.validate()?;
+        let conn = self.connection();
+        conn.execute(
+            "INSERT INTO annotations (timestamp, kind, title, description, updated_at)
+             VALUES (?, ?, ?, ?, ?)",
+            (
+                annotation.timestamp,
+                annotation.kind.trim(),
+                annotation.title.trim(),
+                annotation.description,
+                unix_timestamp()?,
+            ),
+        )?;
+        Ok(conn.last_insert_rowid() as u64)
+    }
+
+    /// Replace annotation `id`. Returns whether it exists.
+    pub fn update_annotation(&self, id: u64, annotation: &NewAnnotation<'_>) -> Result<bool> {
+        annotation.validate()?;
+        let updated = self.connection().execute(
+            "UPDATE annotations
+             SET timestamp = ?, kind = ?, title = ?, description = ?, updated_at = ?
+             WHERE id = ?",
+            (
+                annotation.timestamp,
+                annotation.kind.trim(),
+                annotation.title.trim(),
+                annotation.description,
+                unix_timestamp()?,
+                id,
+            ),
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Remove annotation `id`. Returns whether it existed.
+    pub fn delete_annotation(&self, id: u64) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM annotations WHERE id = ?", [id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Get the annotations of `from..=to`, oldest first.
+    pub fn get_annotations(&self, from: u64, to: u64, limit: u64) -> Result<Vec<AnnotationData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(&format!(
+            "{ANNOTATIONS_SQL} WHERE timestamp BETWEEN ? AND ? ORDER BY timestamp, id LIMIT ?"
+        ))?;
+        let annotations = stmt
+            .query_map((from, to, limit), annotation_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(annotations)
+    }
+
+    /// Get the annotations whose first block falls in
+    /// `from_block..=to_block`, oldest first.
+    pub fn get_block_annotations(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<AnnotationData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM ({ANNOTATIONS_SQL})
+             WHERE block_number BETWEEN ? AND ?
+             ORDER BY timestamp, id"
+        ))?;
+        let annotations = stmt
+            .query_map((from_block, to_block), annotation_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(annotations)
+    }
+
+    /// Remove the label of `address`, falling back to its other attributions.
+    /// Returns whether it had one.
+    pub fn delete_sender_label(&self, address: &Address) -> Result<bool> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        let address = address_key(address);
+        let deleted = tx.execute("DELETE FROM sender_labels WHERE address = ?", [&address])?;
+        reattribute_sender(&tx, &address)?;
+        refresh_sender_chain_stats(&tx, &address)?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Get every sender label, most recently set first.
+    pub fn get_sender_labels(&self) -> Result<Vec<SenderLabelData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT address, label, updated_at FROM sender_labels ORDER BY updated_at DESC, address",
+        )?;
+        let labels = stmt
+            .query_map([], |row| {
+                Ok(SenderLabelData {
+                    address: row.get(0)?,
+                    label: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(labels)
+    }
+
+    /// Record an ETH transfer from `funder` to `recipient`. It is only kept if
+    /// `funder` is attributed to an entity, in which case `recipient` and its
+    /// unattributed blob transactions inherit that entity. Returns whether the
+    /// transfer was kept.
+    pub fn record_funding_transfer(
+        &self,
+        tx_hash: &str,
+        block_number: u64,
+        funder: &Address,
+        recipient: &Address,
+    ) -> Result<bool> {
+        let conn = self.connection();
+        let Some((entity, _)) = attributed_entity(&conn, &address_key(funder), block_number)?
+        else {
+            return Ok(false);
+        };
+        let recipient = address_key(recipient);
+        conn.execute(
+            "INSERT OR REPLACE INTO funding_transfers (tx_hash, block_number, funder, recipient, entity)
+             VALUES (?, ?, ?, ?, ?)",
+            (tx_hash, block_number, address_key(funder), &recipient, &entity),
+        )?;
+        let inherited = conn.execute(
+            "UPDATE blob_transactions SET attributed_entity = ?, label_source = ?
+             WHERE sender = ? AND attributed_entity IS NULL",
+            (&entity, LabelSource::Heuristic.as_str(), &recipient),
+        )?;
+        if inherited > 0 {
+            refresh_sender_chain_stats(&conn, &recipient)?;
+        }
+        Ok(true)
+    }
+
+    /// Delete funding transfers recorded for a block.
+    pub fn delete_funding_transfers(&self, block_number: u64) -> Result<usize> {
+        let deleted = self.connection().execute(
+            "DELETE FROM funding_transfers WHERE block_number = ?",
+            (block_number,),
+        )?;
+        Ok(deleted)
+    }
+
+    /// Record a frame decoded from blob `blob_index` of a transaction.
+    pub fn insert_op_frame(&self, frame: &NewOpFrame<'_>) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO op_frames
+             (tx_hash, blob_index, channel_id, frame_number, block_number, frame_size, is_last)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                frame.tx_hash,
+                frame.blob_index,
+                frame.channel_id,
+                frame.frame_number,
+                frame.block_number,
+                frame.frame_size,
+                frame.is_last,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Record a channel whose frames all came in one transaction.
+    pub fn insert_op_channel(&self, channel: &NewOpChannel<'_>) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO op_channels
+             (channel_id, tx_hash, block_number, frame_count, compressed_size,
+              decompressed_size, compression)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                channel.channel_id,
+                channel.tx_hash,
+                channel.block_number,
+                channel.frame_count,
+                channel.compressed_size,
+                channel.decompressed_size,
+                channel.compression,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Delete OP Stack frames and channels decoded from a block (for reverts).
+    pub fn delete_op_batches(&self, block_number: u64) -> Result<()> {
+        let conn = self.connection();
+        conn.execute(
+            "DELETE FROM op_frames WHERE block_number = ?",
+            (block_number,),
+        )?;
+        conn.execute(
+            "DELETE FROM op_channels WHERE block_number = ?",
+            (block_number,),
+        )?;
+        Ok(())
+    }
+
+    /// Store the EIP-2718 envelope of a blob transaction included in
+    /// `block_number`, without its sidecar.
+    pub fn insert_raw_transaction(
+        &self,
+        tx_hash: &str,
+        block_number: u64,
+        raw: &[u8],
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO tx_raw (tx_hash, block_number, raw) VALUES (?, ?, ?)",
+            (tx_hash, block_number, raw),
+        )?;
+        Ok(())
+    }
+
+    /// Delete the stored envelopes of a block's transactions (for reverts).
+    pub fn delete_raw_transactions(&self, block_number: u64) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM tx_raw WHERE block_number = ?", (block_number,))?;
+        Ok(())
+    }
+
+    /// Get the stored EIP-2718 envelope of a blob transaction, `None` unless
+    /// it was indexed with envelopes stored.
+    pub fn get_raw_transaction(&self, tx_hash: &str) -> Result<Option<Vec<u8>>> {
+        let raw = self
+            .reader()
+            .query_row(
+                "SELECT raw FROM tx_raw WHERE tx_hash = ?",
+                [tx_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(raw)
+    }
+
+    /// Get decoded OP Stack frames and channels per sender, for transactions
+    /// since `since`.
+    pub fn get_op_batch_stats(&self, since: u64) -> Result<Vec<OpBatchStatsData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, attributed_entity, SUM(blobs), SUM(frames), SUM(frame_bytes),
+                    SUM(channels), SUM(channel_frames), SUM(compressed_size),
+                    SUM(decompressed_size), SUM(decompressed_from)
+             FROM (
+                 SELECT t.sender, t.attributed_entity,
+                        COUNT(DISTINCT f.tx_hash || ':' || f.blob_index) AS blobs,
+                        COUNT(*) AS frames, SUM(f.frame_size) AS frame_bytes,
+                        0 AS channels, 0 AS channel_frames, 0 AS compressed_size,
+                        0 AS decompressed_size, 0 AS decompressed_from
+                 FROM op_frames f
+                 JOIN blob_transactions t ON t.tx_hash = f.tx_hash
+                 WHERE t.created_at >= ?1
+                 GROUP BY t.sender, t.attributed_entity
+                 UNION ALL
+                 SELECT t.sender, t.attributed_entity, 0, 0, 0,
+                        COUNT(*), SUM(c.frame_count), SUM(c.compressed_size),
+                        COALESCE(SUM(c.decompressed_size), 0),
+                        COALESCE(SUM(CASE WHEN c.decompressed_size IS NOT NULL
+                                          THEN c.compressed_size END), 0)
+                 FROM op_channels c
+                 JOIN blob_transactions t ON t.tx_hash = c.tx_hash
+                 WHERE t.created_at >= ?1
+                 GROUP BY t.sender, t.attributed_entity
+             )
+             GROUP BY sender, attributed_entity",
+        )?;
+
+        let stats = stmt
+            .query_map([since], |row| {
+                Ok(OpBatchStatsData {
+                    sender: row.get(0)?,
+                    attributed_entity: row.get(1)?,
+                    blobs: row.get(2)?,
+                    frames: row.get(3)?,
+                    frame_bytes: row.get(4)?,
+                    channels: row.get(5)?,
+                    channel_frames: row.get(6)?,
+                    compressed_size: row.get(7)?,
+                    decompressed_size: row.get(8)?,
+                    decompressed_from: row.get(9)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(stats)
+    }
+
+    /// Replace the stored blob schedule (seeded by the ExEx from its chain spec),
+    /// correcting the target and max of blocks indexed under another one.
+    pub fn replace_blob_schedule(&self, schedule: &BlobSchedule) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+        tx.execute("DELETE FROM blob_schedule", ())?;
+        for entry in schedule.entries() {
+            tx.execute(
+                "INSERT INTO blob_schedule VALUES (?, ?, ?, ?)",
+                (
+                    entry.activation_timestamp,
+                    entry.target,
+                    entry.max,
+                    entry.base_fee_update_fraction,
+                ),
+            )?;
+        }
+        fill_block_blob_params(&tx, schedule)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replace the stored regime thresholds (seeded by the ExEx from
+    /// `BLOB_REGIME_THRESHOLDS` or its blob schedule).
+    pub fn replace_regime_thresholds(&self, thresholds: &RegimeThresholds) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO regime_thresholds VALUES (0, ?, ?, ?, ?)",
+            (
+                thresholds.normal,
+                thresholds.pressured,
+                thresholds.congested,
+                thresholds.saturated,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Get the stored regime thresholds, falling back to the
+    /// `BLOB_REGIME_THRESHOLDS` env var and then to those of the stored blob
+    /// schedule if the ExEx hasn't seeded them yet.
+    pub fn get_regime_thresholds(&self) -> Result<RegimeThresholds> {
+        let conn = self.reader();
+        let stored = conn
+            .query_row(
+                "SELECT normal, pressured, congested, saturated FROM regime_thresholds",
+                [],
+                |row| {
+                    Ok(RegimeThresholds {
+                        normal: row.get(0)?,
+                        pressured: row.get(1)?,
+                        congested: row.get(2)?,
+                        saturated: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        match stored {
+            Some(thresholds) => Ok(thresholds),
+            None => match RegimeThresholds::from_env()
+                .map_err(|err| DbError::Schema(format!("{err:#}")))?
+            {
+                Some(thresholds) => Ok(thresholds),
+                None => Ok(RegimeThresholds::for_schedule(&blob_schedule(&conn)?)),
+            },
+        }
+    }
+
+    /// Get the blocks of each fee recipient since a timestamp, most blocks
+    /// first. Blocks indexed before fee recipients were recorded are left out.
+    pub fn get_builder_stats(&self, since: u64) -> Result<Vec<BuilderStatsData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT beneficiary, COUNT(*), SUM(total_blobs),
+                    AVG(total_blobs * 100.0 / NULLIF(blob_target, 0)),
+                    SUM(total_blobs = 0), SUM(total_blobs >= blob_max)
+             FROM blocks
+             WHERE block_timestamp >= ? AND beneficiary IS NOT NULL
+             GROUP BY beneficiary
+             ORDER BY COUNT(*) DESC, beneficiary",
+        )?;
+
+        let builders = stmt
+            .query_map([since], |row| {
+                Ok(BuilderStatsData {
+                    beneficiary: row.get(0)?,
+                    blocks: row.get(1)?,
+                    total_blobs: row.get(2)?,
+                    avg_target_utilization: row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+                    empty_blocks: row.get(4)?,
+                    full_blocks: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(builders)
+    }
+
+    /// Get the `limit` latest observed blob schedule changes, newest first.
+    pub fn get_fork_events(&self, limit: u64) -> Result<Vec<ForkEventData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT block_number, block_timestamp, previous_target, previous_max, target, max
+             FROM fork_events
+             ORDER BY block_number DESC
+             LIMIT ?",
+        )?;
+        let events = stmt
+            .query_map([limit], |row| {
+                Ok(ForkEventData {
+                    block_number: row.get(0)?,
+                    block_timestamp: row.get(1)?,
+                    previous_target: row.get(2)?,
+                    previous_max: row.get(3)?,
+                    target: row.get(4)?,
+                    max: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(events)
+    }
+
+    /// Get the stored blob schedule, falling back to the `BLOB_SCHEDULE` env var
+    /// and then to mainnet if the ExEx hasn't seeded it yet.
+    pub fn get_blob_schedule(&self) -> Result<BlobSchedule> {
+        blob_schedule(&self.reader())
+    }
+
+    /// Get the all-time records, see [`RecordData`].
+    pub fn get_records(&self) -> Result<Vec<RecordData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT kind, value, from_block, to_block, sender, day FROM records WHERE kind != ?",
+        )?;
+
+        let records = stmt
+            .query_map([CURRENT_SATURATED_STREAK], |row| {
+                Ok(RecordData {
+                    kind: row.get(0)?,
+                    value: row.get::<_, Wei>(1)?.0,
+                    from_block: row.get(2)?,
+                    to_block: row.get(3)?,
+                    sender: row.get(4)?,
+                    day: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(records)
+    }
+
+    /// Get overall statistics.
+    pub fn get_stats(&self) -> Result<Stats> {
+        let conn = self.reader();
+
+        let total_blocks: u64 = conn
+            .query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))
+            .unwrap_or(0);
+
+        let total_blobs: u64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(blob_count), 0) FROM blob_transactions",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+
+        let total_transactions: u64 = conn
+            .query_row("SELECT COALESCE(SUM(tx_count), 0) FROM blocks", [], |row| {
+                row.get(0)
+            })
+            .unwrap_or(0);
+
+        let latest_block: Option<u64> = conn
+            .query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))
+            .ok();
+
+        let earliest_block: Option<u64> = conn
+            .query_row("SELECT MIN(block_number) FROM blocks", [], |row| row.get(0))
+            .ok();
+
+        let latest_gas_price = conn
+            .query_row(
+                "SELECT gas_price FROM blocks ORDER BY block_number DESC LIMIT 1",
+                [],
+                |row| row.get::<_, Wei>(0),
+            )
+            .map_or(0, |wei| wei.0);
 
         let avg_blobs_per_block = if total_blocks > 0 {
             total_blobs as f64 / total_blocks as f64
@@ -246,419 +3507,3204 @@ impl Database {
             0.0
         };
 
-        Ok(Stats {
-            total_blocks,
-            total_blobs,
-            total_transactions,
-            avg_blobs_per_block,
-            latest_block,
-            earliest_block,
-            latest_gas_price,
+        let throughput = slot_throughput(&conn, 0)?;
+
+        // Only txs indexed with their EL size are comparable
+        let (total_el_bytes, total_da_bytes, total_payload_bytes): (u64, u64, u64) = conn
+            .query_row(
+                "SELECT COALESCE(SUM(el_size), 0),
+                        COALESCE(SUM(blob_count), 0) * ?,
+                        COALESCE(SUM(payload_size), 0)
+                 FROM blob_transactions WHERE el_size IS NOT NULL",
+                [BLOB_SIZE_BYTES],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap_or((0, 0, 0));
+
+        let (total_blob_refs, distinct_daily_blobs): (u64, u64) = conn
+            .query_row(
+                "SELECT COALESCE(SUM(blob_refs), 0), COALESCE(SUM(distinct_blobs), 0)
+                 FROM daily_blob_hashes",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap_or((0, 0));
+
+        Ok(Stats {
+            total_blocks,
+            total_blobs,
+            total_transactions,
+            avg_blobs_per_block,
+            latest_block,
+            earliest_block,
+            latest_gas_price,
+            total_slots: throughput.as_ref().map_or(0, SlotThroughputData::slots),
+            missed_slots: throughput
+                .as_ref()
+                .map_or(0, SlotThroughputData::missed_slots),
+            avg_blobs_per_slot: throughput
+                .as_ref()
+                .map_or(0.0, SlotThroughputData::blobs_per_slot),
+            total_el_bytes,
+            total_da_bytes,
+            total_payload_bytes,
+            total_blob_refs,
+            distinct_daily_blobs,
+        })
+    }
+
+    /// Get slot-based throughput of blocks with a timestamp of at least
+    /// `since`, or `None` if there are none.
+    pub fn get_slot_throughput(&self, since: u64) -> Result<Option<SlotThroughputData>> {
+        slot_throughput(&self.reader(), since)
+    }
+
+    /// Get the highest DA bandwidth of a single indexed block with a timestamp
+    /// of at least `since`, in bytes per second: its blobs over the time since
+    /// its parent. `None` if no block's parent is indexed.
+    pub fn get_peak_bandwidth(&self, since: u64) -> Result<Option<f64>> {
+        let peak = self.reader().query_row(
+            "SELECT MAX(total_blobs * ?2 * 1.0 / block_interval) FROM blocks
+             WHERE block_timestamp >= ?1 AND block_interval > 0",
+            (since, BLOB_SIZE_BYTES),
+            |row| row.get(0),
+        )?;
+        Ok(peak)
+    }
+
+    /// Get the distribution of intervals between consecutive indexed blocks
+    /// with a timestamp of at least `since`, or `None` if there are none.
+    pub fn get_block_intervals(&self, since: u64) -> Result<Option<BlockIntervalData>> {
+        let conn = self.reader();
+
+        // Intervals are multiples of the slot time, so there are few distinct ones
+        let mut stmt = conn.prepare(
+            "SELECT block_interval, COUNT(*) FROM blocks
+             WHERE block_timestamp >= ? AND block_interval IS NOT NULL
+             GROUP BY block_interval
+             ORDER BY block_interval ASC",
+        )?;
+        let counts: Vec<(u64, u64)> = stmt
+            .query_map([since], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let total: u64 = counts.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return Ok(None);
+        }
+        let percentile = |p: f64| {
+            let rank = ((total as f64 * p).ceil() as u64).max(1);
+            let mut seen = 0;
+            counts
+                .iter()
+                .find(|(_, count)| {
+                    seen += count;
+                    seen >= rank
+                })
+                .map_or(0, |(interval, _)| *interval)
+        };
+
+        Ok(Some(BlockIntervalData {
+            blocks: total,
+            avg_secs: counts
+                .iter()
+                .map(|(interval, count)| (interval * count) as f64)
+                .sum::<f64>()
+                / total as f64,
+            p50_secs: percentile(0.5),
+            p90_secs: percentile(0.9),
+            p99_secs: percentile(0.99),
+            max_secs: counts.last().map_or(0, |(interval, _)| *interval),
+        }))
+    }
+
+    /// Get aggregate stats of the blocks and blob transactions with a
+    /// timestamp in `from..to`.
+    pub fn get_period_stats(&self, from: u64, to: u64) -> Result<PeriodStatsData> {
+        let conn = self.reader();
+
+        let (throughput, avg_gas_price) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(total_blobs), 0),
+                    MIN(block_number), MAX(block_number),
+                    MIN(block_timestamp), MAX(block_timestamp),
+                    COALESCE(AVG(wei(gas_price)), 0)
+             FROM blocks WHERE block_timestamp >= ? AND block_timestamp < ?",
+            [from, to],
+            |row| {
+                let indexed_blocks: u64 = row.get(0)?;
+                let throughput = (indexed_blocks > 0)
+                    .then(|| {
+                        Ok::<_, rusqlite::Error>(SlotThroughputData {
+                            indexed_blocks,
+                            total_blobs: row.get(1)?,
+                            first_block: row.get(2)?,
+                            last_block: row.get(3)?,
+                            first_timestamp: row.get(4)?,
+                            last_timestamp: row.get(5)?,
+                        })
+                    })
+                    .transpose()?;
+                Ok((throughput, row.get(6)?))
+            },
+        )?;
+
+        let (blob_transactions, unique_senders, blob_fees_wei) = conn.query_row(
+            "SELECT COUNT(*), COUNT(DISTINCT sender), TOTAL(blob_count * ?3 * wei(gas_price))
+             FROM blob_transactions WHERE created_at >= ?1 AND created_at < ?2",
+            [from, to, DATA_GAS_PER_BLOB],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        let (saturated_blocks, target_blobs) = conn.query_row(
+            "SELECT COALESCE(SUM(total_blobs >= blob_max), 0), COALESCE(SUM(blob_target), 0)
+             FROM blocks WHERE block_timestamp >= ? AND block_timestamp < ?",
+            [from, to],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        Ok(PeriodStatsData {
+            throughput,
+            avg_gas_price,
+            blob_transactions,
+            unique_senders,
+            blob_fees_wei,
+            saturated_blocks,
+            target_blobs,
+        })
+    }
+
+    /// Get recent blocks with their transactions.
+    pub fn get_recent_blocks(&self, limit: u64) -> Result<Vec<BlockData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {BLOCK_COLUMNS} FROM blocks ORDER BY block_number DESC LIMIT ?"
+        ))?;
+
+        let mut blocks: Vec<BlockData> = stmt
+            .query_map([limit], block_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for block in &mut blocks {
+            block.transactions = query_block_transactions(&conn, block.block_number)?;
+        }
+
+        Ok(blocks)
+    }
+
+    /// Get a specific block by number.
+    pub fn get_block(&self, block_number: u64) -> Result<Option<BlockData>> {
+        let conn = self.reader();
+
+        let block = conn
+            .query_row(
+                &format!("SELECT {BLOCK_COLUMNS} FROM blocks WHERE block_number = ?"),
+                [block_number],
+                block_from_row,
+            )
+            .optional()?;
+
+        let Some(mut block) = block else {
+            return Ok(None);
+        };
+        block.transactions = query_block_transactions(&conn, block_number)?;
+        Ok(Some(block))
+    }
+
+    /// Get a block with its transactions by hash (`0x`-prefixed lowercase hex).
+    /// Blocks indexed before hashes were recorded can only be found by number.
+    pub fn get_block_by_hash(&self, block_hash: &str) -> Result<Option<BlockData>> {
+        let block_number = self
+            .reader()
+            .query_row(
+                "SELECT block_number FROM blocks WHERE block_hash = ?",
+                [block_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match block_number {
+            Some(block_number) => self.get_block(block_number),
+            None => Ok(None),
+        }
+    }
+
+    /// Get blocks `from_block..=to_block` with their transactions, oldest first,
+    /// in a single query.
+    pub fn get_blocks_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<BlockData>> {
+        let conn = self.reader();
+
+        let block_columns: Vec<String> = BLOCK_COLUMNS
+            .split(',')
+            .map(|column| format!("b.{}", column.trim()))
+            .collect();
+        let block_column_count = block_columns.len();
+        let block_columns = block_columns.join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {block_columns},
+                    t.tx_hash, t.sender, t.blob_count,
+                    COALESCE(
+                        (SELECT SUM(COALESCE(h.blob_size, ?3)) FROM blob_hashes h WHERE h.tx_hash = t.tx_hash),
+                        t.blob_count * ?3
+                    ),
+                    t.attributed_entity, t.label_source
+             FROM blocks b
+             LEFT JOIN blob_transactions t ON t.block_number = b.block_number
+             WHERE b.block_number BETWEEN ?1 AND ?2
+             ORDER BY b.block_number ASC, t.tx_hash ASC"
+        ))?;
+
+        let mut blocks: Vec<BlockData> = Vec::new();
+        let mut rows = stmt.query((from_block, to_block, BLOB_SIZE_BYTES))?;
+        while let Some(row) = rows.next()? {
+            let block_number: u64 = row.get(0)?;
+            if blocks.last().map(|b| b.block_number) != Some(block_number) {
+                blocks.push(block_from_row(row)?);
+            }
+
+            // Transaction columns follow the block's
+            let tx_hash: Option<String> = row.get(block_column_count)?;
+            if let (Some(block), Some(tx_hash)) = (blocks.last_mut(), tx_hash) {
+                block.transactions.push(TransactionData {
+                    tx_hash,
+                    sender: row.get(block_column_count + 1)?,
+                    blob_count: row.get(block_column_count + 2)?,
+                    blob_size: row.get(block_column_count + 3)?,
+                    attributed_entity: row.get(block_column_count + 4)?,
+                    label_source: row.get(block_column_count + 5)?,
+                });
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    /// Get senders whose stored stats differ from the ones derived from
+    /// `blob_transactions` and `archived_senders`, including senders missing
+    /// on either side.
+    pub fn get_sender_drift(&self) -> Result<Vec<SenderDriftData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(&format!(
+            "WITH derived AS ({}),
+                  addresses AS (SELECT address FROM senders UNION SELECT address FROM derived)
+             SELECT a.address,
+                    COALESCE(s.tx_count, 0), COALESCE(s.total_blobs, 0), COALESCE(s.total_blob_size, 0),
+                    COALESCE(d.tx_count, 0), COALESCE(d.total_blobs, 0), COALESCE(d.total_blob_size, 0)
+             FROM addresses a
+             LEFT JOIN senders s ON s.address = a.address
+             LEFT JOIN derived d ON d.address = a.address
+             WHERE COALESCE(s.tx_count, 0) != COALESCE(d.tx_count, 0)
+                OR COALESCE(s.total_blobs, 0) != COALESCE(d.total_blobs, 0)
+                OR COALESCE(s.total_blob_size, 0) != COALESCE(d.total_blob_size, 0)
+             ORDER BY a.address",
+            lifetime_sender_totals_sql("", "")
+        ))?;
+
+        let drift = stmt
+            .query_map([], |row| {
+                Ok(SenderDriftData {
+                    address: row.get(0)?,
+                    stored: SenderTotals {
+                        tx_count: row.get(1)?,
+                        total_blobs: row.get(2)?,
+                        total_blob_size: row.get(3)?,
+                    },
+                    derived: SenderTotals {
+                        tx_count: row.get(4)?,
+                        total_blobs: row.get(5)?,
+                        total_blob_size: row.get(6)?,
+                    },
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(drift)
+    }
+
+    /// Recompute every sender's stats from `blob_transactions` and
+    /// `archived_senders`. Returns the number of senders.
+    pub fn rebuild_sender_stats(&self) -> Result<usize> {
+        let conn = self.connection();
+        conn.execute_batch(&format!(
+            "BEGIN;
+             DELETE FROM senders;
+             INSERT INTO senders (
+                 address, tx_count, total_blobs, total_blob_size,
+                 first_seen_block, last_seen_block, last_seen_timestamp
+             ) {};
+             COMMIT;",
+            lifetime_sender_totals_sql("", "")
+        ))?;
+        let senders: usize =
+            conn.query_row("SELECT COUNT(*) FROM senders", [], |row| row.get(0))?;
+        Ok(senders)
+    }
+
+    /// Rebuild the hourly rollups and sender stats covering blocks
+    /// `from_block..=to_block` from `blocks` and `blob_transactions`, in one
+    /// transaction.
+    ///
+    /// Hours are rebuilt whole, including blocks of theirs outside the range,
+    /// except for archived days. Senders with transactions in the range, or
+    /// stats pointing into it, get all-time stats rebuilt, archived
+    /// transactions included; those without any transaction left are removed.
+    pub fn reaggregate(&self, from_block: u64, to_block: u64) -> Result<ReaggregateData> {
+        if from_block > to_block {
+            return Err(DbError::InvalidInput(format!(
+                "invalid block range {from_block}..={to_block}"
+            )));
+        }
+        let mut conn = self.connection();
+        let tx = conn.savepoint()?;
+
+        let (first_hour, last_hour): (Option<u64>, Option<u64>) = tx.query_row(
+            "SELECT group_by_hour(MIN(block_timestamp), 0), group_by_hour(MAX(block_timestamp), 0)
+             FROM blocks WHERE block_number BETWEEN ? AND ?",
+            (from_block, to_block),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let mut hours = 0;
+        if let (Some(first_hour), Some(last_hour)) = (first_hour, last_hour) {
+            tx.execute(
+                "DELETE FROM hourly_blob_stats WHERE hour_start BETWEEN ? AND ?",
+                (first_hour, last_hour),
+            )?;
+            hours = tx.execute(
+                "INSERT INTO hourly_blob_stats
+                 SELECT group_by_hour(block_timestamp, 0), COUNT(*), SUM(tx_count),
+                        SUM(total_blobs), SUM(gas_used), TOTAL(wei(gas_price))
+                 FROM blocks
+                 WHERE block_timestamp >= ?1 AND block_timestamp < ?2 + 3600
+                 GROUP BY 1",
+                (first_hour, last_hour),
+            )?;
+            refresh_hourly_chain_stats(&tx, first_hour, last_hour)?;
+            refresh_daily_blob_hashes(&tx, first_hour, last_hour)?;
+        }
+
+        tx.execute(
+            "CREATE TEMP TABLE reaggregated_senders AS
+                 SELECT sender AS address FROM blob_transactions
+                 WHERE block_number BETWEEN ?1 AND ?2
+                 UNION
+                 SELECT address FROM senders
+                 WHERE first_seen_block BETWEEN ?1 AND ?2 OR last_seen_block BETWEEN ?1 AND ?2",
+            (from_block, to_block),
+        )?;
+        tx.execute(
+            "DELETE FROM senders WHERE address IN (SELECT address FROM reaggregated_senders)",
+            (),
+        )?;
+        let senders = tx.execute(
+            &format!(
+                "INSERT INTO senders (
+                     address, tx_count, total_blobs, total_blob_size,
+                     first_seen_block, last_seen_block, last_seen_timestamp
+                 ) {}",
+                lifetime_sender_totals_sql(
+                    "WHERE t.sender IN (SELECT address FROM reaggregated_senders)",
+                    "WHERE address IN (SELECT address FROM reaggregated_senders)",
+                )
+            ),
+            (),
+        )?;
+        tx.execute("DROP TABLE reaggregated_senders", ())?;
+
+        tx.commit()?;
+        Ok(ReaggregateData { hours, senders })
+    }
+
+    /// Get per-sender resubmission counts for blob txs included since
+    /// `time_limit`.
+    ///
+    /// Every distinct tx hash seen for a canonical tx's sender and nonce, in a
+    /// reorged block or in a mempool snapshot, counts as an attempt. Txs
+    /// indexed before nonces were recorded are skipped.
+    pub fn get_resubmissions(&self, time_limit: u64) -> Result<Vec<SenderResubmissionData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "WITH attempts AS (
+                 SELECT sender, nonce, COUNT(DISTINCT tx_hash) AS attempts
+                 FROM (
+                     SELECT sender, nonce, tx_hash FROM blob_transactions
+                     UNION SELECT sender, nonce, tx_hash FROM reverted_blob_transactions
+                     UNION SELECT sender, nonce, tx_hash FROM pending_blob_transactions
+                 )
+                 WHERE nonce IS NOT NULL
+                 GROUP BY sender, nonce
+             )
+             SELECT t.sender,
+                    COUNT(*),
+                    SUM(a.attempts > 1),
+                    SUM(a.attempts - 1),
+                    SUM(EXISTS(SELECT 1 FROM reverted_blob_transactions r WHERE r.tx_hash = t.tx_hash))
+             FROM blob_transactions t
+             JOIN attempts a ON a.sender = t.sender AND a.nonce = t.nonce
+             WHERE t.created_at > ?
+             GROUP BY t.sender",
+        )?;
+
+        let resubmissions = stmt
+            .query_map([time_limit], |row| {
+                Ok(SenderResubmissionData {
+                    sender: row.get(0)?,
+                    tx_count: row.get(1)?,
+                    resubmitted_tx_count: row.get(2)?,
+                    replaced_attempts: row.get(3)?,
+                    reincluded_tx_count: row.get(4)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(resubmissions)
+    }
+
+    /// Get top senders by total blobs.
+    pub fn get_top_senders(&self, limit: u64) -> Result<Vec<SenderData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT address, tx_count, total_blobs, total_blob_size,
+                    first_seen_block, last_seen_block, last_seen_timestamp
+             FROM senders ORDER BY total_blobs DESC LIMIT ?",
+        )?;
+
+        let senders: Vec<SenderData> = stmt
+            .query_map([limit], |row| {
+                Ok(SenderData {
+                    address: row.get(0)?,
+                    tx_count: row.get(1)?,
+                    total_blobs: row.get(2)?,
+                    total_blob_size: row.get(3)?,
+                    first_seen_block: row.get(4)?,
+                    last_seen_block: row.get(5)?,
+                    last_seen_timestamp: row.get(6)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(senders)
+    }
+
+    /// Get chart data for the last N blocks, filling the gas price of blocks
+    /// that aren't indexed as `gap_fill` says.
+    pub fn get_chart_data(
+        &self,
+        num_blocks: u64,
+        gap_fill: GapFill,
+        schedule: &BlobSchedule,
+    ) -> Result<ChartData> {
+        let conn = self.reader();
+
+        let latest_block: u64 = conn
+            .query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))
+            .unwrap_or(0);
+
+        if latest_block == 0 {
+            return Ok(ChartData {
+                labels: Vec::new(),
+                blobs: Vec::new(),
+                gas_prices: Vec::new(),
+                gas_prices_wei: Vec::new(),
+                non_blob_tx_counts: Vec::new(),
+                non_blob_gas_used: Vec::new(),
+                base_fees: Vec::new(),
+                block_intervals: Vec::new(),
+            });
+        }
+
+        let start_block = latest_block.saturating_sub(num_blocks.saturating_sub(1));
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {BLOCK_COLUMNS}
+             FROM blocks
+             WHERE block_number >= ? AND block_number <= ?
+             ORDER BY block_number ASC"
+        ))?;
+
+        let mut block_data: HashMap<u64, BlockData> = stmt
+            .query_map([start_block, latest_block], block_from_row)?
+            .flatten()
+            .map(|block| (block.block_number, block))
+            .collect();
+
+        // Gaps at the start of the window continue from the block before it
+        let mut previous: Option<BlockData> = conn
+            .query_row(
+                &format!(
+                    "SELECT {BLOCK_COLUMNS}
+                     FROM blocks
+                     WHERE block_number < ?
+                     ORDER BY block_number DESC
+                     LIMIT 1"
+                ),
+                [start_block],
+                block_from_row,
+            )
+            .optional()?;
+
+        let mut labels = Vec::with_capacity(num_blocks as usize);
+        let mut blobs = Vec::with_capacity(num_blocks as usize);
+        let mut gas_prices = Vec::with_capacity(num_blocks as usize);
+        let mut gas_prices_wei = Vec::with_capacity(num_blocks as usize);
+        let mut non_blob_tx_counts = Vec::with_capacity(num_blocks as usize);
+        let mut non_blob_gas_used = Vec::with_capacity(num_blocks as usize);
+        let mut base_fees = Vec::with_capacity(num_blocks as usize);
+        let mut block_intervals = Vec::with_capacity(num_blocks as usize);
+
+        for block_num in start_block..=latest_block {
+            labels.push(block_num);
+            let block = block_data.remove(&block_num);
+            if let Some(block) = &block {
+                blobs.push(block.total_blobs);
+                gas_prices.push(Some(block.gas_price as f64 / 1e9));
+                gas_prices_wei.push(Some(block.gas_price));
+            } else {
+                blobs.push(0);
+                let gas_price = match gap_fill {
+                    GapFill::Previous => previous.as_ref().map(|block| block.gas_price),
+                    GapFill::ExcessBlobGas => previous.as_mut().map(|block| {
+                        // Stand in for the missing block, assumed to carry no blobs
+                        let timestamp = block.block_timestamp + SECONDS_PER_SLOT;
+                        let params = schedule.params_at(timestamp).blob_params();
+                        block.excess_blob_gas = params.next_block_excess_blob_gas_osaka(
+                            block.excess_blob_gas,
+                            block.gas_used,
+                            block.base_fee_per_gas.unwrap_or(0),
+                        );
+                        block.gas_used = 0;
+                        block.block_timestamp = timestamp;
+                        block.gas_price = params.calc_blob_fee(block.excess_blob_gas);
+                        block.gas_price
+                    }),
+                    GapFill::Null => None,
+                };
+                gas_prices.push(gas_price.map(|price| price as f64 / 1e9));
+                gas_prices_wei.push(gas_price);
+            }
+
+            let execution = block.as_ref().and_then(|block| block.execution);
+            non_blob_tx_counts.push(execution.map(|e| e.non_blob_tx_count));
+            non_blob_gas_used.push(execution.map(|e| e.non_blob_gas_used));
+            base_fees.push(
+                block
+                    .as_ref()
+                    .and_then(|block| block.base_fee_per_gas)
+                    .map(|fee| fee as f64 / 1e9),
+            );
+            block_intervals.push(block.as_ref().and_then(|block| block.block_interval));
+            if block.is_some() {
+                previous = block;
+            }
+        }
+
+        Ok(ChartData {
+            labels,
+            blobs,
+            gas_prices,
+            gas_prices_wei,
+            non_blob_tx_counts,
+            non_blob_gas_used,
+            base_fees,
+            block_intervals,
+        })
+    }
+
+    /// Get recent blob transactions.
+    pub fn get_blob_transactions(&self, limit: u64) -> Result<Vec<BlobTransactionData>> {
+        let conn = self.reader();
+        query_blob_transactions(
+            &conn,
+            "SELECT tx_hash, block_number, sender, blob_count, gas_price, attributed_entity,
+                    label_source, tx_type
+             FROM blob_transactions
+             ORDER BY created_at DESC
+             LIMIT ?",
+            [limit],
+        )
+    }
+
+    /// Get a blob transaction by hash (`0x`-prefixed lowercase hex).
+    pub fn get_blob_transaction(&self, tx_hash: &str) -> Result<Option<BlobTransactionData>> {
+        let conn = self.reader();
+        let txs = query_blob_transactions(
+            &conn,
+            "SELECT tx_hash, block_number, sender, blob_count, gas_price, attributed_entity,
+                    label_source, tx_type
+             FROM blob_transactions
+             WHERE tx_hash = ?",
+            [tx_hash],
+        )?;
+        Ok(txs.into_iter().next())
+    }
+
+    /// Get the senders of blob transactions to `inbox` since `time_limit`,
+    /// most blobs first.
+    pub fn get_inbox_senders(
+        &self,
+        inbox: &Address,
+        time_limit: u64,
+    ) -> Result<Vec<InboxSenderData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT sender, chain_of(sender, attributed_entity) AS chain, COUNT(*),
+                    SUM(blob_count), SUM(COALESCE(payload_size, blob_count * ?3)),
+                    MIN(block_number), MAX(block_number),
+                    COUNT(CASE WHEN status = 0 THEN 1 END)
+             FROM blob_transactions
+             WHERE to_address = ?1 AND created_at >= ?2
+             GROUP BY sender, chain
+             ORDER BY SUM(blob_count) DESC",
+        )?;
+        let senders = stmt
+            .query_map((address_key(inbox), time_limit, BLOB_SIZE_BYTES), |row| {
+                Ok(InboxSenderData {
+                    sender: row.get(0)?,
+                    chain: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    total_blobs: row.get(3)?,
+                    total_blob_size: row.get(4)?,
+                    first_block: row.get(5)?,
+                    last_block: row.get(6)?,
+                    reverted_txs: row.get(7)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(senders)
+    }
+
+    /// Get up to `limit` blobs posted by `sender` in blocks
+    /// `from_block..=to_block`, oldest first, resuming after the blob at
+    /// `after` (see [`SenderBlobData::position`]) if given.
+    pub fn get_sender_blobs(
+        &self,
+        sender: &Address,
+        from_block: u64,
+        to_block: u64,
+        after: Option<(u64, u64)>,
+        limit: u64,
+    ) -> Result<Vec<SenderBlobData>> {
+        let (after_block, after_id) = after.unwrap_or((0, 0));
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT t.block_number, h.id, t.created_at, t.tx_hash, h.blob_index, h.blob_hash,
+                    h.blob_size
+             FROM blob_transactions t
+             JOIN blob_hashes h ON h.tx_hash = t.tx_hash
+             WHERE t.sender = ?1
+               AND t.block_number BETWEEN ?2 AND ?3
+               AND (t.block_number, h.id) > (?4, ?5)
+             ORDER BY t.block_number, h.id
+             LIMIT ?6",
+        )?;
+        let blobs = stmt
+            .query_map(
+                (
+                    address_key(sender),
+                    from_block,
+                    to_block,
+                    after_block,
+                    after_id,
+                    limit,
+                ),
+                |row| {
+                    Ok(SenderBlobData {
+                        position: (row.get(0)?, row.get(1)?),
+                        block_timestamp: row.get(2)?,
+                        tx_hash: row.get(3)?,
+                        blob_index: row.get(4)?,
+                        blob_hash: row.get(5)?,
+                        blob_size: row.get(6)?,
+                    })
+                },
+            )?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(blobs)
+    }
+
+    /// Get blob transactions included in blocks `from_block..=to_block`, oldest first.
+    pub fn get_blob_transactions_in_range(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<BlobTransactionData>> {
+        let conn = self.reader();
+        query_blob_transactions(
+            &conn,
+            "SELECT tx_hash, block_number, sender, blob_count, gas_price, attributed_entity,
+                    label_source, tx_type
+             FROM blob_transactions
+             WHERE block_number BETWEEN ? AND ?
+             ORDER BY block_number ASC, tx_hash ASC",
+            [from_block, to_block],
+        )
+    }
+
+    /// Get the number of the latest indexed block.
+    pub fn get_latest_block(&self) -> Result<Option<u64>> {
+        let latest =
+            self.reader()
+                .query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))?;
+        Ok(latest)
+    }
+
+    /// Get all-time chart data with smoothing for visualization.
+    /// Returns roughly `target_points` windows, each downsampled in SQL with `strategy`.
+    pub fn get_all_time_chart_data(
+        &self,
+        target_points: u64,
+        schedule: &BlobSchedule,
+        strategy: Downsample,
+    ) -> Result<AllTimeChartData> {
+        let conn = self.reader();
+
+        // Get total block count and range
+        let (min_block, max_block): (u64, u64) = conn
+            .query_row(
+                "SELECT MIN(block_number), MAX(block_number) FROM blocks",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap_or((0, 0));
+
+        if max_block == 0 {
+            return Ok(AllTimeChartData {
+                labels: Vec::new(),
+                blobs: Vec::new(),
+                gas_prices: Vec::new(),
+                gas_prices_wei: Vec::new(),
+                timestamps: Vec::new(),
+                targets: Vec::new(),
+                maxes: Vec::new(),
+                non_blob_tx_counts: Vec::new(),
+                non_blob_gas_used: Vec::new(),
+                base_fees: Vec::new(),
+                block_intervals: Vec::new(),
+                bpo2_block: None,
+            });
+        }
+
+        let total_blocks = max_block - min_block + 1;
+        let sample_interval = (total_blocks / target_points).max(1);
+
+        // Find the first block under the latest schedule entry (BPO2 on mainnet)
+        let latest_activation = schedule
+            .entries()
+            .last()
+            .map_or(0, |entry| entry.activation_timestamp);
+        let bpo2_block: Option<u64> = conn.query_row(
+            "SELECT MIN(block_number) FROM blocks WHERE block_timestamp >= ?",
+            [latest_activation],
+            |row| row.get(0),
+        )?;
+
+        // Aggregate each window of `sample_interval` blocks in SQL. Mean and max
+        // label a window by its middle block; last uses the window's final block
+        // (SQLite takes bare columns from the row matching MAX()).
+        let sql = match strategy {
+            Downsample::Mean => {
+                "SELECT MIN(block_number) + (MAX(block_number) - MIN(block_number)) / 2,
+                        MIN(block_timestamp) + (MAX(block_timestamp) - MIN(block_timestamp)) / 2,
+                        AVG(total_blobs), AVG(wei(gas_price)),
+                        AVG(non_blob_tx_count), AVG(non_blob_gas_used), AVG(base_fee_per_gas),
+                        AVG(block_interval)
+                 FROM blocks
+                 GROUP BY (block_number - ?1) / ?2
+                 ORDER BY 1 ASC"
+            }
+            Downsample::Max => {
+                "SELECT MIN(block_number) + (MAX(block_number) - MIN(block_number)) / 2,
+                        MIN(block_timestamp) + (MAX(block_timestamp) - MIN(block_timestamp)) / 2,
+                        MAX(total_blobs), MAX(wei(gas_price)),
+                        MAX(non_blob_tx_count), MAX(non_blob_gas_used), MAX(base_fee_per_gas),
+                        MAX(block_interval)
+                 FROM blocks
+                 GROUP BY (block_number - ?1) / ?2
+                 ORDER BY 1 ASC"
+            }
+            Downsample::Last => {
+                "SELECT MAX(block_number), block_timestamp, total_blobs, wei(gas_price),
+                        non_blob_tx_count, non_blob_gas_used, base_fee_per_gas, block_interval
+                 FROM blocks
+                 GROUP BY (block_number - ?1) / ?2
+                 ORDER BY 1 ASC"
+            }
+        };
+
+        let mut stmt = conn.prepare(sql)?;
+        let mut rows = stmt.query([min_block, sample_interval])?;
+
+        let mut labels = Vec::new();
+        let mut blobs = Vec::new();
+        let mut gas_prices = Vec::new();
+        let mut gas_prices_wei = Vec::new();
+        let mut timestamps = Vec::new();
+        let mut targets = Vec::new();
+        let mut maxes = Vec::new();
+        let mut non_blob_tx_counts = Vec::new();
+        let mut non_blob_gas_used = Vec::new();
+        let mut base_fees = Vec::new();
+        let mut block_intervals = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let block_num: u64 = row.get(0)?;
+            let timestamp: u64 = row.get(1)?;
+            let blob_count: f64 = row.get(2)?;
+            let gas_price: f64 = row.get(3)?;
+
+            // Determine target/max based on timestamp
+            let params = schedule.params_at(timestamp);
+
+            labels.push(block_num);
+            blobs.push(blob_count);
+            gas_prices.push(gas_price / 1e9);
+            gas_prices_wei.push(gas_price);
+            timestamps.push(timestamp);
+            targets.push(params.target);
+            maxes.push(params.max);
+            non_blob_tx_counts.push(row.get::<_, Option<f64>>(4)?);
+            non_blob_gas_used.push(row.get::<_, Option<f64>>(5)?);
+            base_fees.push(row.get::<_, Option<f64>>(6)?.map(|fee| fee / 1e9));
+            block_intervals.push(row.get::<_, Option<f64>>(7)?);
+        }
+
+        Ok(AllTimeChartData {
+            labels,
+            blobs,
+            gas_prices,
+            gas_prices_wei,
+            timestamps,
+            targets,
+            maxes,
+            non_blob_tx_counts,
+            non_blob_gas_used,
+            base_fees,
+            block_intervals,
+            bpo2_block,
+        })
+    }
+
+    /// Get priority fees paid by included blob txs and bid by pending blob txs
+    /// that were left out, for the most recent blocks.
+    pub fn get_inclusion_market(&self, limit: u64) -> Result<Vec<InclusionMarketData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT block_number, tx_count, min_priority_fee, median_priority_fee, max_priority_fee
+             FROM blocks
+             WHERE tx_count > 0
+             ORDER BY block_number DESC
+             LIMIT ?",
+        )?;
+
+        let rows: Vec<(u64, u64, Option<PriorityFees>)> = stmt
+            .query_map([limit], |row| {
+                let included = match (row.get(2)?, row.get(3)?, row.get(4)?) {
+                    (Some(min), Some(median), Some(max)) => Some(PriorityFees { min, median, max }),
+                    _ => None,
+                };
+                Ok((row.get(0)?, row.get(1)?, included))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut pending_stmt = conn.prepare(
+            "SELECT priority_fee FROM pending_blob_transactions
+             WHERE block_number = ?
+             ORDER BY priority_fee ASC",
+        )?;
+
+        let mut result = Vec::with_capacity(rows.len());
+
+        for (block_number, tx_count, included) in rows {
+            let pending_fees: Vec<u64> = pending_stmt
+                .query_map([block_number], |row| row.get(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            result.push(InclusionMarketData {
+                block_number,
+                tx_count,
+                included,
+                excluded_count: pending_fees.len() as u64,
+                excluded: PriorityFees::from_sorted(&pending_fees),
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Get how long each blob tx included since `time_limit` waited in the
+    /// mempool, from the first block after which the mempool tracker saw it
+    /// pending.
+    pub fn get_inclusion_latencies(&self, time_limit: u64) -> Result<Vec<InclusionLatencyData>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT t.sender, t.attributed_entity, t.priority_fee, t.block_number, b.block_timestamp,
+                    p.block_number, p.block_timestamp
+             FROM blob_transactions t
+             JOIN blocks b ON b.block_number = t.block_number
+             LEFT JOIN blocks p ON p.block_number = (
+                 SELECT MIN(block_number) FROM pending_blob_transactions
+                 WHERE tx_hash = t.tx_hash AND block_number < t.block_number
+             )
+             WHERE t.created_at >= ?",
+        )?;
+
+        let latencies = stmt
+            .query_map([time_limit], |row| {
+                let block_number: u64 = row.get(3)?;
+                let block_timestamp: u64 = row.get(4)?;
+                let first_seen: Option<(u64, u64)> = match (row.get(5)?, row.get(6)?) {
+                    (Some(number), Some(timestamp)) => Some((number, timestamp)),
+                    _ => None,
+                };
+                Ok(InclusionLatencyData {
+                    sender: row.get(0)?,
+                    attributed_entity: row.get(1)?,
+                    priority_fee: row.get(2)?,
+                    blocks: first_seen.map(|(number, _)| block_number - number),
+                    secs: first_seen
+                        .map(|(_, timestamp)| block_timestamp.saturating_sub(timestamp)),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(latencies)
+    }
+
+    /// Get blob fee history for `block_count` blocks ending at `newest_block`
+    /// (the latest indexed block if `None`), oldest first.
+    pub fn get_fee_history(
+        &self,
+        newest_block: Option<u64>,
+        block_count: u64,
+    ) -> Result<Vec<FeeHistoryBlock>> {
+        let conn = self.reader();
+
+        let newest_block = match newest_block {
+            Some(block_number) => block_number,
+            None => {
+                let latest: Option<u64> =
+                    conn.query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))?;
+                match latest {
+                    Some(block_number) => block_number,
+                    None => return Ok(Vec::new()),
+                }
+            }
+        };
+        let oldest_block = newest_block.saturating_sub(block_count.saturating_sub(1));
+
+        let mut stmt = conn.prepare(
+            "SELECT block_number, block_timestamp, COALESCE(header_blob_gas_used, gas_used),
+                    gas_price, blob_max, excess_blob_gas, base_fee_per_gas
+             FROM blocks
+             WHERE block_number >= ? AND block_number <= ?
+             ORDER BY block_number ASC",
+        )?;
+
+        let rows: Vec<FeeHistoryBlock> = stmt
+            .query_map([oldest_block, newest_block], |row| {
+                Ok(FeeHistoryBlock {
+                    block_number: row.get(0)?,
+                    block_timestamp: row.get(1)?,
+                    gas_used: row.get(2)?,
+                    gas_price: row.get::<_, Wei>(3)?.0,
+                    blob_max: row.get(4)?,
+                    excess_blob_gas: row.get(5)?,
+                    base_fee_per_gas: row.get(6)?,
+                    priority_fees: Vec::new(),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut tx_stmt = conn.prepare(
+            "SELECT COALESCE(priority_fee, 0), blob_count
+             FROM blob_transactions
+             WHERE block_number = ?
+             ORDER BY priority_fee ASC",
+        )?;
+
+        let mut result = rows;
+
+        for block in &mut result {
+            block.priority_fees = tx_stmt
+                .query_map([block.block_number], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .filter_map(|r| r.ok())
+                .collect();
+        }
+
+        Ok(result)
+    }
+
+    /// Get hourly rollups for every UTC hour starting at or after `since`, oldest first.
+    pub fn get_hourly_stats(&self, since: u64) -> Result<Vec<HourlyStatsData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT hour_start, block_count, tx_count, total_blobs, gas_used, gas_price_sum
+             FROM hourly_blob_stats
+             WHERE hour_start >= ?
+             ORDER BY hour_start ASC",
+        )?;
+
+        let hours = stmt
+            .query_map([since], |row| {
+                Ok(HourlyStatsData {
+                    hour_start: row.get(0)?,
+                    block_count: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    total_blobs: row.get(3)?,
+                    gas_used: row.get(4)?,
+                    gas_price_sum: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(hours)
+    }
+
+    /// Get blocks that failed to ingest, most recent first.
+    pub fn get_ingest_errors(&self, limit: u64) -> Result<Vec<IngestErrorData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT block_number, stage, error, attempts, failed_at
+             FROM ingest_errors
+             ORDER BY block_number DESC, stage ASC
+             LIMIT ?",
+        )?;
+
+        let errors = stmt
+            .query_map([limit], |row| {
+                Ok(IngestErrorData {
+                    block_number: row.get(0)?,
+                    stage: row.get(1)?,
+                    error: row.get(2)?,
+                    attempts: row.get(3)?,
+                    failed_at: row.get(4)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(errors)
+    }
+
+    /// Get up to `limit` ingest log entries, most recent first, starting
+    /// below the entry id `before` if given.
+    pub fn get_ingest_log(&self, before: Option<u64>, limit: u64) -> Result<Vec<IngestLogData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, processed_at, kind, reverted_from, reverted_to, committed_from,
+                    committed_to, reverted_txs, committed_txs, duration_ms, outcome, error
+             FROM ingest_log
+             WHERE id < ?
+             ORDER BY id DESC
+             LIMIT ?",
+        )?;
+
+        let entries = stmt
+            .query_map((before.unwrap_or(i64::MAX as u64), limit), |row| {
+                let range = |from: Option<u64>, to: Option<u64>| Some(from?..=to?);
+                Ok(IngestLogData {
+                    id: row.get(0)?,
+                    processed_at: row.get(1)?,
+                    kind: row.get(2)?,
+                    reverted: range(row.get(3)?, row.get(4)?),
+                    committed: range(row.get(5)?, row.get(6)?),
+                    reverted_txs: row.get(7)?,
+                    committed_txs: row.get(8)?,
+                    duration_ms: row.get(9)?,
+                    outcome: row.get(10)?,
+                    error: row.get(11)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Get the blobs posted by each sender since `time_limit`.
+    pub fn get_sender_blob_totals(&self, time_limit: u64) -> Result<Vec<(String, u64)>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, SUM(blob_count)
+             FROM blob_transactions
+             WHERE created_at >= ?
+             GROUP BY sender",
+        )?;
+
+        let totals = stmt
+            .query_map([time_limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(totals)
+    }
+
+    /// Record the execution side of a blob transaction's cost: the EL gas it
+    /// used and what that cost at its effective gas price, completing its
+    /// `total_fee`. Transactions that aren't indexed are ignored.
+    pub fn record_blob_transaction_execution(
+        &self,
+        tx_hash: &str,
+        gas_used: u64,
+        execution_fee: u128,
+    ) -> Result<()> {
+        let conn = self.connection();
+        let blob_fee: Option<Option<Wei>> = conn
+            .query_row(
+                "SELECT blob_fee FROM blob_transactions WHERE tx_hash = ?",
+                [tx_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(blob_fee) = blob_fee else {
+            return Ok(());
+        };
+        let total_fee = blob_fee.map(|fee| Wei(fee.0.saturating_add(execution_fee)));
+        conn.execute(
+            "UPDATE blob_transactions
+             SET execution_gas_used = ?, execution_fee = ?, total_fee = ?
+             WHERE tx_hash = ?",
+            (gas_used, Wei(execution_fee), total_fee, tx_hash),
+        )?;
+        Ok(())
+    }
+
+    /// Record the receipt status of a blob transaction. A reverted transaction
+    /// still paid its blob fee, but its blobs delivered nothing. Transactions
+    /// that aren't indexed are ignored.
+    pub fn record_blob_transaction_status(&self, tx_hash: &str, succeeded: bool) -> Result<()> {
+        self.connection().execute(
+            "UPDATE blob_transactions SET status = ? WHERE tx_hash = ?",
+            (succeeded, tx_hash),
+        )?;
+        Ok(())
+    }
+
+    /// Get the cost of the blob transactions of each sender since
+    /// `time_limit`, split between blob fees and EL execution fees.
+    ///
+    /// Only transactions with a recorded execution fee are summed, the others
+    /// are counted as unpriced. Fees are summed as floats since totals in wei
+    /// can exceed an INTEGER.
+    pub fn get_sender_cost_breakdown(&self, time_limit: u64) -> Result<Vec<CostBreakdownData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, attributed_entity, COUNT(*),
+                    TOTAL(CASE WHEN total_fee IS NOT NULL THEN blob_count END),
+                    TOTAL(CASE WHEN total_fee IS NOT NULL THEN wei(blob_fee) END),
+                    TOTAL(wei(execution_fee)),
+                    TOTAL(execution_gas_used),
+                    COUNT(*) - COUNT(total_fee)
+             FROM blob_transactions
+             WHERE created_at >= ?
+             GROUP BY sender, attributed_entity",
+        )?;
+
+        let costs = stmt
+            .query_map([time_limit], |row| {
+                Ok(CostBreakdownData {
+                    sender: row.get(0)?,
+                    attributed_entity: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    priced_blobs: row.get::<_, f64>(3)? as u64,
+                    blob_fees_wei: row.get(4)?,
+                    execution_fees_wei: row.get(5)?,
+                    execution_gas_used: row.get::<_, f64>(6)? as u64,
+                    unpriced_tx_count: row.get(7)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(costs)
+    }
+
+    /// Get blob fees paid by each sender since `time_limit`, alongside what
+    /// posting the same payload as calldata would have cost at
+    /// `calldata_gas_per_byte` and the block's EL base fee.
+    ///
+    /// Blobs of unknown payload size count as [`BLOB_SIZE_BYTES`]. Fees are
+    /// summed as floats since totals in wei can exceed an INTEGER.
+    pub fn get_sender_blob_costs(
+        &self,
+        time_limit: u64,
+        calldata_gas_per_byte: u64,
+    ) -> Result<Vec<SenderBlobCostData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, COUNT(*), SUM(blob_count), SUM(payload),
+                    TOTAL(blob_count * ?4 * wei(gas_price)),
+                    TOTAL(payload * ?3 * base_fee_per_gas),
+                    COUNT(*) - COUNT(base_fee_per_gas)
+             FROM (
+                 SELECT t.sender, t.blob_count, t.gas_price, b.base_fee_per_gas,
+                        COALESCE(
+                            (SELECT SUM(COALESCE(h.blob_size, ?2)) FROM blob_hashes h WHERE h.tx_hash = t.tx_hash),
+                            t.blob_count * ?2
+                        ) AS payload
+                 FROM blob_transactions t
+                 JOIN blocks b ON b.block_number = t.block_number
+                 WHERE t.created_at >= ?1
+             )
+             GROUP BY sender",
+        )?;
+
+        let costs = stmt
+            .query_map(
+                (
+                    time_limit,
+                    BLOB_SIZE_BYTES,
+                    calldata_gas_per_byte,
+                    DATA_GAS_PER_BLOB,
+                ),
+                |row| {
+                    Ok(SenderBlobCostData {
+                        sender: row.get(0)?,
+                        tx_count: row.get(1)?,
+                        total_blobs: row.get(2)?,
+                        payload_bytes: row.get(3)?,
+                        blob_fees_wei: row.get(4)?,
+                        calldata_fees_wei: row.get(5)?,
+                        unpriced_tx_count: row.get(6)?,
+                    })
+                },
+            )?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(costs)
+    }
+
+    /// Get the excess blob gas of every block with a timestamp of at least
+    /// `since`, oldest first.
+    pub fn get_excess_blob_gas(&self, since: u64) -> Result<Vec<ExcessBlobGasData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT block_number, block_timestamp, excess_blob_gas, excess_blob_gas_delta,
+                    total_blobs, gas_price
+             FROM blocks
+             WHERE block_timestamp >= ?
+             ORDER BY block_number ASC",
+        )?;
+
+        let blocks = stmt
+            .query_map([since], |row| {
+                Ok(ExcessBlobGasData {
+                    block_number: row.get(0)?,
+                    block_timestamp: row.get(1)?,
+                    excess_blob_gas: row.get(2)?,
+                    delta: row.get(3)?,
+                    total_blobs: row.get(4)?,
+                    gas_price: row.get::<_, Wei>(5)?.0,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(blocks)
+    }
+
+    /// Get block averages in `bucket_secs` wide buckets for blocks with a
+    /// timestamp in `from..=to`.
+    pub fn get_block_series(
+        &self,
+        from: u64,
+        to: u64,
+        bucket_secs: u64,
+    ) -> Result<Vec<BlockSeriesData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT block_timestamp / ?3 * ?3, COUNT(*), SUM(tx_count), SUM(total_blobs), AVG(wei(gas_price))
+             FROM blocks
+             WHERE block_timestamp BETWEEN ?1 AND ?2
+             GROUP BY 1
+             ORDER BY 1 ASC",
+        )?;
+
+        let series = stmt
+            .query_map((from, to, bucket_secs.max(1)), |row| {
+                Ok(BlockSeriesData {
+                    bucket_start: row.get(0)?,
+                    block_count: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    total_blobs: row.get(3)?,
+                    avg_gas_price: row.get(4)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(series)
+    }
+
+    /// Get blobs per sender in `bucket_secs` wide buckets for blob transactions
+    /// created in `from..=to`.
+    pub fn get_sender_blob_series(
+        &self,
+        from: u64,
+        to: u64,
+        bucket_secs: u64,
+    ) -> Result<Vec<SenderBlobSeriesData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT created_at / ?3 * ?3, sender, attributed_entity, SUM(blob_count)
+             FROM blob_transactions
+             WHERE created_at BETWEEN ?1 AND ?2
+             GROUP BY 1, 2, 3
+             ORDER BY 1 ASC",
+        )?;
+
+        let series = stmt
+            .query_map((from, to, bucket_secs.max(1)), |row| {
+                Ok(SenderBlobSeriesData {
+                    bucket_start: row.get(0)?,
+                    sender: row.get(1)?,
+                    attributed_entity: row.get(2)?,
+                    blobs: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(series)
+    }
+
+    /// Get transactions in a time range (for chain profiles).
+    pub fn get_transactions_in_time_range(
+        &self,
+        time_limit: i64,
+    ) -> Result<Vec<TimedTransactionData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT sender, attributed_entity, blob_count, created_at, gas_price,
+                    COALESCE(status = 0, 0)
+             FROM blob_transactions
+             WHERE created_at >= ?
+             ORDER BY sender, created_at",
+        )?;
+
+        let rows = stmt
+            .query_map([time_limit], |row| {
+                Ok(TimedTransactionData {
+                    sender: row.get(0)?,
+                    attributed_entity: row.get(1)?,
+                    blob_count: row.get(2)?,
+                    created_at: row.get(3)?,
+                    gas_price: row.get::<_, Wei>(4)?.0,
+                    reverted: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(rows)
+    }
+
+    /// Get every chain's blobs and the blob fees it paid, from the hourly
+    /// rollups, most blobs first.
+    pub fn get_chain_totals(&self) -> Result<Vec<ChainTotalsData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT chain, SUM(total_blobs), TOTAL(blob_fees)
+             FROM hourly_chain_stats
+             GROUP BY chain
+             ORDER BY 2 DESC, 1",
+        )?;
+
+        let totals = stmt
+            .query_map([], |row| {
+                Ok(ChainTotalsData {
+                    chain: row.get(0)?,
+                    total_blobs: row.get(1)?,
+                    blob_fees_wei: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(totals)
+    }
+
+    /// Get blob transactions per chain and hour since `time_limit`: whole
+    /// hours from the rollups, the partial first hour from the transactions.
+    pub fn get_chain_activity(&self, time_limit: u64) -> Result<Vec<ChainActivityData>> {
+        let conn = self.reader();
+        let first_whole_hour = time_limit.div_ceil(SECONDS_PER_HOUR) * SECONDS_PER_HOUR;
+
+        let mut stmt = conn.prepare(
+            "SELECT hour_start, chain, tx_count, total_blobs, first_tx_at, last_tx_at
+             FROM hourly_chain_stats
+             WHERE hour_start >= ?2
+             UNION ALL
+             SELECT group_by_hour(created_at, 0), chain_of(sender, attributed_entity), COUNT(*),
+                    SUM(blob_count), MIN(created_at), MAX(created_at)
+             FROM blob_transactions
+             WHERE created_at >= ?1 AND created_at < ?2
+             GROUP BY 1, 2",
+        )?;
+
+        let activity = stmt
+            .query_map((time_limit, first_whole_hour), |row| {
+                Ok(ChainActivityData {
+                    hour_start: row.get(0)?,
+                    chain: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    total_blobs: row.get(3)?,
+                    first_tx_at: row.get(4)?,
+                    last_tx_at: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(activity)
+    }
+
+    /// Get the blobs `chain` posted per UTC day since `time_limit` and the
+    /// blob fees it paid for them, from the hourly rollups. Chains are matched
+    /// case-insensitively; days without blobs are left out.
+    pub fn get_chain_calendar(
+        &self,
+        chain: &str,
+        time_limit: u64,
+    ) -> Result<Vec<ChainCalendarDayData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT group_by_day(hour_start, 0), chain, SUM(total_blobs), TOTAL(blob_fees)
+             FROM hourly_chain_stats
+             WHERE hour_start >= ?1 AND chain = ?2 COLLATE NOCASE
+             GROUP BY 1, 2
+             ORDER BY 1",
+        )?;
+
+        let days = stmt
+            .query_map((time_limit, chain), |row| {
+                Ok(ChainCalendarDayData {
+                    day_start: row.get(0)?,
+                    chain: row.get(1)?,
+                    total_blobs: row.get(2)?,
+                    blob_fees_wei: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(days)
+    }
+
+    /// Get the blob versioned hash references per UTC day since `time_limit`,
+    /// and how many distinct hashes they were. Days without blobs are left out.
+    pub fn get_daily_blob_hashes(&self, time_limit: u64) -> Result<Vec<DailyBlobHashesData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT day_start, blob_refs, distinct_blobs
+             FROM daily_blob_hashes
+             WHERE day_start >= ?
+             ORDER BY day_start",
+        )?;
+
+        let days = stmt
+            .query_map([time_limit], |row| {
+                Ok(DailyBlobHashesData {
+                    day_start: row.get(0)?,
+                    blob_refs: row.get(1)?,
+                    distinct_blobs: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(days)
+    }
+
+    /// Get blob transactions per chain and UTC day since `time_limit`, with
+    /// the blob fees they paid and their payload sizes where known.
+    pub fn get_chain_days(&self, time_limit: u64) -> Result<Vec<ChainDayData>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT group_by_day(created_at, 0), chain_of(sender, attributed_entity),
+                    COUNT(*), SUM(blob_count), TOTAL(blob_count * ?2 * wei(gas_price)),
+                    SUM(payload_size), SUM(CASE WHEN payload_size IS NOT NULL THEN blob_count END),
+                    MIN(created_at), MAX(created_at)
+             FROM blob_transactions
+             WHERE created_at >= ?1
+             GROUP BY 1, 2",
+        )?;
+
+        let days = stmt
+            .query_map((time_limit, DATA_GAS_PER_BLOB), |row| {
+                Ok(ChainDayData {
+                    day_start: row.get(0)?,
+                    chain: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    total_blobs: row.get(3)?,
+                    blob_fees_wei: row.get(4)?,
+                    payload_bytes: row.get::<_, Option<u64>>(5)?.unwrap_or(0),
+                    sized_blobs: row.get::<_, Option<u64>>(6)?.unwrap_or(0),
+                    first_tx_at: row.get(7)?,
+                    last_tx_at: row.get(8)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(days)
+    }
+}
+
+// Selects annotations with the first indexed block at or after each, filters
+// can be appended
+const ANNOTATIONS_SQL: &str = "
+    SELECT id, timestamp, kind, title, description, updated_at,
+           (SELECT block_number FROM blocks WHERE block_timestamp >= a.timestamp
+            ORDER BY block_timestamp LIMIT 1) AS block_number
+    FROM annotations a";
+
+fn annotation_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AnnotationData> {
+    Ok(AnnotationData {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        kind: row.get(2)?,
+        title: row.get(3)?,
+        description: row.get(4)?,
+        updated_at: row.get(5)?,
+        block_number: row.get(6)?,
+    })
+}
+
+/// A wei amount as stored in fee columns: an INTEGER while it fits, otherwise
+/// a 16 byte big-endian BLOB.
+///
+/// SQLite sorts BLOBs after every number and compares them bytewise, so
+/// `MAX()` and `ORDER BY` stay exact. Arithmetic and aggregates have to go
+/// through the `wei()` SQL function, which reads either form as a REAL.
+struct Wei(u128);
+
+impl ToSql for Wei {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match i64::try_from(self.0) {
+            Ok(value) => ToSqlOutput::from(value),
+            Err(_) => ToSqlOutput::from(self.0.to_be_bytes().to_vec()),
         })
     }
+}
+
+impl FromSql for Wei {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(value) => u128::try_from(value)
+                .map(Wei)
+                .map_err(|_| FromSqlError::OutOfRange(value)),
+            ValueRef::Blob(bytes) => <[u8; 16]>::try_from(bytes)
+                .map(|bytes| Wei(u128::from_be_bytes(bytes)))
+                .map_err(|_| FromSqlError::InvalidBlobSize {
+                    expected_size: 16,
+                    blob_size: bytes.len(),
+                }),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+/// Blob fee of a transaction in wei: its blob gas at the block's blob base fee.
+fn blob_fee(blob_count: u64, blob_gas_price: u128) -> u128 {
+    (blob_count as u128 * DATA_GAS_PER_BLOB as u128).saturating_mul(blob_gas_price)
+}
+
+/// SQLite profiling callback installed by [`Database::log_slow_queries`], see
+/// <https://sqlite.org/c3ref/trace_v2.html>.
+unsafe extern "C" fn profile_statement(
+    _event: c_uint,
+    _context: *mut c_void,
+    statement: *mut c_void,
+    nanos: *mut c_void,
+) -> c_int {
+    // SAFETY: profile events pass the statement that ended and a pointer to
+    // how long it ran, in nanoseconds. The expanded SQL is ours to free.
+    let elapsed = Duration::from_nanos(*nanos.cast::<i64>() as u64);
+    if elapsed.as_nanos() < u128::from(SLOW_QUERY_NANOS.load(Ordering::Relaxed)) {
+        return 0;
+    }
+    let statement = statement.cast::<ffi::sqlite3_stmt>();
+    let expanded = ffi::sqlite3_expanded_sql(statement);
+    let sql = if expanded.is_null() {
+        CStr::from_ptr(ffi::sqlite3_sql(statement))
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        let sql = CStr::from_ptr(expanded).to_string_lossy().into_owned();
+        ffi::sqlite3_free(expanded.cast());
+        sql
+    };
+
+    metrics::counter!("blob_db_slow_queries_total").increment(1);
+    tracing::warn!(
+        elapsed_ms = elapsed.as_millis() as u64,
+        sql = %summarize_sql(&sql),
+        "Slow query"
+    );
+    0
+}
+
+/// `sql` on one line, with string and blob literals longer than
+/// [`MAX_LOGGED_VALUE_LEN`] cut short.
+fn summarize_sql(sql: &str) -> String {
+    let mut summary = String::with_capacity(sql.len());
+    // Odd parts are inside quotes
+    for (i, part) in sql.split('\'').enumerate() {
+        if i > 0 {
+            summary.push('\'');
+        }
+        if i % 2 == 1 {
+            summary.extend(part.chars().take(MAX_LOGGED_VALUE_LEN));
+            if part.chars().count() > MAX_LOGGED_VALUE_LEN {
+                summary.push_str("...");
+            }
+            continue;
+        }
+        let mut in_space = false;
+        for c in part.chars() {
+            if !c.is_whitespace() {
+                summary.push(c);
+            } else if !in_space {
+                summary.push(' ');
+            }
+            in_space = c.is_whitespace();
+        }
+    }
+    summary.trim().to_string()
+}
+
+/// Open a read-only connection to the database at `path` for
+/// [`Database::reader`].
+fn open_reader(path: &str) -> Result<Connection> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    register_functions(&conn)?;
+    Ok(conn)
+}
+
+/// Register the SQL functions queries rely on: `wei()` (see [`Wei`]),
+/// `chain_of(sender, attributed_entity)` (see [`chain_of`]) and the
+/// [`group_by_hour`] and [`group_by_day`] buckets.
+fn register_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "wei",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(ctx.get::<Option<Wei>>(0)?.map(|wei| wei.0 as f64)),
+    )?;
+    conn.create_scalar_function(
+        "chain_of",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let sender = ctx.get::<String>(0)?;
+            let attributed_entity = ctx.get::<Option<String>>(1)?;
+            Ok(chain_of(&sender, attributed_entity.as_deref()))
+        },
+    )?;
+    for (name, bucket) in [
+        ("group_by_hour", group_by_hour as fn(u64, i64) -> u64),
+        ("group_by_day", group_by_day),
+    ] {
+        conn.create_scalar_function(
+            name,
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            move |ctx| {
+                let timestamp = ctx.get::<Option<u64>>(0)?;
+                let utc_offset = ctx.get::<i64>(1)?;
+                Ok(timestamp.map(|timestamp| bucket(timestamp, utc_offset)))
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Entity an address (as stored, see [`address_key`]) belongs to at a block and
+/// where that comes from: the manual label, else the operator configured
+/// entity, else the chain registry's, else the entity of the latest attributed
+/// address that funded it. Configured and registry entities with a block range
+/// only apply within it.
+fn attributed_entity(
+    conn: &Connection,
+    address: &str,
+    block_number: u64,
+) -> Result<Option<(String, LabelSource)>> {
+    let manual: Option<String> = conn
+        .query_row(
+            "SELECT label FROM sender_labels WHERE address = ?",
+            [address],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(label) = manual {
+        return Ok(Some((label, LabelSource::Manual)));
+    }
+
+    let configured: Option<String> = conn
+        .query_row(
+            "SELECT entity FROM entity_addresses
+             WHERE address = ?1
+               AND (from_block IS NULL OR from_block <= ?2)
+               AND (to_block IS NULL OR to_block >= ?2)",
+            (address, block_number),
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(entity) = configured {
+        return Ok(Some((entity, LabelSource::Registry)));
+    }
+
+    let chain = identify_chain_at(address, block_number);
+    if chain != "Other" {
+        return Ok(Some((chain, LabelSource::Registry)));
+    }
+
+    let funded: Option<String> = conn
+        .query_row(
+            "SELECT entity FROM funding_transfers WHERE recipient = ?
+             ORDER BY block_number DESC LIMIT 1",
+            [address],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(funded.map(|entity| (entity, LabelSource::Heuristic)))
+}
+
+/// Recompute `attributed_entity` of every blob transaction from its sender.
+fn reattribute_senders(conn: &Connection) -> Result<()> {
+    let senders: Vec<String> = conn
+        .prepare("SELECT DISTINCT sender FROM blob_transactions")?
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    for sender in senders {
+        reattribute_sender(conn, &sender)?;
+    }
+    rebuild_hourly_chain_stats(conn)
+}
+
+/// Recompute `attributed_entity` of the blob transactions of one sender, one
+/// block range between bounds of its dated mappings at a time.
+fn reattribute_sender(conn: &Connection, sender: &str) -> Result<()> {
+    let mut starts = attribution_bounds(conn, sender)?;
+    starts.insert(0, 0);
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied();
+        let (entity, source) = attributed_entity(conn, sender, start)?.unzip();
+        conn.execute(
+            "UPDATE blob_transactions SET attributed_entity = ?, label_source = ?, registry_version = ?
+             WHERE sender = ? AND block_number >= ? AND (?6 IS NULL OR block_number < ?6)",
+            (
+                entity,
+                source.map(LabelSource::as_str),
+                REGISTRY_VERSION,
+                sender,
+                start,
+                end,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// First blocks (after 0) at which the configured or registry entity of an
+/// address may change, from the block ranges of its dated mappings. Sorted,
+/// empty for addresses mapped for all blocks.
+fn attribution_bounds(conn: &Connection, address: &str) -> Result<Vec<u64>> {
+    let configured: Option<(Option<u64>, Option<u64>)> = conn
+        .query_row(
+            "SELECT from_block, to_block FROM entity_addresses WHERE address = ?",
+            [address],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let registry = DATED_ADDRESSES
+        .iter()
+        .filter(|dated| dated.address == address)
+        .map(|dated| (Some(dated.from_block), dated.to_block));
+
+    let mut bounds: Vec<u64> = configured
+        .into_iter()
+        .chain(registry)
+        .flat_map(|(from, to)| [from, to.and_then(|to| to.checked_add(1))])
+        .flatten()
+        .filter(|&bound| bound > 0)
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+    Ok(bounds)
+}
+
+/// Key under which an address is stored: lowercase hex with a `0x` prefix.
+///
+/// Checksumming is left to the API layer so lookups never depend on casing.
+fn address_key(address: &Address) -> String {
+    format!("{address:#x}")
+}
+
+/// Per-sender `(address, tx_count, total_blobs, total_blob_size)` derived from
+/// the blob transactions matching `filter`, a `WHERE` clause on `t`.
+///
+/// Blobs without a known payload size count as full blobs, as when the stats
+/// were first recorded.
+fn sender_totals_sql(filter: &str) -> String {
+    format!(
+        "SELECT t.sender AS address,
+                COUNT(*) AS tx_count,
+                SUM(t.blob_count) AS total_blobs,
+                SUM((SELECT COALESCE(SUM(COALESCE(h.blob_size, {BLOB_SIZE_BYTES})), 0)
+                     FROM blob_hashes h WHERE h.tx_hash = t.tx_hash)) AS total_blob_size,
+                MIN(t.block_number) AS first_seen_block,
+                MAX(t.block_number) AS last_seen_block,
+                MAX(t.created_at) AS last_seen_timestamp
+         FROM blob_transactions t
+         {filter}
+         GROUP BY t.sender"
+    )
+}
+
+/// Like [`sender_totals_sql`], adding the stats of archived transactions in
+/// `archived_senders`, whose rows can be filtered with `archived_filter`.
+fn lifetime_sender_totals_sql(filter: &str, archived_filter: &str) -> String {
+    format!(
+        "SELECT address,
+                SUM(tx_count) AS tx_count,
+                SUM(total_blobs) AS total_blobs,
+                SUM(total_blob_size) AS total_blob_size,
+                MIN(first_seen_block) AS first_seen_block,
+                MAX(last_seen_block) AS last_seen_block,
+                MAX(last_seen_timestamp) AS last_seen_timestamp
+         FROM (
+             {}
+             UNION ALL
+             SELECT address, tx_count, total_blobs, total_blob_size,
+                    first_seen_block, last_seen_block, last_seen_timestamp
+             FROM archived_senders
+             {archived_filter}
+         )
+         GROUP BY address",
+        sender_totals_sql(filter)
+    )
+}
+
+/// Lowercase sender addresses written by older versions, which stored the
+/// EIP-55 checksummed form, merging sender rows that only differed in casing.
+fn normalize_addresses(conn: &Connection) -> Result<()> {
+    let needs_migration: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM senders WHERE address != lower(address))
+             OR EXISTS(SELECT 1 FROM pending_blob_transactions WHERE sender != lower(sender))",
+        [],
+        |row| row.get(0),
+    )?;
+    if !needs_migration {
+        return Ok(());
+    }
+
+    conn.execute_batch(
+        r#"
+        BEGIN;
+        INSERT INTO senders (
+            address, tx_count, total_blobs, total_blob_size,
+            first_seen_block, last_seen_block, last_seen_timestamp
+        )
+            SELECT lower(address), SUM(tx_count), SUM(total_blobs), SUM(total_blob_size),
+                   MIN(first_seen_block), MAX(last_seen_block), MAX(last_seen_timestamp)
+            FROM senders
+            WHERE address != lower(address)
+            GROUP BY lower(address)
+            ON CONFLICT(address) DO UPDATE SET
+                tx_count = tx_count + excluded.tx_count,
+                total_blobs = total_blobs + excluded.total_blobs,
+                total_blob_size = total_blob_size + excluded.total_blob_size,
+                first_seen_block = MIN(
+                    COALESCE(first_seen_block, excluded.first_seen_block),
+                    COALESCE(excluded.first_seen_block, first_seen_block)
+                ),
+                last_seen_block = MAX(
+                    COALESCE(last_seen_block, excluded.last_seen_block),
+                    COALESCE(excluded.last_seen_block, last_seen_block)
+                ),
+                last_seen_timestamp = MAX(
+                    COALESCE(last_seen_timestamp, excluded.last_seen_timestamp),
+                    COALESCE(excluded.last_seen_timestamp, last_seen_timestamp)
+                );
+        DELETE FROM senders WHERE address != lower(address);
+        UPDATE blob_transactions SET sender = lower(sender) WHERE sender != lower(sender);
+        UPDATE pending_blob_transactions SET sender = lower(sender) WHERE sender != lower(sender);
+        COMMIT;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Drop the duplicate blob hash rows older versions wrote when a block was
+/// reprocessed, then enforce one row per blob with a unique index.
+///
+/// Of each set of duplicates the latest row with a known size is kept, and
+/// payload sizes summed over the duplicates are recomputed.
+fn dedupe_blob_hashes(conn: &Connection) -> Result<()> {
+    let migrated: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master
+                       WHERE type = 'index' AND name = 'idx_blob_hashes_tx_index')",
+        [],
+        |row| row.get(0),
+    )?;
+    if migrated {
+        return Ok(());
+    }
+
+    conn.execute_batch(
+        r#"
+        BEGIN;
+        CREATE TEMP TABLE duplicated_blob_txs AS
+            SELECT DISTINCT tx_hash FROM blob_hashes
+            GROUP BY tx_hash, blob_index
+            HAVING COUNT(*) > 1;
+        DELETE FROM blob_hashes WHERE id NOT IN (
+            SELECT COALESCE(MAX(CASE WHEN blob_size IS NOT NULL THEN id END), MAX(id))
+            FROM blob_hashes
+            GROUP BY tx_hash, blob_index
+        );
+        UPDATE blob_transactions SET payload_size =
+            (SELECT SUM(h.blob_size) FROM blob_hashes h
+             WHERE h.tx_hash = blob_transactions.tx_hash)
+        WHERE payload_size IS NOT NULL
+          AND tx_hash IN (SELECT tx_hash FROM duplicated_blob_txs);
+        DROP TABLE duplicated_blob_txs;
+        CREATE UNIQUE INDEX idx_blob_hashes_tx_index ON blob_hashes(tx_hash, blob_index);
+        COMMIT;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Start of the bucket of `bucket_secs` containing `timestamp`, with bucket
+/// boundaries aligned to the timezone `utc_offset` seconds east of UTC.
+fn bucket_start(timestamp: u64, bucket_secs: u64, utc_offset: i64) -> u64 {
+    let bucket_secs = bucket_secs as i64;
+    let local = timestamp as i64 + utc_offset;
+    (local.div_euclid(bucket_secs) * bucket_secs - utc_offset).max(0) as u64
+}
+
+/// Start of the local hour containing `timestamp`, in the timezone
+/// `utc_offset` seconds east of UTC. Only half and quarter hour offsets move
+/// hour boundaries, so the hourly rollups, stored by UTC hour, can only be
+/// regrouped in whole hour offsets.
+///
+/// Registered as the SQL function `group_by_hour(timestamp, utc_offset)`.
+pub fn group_by_hour(timestamp: u64, utc_offset: i64) -> u64 {
+    bucket_start(timestamp, SECONDS_PER_HOUR, utc_offset)
+}
+
+/// Start of the local day containing `timestamp`, in the timezone
+/// `utc_offset` seconds east of UTC. An offset of 0 gives UTC days.
+///
+/// Registered as the SQL function `group_by_day(timestamp, utc_offset)`.
+pub fn group_by_day(timestamp: u64, utc_offset: i64) -> u64 {
+    bucket_start(timestamp, SECONDS_PER_DAY, utc_offset)
+}
+
+/// Local hour of the day of `timestamp`, 0 to 23.
+pub fn hour_of_day(timestamp: u64, utc_offset: i64) -> usize {
+    let local = timestamp as i64 + utc_offset;
+    local.div_euclid(SECONDS_PER_HOUR as i64).rem_euclid(24) as usize
+}
+
+/// Local day of the week of `timestamp`, 0 for Monday to 6 for Sunday.
+pub fn weekday(timestamp: u64, utc_offset: i64) -> usize {
+    let local = timestamp as i64 + utc_offset;
+    // The Unix epoch was a Thursday
+    (local.div_euclid(SECONDS_PER_DAY as i64) + 3).rem_euclid(7) as usize
+}
+
+/// Add a column to an existing table unless it is already present.
+///
+/// Returns whether the column was added, so callers can backfill it.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+
+    if exists {
+        return Ok(false);
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+        (),
+    )?;
+    Ok(true)
+}
+
+/// Recompute the hourly rollup covering `timestamp` from the blocks table.
+///
+/// Recomputing rather than incrementing keeps the rollup correct when blocks
+/// are replaced or reverted.
+fn refresh_hourly_stats(conn: &Connection, timestamp: u64) -> Result<()> {
+    let hour_start = group_by_hour(timestamp, 0);
+    conn.execute(
+        "DELETE FROM hourly_blob_stats WHERE hour_start = ?",
+        (hour_start,),
+    )?;
+    conn.execute(
+        "INSERT INTO hourly_blob_stats
+         SELECT ?1, COUNT(*), SUM(tx_count), SUM(total_blobs), SUM(gas_used), TOTAL(wei(gas_price))
+         FROM blocks
+         WHERE block_timestamp >= ?1 AND block_timestamp < ?1 + 3600
+         HAVING COUNT(*) > 0",
+        (hour_start,),
+    )?;
+    Ok(())
+}
+
+/// Recompute the per-chain hourly rollups of the hours covering `from..=to`
+/// from the blob transactions table.
+fn refresh_hourly_chain_stats(conn: &Connection, from: u64, to: u64) -> Result<()> {
+    let first_hour = group_by_hour(from, 0).max(archived_before(conn)?);
+    let last_hour = group_by_hour(to, 0);
+    if first_hour > last_hour {
+        return Ok(());
+    }
+    conn.execute(
+        "DELETE FROM hourly_chain_stats WHERE hour_start BETWEEN ? AND ?",
+        (first_hour, last_hour),
+    )?;
+    conn.execute(
+        &format!(
+            "{CHAIN_STATS_SQL} WHERE created_at >= ?1 AND created_at < ?2 + 3600 GROUP BY 1, 2"
+        ),
+        (first_hour, last_hour),
+    )?;
+    Ok(())
+}
+
+// Inserts the daily blob hash rollups, conditions on the blob transactions `t`
+// can be appended
+const DAILY_BLOB_HASHES_SQL: &str = "
+    INSERT INTO daily_blob_hashes (day_start, blob_refs, distinct_blobs)
+    SELECT group_by_day(t.created_at, 0), COUNT(*), COUNT(DISTINCT h.blob_hash)
+    FROM blob_hashes h
+    JOIN blob_transactions t ON t.tx_hash = h.tx_hash";
+
+/// Recompute the daily blob hash rollups of the UTC days covering `from..=to`.
+fn refresh_daily_blob_hashes(conn: &Connection, from: u64, to: u64) -> Result<()> {
+    let first_day = group_by_day(from, 0).max(archived_before(conn)?);
+    let last_day = group_by_day(to, 0);
+    if first_day > last_day {
+        return Ok(());
+    }
+    conn.execute(
+        "DELETE FROM daily_blob_hashes WHERE day_start BETWEEN ? AND ?",
+        (first_day, last_day),
+    )?;
+    conn.execute(
+        &format!(
+            "{DAILY_BLOB_HASHES_SQL}
+             WHERE t.created_at >= ?1 AND t.created_at < ?2 + 86400 GROUP BY 1"
+        ),
+        (first_day, last_day),
+    )?;
+    Ok(())
+}
+
+/// Recompute the per-chain hourly rollups of every hour `sender` posted in,
+/// after its transactions were re-attributed.
+fn refresh_sender_chain_stats(conn: &Connection, sender: &str) -> Result<()> {
+    let hours: Vec<u64> = conn
+        .prepare(
+            "SELECT DISTINCT group_by_hour(created_at, 0) FROM blob_transactions WHERE sender = ?",
+        )?
+        .query_map([sender], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    for hour_start in hours {
+        refresh_hourly_chain_stats(conn, hour_start, hour_start)?;
+    }
+    Ok(())
+}
+
+/// Refuse databases written by a build with a newer schema.
+fn check_schema_version(conn: &Connection) -> Result<()> {
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(DbError::Schema(format!(
+            "database schema version {version} is newer than {SCHEMA_VERSION}, \
+             the latest this build supports"
+        )));
+    }
+    Ok(())
+}
+
+/// Tables and indexes of a database, as compared by [`Database::schema_problems`].
+struct Schema {
+    tables: BTreeMap<String, Vec<ColumnSchema>>,
+    indexes: BTreeMap<String, IndexSchema>,
+}
+
+#[derive(PartialEq, Eq)]
+struct ColumnSchema {
+    name: String,
+    declared_type: String,
+    primary_key: bool,
+}
+
+impl Display for ColumnSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.declared_type)?;
+        if self.primary_key {
+            write!(f, " PRIMARY KEY")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct IndexSchema {
+    table: String,
+    columns: Vec<String>,
+}
+
+impl Display for IndexSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.table, self.columns.join(", "))
+    }
+}
+
+fn read_schema(conn: &Connection) -> Result<Schema> {
+    let objects: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT type, name, tbl_name FROM sqlite_master
+             WHERE type IN ('table', 'index') AND name NOT LIKE 'sqlite_%'",
+        )?;
+        let objects = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        objects
+    };
+
+    let mut schema = Schema {
+        tables: BTreeMap::new(),
+        indexes: BTreeMap::new(),
+    };
+    for (kind, name, table) in objects {
+        if kind == "table" {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({name})"))?;
+            let columns = stmt
+                .query_map([], |row| {
+                    Ok(ColumnSchema {
+                        name: row.get(1)?,
+                        declared_type: row.get::<_, String>(2)?.to_uppercase(),
+                        primary_key: row.get::<_, u32>(5)? > 0,
+                    })
+                })?
+                .filter_map(|r| r.ok())
+                .collect();
+            schema.tables.insert(name, columns);
+        } else {
+            let mut stmt = conn.prepare(&format!("PRAGMA index_info({name})"))?;
+            let columns = stmt
+                .query_map([], |row| row.get(2))?
+                .filter_map(|r| r.ok())
+                .collect();
+            schema.indexes.insert(name, IndexSchema { table, columns });
+        }
+    }
+    Ok(schema)
+}
+
+/// Recreate indexes dropped by [`Database::begin_bulk_ingest`]. Returns
+/// whether there were any.
+fn restore_deferred_indexes(conn: &Connection) -> Result<bool> {
+    let indexes: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT name, sql FROM bulk_deferred_indexes")?;
+        let indexes = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        indexes
+    };
+    for (name, sql) in &indexes {
+        // Another process may have recreated it on startup
+        conn.execute(
+            &sql.replacen("CREATE INDEX", "CREATE INDEX IF NOT EXISTS", 1),
+            (),
+        )?;
+        conn.execute("DELETE FROM bulk_deferred_indexes WHERE name = ?", [name])?;
+    }
+    Ok(!indexes.is_empty())
+}
+
+/// Rebuild the hourly rollups and records, which a bulk ingest doesn't
+/// maintain per insert.
+fn rebuild_derived_tables(conn: &Connection) -> Result<()> {
+    rebuild_hourly_stats(conn)?;
+    rebuild_hourly_chain_stats(conn)?;
+    rebuild_daily_blob_hashes(conn)?;
+    for kind in RECORD_KINDS {
+        rebuild_record(conn, kind)?;
+    }
+    rebuild_current_streak(conn)?;
+    rebuild_fork_events(conn)
+}
+
+/// Set the target and max of every block to those `schedule` has in effect at
+/// its timestamp, only writing blocks that differ.
+fn fill_block_blob_params(conn: &Connection, schedule: &BlobSchedule) -> Result<()> {
+    let entries = schedule.entries();
+    let mut updated = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        // The first entry also covers older blocks
+        let from = if i == 0 {
+            0
+        } else {
+            entry.activation_timestamp
+        };
+        let to = entries
+            .get(i + 1)
+            .map_or(i64::MAX as u64, |next| next.activation_timestamp);
+        updated += conn.execute(
+            "UPDATE blocks SET blob_target = ?3, blob_max = ?4
+             WHERE block_timestamp >= ?1 AND block_timestamp < ?2
+               AND (blob_target IS NOT ?3 OR blob_max IS NOT ?4)",
+            (from, to, entry.target, entry.max),
+        )?;
+    }
+    if updated > 0 {
+        rebuild_fork_events(conn)?;
+    }
+    Ok(())
+}
 
-    /// Get recent blocks with their transactions.
-    pub fn get_recent_blocks(&self, limit: u64) -> eyre::Result<Vec<BlockData>> {
-        let conn = self.connection();
+/// The blob schedule stored by the ExEx, see [`Database::get_blob_schedule`].
+fn blob_schedule(conn: &Connection) -> Result<BlobSchedule> {
+    let mut stmt = conn.prepare(
+        "SELECT activation_timestamp, target, max, base_fee_update_fraction
+         FROM blob_schedule ORDER BY activation_timestamp ASC",
+    )?;
 
-        let mut stmt = conn.prepare(
-            "SELECT block_number, block_timestamp, tx_count, total_blobs, gas_used, gas_price, excess_blob_gas
-             FROM blocks ORDER BY block_number DESC LIMIT ?",
-        )?;
+    let entries: Vec<BlobScheduleEntry> = stmt
+        .query_map([], |row| {
+            Ok(BlobScheduleEntry {
+                activation_timestamp: row.get(0)?,
+                target: row.get(1)?,
+                max: row.get(2)?,
+                base_fee_update_fraction: row.get(3)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
 
-        let block_data: Vec<(u64, u64, u64, u64, u64, u64, u64)> = stmt
-            .query_map([limit], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                    row.get(5)?,
-                    row.get(6)?,
-                ))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+    match BlobSchedule::new(entries) {
+        Some(schedule) => Ok(schedule),
+        None => Ok(BlobSchedule::from_env()
+            .map_err(|err| DbError::Schema(format!("{err:#}")))?
+            .unwrap_or_default()),
+    }
+}
 
-        let mut blocks = Vec::with_capacity(block_data.len());
+/// Rebuild every hourly rollup from the blocks table.
+fn rebuild_hourly_stats(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM hourly_blob_stats", ())?;
+    conn.execute(
+        "INSERT INTO hourly_blob_stats
+         SELECT group_by_hour(block_timestamp, 0), COUNT(*), SUM(tx_count),
+                SUM(total_blobs), SUM(gas_used), TOTAL(wei(gas_price))
+         FROM blocks
+         GROUP BY 1",
+        (),
+    )?;
+    Ok(())
+}
 
-        for (
-            block_number,
-            block_timestamp,
-            tx_count,
-            total_blobs,
-            gas_used,
-            gas_price,
-            excess_blob_gas,
-        ) in block_data
-        {
-            let mut tx_stmt = conn.prepare(
-                "SELECT tx_hash, sender, blob_count FROM blob_transactions WHERE block_number = ?",
-            )?;
+// Inserts blob transactions per chain and hour, a filter and `GROUP BY 1, 2`
+// are appended
+const CHAIN_STATS_SQL: &str = "
+    INSERT INTO hourly_chain_stats
+    SELECT group_by_hour(created_at, 0), chain_of(sender, attributed_entity), COUNT(*),
+           SUM(blob_count), MIN(created_at), MAX(created_at), TOTAL(wei(blob_fee))
+    FROM blob_transactions";
 
-            let transactions: Vec<TransactionData> = tx_stmt
-                .query_map([block_number], |row| {
-                    Ok(TransactionData {
-                        tx_hash: row.get(0)?,
-                        sender: row.get(1)?,
-                        blob_count: row.get(2)?,
-                    })
-                })?
-                .filter_map(|r| r.ok())
-                .collect();
+/// Rebuild every per-chain hourly rollup after the archived days from the
+/// blob transactions table.
+fn rebuild_hourly_chain_stats(conn: &Connection) -> Result<()> {
+    let from = archived_before(conn)?;
+    conn.execute(
+        "DELETE FROM hourly_chain_stats WHERE hour_start >= ?",
+        [from],
+    )?;
+    conn.execute(
+        &format!("{CHAIN_STATS_SQL} WHERE created_at >= ? GROUP BY 1, 2"),
+        [from],
+    )?;
+    Ok(())
+}
 
-            blocks.push(BlockData {
-                block_number,
-                block_timestamp,
-                tx_count,
-                total_blobs,
-                gas_used,
-                gas_price,
-                excess_blob_gas,
-                transactions,
-            });
-        }
+/// Rebuild every daily blob hash rollup after the archived days from the
+/// blob hashes table.
+fn rebuild_daily_blob_hashes(conn: &Connection) -> Result<()> {
+    let from = archived_before(conn)?;
+    conn.execute("DELETE FROM daily_blob_hashes WHERE day_start >= ?", [from])?;
+    conn.execute(
+        &format!("{DAILY_BLOB_HASHES_SQL} WHERE t.created_at >= ? GROUP BY 1"),
+        [from],
+    )?;
+    Ok(())
+}
 
-        Ok(blocks)
+/// Start of the first UTC day after the archived ones, 0 if nothing was
+/// archived. [`Database::archive`] moves whole days, so rollups before it
+/// can't be recomputed from the indexed rows and are left as they are.
+fn archived_before(conn: &Connection) -> Result<u64> {
+    let before = conn.query_row(
+        "SELECT COALESCE(group_by_day(MAX(last_tx_at), 0) + 86400, 0) FROM archives",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(before)
+}
+
+// Inserts the blob schedule changes between blocks and their parents, more
+// conditions on the block `b` can be appended
+const FORK_EVENTS_SQL: &str = "
+    INSERT INTO fork_events (
+        block_number, block_timestamp, previous_target, previous_max, target, max
+    )
+    SELECT b.block_number, b.block_timestamp, p.blob_target, p.blob_max, b.blob_target, b.blob_max
+    FROM blocks b
+    JOIN blocks p ON p.block_number = b.block_number - 1
+    WHERE (b.blob_target != p.blob_target OR b.blob_max != p.blob_max)";
+
+/// Rebuild `fork_events` from the target and max of every block.
+fn rebuild_fork_events(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM fork_events", ())?;
+    conn.execute(FORK_EVENTS_SQL, ())?;
+    Ok(())
+}
+
+/// Re-detect the fork events of `block_number` and its child after the block
+/// was inserted or deleted.
+fn refresh_fork_events(conn: &Connection, block_number: u64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM fork_events WHERE block_number IN (?1, ?1 + 1)",
+        (block_number,),
+    )?;
+    conn.execute(
+        &format!("{FORK_EVENTS_SQL} AND b.block_number IN (?1, ?1 + 1)"),
+        (block_number,),
+    )?;
+    Ok(())
+}
+
+/// Kinds of rows in `records`, see [`RecordData`].
+pub const RECORD_MOST_BLOBS: &str = "most_blobs";
+pub const RECORD_HIGHEST_BLOB_FEE: &str = "highest_blob_fee";
+pub const RECORD_LONGEST_SATURATED_STREAK: &str = "longest_saturated_streak";
+pub const RECORD_LARGEST_SENDER_DAY: &str = "largest_sender_day";
+const RECORD_KINDS: [&str; 4] = [
+    RECORD_MOST_BLOBS,
+    RECORD_HIGHEST_BLOB_FEE,
+    RECORD_LONGEST_SATURATED_STREAK,
+    RECORD_LARGEST_SENDER_DAY,
+];
+
+// The run of blocks at max blobs ending at the latest block, extended as
+// blocks arrive so the longest streak needs no scan
+const CURRENT_SATURATED_STREAK: &str = "current_saturated_streak";
+
+fn record_value(conn: &Connection, kind: &str) -> Result<Option<u128>> {
+    let value = conn
+        .query_row("SELECT value FROM records WHERE kind = ?", [kind], |row| {
+            row.get::<_, Wei>(0)
+        })
+        .optional()?;
+    Ok(value.map(|value| value.0))
+}
+
+fn set_record(
+    conn: &Connection,
+    kind: &str,
+    value: u128,
+    blocks: RangeInclusive<u64>,
+    sender_day: Option<(&str, u64)>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO records (kind, value, from_block, to_block, sender, day)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        (
+            kind,
+            Wei(value),
+            blocks.start(),
+            blocks.end(),
+            sender_day.map(|(sender, _)| sender),
+            sender_day.map(|(_, day)| day),
+        ),
+    )?;
+    Ok(())
+}
+
+/// Beat the block records with a newly inserted block and extend the
+/// saturated streak. Ties keep the earlier record.
+fn update_block_records(conn: &Connection, block: &NewBlock) -> Result<()> {
+    let n = block.block_number;
+    if block.total_blobs > 0
+        && record_value(conn, RECORD_MOST_BLOBS)?.is_none_or(|v| block.total_blobs as u128 > v)
+    {
+        set_record(
+            conn,
+            RECORD_MOST_BLOBS,
+            block.total_blobs as u128,
+            n..=n,
+            None,
+        )?;
+    }
+    if record_value(conn, RECORD_HIGHEST_BLOB_FEE)?.is_none_or(|v| block.gas_price > v) {
+        set_record(conn, RECORD_HIGHEST_BLOB_FEE, block.gas_price, n..=n, None)?;
     }
 
-    /// Get a specific block by number.
-    pub fn get_block(&self, block_number: u64) -> eyre::Result<Option<BlockData>> {
-        let conn = self.connection();
+    if block.total_blobs < block.blob_max {
+        return Ok(());
+    }
+    let latest: u64 =
+        conn.query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))?;
+    let current: Option<(u64, u64)> = conn
+        .query_row(
+            "SELECT from_block, to_block FROM records WHERE kind = ?",
+            [CURRENT_SATURATED_STREAK],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
 
-        let block_row: Option<(u64, u64, u64, u64, u64, u64)> = conn
+    // A new tip extends the current streak, an older block may join up the
+    // streaks around it
+    let run = match current {
+        Some((from, to)) if n == latest && to + 1 == n => from..=n,
+        _ => saturated_run(conn, n)?,
+    };
+    let length = (run.end() - run.start() + 1) as u128;
+    if *run.end() == latest {
+        set_record(conn, CURRENT_SATURATED_STREAK, length, run.clone(), None)?;
+    }
+    if record_value(conn, RECORD_LONGEST_SATURATED_STREAK)?.is_none_or(|v| length > v) {
+        set_record(conn, RECORD_LONGEST_SATURATED_STREAK, length, run, None)?;
+    }
+    Ok(())
+}
+
+/// The run of consecutive blocks at max blobs around block `n`, which must be
+/// at max blobs itself.
+fn saturated_run(conn: &Connection, n: u64) -> Result<RangeInclusive<u64>> {
+    let saturated = |block_number: u64| -> Result<bool> {
+        let saturated: Option<bool> = conn
             .query_row(
-                "SELECT block_timestamp, tx_count, total_blobs, gas_used, gas_price, excess_blob_gas
-                 FROM blocks WHERE block_number = ?",
+                "SELECT total_blobs >= blob_max FROM blocks WHERE block_number = ?",
                 [block_number],
-                |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        row.get(3)?,
-                        row.get(4)?,
-                        row.get(5)?,
-                    ))
-                },
+                |row| row.get(0),
             )
-            .ok();
+            .optional()?;
+        Ok(saturated.unwrap_or(false))
+    };
 
-        if let Some((
-            block_timestamp,
-            tx_count,
-            total_blobs,
-            gas_used,
-            gas_price,
-            excess_blob_gas,
-        )) = block_row
-        {
-            let mut tx_stmt = conn.prepare(
-                "SELECT tx_hash, sender, blob_count FROM blob_transactions WHERE block_number = ?",
-            )?;
+    let (mut from, mut to) = (n, n);
+    while from > 0 && saturated(from - 1)? {
+        from -= 1;
+    }
+    while saturated(to + 1)? {
+        to += 1;
+    }
+    Ok(from..=to)
+}
 
-            let transactions: Vec<TransactionData> = tx_stmt
-                .query_map([block_number], |row| {
-                    Ok(TransactionData {
-                        tx_hash: row.get(0)?,
-                        sender: row.get(1)?,
-                        blob_count: row.get(2)?,
-                    })
-                })?
-                .filter_map(|r| r.ok())
-                .collect();
+/// Beat the sender day record with the UTC day of a newly inserted transaction.
+fn update_sender_day_record(conn: &Connection, sender: &str, created_at: u64) -> Result<()> {
+    let day = group_by_day(created_at, 0);
+    let (blobs, from_block, to_block): (u64, u64, u64) = conn.query_row(
+        "SELECT SUM(blob_count), MIN(block_number), MAX(block_number)
+         FROM blob_transactions
+         WHERE sender = ? AND created_at >= ? AND created_at < ?",
+        (sender, day, day + SECONDS_PER_DAY),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    if record_value(conn, RECORD_LARGEST_SENDER_DAY)?.is_none_or(|v| blobs as u128 > v) {
+        set_record(
+            conn,
+            RECORD_LARGEST_SENDER_DAY,
+            blobs as u128,
+            from_block..=to_block,
+            Some((sender, day)),
+        )?;
+    }
+    Ok(())
+}
 
-            Ok(Some(BlockData {
-                block_number,
-                block_timestamp,
-                tx_count,
-                total_blobs,
-                gas_used,
-                gas_price,
-                excess_blob_gas,
-                transactions,
-            }))
+/// Recompute the records a deleted block was part of. Deleting a block can't
+/// make any other record stale, so after a reorg at the tip this is rarely
+/// more than the current streak.
+fn revert_records(conn: &Connection, block_number: u64) -> Result<()> {
+    let mut stmt =
+        conn.prepare("SELECT kind FROM records WHERE from_block <= ?1 AND ?1 <= to_block")?;
+    let stale: Vec<String> = stmt
+        .query_map([block_number], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    for kind in stale {
+        conn.execute("DELETE FROM records WHERE kind = ?", [&kind])?;
+        if kind == CURRENT_SATURATED_STREAK {
+            rebuild_current_streak(conn)?;
         } else {
-            Ok(None)
+            rebuild_record(conn, &kind)?;
         }
     }
+    Ok(())
+}
 
-    /// Get top senders by total blobs.
-    pub fn get_top_senders(&self, limit: u64) -> eyre::Result<Vec<SenderData>> {
-        let conn = self.connection();
+/// Recompute a record from scratch.
+fn rebuild_record(conn: &Connection, kind: &str) -> Result<()> {
+    match kind {
+        RECORD_MOST_BLOBS | RECORD_HIGHEST_BLOB_FEE => {
+            let column = if kind == RECORD_MOST_BLOBS {
+                "total_blobs"
+            } else {
+                "gas_price"
+            };
+            let best: Option<(u64, Wei)> = conn
+                .query_row(
+                    &format!(
+                        "SELECT block_number, {column} FROM blocks
+                         ORDER BY {column} DESC, block_number ASC LIMIT 1"
+                    ),
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            if let Some((n, value)) =
+                best.filter(|(_, value)| value.0 > 0 || kind != RECORD_MOST_BLOBS)
+            {
+                set_record(conn, kind, value.0, n..=n, None)?;
+            }
+        }
+        RECORD_LONGEST_SATURATED_STREAK => {
+            let mut stmt = conn.prepare(
+                "SELECT block_number, total_blobs >= blob_max FROM blocks ORDER BY block_number",
+            )?;
+            let blocks = stmt.query_map([], |row| {
+                Ok((row.get::<_, u64>(0)?, row.get::<_, bool>(1)?))
+            })?;
 
-        let mut stmt = conn.prepare(
-            "SELECT address, tx_count, total_blobs
-             FROM senders ORDER BY total_blobs DESC LIMIT ?",
-        )?;
+            let mut longest: Option<RangeInclusive<u64>> = None;
+            let mut streak: Option<RangeInclusive<u64>> = None;
+            for block in blocks {
+                let (n, saturated) = block?;
+                if !saturated {
+                    streak = None;
+                    continue;
+                }
+                let run = match streak {
+                    Some(run) if *run.end() + 1 == n => *run.start()..=n,
+                    _ => n..=n,
+                };
+                if longest
+                    .as_ref()
+                    .is_none_or(|longest| run.end() - run.start() > longest.end() - longest.start())
+                {
+                    longest = Some(run.clone());
+                }
+                streak = Some(run);
+            }
+            if let Some(run) = longest {
+                let length = (run.end() - run.start() + 1) as u128;
+                set_record(conn, kind, length, run, None)?;
+            }
+        }
+        RECORD_LARGEST_SENDER_DAY => {
+            let best: Option<(String, u64, u64, u64, u64)> = conn
+                .query_row(
+                    "SELECT sender, group_by_day(created_at, 0) AS day, SUM(blob_count) AS blobs,
+                            MIN(block_number), MAX(block_number)
+                     FROM blob_transactions
+                     GROUP BY sender, day
+                     ORDER BY blobs DESC, day ASC LIMIT 1",
+                    [],
+                    |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(3)?,
+                            row.get(4)?,
+                        ))
+                    },
+                )
+                .optional()?;
+            if let Some((sender, day, blobs, from_block, to_block)) = best {
+                set_record(
+                    conn,
+                    kind,
+                    blobs as u128,
+                    from_block..=to_block,
+                    Some((&sender, day)),
+                )?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
 
-        let senders: Vec<SenderData> = stmt
-            .query_map([limit], |row| {
-                Ok(SenderData {
-                    address: row.get(0)?,
-                    tx_count: row.get(1)?,
-                    total_blobs: row.get(2)?,
-                })
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+/// Recompute the saturated streak ending at the latest block, walking back
+/// from it.
+fn rebuild_current_streak(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "DELETE FROM records WHERE kind = ?",
+        [CURRENT_SATURATED_STREAK],
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT block_number, total_blobs >= blob_max FROM blocks ORDER BY block_number DESC",
+    )?;
+    let mut blocks = stmt.query([])?;
 
-        Ok(senders)
+    let mut streak: Option<RangeInclusive<u64>> = None;
+    while let Some(row) = blocks.next()? {
+        let (n, saturated): (u64, bool) = (row.get(0)?, row.get(1)?);
+        let extends = streak.as_ref().is_none_or(|run| n + 1 == *run.start());
+        if !extends || !saturated {
+            break;
+        }
+        streak = Some(n..=streak.map_or(n, |run| *run.end()));
     }
+    if let Some(run) = streak {
+        let length = (run.end() - run.start() + 1) as u128;
+        set_record(conn, CURRENT_SATURATED_STREAK, length, run, None)?;
+    }
+    Ok(())
+}
 
-    /// Get chart data for the last N blocks.
-    pub fn get_chart_data(&self, num_blocks: u64) -> eyre::Result<ChartData> {
-        let conn = self.connection();
-
-        let latest_block: u64 = conn
-            .query_row("SELECT MAX(block_number) FROM blocks", [], |row| row.get(0))
-            .unwrap_or(0);
+/// Block production between the first and last indexed block at or after
+/// `since`.
+fn slot_throughput(conn: &Connection, since: u64) -> Result<Option<SlotThroughputData>> {
+    let throughput = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(total_blobs), 0),
+                    MIN(block_number), MAX(block_number),
+                    MIN(block_timestamp), MAX(block_timestamp)
+             FROM blocks WHERE block_timestamp >= ?",
+        [since],
+        |row| {
+            let indexed_blocks: u64 = row.get(0)?;
+            if indexed_blocks == 0 {
+                return Ok(None);
+            }
+            Ok(Some(SlotThroughputData {
+                indexed_blocks,
+                total_blobs: row.get(1)?,
+                first_block: row.get(2)?,
+                last_block: row.get(3)?,
+                first_timestamp: row.get(4)?,
+                last_timestamp: row.get(5)?,
+            }))
+        },
+    )?;
+    Ok(throughput)
+}
 
-        if latest_block == 0 {
-            return Ok(ChartData {
-                labels: Vec::new(),
-                blobs: Vec::new(),
-                gas_prices: Vec::new(),
-            });
-        }
+/// Current Unix time in seconds.
+fn unix_timestamp() -> Result<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .map_err(|err| DbError::Io(format!("system clock is before the UNIX epoch: {err}")))
+}
 
-        let start_block = latest_block.saturating_sub(num_blocks - 1);
+/// How long a backup waits before retrying when the source or destination is locked.
+const BACKUP_RETRY_PAUSE: Duration = Duration::from_millis(100);
 
-        let mut stmt = conn.prepare(
-            "SELECT block_number, total_blobs, gas_price
-             FROM blocks
-             WHERE block_number >= ? AND block_number <= ?
-             ORDER BY block_number ASC",
-        )?;
+/// How many times a locked backup is retried before giving up.
+const BACKUP_RETRIES: u32 = 50;
 
-        let mut block_data: std::collections::HashMap<u64, (u64, u64)> =
-            std::collections::HashMap::new();
-        let mut last_gas_price: u64 = 0;
+/// Copy all of `from` into `to` with the SQLite backup API, in a single step.
+fn copy_database(from: &Connection, to: &mut Connection) -> Result<()> {
+    let backup = Backup::new(from, to)?;
+    for _ in 0..BACKUP_RETRIES {
+        match backup.step(-1)? {
+            StepResult::Done => return Ok(()),
+            _ => std::thread::sleep(BACKUP_RETRY_PAUSE),
+        }
+    }
+    Err(DbError::Busy(
+        "database stayed locked during backup".to_string(),
+    ))
+}
 
-        let rows = stmt.query_map([start_block, latest_block], |row| {
+/// Move the blob transactions of `month` (`YYYY-MM`) created before `before`
+/// into the database attached as `archive`, see [`Database::archive`].
+fn archive_month(conn: &Connection, month: &str, before: u64, path: &Path) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS archive.blob_transactions AS
+             SELECT * FROM main.blob_transactions WHERE 0;
+         CREATE UNIQUE INDEX IF NOT EXISTS archive.idx_archive_tx_hash
+             ON blob_transactions(tx_hash);
+         CREATE TABLE IF NOT EXISTS archive.blob_hashes AS
+             SELECT * FROM main.blob_hashes WHERE 0;
+         CREATE UNIQUE INDEX IF NOT EXISTS archive.idx_archive_blob
+             ON blob_hashes(tx_hash, blob_index);
+         CREATE TEMP TABLE IF NOT EXISTS archiving (tx_hash TEXT PRIMARY KEY);
+         DELETE FROM temp.archiving;",
+    )?;
+    conn.execute(
+        "INSERT INTO temp.archiving
+         SELECT tx_hash FROM main.blob_transactions
+         WHERE created_at < ?1 AND strftime('%Y-%m', created_at, 'unixepoch') = ?2",
+        (before, month),
+    )?;
+    let (tx_count, blob_count, first_tx_at, last_tx_at): (u64, u64, u64, u64) = conn.query_row(
+        "SELECT COUNT(*), TOTAL(blob_count), MIN(created_at), MAX(created_at)
+             FROM main.blob_transactions
+             WHERE tx_hash IN (SELECT tx_hash FROM temp.archiving)",
+        [],
+        |row| {
             Ok((
-                row.get::<_, u64>(0)?,
-                row.get::<_, u64>(1)?,
-                row.get::<_, u64>(2)?,
+                row.get(0)?,
+                row.get::<_, f64>(1)? as u64,
+                row.get(2)?,
+                row.get(3)?,
             ))
-        })?;
+        },
+    )?;
 
-        for row in rows.flatten() {
-            block_data.insert(row.0, (row.1, row.2));
-            last_gas_price = row.2;
-        }
-
-        let mut labels = Vec::with_capacity(num_blocks as usize);
-        let mut blobs = Vec::with_capacity(num_blocks as usize);
-        let mut gas_prices = Vec::with_capacity(num_blocks as usize);
+    conn.execute(
+        &format!(
+            "INSERT INTO archived_senders (
+                 address, tx_count, total_blobs, total_blob_size,
+                 first_seen_block, last_seen_block, last_seen_timestamp
+             ) {}
+             ON CONFLICT(address) DO UPDATE SET
+                 tx_count = tx_count + excluded.tx_count,
+                 total_blobs = total_blobs + excluded.total_blobs,
+                 total_blob_size = total_blob_size + excluded.total_blob_size,
+                 first_seen_block = MIN(first_seen_block, excluded.first_seen_block),
+                 last_seen_block = MAX(last_seen_block, excluded.last_seen_block),
+                 last_seen_timestamp = MAX(last_seen_timestamp, excluded.last_seen_timestamp)",
+            sender_totals_sql("WHERE t.tx_hash IN (SELECT tx_hash FROM temp.archiving)")
+        ),
+        (),
+    )?;
 
-        for block_num in start_block..=latest_block {
-            labels.push(block_num);
-            if let Some((blob_count, gas_price)) = block_data.get(&block_num) {
-                blobs.push(*blob_count);
-                gas_prices.push(*gas_price as f64 / 1e9);
-                last_gas_price = *gas_price;
-            } else {
-                blobs.push(0);
-                gas_prices.push(last_gas_price as f64 / 1e9);
-            }
+    for table in ["blob_hashes", "blob_transactions"] {
+        // Columns added to this database since the archive was created
+        let archived: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info(?1, 'archive')")?
+            .query_map([table], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info(?1, 'main')")?
+            .query_map([table], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        for column in columns.iter().filter(|column| !archived.contains(column)) {
+            conn.execute(
+                &format!("ALTER TABLE archive.{table} ADD COLUMN {column}"),
+                (),
+            )?;
         }
 
-        Ok(ChartData {
-            labels,
-            blobs,
-            gas_prices,
-        })
+        let columns = columns.join(", ");
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO archive.{table} ({columns})
+                 SELECT {columns} FROM main.{table}
+                 WHERE tx_hash IN (SELECT tx_hash FROM temp.archiving)"
+            ),
+            (),
+        )?;
+        conn.execute(
+            &format!(
+                "DELETE FROM main.{table} WHERE tx_hash IN (SELECT tx_hash FROM temp.archiving)"
+            ),
+            (),
+        )?;
     }
 
-    /// Get recent blob transactions.
-    pub fn get_blob_transactions(&self, limit: u64) -> eyre::Result<Vec<BlobTransactionData>> {
-        let conn = self.connection();
+    conn.execute(
+        "INSERT INTO archives
+             (month, path, tx_count, blob_count, first_tx_at, last_tx_at, archived_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(month) DO UPDATE SET
+             path = excluded.path,
+             tx_count = tx_count + excluded.tx_count,
+             blob_count = blob_count + excluded.blob_count,
+             first_tx_at = MIN(first_tx_at, excluded.first_tx_at),
+             last_tx_at = MAX(last_tx_at, excluded.last_tx_at),
+             archived_at = excluded.archived_at",
+        (
+            month,
+            path.to_string_lossy(),
+            tx_count,
+            blob_count,
+            first_tx_at,
+            last_tx_at,
+            unix_timestamp()?,
+        ),
+    )?;
+    tx.commit()?;
+    Ok(())
+}
 
-        let mut stmt = conn.prepare(
-            "SELECT tx_hash, block_number, sender, blob_count, gas_price
-             FROM blob_transactions
-             ORDER BY created_at DESC
-             LIMIT ?",
-        )?;
+/// Columns read by [`reprocess_request_from_row`].
+const REPROCESS_COLUMNS: &str = "id, from_block, to_block, next_block, requested_at, completed_at";
 
-        let txs: Vec<(String, u64, String, u64, u64)> = stmt
-            .query_map([limit], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                ))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+fn reprocess_request_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ReprocessRequestData> {
+    Ok(ReprocessRequestData {
+        id: row.get(0)?,
+        from_block: row.get(1)?,
+        to_block: row.get(2)?,
+        next_block: row.get(3)?,
+        requested_at: row.get(4)?,
+        completed_at: row.get(5)?,
+    })
+}
 
-        let mut result = Vec::with_capacity(txs.len());
+/// Columns read by [`relabel_job_from_row`].
+const RELABEL_COLUMNS: &str =
+    "id, from_block, to_block, next_block, relabeled, registry_version, requested_at, completed_at";
 
-        for (tx_hash, block_number, sender, blob_count, gas_price) in txs {
-            let mut blob_stmt = conn.prepare(
-                "SELECT blob_hash FROM blob_hashes WHERE tx_hash = ? ORDER BY blob_index",
-            )?;
+fn relabel_job_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RelabelJobData> {
+    Ok(RelabelJobData {
+        id: row.get(0)?,
+        from_block: row.get(1)?,
+        to_block: row.get(2)?,
+        next_block: row.get(3)?,
+        relabeled: row.get(4)?,
+        registry_version: row.get(5)?,
+        requested_at: row.get(6)?,
+        completed_at: row.get(7)?,
+    })
+}
 
-            let blob_hashes: Vec<String> = blob_stmt
-                .query_map([&tx_hash], |row| row.get(0))?
-                .filter_map(|r| r.ok())
-                .collect();
+/// Columns read by [`block_from_row`].
+const BLOCK_COLUMNS: &str = "block_number, block_timestamp, tx_count, total_blobs, gas_used,
+     gas_price, excess_blob_gas, non_blob_tx_count, non_blob_gas_used, base_fee_per_gas,
+     blob_target, blob_max, block_interval, header_blob_gas_used, block_hash, beneficiary";
 
-            result.push(BlobTransactionData {
-                tx_hash,
-                block_number,
-                sender,
-                blob_count,
-                gas_price,
-                blob_hashes,
-            });
-        }
+/// Map a row of [`BLOCK_COLUMNS`] to a block without its transactions.
+fn block_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BlockData> {
+    let non_blob_tx_count: Option<u64> = row.get(7)?;
+    let non_blob_gas_used: Option<u64> = row.get(8)?;
 
-        Ok(result)
+    Ok(BlockData {
+        block_number: row.get(0)?,
+        block_timestamp: row.get(1)?,
+        tx_count: row.get(2)?,
+        total_blobs: row.get(3)?,
+        gas_used: row.get(4)?,
+        gas_price: row.get::<_, Wei>(5)?.0,
+        excess_blob_gas: row.get(6)?,
+        base_fee_per_gas: row.get(9)?,
+        blob_target: row.get(10)?,
+        blob_max: row.get(11)?,
+        block_interval: row.get(12)?,
+        header_blob_gas_used: row.get(13)?,
+        block_hash: row.get(14)?,
+        beneficiary: row.get(15)?,
+        execution: non_blob_tx_count.zip(non_blob_gas_used).map(
+            |(non_blob_tx_count, non_blob_gas_used)| ExecutionContext {
+                non_blob_tx_count,
+                non_blob_gas_used,
+            },
+        ),
+        transactions: Vec::new(),
+    })
+}
+
+/// Run a query selecting `tx_hash, block_number, sender, blob_count, gas_price,
+/// attributed_entity, label_source, tx_type` from `blob_transactions` and load
+/// the blob hashes of each transaction.
+fn query_blob_transactions(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<BlobTransactionData>> {
+    let mut stmt = conn.prepare(sql)?;
+
+    let mut txs: Vec<BlobTransactionData> = stmt
+        .query_map(params, |row| {
+            Ok(BlobTransactionData {
+                tx_hash: row.get(0)?,
+                block_number: row.get(1)?,
+                sender: row.get(2)?,
+                blob_count: row.get(3)?,
+                gas_price: row.get::<_, Wei>(4)?.0,
+                attributed_entity: row.get(5)?,
+                label_source: row.get(6)?,
+                tx_type: row.get(7)?,
+                blob_hashes: Vec::new(),
+                blob_sizes: Vec::new(),
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut blob_stmt = conn.prepare(
+        "SELECT blob_hash, blob_size FROM blob_hashes WHERE tx_hash = ? ORDER BY blob_index",
+    )?;
+    for tx in &mut txs {
+        let blobs: Vec<(String, Option<u64>)> = blob_stmt
+            .query_map([&tx.tx_hash], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        (tx.blob_hashes, tx.blob_sizes) = blobs.into_iter().unzip();
     }
 
-    /// Get all-time chart data with smoothing for visualization.
-    /// Returns sampled data points to keep the chart performant.
-    pub fn get_all_time_chart_data(
-        &self,
-        target_points: u64,
-        bpo2_timestamp: u64,
-    ) -> eyre::Result<AllTimeChartData> {
-        let conn = self.connection();
+    Ok(txs)
+}
 
-        // BPO1 parameters (before BPO2)
-        const BPO1_TARGET: u64 = 6;
-        const BPO1_MAX: u64 = 9;
-        // BPO2 parameters
-        const BPO2_TARGET: u64 = 10;
-        const BPO2_MAX: u64 = 15;
+/// Get the transactions included in a block.
+///
+/// The blob size of each transaction prefers the real payload sizes recorded
+/// from sidecars, falling back to [`BLOB_SIZE_BYTES`] per blob.
+fn query_block_transactions(conn: &Connection, block_number: u64) -> Result<Vec<TransactionData>> {
+    let mut tx_stmt = conn.prepare(
+        "SELECT t.tx_hash, t.sender, t.blob_count,
+                COALESCE(
+                    (SELECT SUM(COALESCE(h.blob_size, ?2)) FROM blob_hashes h WHERE h.tx_hash = t.tx_hash),
+                    t.blob_count * ?2
+                ),
+                t.attributed_entity, t.label_source
+         FROM blob_transactions t WHERE t.block_number = ?1",
+    )?;
 
-        // Get total block count and range
-        let (min_block, max_block): (u64, u64) = conn
-            .query_row(
-                "SELECT MIN(block_number), MAX(block_number) FROM blocks",
-                [],
-                |row| Ok((row.get(0)?, row.get(1)?)),
-            )
-            .unwrap_or((0, 0));
+    let transactions: Vec<TransactionData> = tx_stmt
+        .query_map((block_number, BLOB_SIZE_BYTES), |row| {
+            Ok(TransactionData {
+                tx_hash: row.get(0)?,
+                sender: row.get(1)?,
+                blob_count: row.get(2)?,
+                blob_size: row.get(3)?,
+                attributed_entity: row.get(4)?,
+                label_source: row.get(5)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
 
-        if max_block == 0 {
-            return Ok(AllTimeChartData {
-                labels: Vec::new(),
-                blobs: Vec::new(),
-                gas_prices: Vec::new(),
-                timestamps: Vec::new(),
-                targets: Vec::new(),
-                maxes: Vec::new(),
-                bpo2_block: None,
-            });
-        }
+    Ok(transactions)
+}
 
-        let total_blocks = max_block - min_block + 1;
-        let sample_interval = (total_blocks / target_points).max(1);
+/// A notification processed by the ExEx, to be appended to the ingest log.
+#[derive(Debug)]
+pub struct NewIngestLogEntry<'a> {
+    /// "commit", "revert" or "reorg".
+    pub kind: &'a str,
+    pub reverted: Option<RangeInclusive<u64>>,
+    pub committed: Option<RangeInclusive<u64>>,
+    /// Blob transactions in the reverted and committed blocks.
+    pub reverted_txs: u64,
+    pub committed_txs: u64,
+    pub duration: Duration,
+    /// "ok", "skipped" if a step gave up and was recorded in `ingest_errors`,
+    /// or "failed" if processing stopped with `error`.
+    pub outcome: &'a str,
+    pub error: Option<String>,
+}
 
-        // Fetch all blocks (we'll aggregate in memory for smoothing)
-        let mut stmt = conn.prepare(
-            "SELECT block_number, block_timestamp, total_blobs, gas_price
-             FROM blocks
-             ORDER BY block_number ASC",
-        )?;
+/// Block row to be inserted by the ExEx.
+#[derive(Debug)]
+pub struct NewBlock {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub gas_used: i64,
+    pub gas_price: u128, // Blob base fee in wei
+    pub excess_blob_gas: i64,
+    pub base_fee_per_gas: u64,
+    pub priority_fees: Option<PriorityFees>, // None if the block has no blob txs
+    pub blob_target: u64,                    // Blob schedule in effect at the block
+    pub blob_max: u64,
+    pub header_blob_gas_used: Option<u64>, // As the header states it, `gas_used` is computed
+    pub block_hash: String,
+    pub beneficiary: Address, // Fee recipient
+}
 
-        let rows: Vec<(u64, u64, u64, u64)> = stmt
-            .query_map([], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+/// Execution layer activity of a block outside its blob transactions.
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutionContext {
+    pub non_blob_tx_count: u64,
+    pub non_blob_gas_used: u64,
+}
 
-        // Find BPO2 block
-        let bpo2_block = rows
-            .iter()
-            .find(|(_, ts, _, _)| *ts >= bpo2_timestamp)
-            .map(|(bn, _, _, _)| *bn);
+/// Blob transaction row to be inserted by the ExEx.
+#[derive(Debug)]
+pub struct NewBlobTransaction<'a> {
+    pub tx_hash: &'a str,
+    pub block_number: u64,
+    pub sender: Address,
+    pub nonce: u64,
+    /// EIP-2718 type, 3 for EIP-4844.
+    pub tx_type: u8,
+    pub blob_count: i64,
+    pub gas_price: u128,
+    pub priority_fee: i64,
+    pub created_at: u64,
+    /// EIP-2718 encoded size of the tx as included in the block, without its
+    /// sidecar.
+    pub el_size: u64,
+    /// Meaningful bytes in the tx's blobs, if its sidecar was available.
+    pub payload_size: Option<u64>,
+    /// Recipient, the inbox contract for rollups posting to one.
+    pub to: Option<Address>,
+}
 
-        // Sample and smooth the data
-        let mut labels = Vec::new();
-        let mut blobs = Vec::new();
-        let mut gas_prices = Vec::new();
-        let mut timestamps = Vec::new();
-        let mut targets = Vec::new();
-        let mut maxes = Vec::new();
+/// An annotation to add or replace, see [`Database::insert_annotation`].
+#[derive(Debug)]
+pub struct NewAnnotation<'a> {
+    pub timestamp: u64,
+    /// What kind of event, e.g. `release`, `incident` or `fork`.
+    pub kind: &'a str,
+    pub title: &'a str,
+    pub description: Option<&'a str>,
+}
 
-        let mut i = 0;
-        while i < rows.len() {
-            let end = (i + sample_interval as usize).min(rows.len());
-            let chunk = &rows[i..end];
-
-            if !chunk.is_empty() {
-                // Take the middle block as representative
-                let mid = chunk.len() / 2;
-                let (block_num, timestamp, _, _) = chunk[mid];
-
-                // Average the blobs and gas prices in this window
-                let avg_blobs: f64 =
-                    chunk.iter().map(|(_, _, b, _)| *b as f64).sum::<f64>() / chunk.len() as f64;
-                let avg_gas_price: f64 = chunk
-                    .iter()
-                    .map(|(_, _, _, g)| *g as f64 / 1e9)
-                    .sum::<f64>()
-                    / chunk.len() as f64;
-
-                // Determine target/max based on timestamp
-                let (target, max) = if timestamp >= bpo2_timestamp {
-                    (BPO2_TARGET, BPO2_MAX)
-                } else {
-                    (BPO1_TARGET, BPO1_MAX)
-                };
+impl NewAnnotation<'_> {
+    fn validate(&self) -> Result<()> {
+        let kind = self.kind.trim();
+        if kind.is_empty() || kind.len() > MAX_ANNOTATION_KIND_LEN {
+            return Err(DbError::InvalidInput(format!(
+                "kind must be 1 to {MAX_ANNOTATION_KIND_LEN} bytes"
+            )));
+        }
+        let title = self.title.trim();
+        if title.is_empty() || title.len() > MAX_ANNOTATION_TITLE_LEN {
+            return Err(DbError::InvalidInput(format!(
+                "title must be 1 to {MAX_ANNOTATION_TITLE_LEN} bytes"
+            )));
+        }
+        if self
+            .description
+            .is_some_and(|description| description.len() > MAX_ANNOTATION_DESCRIPTION_LEN)
+        {
+            return Err(DbError::InvalidInput(format!(
+                "description must be at most {MAX_ANNOTATION_DESCRIPTION_LEN} bytes"
+            )));
+        }
+        Ok(())
+    }
+}
 
-                labels.push(block_num);
-                blobs.push(avg_blobs);
-                gas_prices.push(avg_gas_price);
-                timestamps.push(timestamp);
-                targets.push(target);
-                maxes.push(max);
-            }
+/// A blob transaction left in the mempool after a block, to be inserted.
+#[derive(Debug)]
+pub struct NewPendingBlobTransaction<'a> {
+    pub block_number: u64,
+    pub tx_hash: &'a str,
+    pub sender: Address,
+    pub nonce: u64,
+    pub blob_count: i64,
+    pub priority_fee: i64,
+    pub max_fee_per_blob_gas: i64,
+}
 
-            i = end;
-        }
+/// A frame decoded from an OP Stack batcher blob.
+#[derive(Debug)]
+pub struct NewOpFrame<'a> {
+    pub tx_hash: &'a str,
+    pub blob_index: u64,
+    pub channel_id: &'a str,
+    pub frame_number: u16,
+    pub block_number: u64,
+    pub frame_size: u64,
+    pub is_last: bool,
+}
 
-        Ok(AllTimeChartData {
-            labels,
-            blobs,
-            gas_prices,
-            timestamps,
-            targets,
-            maxes,
-            bpo2_block,
-        })
-    }
+/// An OP Stack channel completed within one transaction.
+#[derive(Debug)]
+pub struct NewOpChannel<'a> {
+    pub channel_id: &'a str,
+    pub tx_hash: &'a str,
+    pub block_number: u64,
+    pub frame_count: u64,
+    pub compressed_size: u64,
+    /// `None` if the channel didn't decompress.
+    pub decompressed_size: Option<u64>,
+    pub compression: Option<&'a str>,
+}
 
-    /// Get transactions in a time range (for chain profiles).
-    pub fn get_transactions_in_time_range(
-        &self,
-        time_limit: i64,
-    ) -> eyre::Result<Vec<(String, u64, i64, u64)>> {
-        let conn = self.connection();
+/// How often a sender's included blob txs were preceded by other attempts at
+/// the same nonce.
+#[derive(Debug)]
+pub struct SenderResubmissionData {
+    pub sender: String,
+    pub tx_count: u64,
+    /// Included txs with at least one other tx hash at the same nonce.
+    pub resubmitted_tx_count: u64,
+    /// Other tx hashes seen across all included txs.
+    pub replaced_attempts: u64,
+    /// Included txs whose exact tx had been in a reorged block before.
+    pub reincluded_tx_count: u64,
+}
 
-        let mut stmt = conn.prepare(
-            "SELECT sender, blob_count, created_at, gas_price
-             FROM blob_transactions
-             WHERE created_at >= ?
-             ORDER BY sender, created_at",
-        )?;
+/// Min/median/max priority fee (wei per gas) among a set of blob transactions.
+#[derive(Debug, Clone, Copy)]
+pub struct PriorityFees {
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+}
 
-        let rows: Vec<(String, u64, i64, u64)> = stmt
-            .query_map([time_limit], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+impl PriorityFees {
+    /// Summarize fees that are already sorted ascending. Returns `None` if empty.
+    pub fn from_sorted(fees: &[u64]) -> Option<Self> {
+        let (first, last) = (fees.first()?, fees.last()?);
+        let mid = fees.len() / 2;
+        let median = if fees.len().is_multiple_of(2) {
+            ((fees[mid - 1] as u128 + fees[mid] as u128) / 2) as u64
+        } else {
+            fees[mid]
+        };
 
-        Ok(rows)
+        Some(Self {
+            min: *first,
+            median,
+            max: *last,
+        })
     }
 }
 
@@ -671,7 +6717,88 @@ pub struct Stats {
     pub avg_blobs_per_block: f64,
     pub latest_block: Option<u64>,
     pub earliest_block: Option<u64>,
-    pub latest_gas_price: u64,
+    pub latest_gas_price: u128,
+    pub total_slots: u64,
+    pub missed_slots: u64,
+    pub avg_blobs_per_slot: f64,
+    /// Bytes blob txs took in EL blocks, without sidecars.
+    pub total_el_bytes: u64,
+    /// Blob space used by the same txs, at [`BLOB_SIZE_BYTES`] per blob.
+    pub total_da_bytes: u64,
+    /// Meaningful payload bytes in those blobs, for txs whose sidecar was seen.
+    pub total_payload_bytes: u64,
+    /// Blob versioned hash references, from the daily rollups.
+    pub total_blob_refs: u64,
+    /// Distinct hashes among them, counting a hash once per day it was posted.
+    pub distinct_daily_blobs: u64,
+}
+
+/// Indexed blocks over a range of slots.
+///
+/// Block numbers increase by one per produced block, so gaps in the indexed
+/// block numbers are indexing gaps, while slots without a block number are
+/// slots the proposer missed.
+#[derive(Debug)]
+pub struct SlotThroughputData {
+    pub indexed_blocks: u64,
+    pub total_blobs: u64,
+    pub first_block: u64,
+    pub last_block: u64,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+}
+
+impl SlotThroughputData {
+    /// Slots from the first to the last block, both included.
+    pub fn slots(&self) -> u64 {
+        (self.last_timestamp - self.first_timestamp) / SECONDS_PER_SLOT + 1
+    }
+
+    /// Blocks produced over [`Self::slots`], indexed or not.
+    pub fn produced_blocks(&self) -> u64 {
+        self.last_block - self.first_block + 1
+    }
+
+    pub fn missed_slots(&self) -> u64 {
+        self.slots().saturating_sub(self.produced_blocks())
+    }
+
+    pub fn blobs_per_block(&self) -> f64 {
+        self.total_blobs as f64 / self.indexed_blocks as f64
+    }
+
+    /// Blobs per slot, counting missed slots as empty. Blocks missing from the
+    /// index are assumed to carry the average of the indexed ones.
+    pub fn blobs_per_slot(&self) -> f64 {
+        self.blobs_per_block() * self.produced_blocks() as f64 / self.slots() as f64
+    }
+}
+
+/// Distribution of the intervals between consecutive blocks.
+#[derive(Debug)]
+pub struct BlockIntervalData {
+    /// Blocks whose parent is indexed too, i.e. with a known interval.
+    pub blocks: u64,
+    pub avg_secs: f64,
+    pub p50_secs: u64,
+    pub p90_secs: u64,
+    pub p99_secs: u64,
+    pub max_secs: u64,
+}
+
+/// Aggregates over a time range, for comparing periods.
+#[derive(Debug)]
+pub struct PeriodStatsData {
+    /// `None` if no block was indexed in the range.
+    pub throughput: Option<SlotThroughputData>,
+    pub avg_gas_price: f64,
+    pub blob_transactions: u64,
+    pub unique_senders: u64,
+    pub blob_fees_wei: f64,
+    /// Blocks at the max blob count of their fork.
+    pub saturated_blocks: u64,
+    /// Sum of the target blob count of every block.
+    pub target_blobs: u64,
 }
 
 /// Raw block data from the database.
@@ -682,8 +6809,16 @@ pub struct BlockData {
     pub tx_count: u64,
     pub total_blobs: u64,
     pub gas_used: u64,
-    pub gas_price: u64,
+    pub gas_price: u128,
     pub excess_blob_gas: u64,
+    pub base_fee_per_gas: Option<u64>, // EL base fee, None for blocks indexed by older versions
+    pub blob_target: u64,
+    pub blob_max: u64,
+    pub block_interval: Option<u64>, // Seconds since the parent, None if it isn't indexed
+    pub header_blob_gas_used: Option<u64>, // None for blocks indexed by older versions
+    pub block_hash: Option<String>,  // None for blocks indexed by older versions
+    pub beneficiary: Option<String>, // Likewise
+    pub execution: Option<ExecutionContext>, // Only recorded with execution tracking enabled
     pub transactions: Vec<TransactionData>,
 }
 
@@ -693,6 +6828,9 @@ pub struct TransactionData {
     pub tx_hash: String,
     pub sender: String,
     pub blob_count: u64,
+    pub blob_size: u64,
+    pub attributed_entity: Option<String>,
+    pub label_source: Option<String>, // manual, registry or heuristic, see `LabelSource`
 }
 
 /// Raw sender data from the database.
@@ -701,6 +6839,133 @@ pub struct SenderData {
     pub address: String,
     pub tx_count: u64,
     pub total_blobs: u64,
+    pub total_blob_size: u64,
+    /// `None` for senders recorded before these were tracked and no longer
+    /// backed by any indexed transaction.
+    pub first_seen_block: Option<u64>,
+    pub last_seen_block: Option<u64>,
+    pub last_seen_timestamp: Option<u64>,
+}
+
+/// A block's excess blob gas and its change from the parent block.
+#[derive(Debug)]
+pub struct ExcessBlobGasData {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub excess_blob_gas: u64,
+    /// `None` if the parent block isn't indexed.
+    pub delta: Option<i64>,
+    pub total_blobs: u64,
+    pub gas_price: u128,
+}
+
+/// Blocks aggregated over a time bucket.
+#[derive(Debug)]
+pub struct BlockSeriesData {
+    pub bucket_start: u64,
+    pub block_count: u64,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub avg_gas_price: f64,
+}
+
+/// Blobs posted by a sender in one bucket of [`Database::get_sender_blob_series`].
+#[derive(Debug)]
+pub struct SenderBlobSeriesData {
+    pub bucket_start: u64,
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub blobs: u64,
+}
+
+/// A blob transaction as returned by [`Database::get_transactions_in_time_range`].
+#[derive(Debug)]
+pub struct TimedTransactionData {
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub blob_count: u64,
+    pub created_at: i64,
+    pub gas_price: u128,
+    pub reverted: bool, // False while the receipt status is unknown
+}
+
+/// All-time blobs of a chain, see [`Database::get_chain_totals`].
+#[derive(Debug)]
+pub struct ChainTotalsData {
+    pub chain: String,
+    pub total_blobs: u64,
+    pub blob_fees_wei: f64,
+}
+
+/// Blob transactions of a chain in an hour, see [`Database::get_chain_activity`].
+#[derive(Debug)]
+pub struct ChainActivityData {
+    pub hour_start: u64,
+    pub chain: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub first_tx_at: u64,
+    pub last_tx_at: u64,
+}
+
+/// Blob transactions of a chain on a UTC day, see [`Database::get_chain_days`].
+#[derive(Debug)]
+pub struct ChainDayData {
+    pub day_start: u64,
+    pub chain: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub blob_fees_wei: f64,
+    pub payload_bytes: u64, // Summed over the transactions with a known payload size
+    pub sized_blobs: u64,   // Blobs of those transactions
+    pub first_tx_at: u64,
+    pub last_tx_at: u64,
+}
+
+/// An event marked by an operator, see [`Database::insert_annotation`].
+#[derive(Debug)]
+pub struct AnnotationData {
+    pub id: u64,
+    pub timestamp: u64,
+    pub kind: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub updated_at: u64,
+    pub block_number: Option<u64>, // First indexed block at or after the timestamp
+}
+
+/// Blobs a chain posted on a UTC day, see [`Database::get_chain_calendar`].
+#[derive(Debug)]
+pub struct ChainCalendarDayData {
+    pub day_start: u64,
+    pub chain: String, // As labeled
+    pub total_blobs: u64,
+    pub blob_fees_wei: f64,
+}
+
+/// Blob versioned hashes posted on a UTC day, see
+/// [`Database::get_daily_blob_hashes`].
+#[derive(Debug)]
+pub struct DailyBlobHashesData {
+    pub day_start: u64,
+    pub blob_refs: u64,      // References by blob txs, re-posts included
+    pub distinct_blobs: u64, // Distinct hashes among them
+}
+
+/// Sender stats as stored or as derived from blob transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SenderTotals {
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub total_blob_size: u64,
+}
+
+/// A sender whose stored stats drifted from its blob transactions.
+#[derive(Debug)]
+pub struct SenderDriftData {
+    pub address: String,
+    pub stored: SenderTotals,
+    pub derived: SenderTotals,
 }
 
 /// Chart data for visualization.
@@ -708,7 +6973,40 @@ pub struct SenderData {
 pub struct ChartData {
     pub labels: Vec<u64>,
     pub blobs: Vec<u64>,
-    pub gas_prices: Vec<f64>,
+    pub gas_prices: Vec<Option<f64>>, // Gwei, None where the gap fill has no value
+    pub gas_prices_wei: Vec<Option<u128>>, // The same, exact
+    // Execution layer context, None where it wasn't recorded
+    pub non_blob_tx_counts: Vec<Option<u64>>,
+    pub non_blob_gas_used: Vec<Option<u64>>,
+    pub base_fees: Vec<Option<f64>>,       // Gwei
+    pub block_intervals: Vec<Option<u64>>, // Seconds since the parent block
+}
+
+/// How a window of blocks is reduced to a single chart point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Downsample {
+    /// Average blobs and gas price over the window.
+    #[default]
+    Mean,
+    /// Peak blobs and gas price within the window.
+    Max,
+    /// Values of the last block in the window.
+    Last,
+}
+
+/// How the chart prices blocks missing from the index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapFill {
+    /// Blob base fee of the closest indexed block before the gap.
+    #[default]
+    Previous,
+    /// Blob base fee derived from the excess blob gas of the closest indexed
+    /// block before the gap, assuming the missing blocks carried no blobs.
+    ExcessBlobGas,
+    /// No value.
+    Null,
 }
 
 /// All-time chart data with smoothing.
@@ -716,20 +7014,393 @@ pub struct ChartData {
 pub struct AllTimeChartData {
     pub labels: Vec<u64>,
     pub blobs: Vec<f64>,
-    pub gas_prices: Vec<f64>,
+    pub gas_prices: Vec<f64>,     // Gwei
+    pub gas_prices_wei: Vec<f64>, // The same, before the conversion to Gwei
     pub timestamps: Vec<u64>,
     pub targets: Vec<u64>, // Dynamic target at each point
     pub maxes: Vec<u64>,   // Dynamic max at each point
+    pub non_blob_tx_counts: Vec<Option<f64>>,
+    pub non_blob_gas_used: Vec<Option<f64>>,
+    pub base_fees: Vec<Option<f64>>,       // Gwei
+    pub block_intervals: Vec<Option<f64>>, // Seconds since the parent block
     pub bpo2_block: Option<u64>,
 }
 
+/// A blob posted by a sender, with its transaction.
+#[derive(Debug)]
+pub struct SenderBlobData {
+    // (block number, blob row id), ordering a sender's blobs
+    pub position: (u64, u64),
+    pub block_timestamp: u64,
+    pub tx_hash: String,
+    pub blob_index: u64,
+    pub blob_hash: String,
+    pub blob_size: Option<u64>, // Payload size, if the sidecar was seen
+}
+
+/// A sender's blob transactions to one inbox.
+#[derive(Debug)]
+pub struct InboxSenderData {
+    pub sender: String,
+    pub chain: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub total_blob_size: u64, // Full blobs where the payload size is unknown
+    pub first_block: u64,
+    pub last_block: u64,
+    pub reverted_txs: u64, // Txs with a failed receipt, unknown ones aren't counted
+}
+
 /// Blob transaction data with hashes.
 #[derive(Debug)]
 pub struct BlobTransactionData {
     pub tx_hash: String,
     pub block_number: u64,
     pub sender: String,
+    pub attributed_entity: Option<String>, // Owner of the sender, see `Database::insert_blob_transaction`
+    pub label_source: Option<String>,
+    pub tx_type: Option<u8>, // None in archives created before it was recorded
     pub blob_count: u64,
-    pub gas_price: u64,
+    pub gas_price: u128,
     pub blob_hashes: Vec<String>,
+    pub blob_sizes: Vec<Option<u64>>, // Payload size per blob, if the sidecar was seen
+}
+
+impl BlobTransactionData {
+    /// Total blob payload size, using [`BLOB_SIZE_BYTES`] for blobs of unknown size.
+    pub fn blob_size(&self) -> u64 {
+        if self.blob_sizes.is_empty() {
+            return self.blob_count * BLOB_SIZE_BYTES;
+        }
+        self.blob_sizes
+            .iter()
+            .map(|size| size.unwrap_or(BLOB_SIZE_BYTES))
+            .sum()
+    }
+}
+
+/// Priority fee competition for blob inclusion in a single block.
+#[derive(Debug)]
+pub struct InclusionMarketData {
+    pub block_number: u64,
+    pub tx_count: u64,
+    pub included: Option<PriorityFees>,
+    pub excluded_count: u64, // Pending blob txs left out (mempool tracking only)
+    pub excluded: Option<PriorityFees>,
+}
+
+/// Blob activity rolled up over one UTC hour.
+#[derive(Debug)]
+pub struct HourlyStatsData {
+    pub hour_start: u64, // Unix timestamp of the start of the hour
+    pub block_count: u64,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub gas_used: u64,
+    pub gas_price_sum: f64, // Sum of blob gas prices (wei), divide by block_count for the average
+}
+
+/// A notification processed by the ExEx, see [`NewIngestLogEntry`].
+#[derive(Debug)]
+pub struct IngestLogData {
+    pub id: u64,
+    pub processed_at: u64,
+    pub kind: String,
+    pub reverted: Option<RangeInclusive<u64>>,
+    pub committed: Option<RangeInclusive<u64>>,
+    pub reverted_txs: u64,
+    pub committed_txs: u64,
+    pub duration_ms: u64,
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+/// A block an ingest stage gave up on after retrying.
+#[derive(Debug)]
+pub struct IngestErrorData {
+    pub block_number: u64,
+    pub stage: String, // "index", "revert" or a secondary processor name
+    pub error: String,
+    pub attempts: u32,
+    pub failed_at: u64,
+}
+
+/// Cost of a sender's blob transactions attributed to one entity, see
+/// [`Database::get_sender_cost_breakdown`].
+#[derive(Debug)]
+pub struct CostBreakdownData {
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub tx_count: u64,
+    // Over the txs with a recorded execution fee only
+    pub priced_blobs: u64,
+    pub blob_fees_wei: f64,
+    pub execution_fees_wei: f64,
+    pub execution_gas_used: u64,
+    pub unpriced_tx_count: u64, // Txs without a recorded execution fee
+}
+
+/// A blob schedule change observed between a block and its parent.
+#[derive(Debug)]
+pub struct ForkEventData {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub previous_target: u64,
+    pub previous_max: u64,
+    pub target: u64,
+    pub max: u64,
+}
+
+/// What [`Database::reaggregate`] rebuilt.
+#[derive(Debug)]
+pub struct ReaggregateData {
+    pub hours: usize,   // Hourly rollups with blocks
+    pub senders: usize, // Senders with transactions left
+}
+
+/// Blob fees paid by a sender and the equivalent calldata cost.
+#[derive(Debug)]
+pub struct SenderBlobCostData {
+    pub sender: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub payload_bytes: u64,
+    pub blob_fees_wei: f64,
+    pub calldata_fees_wei: f64,
+    pub unpriced_tx_count: u64, // Txs in blocks without a recorded base fee, left out of calldata_fees_wei
+}
+
+/// The process holding the single writer lease.
+#[derive(Debug)]
+pub struct WriterLeaseData {
+    pub holder: String,
+    pub acquired_at: u64,
+    pub heartbeat_at: u64,
+    /// Latest node tip reported by the holder, see [`Database::set_node_tip`].
+    pub node_tip: Option<u64>,
+}
+
+/// Outcome of [`Database::query_read_only`].
+#[derive(Debug)]
+pub struct QueryResultData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub truncated: bool, // More rows than the limit matched
+}
+
+/// Latest round of canonical chain verification, see
+/// [`Database::record_canonical_check`].
+#[derive(Debug)]
+pub struct CanonicalCheckData {
+    pub checked_at: u64,
+    pub blocks_checked: u64,
+    pub mismatches: u64,       // In the latest round
+    pub total_mismatches: u64, // Since the first round
+    pub last_mismatch_block: Option<u64>,
+    pub last_mismatch_at: Option<u64>,
+}
+
+/// Latest round of the sender stats check, see
+/// [`Database::record_sender_check`].
+#[derive(Debug)]
+pub struct SenderCheckData {
+    pub checked_at: u64,
+    pub drifted_senders: u64,
+    pub tx_count_drift: u64, // Sum of the differences over drifted senders
+    pub blob_drift: u64,     // Likewise
+    pub repaired: bool,      // Stats were rebuilt in the latest round
+    pub total_repairs: u64,
+    pub last_repair_at: Option<u64>,
+}
+
+/// A monthly archive of blob transactions, see [`Database::archive`].
+#[derive(Debug)]
+pub struct ArchiveData {
+    pub month: String, // YYYY-MM, UTC
+    pub path: String,
+    pub tx_count: u64,
+    pub blob_count: u64,
+    pub first_tx_at: u64,
+    pub last_tx_at: u64,
+    pub archived_at: u64,
+}
+
+/// Outcome of [`Database::checkpoint_wal`].
+#[derive(Debug)]
+pub struct WalCheckpointData {
+    /// Whether every frame was copied and the WAL truncated.
+    pub complete: bool,
+    /// Frames in the WAL and how many of them were copied, both 0 once it
+    /// was truncated.
+    pub wal_frames: u64,
+    pub checkpointed_frames: u64,
+}
+
+/// On-disk size of a database, see [`Database::file_sizes`].
+#[derive(Debug, Clone, Copy)]
+pub struct DbFileSizes {
+    pub db_bytes: u64,
+    pub wal_bytes: u64,
+}
+
+/// An operator action taken through the admin API.
+#[derive(Debug)]
+pub struct AdminActionData {
+    pub id: u64,
+    pub action: String,
+    pub details: String, // JSON encoded parameters
+    pub performed_at: u64,
+}
+
+/// An all-time record, one of the `RECORD_*` kinds.
+#[derive(Debug)]
+pub struct RecordData {
+    pub kind: String,
+    pub value: u128, // Blobs, wei or blocks depending on the kind
+    pub from_block: u64,
+    pub to_block: u64,
+    pub sender: Option<String>, // Largest sender day only
+    pub day: Option<u64>,       // Largest sender day only, start of the UTC day
+}
+
+/// An API key for alert rules, see [`Database::insert_alert_key`].
+#[derive(Debug)]
+pub struct AlertKeyData {
+    pub owner: String, // keccak256 of the key
+    pub name: String,  // Who it was issued to
+    pub created_at: u64,
+}
+
+/// An alert rule registered through `/api/alerts/rules`.
+#[derive(Debug)]
+pub struct AlertRuleData {
+    pub id: u64,
+    pub owner: String,     // keccak256 of the API key that created it
+    pub condition: String, // JSON encoded `alerts::Condition`
+    pub delivery: String,  // JSON encoded `alerts::Delivery`
+    pub created_at: u64,
+    pub firing: bool,
+    pub last_fired_at: Option<u64>,
+}
+
+/// A firing of an alert rule.
+#[derive(Debug, Clone)]
+pub struct AlertEventData {
+    pub id: u64,
+    pub rule_id: u64,
+    pub message: String,
+    pub fired_at: u64,
+}
+
+/// Latest blob transaction of a sender.
+#[derive(Debug)]
+pub struct SenderLastPostData {
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub last_post: u64,
+}
+
+/// Blocks of blob transactions archived as reverted, see
+/// [`Database::get_reverted_since`].
+#[derive(Debug)]
+pub struct RevertedBlocksData {
+    pub first_block: u64,
+    pub last_block: u64,
+    /// Position of the latest of them, to pass as `after` next time.
+    pub last_id: u64,
+}
+
+/// Mempool wait of an included blob transaction, see
+/// [`Database::get_inclusion_latencies`].
+#[derive(Debug)]
+pub struct InclusionLatencyData {
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub priority_fee: u64,
+    /// Blocks and seconds from the block after which the tx was first seen
+    /// pending to the one including it, 1 block if it was included in the
+    /// first block it was eligible for. `None` if it was never seen pending.
+    pub blocks: Option<u64>,
+    pub secs: Option<u64>,
+}
+
+/// Number of a sender's blob transactions carrying `blob_count` blobs.
+#[derive(Debug)]
+pub struct SenderBlobCountData {
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub blob_count: u64,
+    pub tx_count: u64,
+}
+
+/// Decoded OP Stack frames and channels of one sender.
+#[derive(Debug)]
+pub struct OpBatchStatsData {
+    pub sender: String,
+    pub attributed_entity: Option<String>,
+    pub blobs: u64,
+    pub frames: u64,
+    pub frame_bytes: u64,
+    pub channels: u64,
+    pub channel_frames: u64,
+    pub compressed_size: u64,
+    pub decompressed_size: u64,
+    /// Compressed size of the channels that decompressed.
+    pub decompressed_from: u64,
+}
+
+/// A queued request to re-index a block range from the node.
+#[derive(Debug)]
+pub struct ReprocessRequestData {
+    pub id: u64,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub next_block: u64, // First block not yet re-indexed
+    pub requested_at: u64,
+    pub completed_at: Option<u64>,
+}
+
+/// Blob fee data for one block of a fee history.
+#[derive(Debug)]
+pub struct FeeHistoryBlock {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub gas_used: u64, // blob gas used, from the header where known
+    pub gas_price: u128,
+    pub blob_max: u64,
+    pub excess_blob_gas: u64,
+    pub base_fee_per_gas: Option<u64>,
+    pub priority_fees: Vec<(u64, u64)>, // (priority fee, blob count) per blob tx, ascending by fee
+}
+
+/// A relabel job, see [`Database::request_relabel`].
+#[derive(Debug)]
+pub struct RelabelJobData {
+    pub id: u64,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub next_block: u64,               // First block not yet re-attributed
+    pub relabeled: u64,                // Transactions whose entity changed so far
+    pub registry_version: Option<u64>, // `REGISTRY_VERSION` of the ExEx running the job
+    pub requested_at: u64,
+    pub completed_at: Option<u64>,
+}
+
+/// A manual label, see [`Database::set_sender_label`].
+#[derive(Debug)]
+pub struct SenderLabelData {
+    pub address: String,
+    pub label: String,
+    pub updated_at: u64,
+}
+
+/// Blocks of one fee recipient, see [`Database::get_builder_stats`].
+#[derive(Debug)]
+pub struct BuilderStatsData {
+    pub beneficiary: String,
+    pub blocks: u64,
+    pub total_blobs: u64,
+    pub avg_target_utilization: f64, // Percent, averaged over the blocks
+    pub empty_blocks: u64,           // Without blobs
+    pub full_blocks: u64,            // At the blob max
 }
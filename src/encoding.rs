@@ -0,0 +1,237 @@
+//! Compact binary encodings of API responses.
+//!
+//! Endpoints returning large payloads (block ranges, charts over many blocks)
+//! answer in MessagePack or CBOR instead of JSON when asked with `?format=` or
+//! the `Accept` header, for programmatic consumers ingesting months of data.
+//! Field names and values are the same as in JSON.
+//!
+//! They can also answer in CSV for spreadsheets, one row per record of a list
+//! or per index of an object of equally long series, with nested fields
+//! flattened into dotted columns such as `price_sensitivity.correlation`.
+
+use crate::api::ApiError;
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Encoding of a response body, from `?format=json|msgpack|cbor|csv` or else
+/// the `Accept` header, JSON by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Msgpack,
+    Cbor,
+    Csv,
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    format: Option<Format>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Format {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<FormatQuery>::try_from_uri(&parts.uri).map_err(|_| {
+            ApiError::invalid_param(
+                "format",
+                "invalid format, expected json, msgpack, cbor or csv",
+            )
+        })?;
+        if let Some(format) = query.format {
+            return Ok(format);
+        }
+
+        let accept = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        // The first non-JSON type listed wins, without weighing q-values
+        let format = accept
+            .split(',')
+            .map(|media_type| media_type.split(';').next().unwrap_or_default().trim())
+            .find_map(|media_type| match media_type {
+                MSGPACK_CONTENT_TYPE | "application/x-msgpack" => Some(Self::Msgpack),
+                CBOR_CONTENT_TYPE => Some(Self::Cbor),
+                "text/csv" => Some(Self::Csv),
+                _ => None,
+            })
+            .unwrap_or_default();
+        Ok(format)
+    }
+}
+
+/// A response body encoded as the request asked, see [`Format`].
+pub struct Encoded<T>(pub Format, pub T);
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        let Self(format, value) = self;
+        let (content_type, body) = match format {
+            Format::Json => {
+                let mut response = Json(value).into_response();
+                response
+                    .headers_mut()
+                    .insert(header::VARY, HeaderValue::from_static("accept"));
+                return response;
+            }
+            // Through JSON values, as rmp-serde writes u128 fields as bytes
+            Format::Msgpack => (
+                MSGPACK_CONTENT_TYPE,
+                serde_json::to_value(&value)
+                    .map_err(|err| err.to_string())
+                    .and_then(|value| {
+                        rmp_serde::to_vec_named(&value).map_err(|err| err.to_string())
+                    }),
+            ),
+            Format::Cbor => {
+                let mut body = Vec::new();
+                let encoded = ciborium::into_writer(&value, &mut body).map(|()| body);
+                (CBOR_CONTENT_TYPE, encoded.map_err(|err| err.to_string()))
+            }
+            Format::Csv => (
+                CSV_CONTENT_TYPE,
+                serde_json::to_value(&value)
+                    .map(|value| to_csv(&value).into_bytes())
+                    .map_err(|err| err.to_string()),
+            ),
+        };
+
+        match body {
+            Ok(body) => (
+                [
+                    (header::CONTENT_TYPE, content_type),
+                    (header::VARY, "accept"),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(err) => {
+                ApiError::internal(format!("failed to encode response: {err}")).into_response()
+            }
+        }
+    }
+}
+
+/// Write `value` as CSV with a header row, see the module docs for the rows.
+fn to_csv(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    let records: Vec<Value> = match value {
+        Value::Array(records) => records.clone(),
+        Value::Object(series) if is_columnar(series) => {
+            let len = series
+                .values()
+                .next()
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            (0..len)
+                .map(|i| {
+                    Value::Object(
+                        series
+                            .iter()
+                            .map(|(name, values)| (name.clone(), values[i].clone()))
+                            .collect(),
+                    )
+                })
+                .collect()
+        }
+        value => vec![value.clone()],
+    };
+
+    let rows: Vec<Vec<(String, String)>> = records
+        .iter()
+        .map(|record| {
+            let mut row = Vec::new();
+            flatten("", record, &mut row);
+            row
+        })
+        .collect();
+    // Columns in order of first appearance, records missing one leave it empty
+    let mut columns: Vec<&str> = Vec::new();
+    for (column, _) in rows.iter().flatten() {
+        if !columns.contains(&column.as_str()) {
+            columns.push(column);
+        }
+    }
+
+    let mut csv = String::new();
+    let mut write_line = |fields: Vec<&str>| {
+        let fields: Vec<String> = fields.into_iter().map(csv_field).collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    };
+    write_line(columns.clone());
+    for row in &rows {
+        write_line(
+            columns
+                .iter()
+                .map(|&column| {
+                    row.iter()
+                        .find(|(name, _)| name == column)
+                        .map_or("", |(_, value)| value.as_str())
+                })
+                .collect(),
+        );
+    }
+    csv
+}
+
+/// Whether `object` holds only arrays of the same length, one per column.
+fn is_columnar(object: &serde_json::Map<String, serde_json::Value>) -> bool {
+    let mut lengths = object.values().map(|value| value.as_array().map(Vec::len));
+    match lengths.next() {
+        Some(Some(len)) => lengths.all(|other| other == Some(len)),
+        _ => false,
+    }
+}
+
+/// Collect the scalar fields of `value` as `(column, value)` pairs, naming
+/// nested fields and array elements by their dotted path under `prefix`.
+fn flatten(prefix: &str, value: &serde_json::Value, row: &mut Vec<(String, String)>) {
+    use serde_json::Value;
+
+    let child = |key: &dyn std::fmt::Display| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{prefix}.{key}")
+        }
+    };
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                flatten(&child(key), value, row);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                flatten(&child(&i), value, row);
+            }
+        }
+        Value::Null => row.push((prefix.to_string(), String::new())),
+        Value::String(value) => row.push((prefix.to_string(), value.clone())),
+        value => row.push((prefix.to_string(), value.to_string())),
+    }
+}
+
+/// Quote a field if it holds a separator, quote or line break (RFC 4180).
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,180 @@
+//! In-process event bus between the indexer, the web server and the alert
+//! engine.
+//!
+//! The live streams and the alert engine wait on the bus instead of each
+//! polling the database. Events only announce that something changed:
+//! consumers read what changed from the database, from their own cursor, so a
+//! missed or repeated event is harmless.
+//!
+//! An ExEx running in the same process as the web server publishes to the bus
+//! directly through [`crate::processors::EventPublisher`]. Since the web server
+//! may also read a database written by another process, it runs
+//! [`watch_database`] too, which publishes the changes it sees in the database
+//! that nobody announced.
+
+use crate::{db::DbError, Database};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tracing::warn;
+
+/// Events a subscriber that falls behind may miss, it then gets
+/// [`broadcast::error::RecvError::Lagged`] and should re-read its cursor.
+const CAPACITY: usize = 1024;
+
+/// How often [`watch_database`] checks the database for changes.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Something changed in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Blocks up to `block_number` were indexed.
+    BlockIndexed { block_number: u64 },
+    /// Blocks `first_block..=last_block` were reverted. Blocks replacing them
+    /// get their own [`Event::BlockIndexed`].
+    ReorgDetected { first_block: u64, last_block: u64 },
+    /// Alert event `id` was recorded.
+    AlertFired { id: u64 },
+}
+
+/// Sending half of the bus, cheap to clone. Every clone publishes to and
+/// subscribes from the same bus.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+
+    /// Send `event` to the current subscribers, if any.
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Receive the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+/// Publish the database changes nobody announced on `bus`, checking every
+/// [`WATCH_INTERVAL`], forever.
+///
+/// Events published by others count as announced, so with the ExEx in the
+/// same process this only picks up alerts fired by other web servers.
+pub async fn watch_database(db: Database, bus: EventBus) {
+    let mut announced = loop {
+        match Announced::read(&db) {
+            Ok(announced) => break announced,
+            Err(err) => {
+                warn!(%err, "Failed to read the database to watch");
+                tokio::time::sleep(WATCH_INTERVAL).await;
+            }
+        }
+    };
+    let mut events = bus.subscribe();
+
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let mut reorg_announced = false;
+        loop {
+            match events.try_recv() {
+                Ok(event) => {
+                    reorg_announced |= matches!(event, Event::ReorgDetected { .. });
+                    announced.apply(event);
+                }
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+        // The reverted blocks were archived before the reorg was announced
+        if reorg_announced {
+            match db.get_latest_reverted_id() {
+                Ok(id) => announced.reverted_id = id,
+                Err(err) => warn!(%err, "Failed to watch the database"),
+            }
+        }
+        match announced.changes(&db) {
+            Ok(changes) => {
+                for event in changes {
+                    announced.apply(event);
+                    bus.publish(event);
+                }
+            }
+            Err(err) if err.is_transient() => {}
+            Err(err) => warn!(%err, "Failed to watch the database"),
+        }
+    }
+}
+
+/// What [`watch_database`] knows subscribers were told about.
+struct Announced {
+    latest_block: u64,
+    alert_event_id: u64,
+    reverted_id: u64,
+}
+
+impl Announced {
+    fn read(db: &Database) -> Result<Self, DbError> {
+        Ok(Self {
+            latest_block: db.get_latest_block()?.unwrap_or(0),
+            alert_event_id: db.get_latest_alert_event_id()?,
+            reverted_id: db.get_latest_reverted_id()?,
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::BlockIndexed { block_number } => {
+                self.latest_block = self.latest_block.max(block_number);
+            }
+            Event::ReorgDetected { first_block, .. } => {
+                self.latest_block = self.latest_block.min(first_block.saturating_sub(1));
+            }
+            Event::AlertFired { id } => self.alert_event_id = self.alert_event_id.max(id),
+        }
+    }
+
+    /// Events for the changes since the last announced state, reverts first.
+    fn changes(&mut self, db: &Database) -> Result<Vec<Event>, DbError> {
+        let mut changes = Vec::new();
+        if let Some(reverted) = db.get_reverted_since(self.reverted_id)? {
+            self.reverted_id = reverted.last_id;
+            changes.push(Event::ReorgDetected {
+                first_block: reverted.first_block,
+                last_block: reverted.last_block,
+            });
+        }
+
+        let latest_block = db.get_latest_block()?.unwrap_or(0);
+        if latest_block < self.latest_block && changes.is_empty() {
+            // Reverted blocks without blob transactions aren't archived
+            changes.push(Event::ReorgDetected {
+                first_block: latest_block + 1,
+                last_block: self.latest_block,
+            });
+        }
+        if latest_block > self.latest_block || !changes.is_empty() {
+            changes.push(Event::BlockIndexed {
+                block_number: latest_block,
+            });
+        }
+
+        let alert_event_id = db.get_latest_alert_event_id()?;
+        if alert_event_id > self.alert_event_id {
+            changes.push(Event::AlertFired { id: alert_event_id });
+        }
+        Ok(changes)
+    }
+}
@@ -1,146 +1,187 @@
-use alloy_consensus::{transaction::SignerRecoverable, BlockHeader, Transaction};
-use alloy_eips::{eip4844::DATA_GAS_PER_BLOB, eip7840::BlobParams};
-use blob_exex::Database;
-use futures::{Future, TryStreamExt};
-use reth_execution_types::Chain;
-use reth_exex::{ExExContext, ExExEvent, ExExNotification};
-use reth_node_api::FullNodeComponents;
+use blob_exex::{
+    backfill, chain_metrics,
+    config::{self, Role, SizeWarnings, WebConfig},
+    events::EventBus,
+    indexer::{self, Standby},
+    lease, maintenance,
+    processors::{self, EventPublisher},
+    replay, self_test, server,
+    telemetry::{self, BlockLog},
+    Database,
+};
 use reth_node_ethereum::EthereumNode;
-use reth_primitives::EthPrimitives;
-use reth_tracing::tracing::info;
 
-async fn init<Node>(
-    ctx: ExExContext<Node>,
-    db: Database,
-) -> eyre::Result<impl Future<Output = eyre::Result<()>>>
-where
-    Node: FullNodeComponents<Types: reth::api::NodeTypes<Primitives = EthPrimitives>>,
-{
-    Ok(blob_exex(ctx, db))
-}
-
-/// Main ExEx logic
-async fn blob_exex<Node>(mut ctx: ExExContext<Node>, db: Database) -> eyre::Result<()>
-where
-    Node: FullNodeComponents<Types: reth::api::NodeTypes<Primitives = EthPrimitives>>,
-{
-    while let Some(notification) = ctx.notifications.try_next().await? {
-        match &notification {
-            ExExNotification::ChainCommitted { new } => {
-                process_chain(&db, new)?;
-            }
-            ExExNotification::ChainReorged { old, new } => {
-                revert_chain(&db, old)?;
-                process_chain(&db, new)?;
-            }
-            ExExNotification::ChainReverted { old } => {
-                revert_chain(&db, old)?;
-            }
-        }
-
-        if let Some(committed_chain) = notification.committed_chain() {
-            ctx.events
-                .send(ExExEvent::FinishedHeight(committed_chain.tip().num_hash()))?;
-        }
+fn main() -> eyre::Result<()> {
+    let (role, args) = Role::from_args(std::env::args())?;
+
+    // Validate the whole configuration before starting anything
+    let db_path = config::db_path()?;
+    let db_readers = config::db_readers()?;
+    let entity_addresses = config::entity_addresses()?;
+    let size_warnings = SizeWarnings::from_env()?;
+    let log = BlockLog::new(config::log_verbosity()?);
+    let web_config = role.runs_web().then(WebConfig::from_env).transpose()?;
+    let standby = config::standby();
+    let slow_queries = config::slow_query_threshold()?;
+    let ingest_log_retention = config::ingest_log_retention()?;
+    let sender_repair_threshold = config::sender_repair_threshold()?;
+
+    if args.iter().any(|arg| arg == "--self-test") {
+        let static_dir = web_config
+            .as_ref()
+            .map(|config| config.static_dir.as_path());
+        return self_test::run(&db_path, static_dir);
     }
-    Ok(())
-}
-
-fn process_chain(db: &Database, chain: &Chain) -> eyre::Result<()> {
-    for block in chain.blocks_iter() {
-        let block_number = block.header().number();
-        let block_timestamp = block.header().timestamp();
-        let mut blob_tx_count = 0u64;
-        let mut total_blobs = 0u64;
-        let mut blob_gas_used = 0u128;
-
-        let blob_gas_price: i64 = block
-            .header()
-            .blob_fee(BlobParams::bpo2)
-            .unwrap_or(0)
-            .try_into()
-            .unwrap_or(i64::MAX);
-
-        let excess_blob_gas: i64 = block
-            .header()
-            .excess_blob_gas()
-            .unwrap_or(0)
-            .try_into()
-            .unwrap_or(0);
-
-        for tx in block.body().transactions() {
-            if tx.tx_type() == 3 {
-                blob_tx_count += 1;
-
-                if let Some(blob_hashes) = tx.blob_versioned_hashes() {
-                    let num_blobs = blob_hashes.len() as u64;
-                    total_blobs += num_blobs;
-                    blob_gas_used += (num_blobs as u128) * (DATA_GAS_PER_BLOB as u128);
 
-                    if let Ok(sender) = tx.recover_signer() {
-                        let tx_hash = tx.tx_hash().to_string();
+    // `blob-exex backfill ...` indexes history from a reth datadir instead of running a node
+    if args.get(1).is_some_and(|arg| arg == "backfill") {
+        let options = backfill::Options::from_args(&args[2..])?;
+        return tokio::runtime::Runtime::new()?.block_on(async move {
+            let _telemetry = telemetry::init("blob-exex")?;
+            let db = Database::with_readers(&db_path, db_readers)?;
+            if let Some(threshold) = slow_queries {
+                db.log_slow_queries(threshold)?;
+            }
+            let writer = lease::writer_identity("blob-exex backfill");
+            lease::acquire(&db, &writer).await?;
 
-                        // Insert blob transaction
-                        db.insert_blob_transaction(
-                            &tx_hash,
-                            block_number,
-                            &sender.to_string(),
-                            num_blobs as i64,
-                            blob_gas_price,
-                            block_timestamp,
-                        )?;
+            db.replace_entity_addresses(&entity_addresses)?;
 
-                        // Insert blob hashes
-                        for (idx, blob_hash) in blob_hashes.iter().enumerate() {
-                            db.insert_blob_hash(&tx_hash, &blob_hash.to_string(), idx as i64)?;
-                        }
+            tokio::select! {
+                result = backfill::run(db.clone(), options, log) => result,
+                result = lease::hold(&db, &writer) => result,
+                () = maintenance::run(&db, size_warnings, ingest_log_retention) => Ok(()),
+            }
+        });
+    }
 
-                        db.update_sender(&sender, num_blobs)?;
-                    }
-                }
+    // `blob-exex replay ...` indexes recorded notifications instead of running a node
+    if args.get(1).is_some_and(|arg| arg == "replay") {
+        let options = replay::Options::from_args(&args[2..])?;
+        return tokio::runtime::Runtime::new()?.block_on(async move {
+            let _telemetry = telemetry::init("blob-exex")?;
+            let db = Database::with_readers(&db_path, db_readers)?;
+            if let Some(threshold) = slow_queries {
+                db.log_slow_queries(threshold)?;
             }
-        }
+            let writer = lease::writer_identity("blob-exex replay");
+            lease::acquire(&db, &writer).await?;
 
-        db.insert_block(
-            block_number,
-            block_timestamp,
-            blob_tx_count,
-            total_blobs,
-            blob_gas_used as i64,
-            blob_gas_price,
-            excess_blob_gas,
-        )?;
+            db.replace_entity_addresses(&entity_addresses)?;
 
-        info!(
-            block = block_number,
-            txs = blob_tx_count,
-            blobs = total_blobs,
-            "ExBlob"
-        );
+            tokio::select! {
+                result = replay::run(db.clone(), options, log) => result,
+                result = lease::hold(&db, &writer) => result,
+                () = maintenance::run(&db, size_warnings, ingest_log_retention) => Ok(()),
+            }
+        });
     }
-    Ok(())
-}
 
-/// Revert blob stats for reorged blocks
-fn revert_chain(db: &Database, chain: &Chain) -> eyre::Result<()> {
-    for block in chain.blocks_iter() {
-        db.delete_block(block.header().number())?;
+    if !role.runs_exex() {
+        let web_config = web_config.expect("web role has a web config");
+        return tokio::runtime::Runtime::new()?.block_on(async move {
+            let _telemetry = telemetry::init("blob-exex")?;
+            let db = Database::with_readers(&db_path, db_readers)?;
+            if let Some(threshold) = slow_queries {
+                db.log_slow_queries(threshold)?;
+            }
+            server::bind(db, EventBus::new(), None, &web_config)
+                .await?
+                .await
+        });
     }
-    info!(range = ?chain.range(), "Reverted blocks");
-    Ok(())
-}
 
-fn main() -> eyre::Result<()> {
-    reth::cli::Cli::parse_args().run(|builder, _| async move {
-        let db_path = std::env::var("BLOB_DB_PATH").unwrap_or_else(|_| "blob_stats.db".to_string());
-        let db = Database::new(&db_path)?;
+    let cli = reth::cli::Cli::try_parse_args_from(args).unwrap_or_else(|err| err.exit());
+    cli.run(|builder, _| async move {
+        let db = Database::with_readers(&db_path, db_readers)?;
+        if let Some(threshold) = slow_queries {
+            db.log_slow_queries(threshold)?;
+        }
+        let writer = lease::writer_identity("blob-exex");
+        let chain_spec = builder.config().chain.clone();
+        let prepare = {
+            let db = db.clone();
+            move || -> eyre::Result<()> {
+                indexer::seed_chain_params(&db, &chain_spec)?;
+                db.replace_entity_addresses(&entity_addresses)?;
+                Ok(())
+            }
+        };
+        // A standby claims the lease once the active writer stops, from the
+        // ExEx, and only writes from then on
+        let schedule = indexer::chain_schedule(&builder.config().chain)?;
+        let standby = if standby {
+            db.fence_writes(&writer);
+            Some(Standby {
+                writer: writer.clone(),
+                prepare: Box::new(prepare),
+            })
+        } else {
+            lease::acquire(&db, &writer).await?;
+            prepare()?;
+            None
+        };
+
+        // The ExEx announces blocks to the web server running alongside it,
+        // which can change how it logs them
+        let mut processors = processors::from_env()?;
+        let web = match &web_config {
+            Some(web_config) => {
+                let bus = EventBus::new();
+                processors.push(Box::new(EventPublisher { bus: bus.clone() }));
+                Some(server::bind(db.clone(), bus, Some(log.clone()), web_config).await?)
+            }
+            None => None,
+        };
 
         let handle = builder
             .node(EthereumNode::default())
-            .install_exex("blob-exex", |ctx| init(ctx, db))
+            .install_exex("blob-exex", move |ctx| async move {
+                let provider = ctx.provider().clone();
+                let exex = indexer::init(
+                    ctx,
+                    db.clone(),
+                    schedule.clone(),
+                    processors,
+                    log.clone(),
+                    standby,
+                )
+                .await?;
+                Ok(async move {
+                    // Stop indexing if another writer took over the database
+                    tokio::select! {
+                        result = exex => result,
+                        result = lease::hold(&db, &writer) => result,
+                        () = chain_metrics::run(&db) => Ok(()),
+                        // Only the writer maintains the file and repairs blocks
+                        // and sender stats
+                        result = async {
+                            lease::held(&db, &writer).await?;
+                            maintenance::run(&db, size_warnings, ingest_log_retention).await;
+                            Ok(())
+                        } => result,
+                        result = async {
+                            lease::held(&db, &writer).await?;
+                            indexer::verify_canonical(&db, &schedule, provider, &log).await;
+                            Ok(())
+                        } => result,
+                        result = async {
+                            lease::held(&db, &writer).await?;
+                            maintenance::check_senders(&db, sender_repair_threshold).await;
+                            Ok(())
+                        } => result,
+                    }
+                })
+            })
             .launch_with_debug_capabilities()
             .await?;
 
-        handle.wait_for_node_exit().await
+        match web {
+            Some(web) => tokio::select! {
+                result = handle.wait_for_node_exit() => result,
+                result = web => result,
+            },
+            None => handle.wait_for_node_exit().await,
+        }
     })
 }
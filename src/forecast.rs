@@ -0,0 +1,126 @@
+//! Short-horizon forecast of blob demand.
+//!
+//! Each chain is modelled as posting on a fixed cadence, the median gap between
+//! its recent posts, with its average number of blobs per post. Projecting the
+//! next posts of every chain over the horizon gives the blobs expected per
+//! block. A chain that has been silent for several cadences is assumed down and
+//! projected to post nothing.
+//!
+//! Longer term, daily demand is extrapolated along its exponential trend (see
+//! [`GrowthTrend`]) to tell when it outgrows the blob target.
+
+use crate::db::SECONDS_PER_SLOT;
+
+/// How far ahead demand is forecast.
+pub const HORIZON_SECS: u64 = 3600;
+
+/// History the cadence models are fitted on.
+pub const LOOKBACK_SECS: u64 = 6 * 3600;
+
+// A chain counts as down once it hasn't posted for this many cadences
+const STALE_FACTOR: u64 = 3;
+
+/// Days with demand a growth trend needs to be fitted.
+pub const MIN_TREND_DAYS: usize = 7;
+
+/// Posting process of one chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CadenceModel {
+    pub cadence_secs: u64,
+    pub blobs_per_post: f64,
+    pub last_post: u64,
+}
+
+impl CadenceModel {
+    /// Fit a model to `posts`, `(timestamp, blobs)` per post sorted by
+    /// timestamp, or `None` with fewer than three posts.
+    pub fn fit(posts: &[(u64, u64)]) -> Option<Self> {
+        if posts.len() < 3 {
+            return None;
+        }
+        let mut gaps: Vec<u64> = posts.windows(2).map(|w| w[1].0 - w[0].0).collect();
+        gaps.sort_unstable();
+
+        Some(Self {
+            cadence_secs: gaps[gaps.len() / 2].max(SECONDS_PER_SLOT),
+            blobs_per_post: posts.iter().map(|(_, blobs)| *blobs as f64).sum::<f64>()
+                / posts.len() as f64,
+            last_post: posts.last()?.0,
+        })
+    }
+
+    /// Projected post times in `now..until`. An overdue chain is expected to
+    /// post right away, one silent for too long not at all.
+    pub fn next_posts(&self, now: u64, until: u64) -> impl Iterator<Item = u64> {
+        let cadence = self.cadence_secs;
+        let next = self.last_post + cadence;
+        let stale = now.saturating_sub(self.last_post) > cadence * STALE_FACTOR;
+        let first = if stale { until } else { next.max(now) };
+
+        (0..)
+            .map(move |k| first + k * cadence)
+            .take_while(move |&post| post < until)
+    }
+}
+
+/// Exponential trend of a daily series, `level * (1 + daily_growth)^days`
+/// from its last day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrowthTrend {
+    /// Trend value on the last day.
+    pub level: f64,
+    /// e.g. 0.01 for 1% a day, negative when shrinking.
+    pub daily_growth: f64,
+}
+
+impl GrowthTrend {
+    /// Fit a trend to `(day, value)` points by least squares on the log of
+    /// the values, or `None` with fewer than [`MIN_TREND_DAYS`] positive ones.
+    pub fn fit(series: &[(f64, f64)]) -> Option<Self> {
+        let points: Vec<(f64, f64)> = series
+            .iter()
+            .filter(|(_, value)| *value > 0.0)
+            .map(|&(day, value)| (day, value.ln()))
+            .collect();
+        if points.len() < MIN_TREND_DAYS {
+            return None;
+        }
+        let slope = linear_slope(&points);
+        let n = points.len() as f64;
+        let mean_day = points.iter().map(|(day, _)| day).sum::<f64>() / n;
+        let mean_log = points.iter().map(|(_, log)| log).sum::<f64>() / n;
+        let last_day = series.iter().map(|(day, _)| *day).fold(f64::MIN, f64::max);
+
+        Some(Self {
+            level: (mean_log + slope * (last_day - mean_day)).exp(),
+            daily_growth: slope.exp() - 1.0,
+        })
+    }
+
+    /// Days after the last one until the trend reaches `threshold`, 0 if it
+    /// already has, `None` if it never will.
+    pub fn days_until(&self, threshold: f64) -> Option<f64> {
+        if self.level >= threshold {
+            return Some(0.0);
+        }
+        (self.daily_growth > 0.0).then(|| (threshold / self.level).ln() / self.daily_growth.ln_1p())
+    }
+}
+
+/// Least squares slope of `(x, y)` points, 0 unless there are two distinct xs.
+pub fn linear_slope(points: &[(f64, f64)]) -> f64 {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (covariance, variance) = points.iter().fold((0.0, 0.0), |(cov, var), (x, y)| {
+        (
+            cov + (x - mean_x) * (y - mean_y),
+            var + (x - mean_x).powi(2),
+        )
+    });
+    if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    }
+}
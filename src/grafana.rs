@@ -0,0 +1,260 @@
+//! Grafana JSON datasource under `/grafana`.
+//!
+//! Implements the SimpleJSON contract (also usable from the Infinity plugin):
+//! `GET /grafana` for the connection test, `POST /grafana/search` to list
+//! metrics, `POST /grafana/query` for time series and
+//! `POST /grafana/annotations` for blob schedule changes.
+
+use crate::{
+    api::ApiError,
+    chains::{chain_of, identify_chain},
+    db::SECONDS_PER_SLOT,
+    Database,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BLOBS_PER_BLOCK: &str = "blobs_per_block";
+const BLOB_TXS_PER_BLOCK: &str = "blob_txs_per_block";
+const BLOB_BASE_FEE_GWEI: &str = "blob_base_fee_gwei";
+
+/// Prefix of per-chain metrics, e.g. `chain_blobs:Base`.
+const CHAIN_BLOBS_PREFIX: &str = "chain_blobs:";
+
+/// Points returned per series when Grafana doesn't send `maxDataPoints`.
+const DEFAULT_MAX_DATA_POINTS: u64 = 1000;
+
+#[derive(Deserialize)]
+struct SearchRequest {
+    #[serde(default)]
+    target: String,
+}
+
+#[derive(Deserialize)]
+struct TimeRange {
+    from: String, // RFC 3339, e.g. "2025-12-01T00:00:00.000Z"
+    to: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryRequest {
+    range: TimeRange,
+    interval_ms: Option<u64>,
+    max_data_points: Option<u64>,
+    targets: Vec<QueryTarget>,
+}
+
+#[derive(Deserialize)]
+struct QueryTarget {
+    #[serde(default)]
+    target: String,
+}
+
+#[derive(Serialize)]
+struct TimeSeries {
+    target: String,
+    datapoints: Vec<(f64, u64)>, // [value, unix ms]
+}
+
+#[derive(Deserialize)]
+struct AnnotationRequest {
+    range: TimeRange,
+    annotation: AnnotationQuery,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct AnnotationQuery {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Serialize)]
+struct Annotation {
+    annotation: AnnotationQuery,
+    time: u64, // unix ms
+    title: String,
+    text: String,
+    tags: Vec<String>,
+}
+
+async fn test_connection() -> StatusCode {
+    StatusCode::OK
+}
+
+async fn search(
+    State(db): State<Database>,
+    Json(request): Json<SearchRequest>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let mut chains: Vec<String> = db
+        .get_top_senders(1000)?
+        .into_iter()
+        .map(|sender| identify_chain(&sender.address))
+        .collect();
+    chains.sort();
+    chains.dedup();
+
+    let filter = request.target.to_lowercase();
+    let metrics = [BLOBS_PER_BLOCK, BLOB_TXS_PER_BLOCK, BLOB_BASE_FEE_GWEI]
+        .into_iter()
+        .map(str::to_string)
+        .chain(
+            chains
+                .into_iter()
+                .map(|chain| format!("{CHAIN_BLOBS_PREFIX}{chain}")),
+        )
+        .filter(|metric| metric.to_lowercase().contains(&filter))
+        .collect();
+
+    Ok(Json(metrics))
+}
+
+async fn query(
+    State(db): State<Database>,
+    Json(request): Json<QueryRequest>,
+) -> Result<Json<Vec<TimeSeries>>, ApiError> {
+    let (from, to) = parse_range(&request.range)?;
+
+    // Grafana's interval already accounts for the panel width, but never go
+    // below one point per slot or above maxDataPoints
+    let max_points = request
+        .max_data_points
+        .unwrap_or(DEFAULT_MAX_DATA_POINTS)
+        .max(1);
+    let bucket_secs = (request.interval_ms.unwrap_or(0) / 1000)
+        .max((to - from) / max_points)
+        .max(SECONDS_PER_SLOT);
+
+    let mut series = Vec::with_capacity(request.targets.len());
+    for target in request.targets {
+        let datapoints = if let Some(chain) = target.target.strip_prefix(CHAIN_BLOBS_PREFIX) {
+            let mut buckets: BTreeMap<u64, u64> = BTreeMap::new();
+            for bucket in db.get_sender_blob_series(from, to, bucket_secs)? {
+                if chain_of(&bucket.sender, bucket.attributed_entity.as_deref()) == chain {
+                    *buckets.entry(bucket.bucket_start).or_default() += bucket.blobs;
+                }
+            }
+            buckets
+                .into_iter()
+                .map(|(bucket_start, blobs)| (blobs as f64, bucket_start * 1000))
+                .collect()
+        } else {
+            let value: fn(&crate::db::BlockSeriesData) -> f64 = match target.target.as_str() {
+                BLOBS_PER_BLOCK => |b| b.total_blobs as f64 / b.block_count as f64,
+                BLOB_TXS_PER_BLOCK => |b| b.tx_count as f64 / b.block_count as f64,
+                BLOB_BASE_FEE_GWEI => |b| b.avg_gas_price / 1e9,
+                other => {
+                    return Err(ApiError::new(
+                        StatusCode::BAD_REQUEST,
+                        format!("unknown metric: {other}"),
+                    ));
+                }
+            };
+            db.get_block_series(from, to, bucket_secs)?
+                .iter()
+                .map(|bucket| (value(bucket), bucket.bucket_start * 1000))
+                .collect()
+        };
+
+        series.push(TimeSeries {
+            target: target.target,
+            datapoints,
+        });
+    }
+
+    Ok(Json(series))
+}
+
+async fn annotations(
+    State(db): State<Database>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<Json<Vec<Annotation>>, ApiError> {
+    let (from, to) = parse_range(&request.range)?;
+    let schedule = db.get_blob_schedule()?;
+
+    let annotations = schedule
+        .entries()
+        .iter()
+        .filter(|entry| (from..=to).contains(&entry.activation_timestamp))
+        .map(|entry| Annotation {
+            annotation: request.annotation.clone(),
+            time: entry.activation_timestamp * 1000,
+            title: format!("Blob target {} / max {}", entry.target, entry.max),
+            text: format!(
+                "Blob schedule change: target {} and max {} blobs per block, base fee update fraction {}",
+                entry.target, entry.max, entry.base_fee_update_fraction
+            ),
+            tags: vec!["blob-schedule".to_string()],
+        })
+        .collect();
+
+    Ok(Json(annotations))
+}
+
+/// Parse a Grafana time range into unix seconds.
+fn parse_range(range: &TimeRange) -> Result<(u64, u64), ApiError> {
+    let parse = |value: &str| {
+        parse_rfc3339(value)
+            .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, format!("invalid time: {value}")))
+    };
+    let (from, to) = (parse(&range.from)?, parse(&range.to)?);
+    if from > to {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("range starts after it ends: {} > {}", range.from, range.to),
+        ));
+    }
+    Ok((from, to))
+}
+
+/// Parse a UTC RFC 3339 timestamp like `2025-12-01T00:00:00.000Z` into unix
+/// seconds, dropping fractional seconds.
+fn parse_rfc3339(value: &str) -> Option<u64> {
+    let (date, time) = value.strip_suffix('Z')?.split_once('T')?;
+
+    let mut date = date.splitn(3, '-').map(str::parse::<u64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Days since the epoch of a proleptic Gregorian date, with years starting
+    // in March so leap days fall at the end
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Grafana datasource routes.
+pub fn router(db: Database) -> Router {
+    Router::new()
+        .route("/grafana", get(test_connection))
+        .route("/grafana/", get(test_connection))
+        .route("/grafana/search", post(search))
+        .route("/grafana/query", post(query))
+        .route("/grafana/annotations", post(annotations))
+        .with_state(db)
+}
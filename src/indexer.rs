@@ -0,0 +1,741 @@
+use crate::{
+    chains::REGISTRY_VERSION,
+    db::{DbError, NewBlobTransaction, NewBlock, NewIngestLogEntry, PriorityFees, BLOB_SIZE_BYTES},
+    lease,
+    processors::Processor,
+    regime::RegimeThresholds,
+    schedule::BlobScheduleEntry,
+    telemetry::BlockLog,
+    BlobSchedule, Database,
+};
+use alloy_consensus::{transaction::SignerRecoverable, BlockHeader, Transaction};
+use alloy_eips::{
+    eip2718::{Encodable2718, Typed2718},
+    eip4844::DATA_GAS_PER_BLOB,
+    eip7594::BlobTransactionSidecarVariant,
+};
+use alloy_primitives::TxHash;
+use futures::{Future, TryStreamExt};
+use reth::{
+    chainspec::{ChainSpec, EthereumHardfork, Hardforks},
+    providers::{BlockHashReader, BlockNumReader, BlockReader, TransactionVariant},
+    transaction_pool::TransactionPool,
+};
+use reth_execution_types::Chain;
+use reth_exex::{ExExContext, ExExEvent, ExExNotification};
+use reth_node_api::FullNodeComponents;
+use reth_primitives::{Block, EthPrimitives, RecoveredBlock};
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+use tracing::{error, info, info_span, instrument, warn, Instrument};
+
+/// Attempts made at each ingest step before it is recorded as failed and skipped.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry of a failed ingest step, doubled on every retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Blocks of a re-process request handled per notification, so large ranges
+/// don't hold up live indexing.
+const REPROCESS_BATCH: u64 = 100;
+
+/// Blocks of a relabel job re-attributed per notification. Only the database is
+/// read, so batches can be much larger than re-process ones.
+const RELABEL_BATCH: u64 = 10_000;
+
+/// How often stored blocks are sampled and checked against the node's
+/// canonical chain, see [`verify_canonical`].
+pub const VERIFY_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Blocks this close to the latest indexed one aren't verified, a reorg of them
+/// may still be on its way as a notification.
+const VERIFY_DEPTH: u64 = 64;
+
+/// Latest blocks verified per round, where a missed revert is most likely.
+const VERIFY_RECENT_BLOCKS: u64 = 256;
+
+/// Older blocks verified per round, picked at random.
+const VERIFY_RANDOM_BLOCKS: u64 = 64;
+
+/// Create the blob indexing ExEx future.
+///
+/// `processors` are secondary indexers (see [`crate::processors`]) fed the same
+/// notifications after the blob indexer has handled them. Indexed blocks are
+/// logged through `log`.
+///
+/// With `standby` set, the ExEx only acknowledges notifications until it takes
+/// over the writer lease (see [`lease::wait`]), then catches up from its own
+/// node, see [`take_over`].
+pub async fn init<Node>(
+    ctx: ExExContext<Node>,
+    db: Database,
+    schedule: BlobSchedule,
+    processors: Vec<Box<dyn Processor<Node>>>,
+    log: BlockLog,
+    standby: Option<Standby>,
+) -> eyre::Result<impl Future<Output = eyre::Result<()>>>
+where
+    Node: FullNodeComponents<Types: reth::api::NodeTypes<Primitives = EthPrimitives>>,
+{
+    for processor in &processors {
+        info!(processor = processor.name(), "Enabled secondary processor");
+    }
+    Ok(blob_exex(ctx, db, schedule, processors, log, standby))
+}
+
+/// A standby writer (`BLOB_STANDBY=true`), see [`init`].
+pub struct Standby {
+    /// Identity the writer lease is claimed for.
+    pub writer: String,
+    /// The writes an active writer makes on startup, e.g. storing the chain's
+    /// params, made once the lease is ours and before indexing.
+    pub prepare: Box<dyn FnOnce() -> eyre::Result<()> + Send>,
+}
+
+/// Main ExEx logic
+async fn blob_exex<Node>(
+    mut ctx: ExExContext<Node>,
+    db: Database,
+    schedule: BlobSchedule,
+    processors: Vec<Box<dyn Processor<Node>>>,
+    log: BlockLog,
+    standby: Option<Standby>,
+) -> eyre::Result<()>
+where
+    Node: FullNodeComponents<Types: reth::api::NodeTypes<Primitives = EthPrimitives>>,
+{
+    if let Some(standby) = standby {
+        if !take_over(&mut ctx, &db, &standby.writer).await? {
+            return Ok(());
+        }
+        (standby.prepare)()?;
+    }
+
+    while let Some(notification) = ctx.notifications.try_next().await? {
+        let span = info_span!(
+            "notification",
+            committed = ?notification.committed_chain().map(|chain| chain.range()),
+            reverted = ?notification.reverted_chain().map(|chain| chain.range()),
+        );
+        let started = Instant::now();
+        let handled = handle_notification(&ctx, &db, &schedule, &processors, &log, &notification)
+            .instrument(span)
+            .await;
+        metrics::histogram!("blob_exex_notification_seconds").record(started.elapsed());
+
+        let (outcome, error) = match &handled {
+            Ok(true) => ("ok", None),
+            Ok(false) => ("skipped", None),
+            Err(err) => ("failed", Some(format!("{err:#}"))),
+        };
+        let reverted = notification.reverted_chain();
+        let committed = notification.committed_chain();
+        let entry = NewIngestLogEntry {
+            kind: match notification {
+                ExExNotification::ChainCommitted { .. } => "commit",
+                ExExNotification::ChainReverted { .. } => "revert",
+                ExExNotification::ChainReorged { .. } => "reorg",
+            },
+            reverted: reverted.as_ref().map(|chain| chain.range()),
+            committed: committed.as_ref().map(|chain| chain.range()),
+            reverted_txs: reverted.as_deref().map_or(0, blob_tx_count),
+            committed_txs: committed.as_deref().map_or(0, blob_tx_count),
+            duration: started.elapsed(),
+            outcome,
+            error,
+        };
+        if let Err(err) = db.append_ingest_log(&entry) {
+            warn!(%err, "Failed to append to the ingest log");
+        }
+        handled?;
+    }
+    Ok(())
+}
+
+/// Blob transactions in the blocks of `chain`.
+fn blob_tx_count(chain: &Chain) -> u64 {
+    chain
+        .blocks_iter()
+        .flat_map(|block| block.body().transactions())
+        .filter(|tx| tx.blob_versioned_hashes().is_some())
+        .count() as u64
+}
+
+/// Follow the node as a standby writer until the writer lease can be claimed
+/// for `writer`, acknowledging notifications without indexing them. Returns
+/// false if the notification stream ended first.
+///
+/// Once the lease is ours, the blocks between the last indexed one and the
+/// node's tip are queued for re-processing, which fetches them from this
+/// node's chain one batch per notification. Secondary processors don't see
+/// them, as for any re-processed block.
+async fn take_over<Node>(
+    ctx: &mut ExExContext<Node>,
+    db: &Database,
+    writer: &str,
+) -> eyre::Result<bool>
+where
+    Node: FullNodeComponents<Types: reth::api::NodeTypes<Primitives = EthPrimitives>>,
+{
+    info!(writer, "Standing by for the writer lease");
+    let lease = lease::wait(db, writer);
+    tokio::pin!(lease);
+    loop {
+        tokio::select! {
+            result = &mut lease => {
+                result?;
+                break;
+            }
+            notification = ctx.notifications.try_next() => {
+                let Some(notification) = notification? else {
+                    return Ok(false);
+                };
+                if let Some(committed_chain) = notification.committed_chain() {
+                    ctx.events
+                        .send(ExExEvent::FinishedHeight(committed_chain.tip().num_hash()))?;
+                }
+            }
+        }
+    }
+
+    let tip = ctx.provider().best_block_number()?;
+    match db.get_latest_block()? {
+        Some(latest) if latest < tip => {
+            let request = db.request_reprocess(latest + 1, tip)?;
+            info!(
+                writer,
+                request,
+                from = latest + 1,
+                to = tip,
+                "Took over the writer lease, catching up"
+            );
+        }
+        _ => info!(writer, "Took over the writer lease"),
+    }
+    Ok(true)
+}
+
+/// Index the reverted and committed blocks of a notification and the next batch
+/// of queued re-processing. Returns whether no step of the notification's own
+/// blocks was skipped, see [`with_retry`].
+async fn handle_notification<Node>(
+    ctx: &ExExContext<Node>,
+    db: &Database,
+    schedule: &BlobSchedule,
+    processors: &[Box<dyn Processor<Node>>],
+    log: &BlockLog,
+    notification: &ExExNotification<EthPrimitives>,
+) -> eyre::Result<bool>
+where
+    Node: FullNodeComponents<Types: reth::api::NodeTypes<Primitives = EthPrimitives>>,
+{
+    // Recorded for the web process, which can't see the node, to report lag
+    match ctx.provider().best_block_number() {
+        Ok(tip) => {
+            if let Err(err) = db.set_node_tip(tip) {
+                warn!(%err, "Failed to record node tip");
+            }
+        }
+        Err(err) => warn!(%err, "Failed to read node tip"),
+    }
+
+    // Sidecars are only available for blob txs that went through our own mempool
+    let pool = ctx.pool();
+    let blob_sizes = |tx_hash: TxHash| {
+        pool.get_blob(tx_hash)
+            .ok()
+            .flatten()
+            .map(|sidecar| blob_payload_sizes(&sidecar))
+    };
+
+    let mut complete = true;
+    if let Some(reverted_chain) = notification.reverted_chain() {
+        for block in reverted_chain.blocks_iter() {
+            let block_number = block.header().number();
+            complete &= with_retry(db, block_number..=block_number, "revert", || {
+                db.archive_reverted_block(block_number)?;
+                db.delete_block(block_number)?;
+                Ok(())
+            })
+            .await;
+        }
+        info!(range = ?reverted_chain.range(), "Reverted blocks");
+
+        for processor in processors {
+            complete &= with_retry(db, reverted_chain.range(), processor.name(), || {
+                processor.revert_chain(&ctx.components, db, &reverted_chain)
+            })
+            .await;
+        }
+    }
+
+    if let Some(committed_chain) = notification.committed_chain() {
+        for block in committed_chain.blocks_iter() {
+            let block_number = block.header().number();
+            complete &= with_retry(db, block_number..=block_number, "index", || {
+                process_block(db, schedule, block, &blob_sizes, log)
+            })
+            .await;
+        }
+
+        for processor in processors {
+            complete &= with_retry(db, committed_chain.range(), processor.name(), || {
+                processor.process_chain(&ctx.components, db, &committed_chain)
+            })
+            .await;
+        }
+
+        ctx.events
+            .send(ExExEvent::FinishedHeight(committed_chain.tip().num_hash()))?;
+    }
+
+    reprocess_requested(db, schedule, ctx.provider(), &blob_sizes, log).await?;
+    relabel_requested(db)?;
+    Ok(complete)
+}
+
+/// Run an ingest step for a range of blocks inside a database transaction,
+/// retrying with exponential backoff.
+///
+/// If every attempt fails the failure is recorded in `ingest_errors` for later
+/// re-processing and the step is skipped, so one bad block can't halt indexing.
+/// Database errors that can't go away by retrying (see [`DbError::is_transient`])
+/// are recorded right away. A successful step clears earlier failures of the
+/// same stage. Returns whether the step succeeded.
+#[instrument(name = "ingest", skip_all, fields(blocks = ?blocks, stage))]
+async fn with_retry(
+    db: &Database,
+    blocks: RangeInclusive<u64>,
+    stage: &str,
+    mut step: impl FnMut() -> eyre::Result<()>,
+) -> bool {
+    let mut backoff = RETRY_BACKOFF;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = db.transaction(|| -> eyre::Result<()> {
+            step()?;
+            db.resolve_ingest_errors(&blocks, stage)?;
+            Ok(())
+        });
+
+        let err = match result {
+            Ok(()) => return true,
+            Err(err) => err,
+        };
+
+        let permanent = err
+            .downcast_ref::<DbError>()
+            .is_some_and(|err| !err.is_transient());
+        if attempt < MAX_ATTEMPTS && !permanent {
+            warn!(?blocks, stage, attempt, %err, "Ingest failed, retrying");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            continue;
+        }
+
+        error!(?blocks, stage, %err, "Ingest failed, skipping");
+        metrics::counter!("blob_exex_ingest_errors_total", "stage" => stage.to_string())
+            .increment(1);
+        for block_number in blocks.clone() {
+            if let Err(err) =
+                db.record_ingest_error(block_number, stage, &format!("{err:#}"), attempt)
+            {
+                error!(block = block_number, %err, "Failed to record ingest error");
+            }
+        }
+        break;
+    }
+    false
+}
+
+/// Re-index the next batch of the oldest queued re-process request (see
+/// [`Database::request_reprocess`]), re-fetching its blocks from the node.
+///
+/// Each block is deleted and rewritten in one transaction. Blob sizes and
+/// execution context recorded when the block first arrived are carried over,
+/// since sidecars and secondary processors aren't available for old blocks.
+async fn reprocess_requested<Provider>(
+    db: &Database,
+    schedule: &BlobSchedule,
+    provider: &Provider,
+    blob_sizes: impl Fn(TxHash) -> Option<Vec<u64>>,
+    log: &BlockLog,
+) -> eyre::Result<()>
+where
+    Provider: BlockReader<Block = Block>,
+{
+    let Some(request) = db.next_reprocess_request()? else {
+        return Ok(());
+    };
+    let last_block = request
+        .to_block
+        .min(request.next_block.saturating_add(REPROCESS_BATCH - 1));
+
+    for block_number in request.next_block..=last_block {
+        let Some(block) =
+            provider.recovered_block(block_number.into(), TransactionVariant::WithHash)?
+        else {
+            warn!(block = block_number, "Block to re-process not found");
+            continue;
+        };
+
+        with_retry(db, block_number..=block_number, "index", || {
+            let stored_sizes = db.get_block_blob_sizes(block_number)?;
+            let execution = db.get_block(block_number)?.and_then(|b| b.execution);
+
+            db.delete_block(block_number)?;
+            process_block(
+                db,
+                schedule,
+                &block,
+                |tx_hash: TxHash| {
+                    blob_sizes(tx_hash).or_else(|| stored_sizes.get(&tx_hash.to_string()).cloned())
+                },
+                log,
+            )?;
+            if let Some(execution) = execution {
+                db.update_block_execution(block_number, &execution)?;
+            }
+            Ok(())
+        })
+        .await;
+    }
+
+    db.advance_reprocess_request(request.id, last_block + 1)?;
+    info!(
+        request = request.id,
+        range = ?(request.next_block..=last_block),
+        "Re-processed blocks"
+    );
+    Ok(())
+}
+
+/// Re-attribute the next batch of the oldest queued relabel job (see
+/// [`Database::request_relabel`]) with this build's chain registry.
+fn relabel_requested(db: &Database) -> eyre::Result<()> {
+    let Some(job) = db.next_relabel_job()? else {
+        return Ok(());
+    };
+    let last_block = job
+        .to_block
+        .min(job.next_block.saturating_add(RELABEL_BATCH - 1));
+
+    // Left queued on failure, the next notification retries the batch
+    match db.relabel_blocks(job.id, job.next_block, last_block) {
+        Ok(relabeled) => info!(
+            job = job.id,
+            range = ?(job.next_block..=last_block),
+            relabeled,
+            registry_version = REGISTRY_VERSION,
+            "Relabeled blocks"
+        ),
+        Err(err) => warn!(job = job.id, %err, "Failed to relabel blocks"),
+    }
+    Ok(())
+}
+
+/// Check a sample of stored block hashes against the node's canonical chain
+/// every [`VERIFY_INTERVAL`], repairing blocks whose revert the ExEx missed
+/// (e.g. after a crash mid-reorg). Never returns; failures are logged and
+/// retried on the next round.
+///
+/// A block off the canonical chain is archived like a reverted one and
+/// re-indexed from the canonical block at its height, or just removed if the
+/// node has no block there. Rounds are recorded for `/api/health`.
+pub async fn verify_canonical<Provider>(
+    db: &Database,
+    schedule: &BlobSchedule,
+    provider: Provider,
+    log: &BlockLog,
+) where
+    Provider: BlockHashReader + BlockReader<Block = Block>,
+{
+    let mut interval = tokio::time::interval(VERIFY_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = verify_sample(db, schedule, &provider, log).await {
+            warn!(%err, "Failed to verify canonical chain");
+        }
+    }
+}
+
+async fn verify_sample<Provider>(
+    db: &Database,
+    schedule: &BlobSchedule,
+    provider: &Provider,
+    log: &BlockLog,
+) -> eyre::Result<()>
+where
+    Provider: BlockHashReader + BlockReader<Block = Block>,
+{
+    let samples =
+        db.sample_block_hashes(VERIFY_DEPTH, VERIFY_RECENT_BLOCKS, VERIFY_RANDOM_BLOCKS)?;
+
+    let mut mismatched = Vec::new();
+    for (block_number, stored_hash) in &samples {
+        let canonical_hash = provider.block_hash(*block_number)?;
+        if canonical_hash.is_some_and(|hash| hash.to_string() == *stored_hash) {
+            continue;
+        }
+        warn!(
+            block = block_number,
+            stored = %stored_hash,
+            canonical = ?canonical_hash,
+            "Stored block is not canonical, repairing"
+        );
+        mismatched.push(*block_number);
+
+        let block =
+            provider.recovered_block((*block_number).into(), TransactionVariant::WithHash)?;
+        let block_number = *block_number;
+        with_retry(db, block_number..=block_number, "verify", || {
+            let stored_sizes = db.get_block_blob_sizes(block_number)?;
+            db.archive_reverted_block(block_number)?;
+            db.delete_block(block_number)?;
+            if let Some(block) = &block {
+                process_block(
+                    db,
+                    schedule,
+                    block,
+                    |tx_hash: TxHash| stored_sizes.get(&tx_hash.to_string()).cloned(),
+                    log,
+                )?;
+            }
+            Ok(())
+        })
+        .await;
+    }
+
+    metrics::counter!("blob_exex_canonical_checked_total").increment(samples.len() as u64);
+    metrics::counter!("blob_exex_canonical_mismatches_total").increment(mismatched.len() as u64);
+    db.record_canonical_check(samples.len() as u64, &mismatched)?;
+    info!(
+        checked = samples.len(),
+        mismatches = mismatched.len(),
+        "Verified canonical chain"
+    );
+    Ok(())
+}
+
+/// Store the blob schedule and regime thresholds of a chain, the ones in
+/// `BLOB_SCHEDULE` and `BLOB_REGIME_THRESHOLDS` if set, so the web server reads
+/// back what blocks are indexed with. Returns the schedule.
+pub fn seed_chain_params(db: &Database, chain_spec: &ChainSpec) -> eyre::Result<BlobSchedule> {
+    let schedule = chain_schedule(chain_spec)?;
+    db.replace_blob_schedule(&schedule)?;
+    let thresholds = match RegimeThresholds::from_env()? {
+        Some(thresholds) => thresholds,
+        None => RegimeThresholds::for_schedule(&schedule),
+    };
+    db.replace_regime_thresholds(&thresholds)?;
+    Ok(schedule)
+}
+
+/// Blob schedule blocks of a chain are indexed with, the one in
+/// `BLOB_SCHEDULE` if set, without storing it like [`seed_chain_params`].
+pub fn chain_schedule(chain_spec: &ChainSpec) -> eyre::Result<BlobSchedule> {
+    Ok(BlobSchedule::from_env()?.unwrap_or_else(|| blob_schedule(chain_spec)))
+}
+
+/// Blob schedule from the chain spec: the Cancun/Prague/Osaka forks plus any
+/// timestamp-scheduled BPO forks.
+pub fn blob_schedule(chain_spec: &ChainSpec) -> BlobSchedule {
+    let params = &chain_spec.blob_params;
+    let forks = [
+        (EthereumHardfork::Cancun, &params.cancun),
+        (EthereumHardfork::Prague, &params.prague),
+        (EthereumHardfork::Osaka, &params.osaka),
+    ];
+
+    let entries = forks
+        .into_iter()
+        .filter_map(|(fork, params)| {
+            let activation = chain_spec.fork(fork).as_timestamp()?;
+            Some(BlobScheduleEntry::new(activation, params))
+        })
+        .chain(
+            params
+                .scheduled
+                .iter()
+                .map(|(activation, params)| BlobScheduleEntry::new(*activation, params)),
+        )
+        .collect();
+
+    BlobSchedule::new(entries).unwrap_or_default()
+}
+
+/// Meaningful payload length of each blob in a sidecar, with trailing zero padding trimmed.
+fn blob_payload_sizes(sidecar: &BlobTransactionSidecarVariant) -> Vec<u64> {
+    sidecar
+        .blobs()
+        .iter()
+        .map(|blob| {
+            blob.iter()
+                .rposition(|byte| *byte != 0)
+                .map_or(0, |last| last as u64 + 1)
+        })
+        .collect()
+}
+
+/// Clamp a wei amount into the range SQLite can store as an INTEGER.
+pub(crate) fn clamp_fee(fee: u128) -> u64 {
+    fee.min(i64::MAX as u128) as u64
+}
+
+/// Index every block of a committed chain, logging a line per block.
+///
+/// `blob_sizes` looks up the payload size of each blob of a transaction from its sidecar.
+pub fn process_chain(
+    db: &Database,
+    schedule: &BlobSchedule,
+    chain: &Chain,
+    blob_sizes: impl Fn(TxHash) -> Option<Vec<u64>>,
+) -> eyre::Result<()> {
+    let log = BlockLog::default();
+    for block in chain.blocks_iter() {
+        process_block(db, schedule, block, &blob_sizes, &log)?;
+    }
+    Ok(())
+}
+
+/// Index a single block, logging it through `log`.
+#[instrument(skip_all, fields(block = block.header().number()))]
+pub fn process_block(
+    db: &Database,
+    schedule: &BlobSchedule,
+    block: &RecoveredBlock<Block>,
+    blob_sizes: impl Fn(TxHash) -> Option<Vec<u64>>,
+    log: &BlockLog,
+) -> eyre::Result<()> {
+    let started = Instant::now();
+    let block_number = block.header().number();
+    let block_timestamp = block.header().timestamp();
+    let mut blob_tx_count = 0u64;
+    let mut total_blobs = 0u64;
+    let mut blob_gas_used = 0u128;
+    let mut priority_fees = Vec::new();
+    let base_fee = block.header().base_fee_per_gas().unwrap_or_default();
+    let params = schedule.params_at(block_timestamp);
+    let log_txs = log.logs_txs();
+
+    let blob_gas_price = block.header().blob_fee(params.blob_params()).unwrap_or(0);
+
+    let excess_blob_gas: i64 = block
+        .header()
+        .excess_blob_gas()
+        .unwrap_or(0)
+        .try_into()
+        .unwrap_or(0);
+
+    // Any tx carrying blobs, whatever its type, so blob-carrying types of
+    // future forks are indexed without a change here
+    for tx in block.body().transactions() {
+        if let Some(blob_hashes) = tx.blob_versioned_hashes() {
+            blob_tx_count += 1;
+            let priority_fee = clamp_fee(tx.effective_tip_per_gas(base_fee).unwrap_or(0));
+            priority_fees.push(priority_fee);
+
+            let num_blobs = blob_hashes.len() as u64;
+            total_blobs += num_blobs;
+            blob_gas_used += (num_blobs as u128) * (DATA_GAS_PER_BLOB as u128);
+
+            if let Ok(sender) = tx.recover_signer() {
+                let sizes =
+                    blob_sizes(*tx.tx_hash()).filter(|sizes| sizes.len() == blob_hashes.len());
+                let tx_hash = tx.tx_hash().to_string();
+                let payload_size: Option<u64> = sizes.as_ref().map(|sizes| sizes.iter().sum());
+
+                // Insert blob transaction
+                db.insert_blob_transaction(&NewBlobTransaction {
+                    tx_hash: &tx_hash,
+                    block_number,
+                    sender,
+                    nonce: tx.nonce(),
+                    tx_type: tx.ty(),
+                    blob_count: num_blobs as i64,
+                    gas_price: blob_gas_price,
+                    priority_fee: priority_fee as i64,
+                    created_at: block_timestamp,
+                    el_size: tx.encode_2718_len() as u64,
+                    payload_size,
+                    to: tx.to(),
+                })?;
+
+                // Insert blob hashes
+                for (idx, blob_hash) in blob_hashes.iter().enumerate() {
+                    db.insert_blob_hash(
+                        &tx_hash,
+                        &blob_hash.to_string(),
+                        idx as i64,
+                        sizes.as_ref().map(|sizes| sizes[idx]),
+                    )?;
+                }
+
+                let blob_size = payload_size.unwrap_or(num_blobs * BLOB_SIZE_BYTES);
+                db.update_sender(&sender, block_number, block_timestamp, num_blobs, blob_size)?;
+
+                if log_txs {
+                    info!(
+                        block = block_number,
+                        tx = %tx_hash,
+                        %sender,
+                        blobs = num_blobs,
+                        payload_size,
+                        priority_fee,
+                        "Blob transaction"
+                    );
+                }
+            }
+        }
+    }
+
+    priority_fees.sort_unstable();
+
+    // A mismatch means a blob tx was missed or miscounted, or an unusual block
+    let header_blob_gas_used = block.header().blob_gas_used();
+    if let Some(header_blob_gas_used) =
+        header_blob_gas_used.filter(|&used| used as u128 != blob_gas_used)
+    {
+        warn!(
+            block = block_number,
+            header_blob_gas_used,
+            computed_blob_gas_used = blob_gas_used as u64,
+            "Blob gas used doesn't match the header"
+        );
+        metrics::counter!("blob_exex_blob_gas_mismatches_total").increment(1);
+    }
+
+    db.insert_block(&NewBlock {
+        block_number,
+        block_timestamp,
+        tx_count: blob_tx_count,
+        total_blobs,
+        gas_used: blob_gas_used as i64,
+        gas_price: blob_gas_price,
+        excess_blob_gas,
+        base_fee_per_gas: base_fee,
+        priority_fees: PriorityFees::from_sorted(&priority_fees),
+        blob_target: params.target,
+        blob_max: params.max,
+        header_blob_gas_used,
+        block_hash: block.hash().to_string(),
+        beneficiary: block.header().beneficiary(),
+    })?;
+
+    let elapsed = started.elapsed();
+    metrics::histogram!("blob_exex_block_processing_seconds").record(elapsed);
+    log.block(block_number, blob_tx_count, total_blobs, elapsed);
+    Ok(())
+}
+
+/// Revert blob stats for reorged blocks
+pub fn revert_chain(db: &Database, chain: &Chain) -> eyre::Result<()> {
+    for block in chain.blocks_iter() {
+        db.archive_reverted_block(block.header().number())?;
+        db.delete_block(block.header().number())?;
+    }
+    info!(range = ?chain.range(), "Reverted blocks");
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Single-writer enforcement.
+//!
+//! The ExEx claims a lease row in the database on startup and keeps it alive
+//! with heartbeats, so a second indexer pointed at the same file refuses to
+//! start instead of interleaving writes.
+//!
+//! A standby indexer (`BLOB_STANDBY=true`) instead waits for the lease to
+//! expire and takes over, see [`wait`]. Both indexers open the same SQLite
+//! file, so they must run on the same host: SQLite's locking isn't reliable
+//! over network filesystems. A standby covers its node's process stalling,
+//! crashing or restarting, not the loss of the host itself.
+//!
+//! Heartbeats alone don't stop a writer that stalled past [`LEASE_TIMEOUT`]
+//! from writing after a standby took over, so the holder's
+//! [`Database::transaction`]s also check that it still holds the lease, see
+//! [`Database::fence_writes`].
+
+use crate::{db::DbError, Database};
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+/// A lease without a heartbeat for this long can be taken over.
+pub const LEASE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the lease holder sends a heartbeat.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Identity of this process as a lease holder, e.g. `blob-exex pid 42 on host`.
+pub fn writer_identity(name: &str) -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .unwrap_or_else(|| "unknown host".to_string());
+    format!("{name} pid {} on {host}", std::process::id())
+}
+
+/// Claim the writer lease for `holder`, failing if another writer is alive.
+///
+/// A lease whose holder stopped sending heartbeats, e.g. the previous process
+/// of a restarted indexer, is waited out for up to [`LEASE_TIMEOUT`] and then
+/// taken over.
+pub async fn acquire(db: &Database, holder: &str) -> Result<(), DbError> {
+    let deadline = Instant::now() + LEASE_TIMEOUT + Duration::from_secs(1);
+    let first_heartbeat = db.get_writer_lease()?.map(|lease| lease.heartbeat_at);
+    loop {
+        match try_acquire(db, holder) {
+            Err(DbError::Busy(current)) => {
+                let heartbeat = db.get_writer_lease()?.map(|lease| lease.heartbeat_at);
+                // A holder still sending heartbeats is alive
+                if heartbeat != first_heartbeat || Instant::now() >= deadline {
+                    return Err(DbError::Busy(current));
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Claim the lease once, and fence the writes of `db` with it.
+fn try_acquire(db: &Database, holder: &str) -> Result<(), DbError> {
+    db.acquire_writer_lease(holder, LEASE_TIMEOUT.as_secs())?;
+    db.fence_writes(holder);
+    Ok(())
+}
+
+/// Wait until `holder` can claim the lease, i.e. the current writer stopped
+/// sending heartbeats for [`LEASE_TIMEOUT`], and claim it.
+pub async fn wait(db: &Database, holder: &str) -> eyre::Result<()> {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        interval.tick().await;
+        match try_acquire(db, holder) {
+            Ok(()) => return Ok(()),
+            Err(DbError::Busy(_)) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Wait until `holder` has the lease, right away unless it's a standby that
+/// hasn't taken over yet.
+pub async fn held(db: &Database, holder: &str) -> eyre::Result<()> {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        interval.tick().await;
+        if active_writer(db)?.as_deref() == Some(holder) {
+            return Ok(());
+        }
+    }
+}
+
+/// Keep the lease alive once `holder` has it, see [`held`]. Only returns if
+/// the lease was lost, e.g. because this process stalled past
+/// [`LEASE_TIMEOUT`] and another writer took over.
+///
+/// Heartbeats are sent from a thread of their own, so busy async workers don't
+/// delay them. The thread stops once this future is dropped.
+pub async fn hold(db: &Database, holder: &str) -> eyre::Result<()> {
+    held(db, holder).await?;
+    let (_stop, stopped) = mpsc::channel::<()>();
+    let (lost, lost_rx) = tokio::sync::oneshot::channel();
+    let (db, holder) = (db.clone(), holder.to_string());
+    std::thread::Builder::new()
+        .name("lease-heartbeat".to_string())
+        .spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(HEARTBEAT_INTERVAL) {
+                let err = match db.renew_writer_lease(&holder) {
+                    Ok(true) => continue,
+                    Ok(false) => match db.get_writer_lease() {
+                        Ok(lease) => eyre::eyre!(
+                            "writer lease lost to {}",
+                            lease.map_or("nobody".to_string(), |lease| lease.holder)
+                        ),
+                        Err(err) => err.into(),
+                    },
+                    Err(err) => err.into(),
+                };
+                let _ = lost.send(err);
+                return;
+            }
+        })?;
+    Err(lost_rx
+        .await
+        .unwrap_or_else(|_| eyre::eyre!("lease heartbeat thread panicked")))
+}
+
+/// Holder of the lease if it sent a heartbeat within [`LEASE_TIMEOUT`].
+pub fn active_writer(db: &Database) -> Result<Option<String>, DbError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    Ok(db.get_writer_lease()?.and_then(|lease| {
+        (now.saturating_sub(lease.heartbeat_at) <= LEASE_TIMEOUT.as_secs()).then_some(lease.holder)
+    }))
+}
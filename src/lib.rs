@@ -1,3 +1,32 @@
+pub mod admin;
+pub mod alerts;
+pub mod api;
+pub mod backfill;
+pub mod chain_metrics;
+pub mod chains;
+pub mod config;
 pub mod db;
+pub mod encoding;
+pub mod events;
+pub mod forecast;
+pub mod grafana;
+pub mod indexer;
+pub mod lease;
+pub mod maintenance;
+pub mod op_batch;
+pub mod params;
+pub mod processors;
+pub mod query;
+pub mod regime;
+pub mod replay;
+pub mod schedule;
+pub mod self_test;
+pub mod sensitivity;
+pub mod server;
+pub mod snapshot;
+pub mod telemetry;
+pub mod tls;
+pub mod types;
 
-pub use db::Database;
+pub use db::{Database, DbError};
+pub use schedule::BlobSchedule;
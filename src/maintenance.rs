@@ -0,0 +1,120 @@
+//! Upkeep of the database files by the writer.
+//!
+//! SQLite's automatic checkpoints only reset the WAL once no reader is using
+//! it, which a busy web process sharing the database rarely allows, so the WAL
+//! of a long-running deployment keeps growing. The writer checkpoints it with
+//! `TRUNCATE` periodically instead, and warns once the files grow past
+//! [`SizeWarnings`]. It also drops ingest log entries past their retention.
+//!
+//! Separately, the writer checks the `senders` table against the stats derived
+//! from `blob_transactions`, see [`check_senders`].
+
+use crate::{
+    config::SizeWarnings,
+    db::{DbError, DbFileSizes, SenderCheckData},
+    Database,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// How often the WAL is checkpointed and the file sizes checked.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How often sender stats are checked, see [`check_senders`]. Each check scans
+/// all of `blob_transactions`.
+pub const SENDER_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Checkpoint the WAL every [`CHECKPOINT_INTERVAL`], and prune the ingest log
+/// of entries older than `ingest_log_retention` if set. Never returns; failures
+/// are logged and retried on the next round.
+pub async fn run(db: &Database, warnings: SizeWarnings, ingest_log_retention: Option<Duration>) {
+    let mut interval = tokio::time::interval(CHECKPOINT_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Some(retention) = ingest_log_retention {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            match db.prune_ingest_log(now.saturating_sub(retention).as_secs()) {
+                Ok(0) => {}
+                Ok(deleted) => debug!(deleted, "Pruned ingest log"),
+                Err(err) => warn!(%err, "Failed to prune ingest log"),
+            }
+        }
+        match db.checkpoint_wal() {
+            Ok(checkpoint) if checkpoint.complete => debug!("Checkpointed WAL"),
+            Ok(checkpoint) => debug!(
+                frames = checkpoint.wal_frames,
+                checkpointed = checkpoint.checkpointed_frames,
+                "WAL checkpoint blocked by readers"
+            ),
+            Err(err) => warn!(%err, "Failed to checkpoint WAL"),
+        }
+        match db.file_sizes() {
+            Ok(Some(sizes)) => warn_if_large(sizes, warnings),
+            Ok(None) => {}
+            Err(err) => warn!(%err, "Failed to read database size"),
+        }
+    }
+}
+
+fn warn_if_large(sizes: DbFileSizes, warnings: SizeWarnings) {
+    if let Some(threshold) = warnings.db_bytes.filter(|&bytes| sizes.db_bytes > bytes) {
+        warn!(
+            db_bytes = sizes.db_bytes,
+            threshold, "Database is larger than BLOB_DB_WARN_MB"
+        );
+    }
+    if let Some(threshold) = warnings.wal_bytes.filter(|&bytes| sizes.wal_bytes > bytes) {
+        warn!(
+            wal_bytes = sizes.wal_bytes,
+            threshold,
+            "WAL is larger than BLOB_WAL_WARN_MB, long-lived readers may be blocking checkpoints"
+        );
+    }
+}
+
+/// Recompute sender stats from `blob_transactions` every
+/// [`SENDER_CHECK_INTERVAL`] and compare them with the `senders` table. Never
+/// returns; failures are logged and retried on the next round.
+///
+/// The drift found is exported as metrics and recorded for `/api/health`. With
+/// `repair_above` set, stats are rebuilt once more senders than that drifted.
+pub async fn check_senders(db: &Database, repair_above: Option<u64>) {
+    let mut interval = tokio::time::interval(SENDER_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = check_sender_drift(db, repair_above) {
+            warn!(%err, "Failed to check sender stats");
+        }
+    }
+}
+
+fn check_sender_drift(db: &Database, repair_above: Option<u64>) -> Result<(), DbError> {
+    let drift = db.get_sender_drift()?;
+    let repair = repair_above.is_some_and(|threshold| drift.len() as u64 > threshold);
+    if repair {
+        let senders = db.rebuild_sender_stats()?;
+        warn!(
+            drifted = drift.len(),
+            senders, "Sender stats drifted past BLOB_SENDER_REPAIR_THRESHOLD, rebuilt them"
+        );
+        metrics::counter!("blob_exex_sender_repairs_total").increment(1);
+    } else if !drift.is_empty() {
+        info!(drifted = drift.len(), "Sender stats drifted");
+    }
+    db.record_sender_check(&drift, repair)?;
+
+    if let Some(check) = db.get_sender_check()? {
+        set_drift_gauges(&check);
+    }
+    Ok(())
+}
+
+/// Export the drift of a sender stats check, as of when it ran.
+pub fn set_drift_gauges(check: &SenderCheckData) {
+    metrics::gauge!("blob_exex_sender_drift_senders").set(check.drifted_senders as f64);
+    metrics::gauge!("blob_exex_sender_drift_transactions").set(check.tx_count_drift as f64);
+    metrics::gauge!("blob_exex_sender_drift_blobs").set(check.blob_drift as f64);
+    metrics::gauge!("blob_exex_sender_checked_at").set(check.checked_at as f64);
+}
@@ -0,0 +1,224 @@
+//! Decoding of OP Stack batcher blobs.
+//!
+//! OP Stack batchers pack their channel frames into blobs with a fixed field
+//! element encoding (version 0): each round of four field elements carries 127
+//! bytes, the top two bits of every field element being spread over the other
+//! bytes. The decoded data is batcher transaction data, i.e. a derivation
+//! version byte followed by frames:
+//!
+//! `channel_id (16) ++ frame_number (u16) ++ frame_data_length (u32) ++ frame_data ++ is_last (u8)`
+//!
+//! A channel's frame data concatenated is the compressed channel, zlib or,
+//! since Fjord, a `0x01` byte followed by brotli.
+//!
+//! The encoding is strict enough that blobs of other rollups fail to decode.
+
+use alloy_eips::eip4844::BYTES_PER_BLOB;
+use std::io::Read;
+
+/// Most bytes a blob can carry in encoding version 0.
+pub const MAX_BLOB_DATA_SIZE: usize = (4 * 31 + 3) * 1024 - 4;
+
+/// Decompressed channels larger than this are invalid (Fjord's
+/// `max_rlp_bytes_per_channel`), so decompression stops there.
+pub const MAX_CHANNEL_BYTES: u64 = 100_000_000;
+
+const ENCODING_VERSION: u8 = 0;
+const DERIVATION_VERSION: u8 = 0;
+const ROUNDS: usize = 1024;
+const FRAME_OVERHEAD: usize = 16 + 2 + 4 + 1;
+
+/// One frame of a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub channel_id: [u8; 16],
+    pub frame_number: u16,
+    pub data: Vec<u8>,
+    pub is_last: bool,
+}
+
+/// A channel whose frames were all seen, in order of frame number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel {
+    pub id: [u8; 16],
+    pub frame_count: u64,
+    /// The frames' data concatenated, i.e. the compressed channel.
+    pub data: Vec<u8>,
+}
+
+/// How a channel is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Zlib,
+    Brotli,
+}
+
+impl Compression {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Zlib => "zlib",
+            Self::Brotli => "brotli",
+        }
+    }
+}
+
+/// Decode the data of an OP Stack blob, or `None` if it isn't one.
+pub fn decode_blob(blob: &[u8]) -> Option<Vec<u8>> {
+    if blob.len() != BYTES_PER_BLOB || blob[1] != ENCODING_VERSION {
+        return None;
+    }
+    let len = (blob[2] as usize) << 16 | (blob[3] as usize) << 8 | blob[4] as usize;
+    if len > MAX_BLOB_DATA_SIZE {
+        return None;
+    }
+
+    let mut output = vec![0u8; MAX_BLOB_DATA_SIZE];
+    let mut encoded = [0u8; 4];
+
+    // Round 0: the first field element holds the version and length before its data
+    output[..27].copy_from_slice(&blob[5..32]);
+    encoded[0] = blob[0];
+    let (mut opos, mut ipos) = (28, 32);
+    for byte in &mut encoded[1..] {
+        *byte = decode_field_element(blob, &mut opos, &mut ipos, &mut output)?;
+    }
+    if encoded[0] & 0b1100_0000 != 0 {
+        return None;
+    }
+    opos = reassemble_bytes(opos, &encoded, &mut output);
+
+    for _ in 1..ROUNDS {
+        if opos >= len {
+            break;
+        }
+        for byte in &mut encoded {
+            *byte = decode_field_element(blob, &mut opos, &mut ipos, &mut output)?;
+        }
+        opos = reassemble_bytes(opos, &encoded, &mut output);
+    }
+
+    // Everything past the data must be padding
+    if output[len..].iter().any(|byte| *byte != 0) || blob[ipos..].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    output.truncate(len);
+    Some(output)
+}
+
+/// Copy the 31 data bytes of the field element at `ipos` to `opos`, returning
+/// its first byte, which carries 6 bits of the round's reassembled bytes.
+fn decode_field_element(
+    blob: &[u8],
+    opos: &mut usize,
+    ipos: &mut usize,
+    output: &mut [u8],
+) -> Option<u8> {
+    let first = blob[*ipos];
+    if first & 0b1100_0000 != 0 {
+        return None;
+    }
+    output[*opos..*opos + 31].copy_from_slice(&blob[*ipos + 1..*ipos + 32]);
+    *opos += 32;
+    *ipos += 32;
+    Some(first)
+}
+
+/// Fill the three bytes a round's field elements left out from the 6-bit
+/// chunks in their first bytes.
+fn reassemble_bytes(opos: usize, encoded: &[u8; 4], output: &mut [u8]) -> usize {
+    let opos = opos - 1;
+    let x = (encoded[0] & 0b0011_1111) | ((encoded[1] & 0b0011_0000) << 2);
+    let y = (encoded[1] & 0b0000_1111) | ((encoded[3] & 0b0000_1111) << 4);
+    let z = (encoded[2] & 0b0011_1111) | ((encoded[3] & 0b0011_0000) << 2);
+    output[opos - 32] = z;
+    output[opos - 32 * 2] = y;
+    output[opos - 32 * 3] = x;
+    opos
+}
+
+/// Parse batcher transaction data into its frames, or `None` if it isn't
+/// exactly a derivation version byte followed by frames.
+pub fn parse_frames(data: &[u8]) -> Option<Vec<Frame>> {
+    let (&version, mut rest) = data.split_first()?;
+    if version != DERIVATION_VERSION || rest.is_empty() {
+        return None;
+    }
+
+    let mut frames = Vec::new();
+    while !rest.is_empty() {
+        if rest.len() < FRAME_OVERHEAD {
+            return None;
+        }
+        let channel_id: [u8; 16] = rest[..16].try_into().ok()?;
+        let frame_number = u16::from_be_bytes(rest[16..18].try_into().ok()?);
+        let data_len = u32::from_be_bytes(rest[18..22].try_into().ok()?) as usize;
+        let data = rest.get(22..22 + data_len)?.to_vec();
+        let is_last = match rest.get(22 + data_len)? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        frames.push(Frame {
+            channel_id,
+            frame_number,
+            data,
+            is_last,
+        });
+        rest = &rest[22 + data_len + 1..];
+    }
+    Some(frames)
+}
+
+/// Channels of which `frames` hold every frame up to the last one, in order of
+/// first appearance. Channels also spanning other transactions are left out.
+pub fn complete_channels(frames: &[Frame]) -> Vec<Channel> {
+    let mut ids: Vec<[u8; 16]> = Vec::new();
+    for frame in frames {
+        if !ids.contains(&frame.channel_id) {
+            ids.push(frame.channel_id);
+        }
+    }
+
+    ids.into_iter()
+        .filter_map(|id| {
+            let mut channel: Vec<&Frame> = frames.iter().filter(|f| f.channel_id == id).collect();
+            channel.sort_by_key(|f| f.frame_number);
+            let complete = channel
+                .iter()
+                .enumerate()
+                .all(|(i, f)| f.frame_number as usize == i)
+                && channel.last()?.is_last
+                && channel.iter().filter(|f| f.is_last).count() == 1;
+            complete.then(|| Channel {
+                id,
+                frame_count: channel.len() as u64,
+                data: channel
+                    .iter()
+                    .flat_map(|f| f.data.iter().copied())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Compression and decompressed size of a complete channel, or `None` if it
+/// doesn't decompress within [`MAX_CHANNEL_BYTES`].
+pub fn decompressed_size(channel: &[u8]) -> Option<(Compression, u64)> {
+    let (&first, rest) = channel.split_first()?;
+    let (compression, reader): (_, Box<dyn Read + '_>) = match first {
+        // zlib with the deflate method and a window of up to 32K
+        _ if first & 0x0f == 8 || first & 0x0f == 15 => (
+            Compression::Zlib,
+            Box::new(flate2::read::ZlibDecoder::new(channel)),
+        ),
+        1 => (
+            Compression::Brotli,
+            Box::new(brotli_decompressor::Decompressor::new(rest, 4096)),
+        ),
+        _ => return None,
+    };
+
+    let mut limited = reader.take(MAX_CHANNEL_BYTES + 1);
+    let size = std::io::copy(&mut limited, &mut std::io::sink()).ok()?;
+    (size <= MAX_CHANNEL_BYTES).then_some((compression, size))
+}
@@ -0,0 +1,89 @@
+//! Validation of the count parameters shared across endpoints.
+//!
+//! `hours`, `days`, `blocks` and `limit` are read by the [`Counts`] extractor
+//! rather than each endpoint's own query struct, so they are rejected the same
+//! way everywhere: zero, anything but a whole number, or a value above the
+//! ceiling of its parameter answers 400 with a JSON error naming it. Endpoints
+//! still apply their own, lower limits on top.
+//!
+//! The other parameters of an endpoint are read through [`Query`], so those
+//! that don't parse get the same JSON error.
+
+use crate::api::ApiError;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// Ceilings of the count parameters, far above the limits of any endpoint, so
+/// arithmetic on them can't overflow.
+pub const MAX_HOURS: u64 = 24 * 365 * 10;
+pub const MAX_DAYS: u64 = 365 * 10;
+pub const MAX_BLOCKS: u64 = 10_000_000;
+pub const MAX_LIMIT: u64 = 1_000_000;
+
+/// The `hours`, `days`, `blocks` and `limit` query parameters of a request,
+/// each either absent or between 1 and its ceiling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub hours: Option<u64>,
+    pub days: Option<u64>,
+    pub blocks: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+// Kept as strings so a bad value gets our error rather than serde's
+#[derive(Deserialize)]
+struct CountsQuery {
+    hours: Option<String>,
+    days: Option<String>,
+    blocks: Option<String>,
+    limit: Option<String>,
+}
+
+fn parse(param: &'static str, value: Option<String>, max: u64) -> Result<Option<u64>, ApiError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<u64>() {
+        Ok(count) if (1..=max).contains(&count) => Ok(Some(count)),
+        // Zero, too large, negative or not a number at all
+        _ => Err(ApiError::invalid_param(
+            param,
+            format!("{param} must be a whole number between 1 and {max}, got {value:?}"),
+        )),
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Counts {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<CountsQuery>::from_request_parts(parts, state).await?;
+        Self::validate(query)
+    }
+}
+
+impl Counts {
+    fn validate(query: CountsQuery) -> Result<Self, ApiError> {
+        Ok(Self {
+            hours: parse("hours", query.hours, MAX_HOURS)?,
+            days: parse("days", query.days, MAX_DAYS)?,
+            blocks: parse("blocks", query.blocks, MAX_BLOCKS)?,
+            limit: parse("limit", query.limit, MAX_LIMIT)?,
+        })
+    }
+}
+
+/// [`axum::extract::Query`], answering parameters that don't parse with an
+/// [`ApiError`] rather than a plain-text 400.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for Query<T> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Query::try_from_uri(&parts.uri)
+            .map(|axum::extract::Query(query)| Self(query))
+            .map_err(|err| ApiError::new(err.status(), err.body_text()))
+    }
+}
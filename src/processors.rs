@@ -0,0 +1,539 @@
+//! Secondary indexers that run alongside the blob indexer.
+//!
+//! Each processor is registered to the same ExEx notification loop, has its own
+//! enable flag and writes only its own tables or columns, so new indexers don't
+//! have to grow [`crate::indexer::process_chain`].
+
+use crate::{
+    chains::chain_of,
+    db::{ExecutionContext, NewOpChannel, NewOpFrame, NewPendingBlobTransaction},
+    events::{Event, EventBus},
+    indexer::clamp_fee,
+    op_batch, Database,
+};
+use alloy_consensus::{BlockHeader, Transaction, TxReceipt};
+use alloy_eips::eip2718::Encodable2718;
+use alloy_primitives::{hex, TxHash};
+use eyre::WrapErr;
+use reth::transaction_pool::TransactionPool;
+use reth_execution_types::Chain;
+use reth_exex::{ExExNotification, Wal};
+use reth_node_api::FullNodeComponents;
+use reth_primitives::EthPrimitives;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::OpenOptions,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// A secondary indexer fed every notification after the blob indexer.
+pub trait Processor<Node: FullNodeComponents>: Send + Sync {
+    /// Name used in logs and errors.
+    fn name(&self) -> &'static str;
+
+    /// Index a newly committed chain.
+    fn process_chain(&self, node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()>;
+
+    /// Remove this processor's rows for a reverted chain.
+    fn revert_chain(&self, node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()>;
+}
+
+/// Processors enabled through their env flags.
+///
+/// - `BLOB_TRACK_MEMPOOL=true`: [`MempoolTracker`]
+/// - `BLOB_TRACK_EXECUTION=true`: [`ExecutionTracker`]
+/// - `BLOB_TRACK_FUNDING=true`: [`FundingTracker`]
+/// - `BLOB_DECODE_OP_BATCHES=true`: [`OpBatchDecoder`]
+/// - `BLOB_STORE_RAW_TXS=true`: [`RawTxStore`]
+/// - `BLOB_INGEST_LOG=stdout` or `BLOB_INGEST_LOG=<path>`: [`IngestLog`]
+/// - `BLOB_RECORD_NOTIFICATIONS=<dir>`: [`NotificationRecorder`]
+pub fn from_env<Node: FullNodeComponents>() -> eyre::Result<Vec<Box<dyn Processor<Node>>>> {
+    let mut processors: Vec<Box<dyn Processor<Node>>> = Vec::new();
+    if env_flag("BLOB_TRACK_MEMPOOL") {
+        processors.push(Box::new(MempoolTracker));
+    }
+    if env_flag("BLOB_TRACK_EXECUTION") {
+        processors.push(Box::new(ExecutionTracker));
+    }
+    if env_flag("BLOB_TRACK_FUNDING") {
+        processors.push(Box::new(FundingTracker));
+    }
+    if env_flag("BLOB_DECODE_OP_BATCHES") {
+        processors.push(Box::new(OpBatchDecoder));
+    }
+    if env_flag("BLOB_STORE_RAW_TXS") {
+        processors.push(Box::new(RawTxStore));
+    }
+    if let Ok(target) = std::env::var("BLOB_INGEST_LOG") {
+        processors.push(Box::new(IngestLog::open(&target)?));
+    }
+    if let Ok(dir) = std::env::var("BLOB_RECORD_NOTIFICATIONS") {
+        processors.push(Box::new(NotificationRecorder::open(&dir)?));
+    }
+    Ok(processors)
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| v == "true")
+}
+
+/// Snapshots blob txs still waiting in the mempool after each chain tip, i.e. the
+/// bids that lost out on inclusion in that block.
+///
+/// Writes `pending_blob_transactions`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MempoolTracker;
+
+impl<Node: FullNodeComponents> Processor<Node> for MempoolTracker {
+    fn name(&self) -> &'static str {
+        "mempool-tracker"
+    }
+
+    fn process_chain(&self, node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        let tip = chain.tip();
+        let block_number = tip.header().number();
+        let base_fee = tip.header().base_fee_per_gas().unwrap_or_default();
+        let included: HashSet<TxHash> = tip
+            .body()
+            .transactions()
+            .iter()
+            .map(|tx| *tx.tx_hash())
+            .collect();
+
+        for pooled in node.pool().pooled_transactions() {
+            let tx = &pooled.transaction;
+            let Some(blob_hashes) = tx.blob_versioned_hashes() else {
+                continue;
+            };
+            if included.contains(pooled.hash()) {
+                continue;
+            }
+
+            db.insert_pending_blob_transaction(&NewPendingBlobTransaction {
+                block_number,
+                tx_hash: &pooled.hash().to_string(),
+                sender: pooled.sender(),
+                nonce: pooled.nonce(),
+                blob_count: blob_hashes.len() as i64,
+                priority_fee: clamp_fee(tx.effective_tip_per_gas(base_fee).unwrap_or(0)) as i64,
+                max_fee_per_blob_gas: clamp_fee(tx.max_fee_per_blob_gas().unwrap_or(0)) as i64,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            db.delete_pending_blob_transactions(block.header().number())?;
+        }
+        Ok(())
+    }
+}
+
+/// Records execution layer activity outside blob txs (tx count and gas used) so
+/// blob space usage can be correlated with EL congestion.
+///
+/// Writes the `non_blob_tx_count` and `non_blob_gas_used` columns of `blocks`,
+/// and the `execution_gas_used`, `execution_fee`, `total_fee` and `status`
+/// columns of `blob_transactions`, which are removed together with the block
+/// on reverts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionTracker;
+
+impl<Node: FullNodeComponents> Processor<Node> for ExecutionTracker {
+    fn name(&self) -> &'static str {
+        "execution-tracker"
+    }
+
+    fn process_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        let outcome = chain.execution_outcome();
+
+        for block in chain.blocks_iter() {
+            let block_number = block.header().number();
+            let base_fee = block.header().base_fee_per_gas();
+            let transactions = block.body().transactions();
+            let Some(receipts) = block_number
+                .checked_sub(outcome.first_block())
+                .and_then(|index| outcome.receipts().get(index as usize))
+                .filter(|receipts| receipts.len() == transactions.len())
+            else {
+                continue;
+            };
+
+            let mut non_blob_tx_count = 0u64;
+            let mut non_blob_gas_used = 0u64;
+            let mut cumulative_gas_used = 0u64;
+            for (tx, receipt) in transactions.iter().zip(receipts) {
+                let gas_used = receipt
+                    .cumulative_gas_used()
+                    .saturating_sub(cumulative_gas_used);
+                cumulative_gas_used = receipt.cumulative_gas_used();
+
+                if tx.blob_versioned_hashes().is_none() {
+                    non_blob_tx_count += 1;
+                    non_blob_gas_used += gas_used;
+                } else {
+                    let execution_fee = gas_used as u128 * tx.effective_gas_price(base_fee);
+                    db.record_blob_transaction_execution(
+                        &tx.tx_hash().to_string(),
+                        gas_used,
+                        execution_fee,
+                    )?;
+                    db.record_blob_transaction_status(&tx.tx_hash().to_string(), receipt.status())?;
+                }
+            }
+
+            db.update_block_execution(
+                block_number,
+                &ExecutionContext {
+                    non_blob_tx_count,
+                    non_blob_gas_used,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, _db: &Database, _chain: &Chain) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+/// Follows ETH transfers out of attributed addresses (a known batcher, or a
+/// treasury from `BLOB_ENTITY_MAP`), so blobs posted by a freshly funded key
+/// keep being attributed to its entity after a batcher key rotation.
+///
+/// Writes `funding_transfers` and fills in `attributed_entity` of the
+/// recipient's blob transactions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FundingTracker;
+
+impl<Node: FullNodeComponents> Processor<Node> for FundingTracker {
+    fn name(&self) -> &'static str {
+        "funding-tracker"
+    }
+
+    fn process_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            let block_number = block.header().number();
+            for (funder, tx) in block.transactions_with_sender() {
+                let Some(recipient) = tx.to() else {
+                    continue;
+                };
+                // Plain ETH transfers only
+                if tx.value().is_zero() || !tx.input().is_empty() || recipient == *funder {
+                    continue;
+                }
+                db.record_funding_transfer(
+                    &tx.tx_hash().to_string(),
+                    block_number,
+                    funder,
+                    &recipient,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            db.delete_funding_transfers(block.header().number())?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the sidecars of included blob txs as OP Stack batcher data, keeping
+/// the frames of blobs that decode and the channels completed within one
+/// transaction, with their compressed and decompressed sizes.
+///
+/// Like blob payload sizes, this needs the sidecar from our own mempool, so
+/// blob txs the node never saw go undecoded. Writes `op_frames` and
+/// `op_channels`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpBatchDecoder;
+
+impl<Node: FullNodeComponents> Processor<Node> for OpBatchDecoder {
+    fn name(&self) -> &'static str {
+        "op-batch-decoder"
+    }
+
+    fn process_chain(&self, node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            let block_number = block.header().number();
+            for tx in block.body().transactions() {
+                if tx.blob_versioned_hashes().is_none() {
+                    continue;
+                }
+                let Some(sidecar) = node.pool().get_blob(*tx.tx_hash()).ok().flatten() else {
+                    continue;
+                };
+                let tx_hash = tx.tx_hash().to_string();
+
+                let mut frames = Vec::new();
+                for (blob_index, blob) in sidecar.blobs().iter().enumerate() {
+                    let Some(blob_frames) = op_batch::decode_blob(blob.as_slice())
+                        .and_then(|data| op_batch::parse_frames(&data))
+                    else {
+                        continue;
+                    };
+                    for frame in &blob_frames {
+                        db.insert_op_frame(&NewOpFrame {
+                            tx_hash: &tx_hash,
+                            blob_index: blob_index as u64,
+                            channel_id: &hex::encode_prefixed(frame.channel_id),
+                            frame_number: frame.frame_number,
+                            block_number,
+                            frame_size: frame.data.len() as u64,
+                            is_last: frame.is_last,
+                        })?;
+                    }
+                    frames.extend(blob_frames);
+                }
+
+                for channel in op_batch::complete_channels(&frames) {
+                    let decompressed = op_batch::decompressed_size(&channel.data);
+                    db.insert_op_channel(&NewOpChannel {
+                        channel_id: &hex::encode_prefixed(channel.id),
+                        tx_hash: &tx_hash,
+                        block_number,
+                        frame_count: channel.frame_count,
+                        compressed_size: channel.data.len() as u64,
+                        decompressed_size: decompressed.map(|(_, size)| size),
+                        compression: decompressed.map(|(compression, _)| compression.as_str()),
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            db.delete_op_batches(block.header().number())?;
+        }
+        Ok(())
+    }
+}
+
+/// Stores every included blob tx as its EIP-2718 envelope, as it appears in
+/// the block (sidecars aren't part of it), so fields this build doesn't index
+/// can be decoded and signatures checked later without the node.
+///
+/// Writes `tx_raw`, about as large as the blob txs' EL size, see
+/// `blob_transactions.el_size`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawTxStore;
+
+impl<Node: FullNodeComponents> Processor<Node> for RawTxStore {
+    fn name(&self) -> &'static str {
+        "raw-tx-store"
+    }
+
+    fn process_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            let block_number = block.header().number();
+            for tx in block.body().transactions() {
+                if tx.blob_versioned_hashes().is_none() {
+                    continue;
+                }
+                db.insert_raw_transaction(
+                    &tx.tx_hash().to_string(),
+                    block_number,
+                    &tx.encoded_2718(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        for block in chain.blocks_iter() {
+            db.delete_raw_transactions(block.header().number())?;
+        }
+        Ok(())
+    }
+}
+
+/// Announces indexed and reverted blocks on the event bus of a web server
+/// running in the same process, so its live streams and alerts don't wait for
+/// [`crate::events::watch_database`] to notice.
+///
+/// Writes no tables. Not enabled through an env flag, the binary registers it
+/// along with the web server.
+#[derive(Debug, Clone)]
+pub struct EventPublisher {
+    pub bus: EventBus,
+}
+
+impl<Node: FullNodeComponents> Processor<Node> for EventPublisher {
+    fn name(&self) -> &'static str {
+        "event-publisher"
+    }
+
+    fn process_chain(&self, _node: &Node, _db: &Database, chain: &Chain) -> eyre::Result<()> {
+        self.bus.publish(Event::BlockIndexed {
+            block_number: chain.tip().header().number(),
+        });
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, _db: &Database, chain: &Chain) -> eyre::Result<()> {
+        let range = chain.range();
+        self.bus.publish(Event::ReorgDetected {
+            first_block: *range.start(),
+            last_block: *range.end(),
+        });
+        Ok(())
+    }
+}
+
+/// Writes a JSON line per indexed block, with its blobs per chain and blob fee,
+/// to stdout or appended to a file, for log pipelines such as Loki or Elastic
+/// that would rather tail a log than poll the API. Reverted blocks get a line
+/// too, so consumers can drop what they ingested for them.
+///
+/// Writes no tables. Lines of a chain whose processing is retried may repeat.
+pub struct IngestLog {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum IngestEvent {
+    BlockIndexed {
+        block_number: u64,
+        block_timestamp: u64,
+        tx_count: u64,
+        total_blobs: u64,
+        blob_target: u64,
+        blob_max: u64,
+        blob_gas_price: u128,
+        chains: BTreeMap<String, ChainIngest>,
+    },
+    BlockReverted {
+        block_number: u64,
+    },
+}
+
+#[derive(Default, Serialize)]
+struct ChainIngest {
+    tx_count: u64,
+    blobs: u64,
+    blob_size: u64,
+}
+
+impl IngestLog {
+    /// Log to stdout for `stdout`, else append to the file at `target`.
+    pub fn open(target: &str) -> eyre::Result<Self> {
+        let out: Box<dyn Write + Send> = match target {
+            "stdout" => Box::new(std::io::stdout()),
+            path => Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .wrap_err_with(|| format!("failed to open BLOB_INGEST_LOG={path}"))?,
+            ),
+        };
+        Ok(Self {
+            out: Mutex::new(out),
+        })
+    }
+
+    fn write(&self, events: &[IngestEvent]) -> eyre::Result<()> {
+        let mut out = self.out.lock().expect("failed to acquire ingest log lock");
+        for event in events {
+            serde_json::to_writer(&mut *out, event)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl<Node: FullNodeComponents> Processor<Node> for IngestLog {
+    fn name(&self) -> &'static str {
+        "ingest-log"
+    }
+
+    fn process_chain(&self, _node: &Node, db: &Database, chain: &Chain) -> eyre::Result<()> {
+        let mut events = Vec::new();
+        for block in chain.blocks_iter() {
+            // Read back as indexed, with each transaction's attribution
+            let Some(block) = db.get_block(block.header().number())? else {
+                continue;
+            };
+            let mut chains: BTreeMap<String, ChainIngest> = BTreeMap::new();
+            for tx in &block.transactions {
+                let totals = chains
+                    .entry(chain_of(&tx.sender, tx.attributed_entity.as_deref()))
+                    .or_default();
+                totals.tx_count += 1;
+                totals.blobs += tx.blob_count;
+                totals.blob_size += tx.blob_size;
+            }
+            events.push(IngestEvent::BlockIndexed {
+                block_number: block.block_number,
+                block_timestamp: block.block_timestamp,
+                tx_count: block.tx_count,
+                total_blobs: block.total_blobs,
+                blob_target: block.blob_target,
+                blob_max: block.blob_max,
+                blob_gas_price: block.gas_price,
+                chains,
+            });
+        }
+        self.write(&events)
+    }
+
+    fn revert_chain(&self, _node: &Node, _db: &Database, chain: &Chain) -> eyre::Result<()> {
+        let events: Vec<IngestEvent> = chain
+            .blocks_iter()
+            .map(|block| IngestEvent::BlockReverted {
+                block_number: block.header().number(),
+            })
+            .collect();
+        self.write(&events)
+    }
+}
+
+/// Records every notification to a WAL directory in reth's ExEx WAL format,
+/// for `blob-exex replay` to index again later without a node. Unlike the
+/// node's own WAL, nothing is ever pruned from it.
+///
+/// Writes no tables. A reorg is recorded as its revert followed by its commit.
+pub struct NotificationRecorder {
+    wal: Wal<EthPrimitives>,
+}
+
+impl NotificationRecorder {
+    /// Open the WAL at `dir`, creating it if needed, and append to it.
+    pub fn open(dir: &str) -> eyre::Result<Self> {
+        let wal = Wal::new(dir)
+            .wrap_err_with(|| format!("failed to open BLOB_RECORD_NOTIFICATIONS={dir}"))?;
+        Ok(Self { wal })
+    }
+}
+
+impl<Node: FullNodeComponents> Processor<Node> for NotificationRecorder {
+    fn name(&self) -> &'static str {
+        "notification-recorder"
+    }
+
+    fn process_chain(&self, _node: &Node, _db: &Database, chain: &Chain) -> eyre::Result<()> {
+        self.wal.commit(&ExExNotification::ChainCommitted {
+            new: Arc::new(chain.clone()),
+        })?;
+        Ok(())
+    }
+
+    fn revert_chain(&self, _node: &Node, _db: &Database, chain: &Chain) -> eyre::Result<()> {
+        self.wal.commit(&ExExNotification::ChainReverted {
+            old: Arc::new(chain.clone()),
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! Ad-hoc read-only SQL over `/api/query`, authenticated with a bearer token.
+//!
+//! Statements run on their own read-only connection whose authorizer only
+//! allows reads outside [`PRIVATE_TABLES`](crate::db::PRIVATE_TABLES), see
+//! [`Database::query_read_only`], so power users can answer one-off questions
+//! without exporting the whole database.
+
+use crate::{
+    admin::require_token,
+    api::{ApiError, Limits},
+    Database,
+};
+use alloy_primitives::hex;
+use axum::{extract::State, middleware, routing::post, Json, Router};
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+/// Statements running longer than this are interrupted.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct QueryRequest {
+    sql: String,        // A single SELECT statement
+    limit: Option<u64>, // Rows returned, clamped to the row limit
+}
+
+#[derive(Serialize)]
+struct QueryResult {
+    columns: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>, // Blobs as 0x-prefixed hex
+    truncated: bool,                   // More rows matched than were returned
+}
+
+fn json_value(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(value) => value.into(),
+        Value::Real(value) => value.into(),
+        Value::Text(value) => value.into(),
+        Value::Blob(value) => hex::encode_prefixed(value).into(),
+    }
+}
+
+async fn run_query(
+    State((db, limits)): State<(Database, Limits)>,
+    Json(request): Json<QueryRequest>,
+) -> Result<Json<QueryResult>, ApiError> {
+    let max_rows = request
+        .limit
+        .unwrap_or(limits.max_rows)
+        .min(limits.max_rows) as usize;
+    // Queries may run up to the timeout, off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        db.query_read_only(&request.sql, max_rows, QUERY_TIMEOUT)
+    })
+    .await
+    .map_err(ApiError::internal)??;
+
+    Ok(Json(QueryResult {
+        columns: result.columns,
+        rows: result
+            .rows
+            .into_iter()
+            .map(|row| row.into_iter().map(json_value).collect())
+            .collect(),
+        truncated: result.truncated,
+    }))
+}
+
+pub fn router(db: Database, token: &str, limits: Limits) -> Router {
+    Router::new()
+        .route("/api/query", post(run_query))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_token,
+        ))
+        .with_state((db, limits))
+}
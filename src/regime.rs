@@ -0,0 +1,87 @@
+use crate::BlobSchedule;
+
+/// Demand regimes, lowest first. Blocks are classified into them by their
+/// target utilization, see [`RegimeThresholds`].
+pub const REGIMES: [&str; 5] = ["abundant", "normal", "pressured", "congested", "saturated"];
+
+/// Target utilization, in percent, from which each regime after `abundant`
+/// applies.
+///
+/// The ExEx seeds these from `BLOB_REGIME_THRESHOLDS`, or from its network's
+/// blob schedule, and the web server reads them back from the database so
+/// every response and legend uses the same cutoffs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegimeThresholds {
+    pub normal: f64,
+    pub pressured: f64,
+    pub congested: f64,
+    pub saturated: f64,
+}
+
+impl RegimeThresholds {
+    /// Default thresholds for a network: congested above its target, as the
+    /// blob base fee rises from there, and saturated at the max of its latest
+    /// schedule entry.
+    pub fn for_schedule(schedule: &BlobSchedule) -> Self {
+        let latest = schedule.entries().last().expect("schedule is never empty");
+        Self {
+            normal: 50.0,
+            pressured: 90.0,
+            congested: 100.0,
+            saturated: (latest.max as f64 / latest.target.max(1) as f64 * 100.0).max(100.0),
+        }
+    }
+
+    /// Parse thresholds from four ascending percentages separated by commas,
+    /// e.g. the value of `BLOB_REGIME_THRESHOLDS`.
+    pub fn parse(value: &str) -> eyre::Result<Self> {
+        let fields: Vec<f64> = value
+            .split(',')
+            .map(|field| field.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|err| eyre::eyre!("invalid regime thresholds {value:?}: {err}"))?;
+
+        match fields[..] {
+            [normal, pressured, congested, saturated]
+                if fields.iter().all(|pct| pct.is_finite() && *pct >= 0.0)
+                    && fields.is_sorted() =>
+            {
+                Ok(Self {
+                    normal,
+                    pressured,
+                    congested,
+                    saturated,
+                })
+            }
+            _ => eyre::bail!(
+                "invalid regime thresholds {value:?}: expected normal,pressured,congested,saturated percentages in ascending order"
+            ),
+        }
+    }
+
+    /// Thresholds configured through the `BLOB_REGIME_THRESHOLDS` env var, if set.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        std::env::var("BLOB_REGIME_THRESHOLDS")
+            .ok()
+            .map(|value| Self::parse(&value))
+            .transpose()
+    }
+
+    /// Lower bound of each of [`REGIMES`], `abundant`'s being 0.
+    pub fn lower_bounds(&self) -> [f64; 5] {
+        [
+            0.0,
+            self.normal,
+            self.pressured,
+            self.congested,
+            self.saturated,
+        ]
+    }
+
+    /// Regime of a block, or of an average, at `utilization` percent of target.
+    pub fn regime(&self, utilization: f64) -> &'static str {
+        let bounds = self.lower_bounds();
+        let index = bounds.iter().rposition(|&from| utilization >= from);
+        REGIMES[index.unwrap_or(0)]
+    }
+}
@@ -0,0 +1,134 @@
+//! Replay of recorded ExEx notifications, without a live node.
+//!
+//! Notifications are read from a WAL directory in reth's ExEx WAL format:
+//! either the node's own (`<datadir>/exex/wal`), which only holds those since
+//! the last finalized block, or one written by
+//! [`NotificationRecorder`](crate::processors::NotificationRecorder), which
+//! keeps every notification. Copy the directory rather than replaying from one
+//! a node is still writing.
+//!
+//! Each notification is applied as the live ExEx would: reverted blocks are
+//! removed, committed blocks (re-)indexed, oldest notification first. Blob
+//! sizes and execution stats already stored for a block are carried over,
+//! since sidecars aren't recorded and secondary processors don't run. Replaying the same WAL into
+//! a database thus rebuilds the same blocks and blob transactions, which makes
+//! it suitable to rebuild a corrupted database or try schema changes against
+//! recorded production traffic.
+
+use crate::{backfill, indexer, telemetry::BlockLog, BlobSchedule, Database};
+use alloy_consensus::BlockHeader;
+use alloy_primitives::TxHash;
+use reth::chainspec::{ChainSpec, MAINNET};
+use reth_exex::{ExExNotification, Wal};
+use reth_primitives::EthPrimitives;
+use std::{path::PathBuf, sync::Arc, time::Instant};
+use tracing::info;
+
+/// Notifications between progress log lines.
+const LOG_INTERVAL: u64 = 1000;
+
+pub const USAGE: &str =
+    "usage: blob-exex replay --wal <dir> [--chain mainnet|sepolia|holesky|hoodi]";
+
+/// What to replay, from `blob-exex replay` arguments.
+#[derive(Debug)]
+pub struct Options {
+    /// The WAL directory.
+    pub wal: PathBuf,
+    /// Chain the notifications are from, for its blob schedule.
+    pub chain: Arc<ChainSpec>,
+}
+
+impl Options {
+    pub fn from_args(args: &[String]) -> eyre::Result<Self> {
+        let mut wal = None;
+        let mut chain = MAINNET.clone();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| eyre::eyre!("{arg} requires a value\n{USAGE}"))?;
+            match arg.as_str() {
+                "--wal" => wal = Some(PathBuf::from(value)),
+                "--chain" => chain = backfill::chain_spec(value, USAGE)?,
+                _ => eyre::bail!("unknown argument: {arg}\n{USAGE}"),
+            }
+        }
+
+        let options = Self {
+            wal: wal.ok_or_else(|| eyre::eyre!("--wal is required\n{USAGE}"))?,
+            chain,
+        };
+        eyre::ensure!(
+            options.wal.is_dir(),
+            "--wal {}: directory does not exist",
+            options.wal.display()
+        );
+        Ok(options)
+    }
+}
+
+/// Apply every notification of the WAL at `options.wal`, each in its own
+/// database transaction. Indexed blocks are logged through `log`.
+///
+/// The chain's blob schedule and regime thresholds are stored first, see
+/// [`indexer::seed_chain_params`].
+pub async fn run(db: Database, options: Options, log: BlockLog) -> eyre::Result<()> {
+    let schedule = indexer::seed_chain_params(&db, &options.chain)?;
+    // Reading and SQLite writes block, so the lease heartbeat runs meanwhile
+    tokio::task::spawn_blocking(move || {
+        let wal = Wal::<EthPrimitives>::new(&options.wal)?;
+        info!(wal = %options.wal.display(), "Replaying notifications");
+
+        let started = Instant::now();
+        let mut replayed = 0u64;
+        for notification in wal.iter_notifications()? {
+            let notification = notification?;
+            db.transaction(|| apply(&db, &schedule, &notification, &log))?;
+            replayed += 1;
+            if replayed % LOG_INTERVAL == 0 {
+                info!(
+                    replayed,
+                    committed = ?notification.committed_chain().map(|chain| chain.range()),
+                    "Replayed notifications"
+                );
+            }
+        }
+        info!(replayed, elapsed = ?started.elapsed(), "Replay complete");
+        Ok(())
+    })
+    .await?
+}
+
+/// Apply one notification: remove its reverted blocks, then re-index its
+/// committed ones, keeping the blob sizes and execution stats stored for them.
+fn apply(
+    db: &Database,
+    schedule: &BlobSchedule,
+    notification: &ExExNotification<EthPrimitives>,
+    log: &BlockLog,
+) -> eyre::Result<()> {
+    if let Some(reverted_chain) = notification.reverted_chain() {
+        indexer::revert_chain(db, &reverted_chain)?;
+    }
+    if let Some(committed_chain) = notification.committed_chain() {
+        for block in committed_chain.blocks_iter() {
+            let block_number = block.header().number();
+            let stored_sizes = db.get_block_blob_sizes(block_number)?;
+            let execution = db.get_block(block_number)?.and_then(|b| b.execution);
+            db.delete_block(block_number)?;
+            indexer::process_block(
+                db,
+                schedule,
+                block,
+                |tx_hash: TxHash| stored_sizes.get(&tx_hash.to_string()).cloned(),
+                log,
+            )?;
+            if let Some(execution) = execution {
+                db.update_block_execution(block_number, &execution)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use alloy_eips::eip7840::BlobParams;
+
+/// Blob parameters that take effect from a given block timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobScheduleEntry {
+    pub activation_timestamp: u64,
+    pub target: u64,
+    pub max: u64,
+    pub base_fee_update_fraction: u64,
+}
+
+impl BlobScheduleEntry {
+    /// Create an entry from EIP-7840 blob parameters.
+    pub fn new(activation_timestamp: u64, params: &BlobParams) -> Self {
+        Self {
+            activation_timestamp,
+            target: params.target_blob_count,
+            max: params.max_blob_count,
+            base_fee_update_fraction: params.update_fraction as u64,
+        }
+    }
+
+    /// EIP-7840 blob parameters for this entry, used to derive the blob base fee.
+    pub fn blob_params(&self) -> BlobParams {
+        BlobParams {
+            target_blob_count: self.target,
+            max_blob_count: self.max,
+            update_fraction: self.base_fee_update_fraction as u128,
+            ..BlobParams::cancun()
+        }
+    }
+}
+
+/// History of blob target/max changes (forks and BPO forks), ordered by activation.
+///
+/// The ExEx seeds this from the node's chain spec; the web server reads it back
+/// from the database so both agree on which parameters applied to each block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobSchedule {
+    entries: Vec<BlobScheduleEntry>,
+}
+
+impl BlobSchedule {
+    /// Create a schedule from entries in any order. Returns `None` if empty.
+    pub fn new(mut entries: Vec<BlobScheduleEntry>) -> Option<Self> {
+        if entries.is_empty() {
+            return None;
+        }
+        entries.sort_by_key(|entry| entry.activation_timestamp);
+        entries.dedup_by_key(|entry| entry.activation_timestamp);
+        Some(Self { entries })
+    }
+
+    /// Ethereum mainnet schedule: Cancun, Prague, Osaka, BPO1 and BPO2.
+    pub fn mainnet() -> Self {
+        Self {
+            entries: vec![
+                BlobScheduleEntry::new(1710338135, &BlobParams::cancun()),
+                BlobScheduleEntry::new(1746612311, &BlobParams::prague()),
+                BlobScheduleEntry::new(1764798551, &BlobParams::osaka()),
+                BlobScheduleEntry::new(1765290071, &BlobParams::bpo1()),
+                BlobScheduleEntry::new(1767747671, &BlobParams::bpo2()),
+            ],
+        }
+    }
+
+    /// Parse a schedule from `timestamp:target:max:update_fraction` entries
+    /// separated by commas, e.g. the value of `BLOB_SCHEDULE`.
+    pub fn parse(value: &str) -> eyre::Result<Self> {
+        let entries = value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let fields: Vec<u64> = entry
+                    .split(':')
+                    .map(|field| field.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| eyre::eyre!("invalid blob schedule entry {entry:?}: {err}"))?;
+
+                match fields[..] {
+                    [activation_timestamp, target, max, base_fee_update_fraction] => {
+                        Ok(BlobScheduleEntry {
+                            activation_timestamp,
+                            target,
+                            max,
+                            base_fee_update_fraction,
+                        })
+                    }
+                    _ => eyre::bail!(
+                        "invalid blob schedule entry {entry:?}: expected timestamp:target:max:update_fraction"
+                    ),
+                }
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        Self::new(entries).ok_or_else(|| eyre::eyre!("blob schedule is empty"))
+    }
+
+    /// Schedule configured through the `BLOB_SCHEDULE` env var, if set.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        std::env::var("BLOB_SCHEDULE")
+            .ok()
+            .map(|value| Self::parse(&value))
+            .transpose()
+    }
+
+    /// All entries, ordered by activation timestamp.
+    pub fn entries(&self) -> &[BlobScheduleEntry] {
+        &self.entries
+    }
+
+    /// Parameters in effect for a block with the given timestamp.
+    ///
+    /// Blocks older than the first entry use the first entry.
+    pub fn params_at(&self, timestamp: u64) -> &BlobScheduleEntry {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| timestamp >= entry.activation_timestamp)
+            .unwrap_or(&self.entries[0])
+    }
+}
+
+impl Default for BlobSchedule {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
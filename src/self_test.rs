@@ -0,0 +1,80 @@
+//! `--self-test`: smoke checks of a deployment, run instead of starting.
+//!
+//! Each check prints a line, and the process exits non-zero if any failed, so
+//! a wrong volume, a stale schema or missing dashboard assets are caught before
+//! the service starts and quietly serves empty data.
+
+use crate::{chains, config, server, Database};
+use eyre::WrapErr;
+use std::path::Path;
+
+/// Run every check on the database at `db_path`, and on the dashboard assets
+/// in `static_dir` when the process serves them.
+pub fn run(db_path: &str, static_dir: Option<&Path>) -> eyre::Result<()> {
+    let mut failed = Vec::new();
+    let mut check = |name: &'static str, result: eyre::Result<String>| match result {
+        Ok(detail) => println!("ok    {name}: {detail}"),
+        Err(err) => {
+            println!("FAIL  {name}: {err:#}");
+            failed.push(name);
+        }
+    };
+
+    match Database::new(db_path).wrap_err_with(|| format!("failed to open BLOB_DB_PATH={db_path}"))
+    {
+        Ok(db) => {
+            check("database", Ok(format!("opened {db_path}")));
+            check(
+                "round trip",
+                db.round_trip()
+                    .map(|()| "wrote a row and read it back".to_string())
+                    .wrap_err("the database doesn't take writes, check the file and directory permissions and free space"),
+            );
+            check("indexes", schema(&db));
+        }
+        Err(err) => check("database", Err(err)),
+    }
+    check("chain registry", registry());
+    if let Some(static_dir) = static_dir {
+        check("static assets", assets(static_dir));
+    }
+
+    eyre::ensure!(failed.is_empty(), "self-test failed: {}", failed.join(", "));
+    println!("self-test passed");
+    Ok(())
+}
+
+fn schema(db: &Database) -> eyre::Result<String> {
+    let problems = db.schema_problems()?;
+    eyre::ensure!(
+        problems.is_empty(),
+        "schema doesn't match this build (a running bulk backfill drops indexes until it ends):\n  {}",
+        problems.join("\n  ")
+    );
+    Ok("tables and indexes match this build".to_string())
+}
+
+fn registry() -> eyre::Result<String> {
+    let problems = chains::registry_problems();
+    eyre::ensure!(
+        problems.is_empty(),
+        "invalid dated addresses:\n  {}",
+        problems.join("\n  ")
+    );
+    let entities = config::entity_addresses()?;
+    Ok(format!(
+        "{} dated addresses, {} entity addresses",
+        chains::DATED_ADDRESSES.len(),
+        entities.len()
+    ))
+}
+
+fn assets(static_dir: &Path) -> eyre::Result<String> {
+    let problems = server::asset_problems(static_dir);
+    eyre::ensure!(
+        problems.is_empty(),
+        "the dashboard won't load, check BLOB_STATIC_DIR:\n  {}",
+        problems.join("\n  ")
+    );
+    Ok(format!("found in {}", static_dir.display()))
+}
@@ -0,0 +1,84 @@
+//! Price sensitivity of blob posters.
+//!
+//! Correlates how many blobs a chain posts with the block-level blob base fee,
+//! both in the same interval and some time after the fee moved. A chain that
+//! holds back its batches during fee spikes shows a negative correlation, one
+//! that posts on a fixed cadence shows none.
+
+use serde::{Deserialize, Serialize};
+
+/// Width of the intervals fees and postings are compared in.
+pub const BUCKET_SECS: u64 = 600;
+
+/// Delays after a fee change at which postings are compared again.
+pub const LAGS_MINUTES: [u64; 3] = [10, 30, 60];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceSensitivity {
+    pub correlation: Option<f64>, // Fee vs. blobs posted in the same interval
+    pub lagged: Vec<LaggedCorrelation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaggedCorrelation {
+    pub lag_minutes: u64,
+    pub correlation: Option<f64>, // Fee vs. blobs posted `lag_minutes` later
+}
+
+/// Price sensitivity of one chain.
+///
+/// `fees` and `blobs` are aligned per [`BUCKET_SECS`] interval: the average
+/// blob base fee of the blocks in it (`None` if there were none) and the blobs
+/// the chain posted in it.
+pub fn price_sensitivity(fees: &[Option<f64>], blobs: &[f64]) -> PriceSensitivity {
+    PriceSensitivity {
+        correlation: lagged_correlation(fees, blobs, 0),
+        lagged: LAGS_MINUTES
+            .iter()
+            .map(|&lag_minutes| LaggedCorrelation {
+                lag_minutes,
+                correlation: lagged_correlation(
+                    fees,
+                    blobs,
+                    (lag_minutes * 60 / BUCKET_SECS) as usize,
+                ),
+            })
+            .collect(),
+    }
+}
+
+/// Correlation between the fee of each interval and the blobs posted `lag`
+/// intervals later.
+fn lagged_correlation(fees: &[Option<f64>], blobs: &[f64], lag: usize) -> Option<f64> {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = fees
+        .iter()
+        .zip(blobs.iter().skip(lag))
+        .filter_map(|(fee, blobs)| Some(((*fee)?, *blobs)))
+        .unzip();
+    pearson(&xs, &ys)
+}
+
+/// Pearson correlation coefficient, or `None` with fewer than three pairs or
+/// when either side is constant.
+pub fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len().min(ys.len());
+    if n < 3 {
+        return None;
+    }
+    let (xs, ys) = (&xs[..n], &ys[..n]);
+
+    let mean_x = xs.iter().sum::<f64>() / n as f64;
+    let mean_y = ys.iter().sum::<f64>() / n as f64;
+    let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        covariance += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(covariance / (var_x * var_y).sqrt())
+}
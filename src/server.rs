@@ -0,0 +1,353 @@
+//! The web server: dashboard, JSON API, Grafana datasource and, with tokens
+//! configured, ad-hoc queries, alert rules and the admin routes.
+//!
+//! The public profile (see [`Profile::Public`]) only serves the dashboard and
+//! the JSON API, which only reads.
+//!
+//! It serves HTTPS itself with a certificate configured, and behind reverse
+//! proxies listed in `BLOB_TRUSTED_PROXIES` takes the client from their
+//! `X-Forwarded-For` and `X-Forwarded-Proto` headers, see [`Client`].
+
+use crate::{
+    admin, alerts, api, chain_metrics,
+    config::{Profile, WebConfig},
+    events::{self, EventBus},
+    grafana, maintenance, query,
+    telemetry::{self, BlockLog},
+    tls::{self, TlsListener},
+    Database,
+};
+use axum::{
+    extract::{connect_info::Connected, ConnectInfo, MatchedPath, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    serve::IncomingStream,
+    Router,
+};
+use eyre::WrapErr;
+use futures::future::BoxFuture;
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+use tokio::net::TcpListener;
+use tower_http::{
+    compression::{
+        predicate::{NotForContentType, Predicate},
+        CompressionLayer, DefaultPredicate,
+    },
+    cors::{AllowOrigin, CorsLayer},
+    services::ServeDir,
+    trace::TraceLayer,
+};
+
+// The dashboard page, built into the binary. Its assets are served from
+// the static dir.
+const INDEX_HTML: &str = include_str!("../web/dist/index.html");
+
+async fn index() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/html")], Html(INDEX_HTML))
+}
+
+/// Why the dashboard wouldn't load: a page built in without content, or files
+/// it references missing from `static_dir`.
+pub fn asset_problems(static_dir: &Path) -> Vec<String> {
+    if INDEX_HTML.trim().is_empty() {
+        return vec!["the dashboard page is empty, build web/ before this binary".to_string()];
+    }
+    // Attribute values are the odd pieces between quotes
+    INDEX_HTML
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .filter(|value| value.starts_with("/assets/") || value.starts_with("/icons/"))
+        .map(|value| value.split(['?', '#']).next().unwrap_or(value))
+        .filter(|path| !static_dir.join(&path[1..]).is_file())
+        .map(|path| format!("{path} is missing from {}", static_dir.display()))
+        .collect()
+}
+
+/// Build the full router, with live streams following `events`.
+///
+/// `log` is the indexer's when it runs in the same process, letting admins
+/// change its verbosity.
+pub fn app(db: Database, events: EventBus, log: Option<BlockLog>, config: &WebConfig) -> Router {
+    let api = api::router(db.clone(), config.limits, events.clone());
+    let mut app = Router::new().route("/", get(index)).merge(api);
+
+    if config.profile == Profile::Internal {
+        app = app.merge(grafana::router(db.clone()));
+
+        if let Some(metrics) = telemetry::metrics() {
+            app = app.route(
+                "/metrics",
+                get(render_metrics).with_state((metrics.clone(), db.clone())),
+            );
+        }
+        if let Some(token) = &config.query_token {
+            app = app.merge(query::router(db.clone(), token, config.limits));
+        }
+        // Admin routes are only served when a token is configured, and alert
+        // rules need keys issued through them
+        if let Some(token) = &config.admin_token {
+            app = app
+                .merge(alerts::router(db.clone(), events))
+                .merge(admin::router(db, token, log));
+        }
+    }
+
+    let app = app
+        .nest_service("/assets", ServeDir::new(config.static_dir.join("assets")))
+        .nest_service("/icons", ServeDir::new(config.static_dir.join("icons")))
+        .layer(middleware::from_fn(record_request))
+        // One span per request, named after the route rather than the full path
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &Request| {
+                let route = request
+                    .extensions()
+                    .get::<MatchedPath>()
+                    .map_or(request.uri().path(), MatchedPath::as_str);
+                let client = request
+                    .extensions()
+                    .get::<Client>()
+                    .map(|client| tracing::field::display(client.ip));
+                tracing::info_span!("request", method = %request.method(), route, client)
+            }),
+        )
+        .layer(middleware::from_fn_with_state(
+            Arc::<[IpAddr]>::from(config.trusted_proxies.as_slice()),
+            resolve_client,
+        ));
+
+    let app = match config.profile {
+        Profile::Internal => app.layer(CorsLayer::permissive()),
+        Profile::Public => app
+            .layer(middleware::map_response_with_state(
+                config.cache_max_age,
+                cache_control,
+            ))
+            .layer(
+                CorsLayer::new()
+                    .allow_origin(AllowOrigin::list(config.cors_origins.clone()))
+                    .allow_methods([Method::GET]),
+            ),
+    };
+
+    // Streamed NDJSON isn't compressed so lines aren't held back in the encoder
+    app.layer(CompressionLayer::new().compress_when(
+        DefaultPredicate::new().and(NotForContentType::const_new("application/x-ndjson")),
+    ))
+}
+
+/// The connection a request came in on, as [`ConnectInfo`].
+#[derive(Debug, Clone, Copy)]
+pub struct Peer {
+    pub addr: SocketAddr,
+    /// Whether the connection is TLS.
+    pub tls: bool,
+}
+
+impl Connected<IncomingStream<'_, TcpListener>> for Peer {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self {
+            addr: *stream.remote_addr(),
+            tls: false,
+        }
+    }
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for Peer {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        Self {
+            addr: *stream.remote_addr(),
+            tls: true,
+        }
+    }
+}
+
+/// The client a request is from, through trusted reverse proxies, as a
+/// request extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Client {
+    pub ip: IpAddr,
+    /// Whether the client connected over HTTPS, to this server or the proxy.
+    pub https: bool,
+}
+
+impl Client {
+    /// Resolve the client of a connection from `peer`, trusting the forwarding
+    /// headers only when `peer` is one of the `trusted_proxies`.
+    ///
+    /// Each proxy appends the address it got the request from to
+    /// `X-Forwarded-For`, so the client is the last address not a trusted
+    /// proxy, earlier ones being whatever the client claimed.
+    pub fn resolve(peer: Peer, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> Self {
+        let peer_ip = peer.addr.ip().to_canonical();
+        let direct = Self {
+            ip: peer_ip,
+            https: peer.tls,
+        };
+        if !trusted_proxies.contains(&peer_ip) {
+            return direct;
+        }
+
+        let forwarded: Option<Vec<IpAddr>> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+            .map(|ip| ip.trim().parse().ok().map(|ip: IpAddr| ip.to_canonical()))
+            .collect();
+        // A malformed chain can't be walked, so the proxy stands in for the client
+        let Some(forwarded) = forwarded else {
+            return direct;
+        };
+        let ip = forwarded
+            .iter()
+            .rev()
+            .find(|ip| !trusted_proxies.contains(ip))
+            .or(forwarded.first())
+            .copied()
+            .unwrap_or(peer_ip);
+
+        // The first proxy saw the client's scheme
+        let https = headers
+            .get("x-forwarded-proto")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map_or(peer.tls, |proto| proto.trim().eq_ignore_ascii_case("https"));
+        Self { ip, https }
+    }
+}
+
+/// Attach the [`Client`] of each request for logging and handlers.
+async fn resolve_client(
+    State(trusted_proxies): State<Arc<[IpAddr]>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<Peer>>()
+        .map(|ConnectInfo(peer)| *peer);
+    // Routers served without connect info, as in tests, have no client
+    if let Some(peer) = peer {
+        let client = Client::resolve(peer, request.headers(), &trusted_proxies);
+        request.extensions_mut().insert(client);
+    }
+    next.run(request).await
+}
+
+/// Count requests and time responses per route, method and status. Streams
+/// are timed until their headers are sent.
+async fn record_request(request: Request, next: Next) -> Response {
+    // Unmatched paths share a label, so scanners can't grow the label set
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(
+        "blob_web_requests_total",
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status
+    )
+    .increment(1);
+    metrics::histogram!(
+        "blob_web_request_duration_seconds",
+        "method" => method,
+        "route" => route
+    )
+    .record(started.elapsed());
+    response
+}
+
+async fn render_metrics(State((metrics, db)): State<(PrometheusHandle, Database)>) -> String {
+    // The writer's checks run in another process, so their outcome is read back
+    match db.get_sender_check() {
+        Ok(Some(check)) => maintenance::set_drift_gauges(&check),
+        Ok(None) => {}
+        Err(err) => tracing::warn!(%err, "Failed to read sender check"),
+    }
+    if let Err(err) = chain_metrics::refresh(&db) {
+        tracing::warn!(%err, "Failed to export chain totals");
+    }
+    metrics.render()
+}
+
+/// Let browsers and CDNs cache successful responses for `max_age` seconds.
+/// Streams aren't cached, nor are responses that set their own policy.
+async fn cache_control(State(max_age): State<u64>, mut response: Response) -> Response {
+    let streamed = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value == "application/x-ndjson");
+    if response.status().is_success()
+        && !streamed
+        && !response.headers().contains_key(header::CACHE_CONTROL)
+    {
+        let value = format!("public, max-age={max_age}");
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+    }
+    response
+}
+
+/// Bind `config.addr` and return a future serving [`app`] on it, along with
+/// [`events::watch_database`] publishing to `events` and the alert rule
+/// evaluation in the internal profile.
+///
+/// Binding happens before returning so a taken port fails startup rather than
+/// the spawned server.
+pub async fn bind(
+    db: Database,
+    events: EventBus,
+    log: Option<BlockLog>,
+    config: &WebConfig,
+) -> eyre::Result<impl Future<Output = eyre::Result<()>>> {
+    let listener = TcpListener::bind(config.addr)
+        .await
+        .wrap_err_with(|| format!("failed to bind BLOB_WEB_ADDR={}", config.addr))?;
+    let app =
+        app(db.clone(), events.clone(), log, config).into_make_service_with_connect_info::<Peer>();
+
+    let served: BoxFuture<'static, std::io::Result<()>> = match &config.tls {
+        Some(tls) => {
+            let listener = TlsListener::new(listener, tls::acceptor(tls)?);
+            Box::pin(async move { axum::serve(listener, app).await })
+        }
+        None => Box::pin(async move { axum::serve(listener, app).await }),
+    };
+    let scheme = if config.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    println!("ExBlob running at {scheme}://{}", config.addr);
+
+    // Alert rules can't be managed on public servers, which leave evaluating
+    // them to an internal one sharing the database
+    let evaluate_alerts = config.profile == Profile::Internal;
+
+    Ok(async move {
+        tokio::select! {
+            served = served => served?,
+            () = events::watch_database(db.clone(), events.clone()) => {}
+            () = alerts::run(db, events), if evaluate_alerts => {}
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,61 @@
+//! Periodic database snapshots with retention.
+//!
+//! Snapshots go through the SQLite backup API (see [`Database::snapshot`]).
+//! Copying the database file while the ExEx writes misses whatever is still in
+//! the `-wal` file and yields corrupt backups.
+
+use crate::Database;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// File name prefix of scheduled snapshots, followed by a UNIX timestamp.
+const FILE_PREFIX: &str = "blob_stats-";
+
+/// Write a snapshot named after the current time into `dir`, then delete all
+/// but the newest `keep` scheduled snapshots there. Returns the new snapshot.
+pub fn rotate(db: &Database, dir: &Path, keep: usize) -> eyre::Result<PathBuf> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = dir.join(format!("{FILE_PREFIX}{now}.db"));
+    db.snapshot(&path)?;
+
+    let mut snapshots = list(dir)?;
+    let expired = snapshots.len().saturating_sub(keep);
+    for (_, old) in snapshots.drain(..expired) {
+        std::fs::remove_file(&old)?;
+    }
+    Ok(path)
+}
+
+/// Take a snapshot every `interval` forever, keeping the newest `keep`.
+pub fn run_schedule(
+    db: &Database,
+    dir: &Path,
+    interval: Duration,
+    keep: usize,
+) -> eyre::Result<()> {
+    loop {
+        let path = rotate(db, dir, keep)?;
+        println!("Wrote snapshot {}", path.display());
+        std::thread::sleep(interval);
+    }
+}
+
+/// Scheduled snapshots in `dir` with their timestamps, oldest first.
+fn list(dir: &Path) -> std::io::Result<Vec<(u64, PathBuf)>> {
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(FILE_PREFIX)?.strip_suffix(".db"))
+            .and_then(|timestamp| timestamp.parse().ok());
+        if let Some(timestamp) = timestamp {
+            snapshots.push((timestamp, path));
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
@@ -0,0 +1,190 @@
+//! Tracing setup for processes that don't run the reth node.
+//!
+//! The node installs its own subscriber, which the indexer's spans go to. The
+//! web server alone gets a subscriber here: log lines filtered by `RUST_LOG`
+//! (`info` by default), plus span export over OTLP/gRPC when
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` is set. It also gets the Prometheus recorder
+//! behind `/metrics`; in the ExEx process metrics go to the node's exporter.
+//!
+//! How much the indexer logs is set separately, at runtime, through a
+//! [`BlockLog`].
+
+use crate::config::Verbosity;
+use eyre::WrapErr;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use std::{
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+    time::Duration,
+};
+use tracing::info;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Histogram buckets of `*_seconds` metrics, from 1ms to 10s.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// How often recorded histograms are drained into their buckets.
+const METRICS_UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static METRICS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Metrics recorded by this process, if [`init`] installed the recorder.
+pub fn metrics() -> Option<&'static PrometheusHandle> {
+    METRICS.get()
+}
+
+/// Flushes exported spans when dropped.
+#[must_use = "spans are only flushed when the guard is dropped"]
+pub struct Guard(Option<SdkTracerProvider>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.0.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("Failed to flush spans: {err}");
+            }
+        }
+    }
+}
+
+/// Install the global subscriber, naming exported spans after `service`, and
+/// the metrics recorder.
+///
+/// Must be called from within a Tokio runtime.
+pub fn init(service: &'static str) -> eyre::Result<Guard> {
+    let provider = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) if !endpoint.is_empty() => {
+            let exporter = SpanExporter::builder()
+                .with_tonic()
+                .build()
+                .wrap_err_with(|| format!("invalid OTEL_EXPORTER_OTLP_ENDPOINT={endpoint}"))?;
+            Some(
+                SdkTracerProvider::builder()
+                    .with_batch_exporter(exporter)
+                    .with_resource(Resource::builder().with_service_name(service).build())
+                    .build(),
+            )
+        }
+        _ => None,
+    };
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let otlp = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service)));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otlp)
+        .try_init()
+        .wrap_err("failed to install tracing subscriber")?;
+
+    let metrics = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)?
+        .install_recorder()
+        .wrap_err("failed to install metrics recorder")?;
+    let upkeep = metrics.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(METRICS_UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
+    let _ = METRICS.set(metrics);
+
+    Ok(Guard(provider))
+}
+
+/// Logs the blocks the indexer indexed at the current [`Verbosity`].
+///
+/// Clones share the verbosity, so a web server running in the same process as
+/// the indexer can change it through `/admin/log-verbosity`.
+#[derive(Debug, Clone, Default)]
+pub struct BlockLog {
+    state: Arc<Mutex<BlockLogState>>,
+}
+
+#[derive(Debug, Default)]
+struct BlockLogState {
+    verbosity: Verbosity,
+    // Blocks logged since the last summary line
+    summary: Option<BlockSummary>,
+}
+
+#[derive(Debug)]
+struct BlockSummary {
+    first_block: u64,
+    last_block: u64,
+    blocks: u64,
+    txs: u64,
+    blobs: u64,
+    elapsed: Duration,
+}
+
+impl BlockLog {
+    pub fn new(verbosity: Verbosity) -> Self {
+        let log = Self::default();
+        log.set_verbosity(verbosity);
+        log
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        self.state().verbosity
+    }
+
+    /// Switch to `verbosity`, dropping blocks not yet summarized.
+    pub fn set_verbosity(&self, verbosity: Verbosity) {
+        let mut state = self.state();
+        state.verbosity = verbosity;
+        state.summary = None;
+    }
+
+    /// Whether blob txs get a line of their own.
+    pub fn logs_txs(&self) -> bool {
+        self.verbosity() == Verbosity::Tx
+    }
+
+    /// Log block `block_number`, indexed with `txs` blob txs carrying `blobs`
+    /// blobs in `elapsed`.
+    pub fn block(&self, block_number: u64, txs: u64, blobs: u64, elapsed: Duration) {
+        let mut state = self.state();
+        let Verbosity::Summary { every } = state.verbosity else {
+            info!(block = block_number, txs, blobs, ?elapsed, "ExBlob");
+            return;
+        };
+
+        let summary = state.summary.get_or_insert(BlockSummary {
+            first_block: block_number,
+            last_block: block_number,
+            blocks: 0,
+            txs: 0,
+            blobs: 0,
+            elapsed: Duration::ZERO,
+        });
+        summary.last_block = block_number;
+        summary.blocks += 1;
+        summary.txs += txs;
+        summary.blobs += blobs;
+        summary.elapsed += elapsed;
+        if summary.blocks >= every {
+            info!(
+                blocks = ?(summary.first_block..=summary.last_block),
+                txs = summary.txs,
+                blobs = summary.blobs,
+                avg_elapsed = ?(summary.elapsed / summary.blocks as u32),
+                "ExBlob"
+            );
+            state.summary = None;
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, BlockLogState> {
+        self.state.lock().expect("failed to acquire block log lock")
+    }
+}
@@ -0,0 +1,112 @@
+//! HTTPS for the web server, when it isn't behind a TLS terminating proxy.
+//!
+//! [`TlsListener`] completes handshakes concurrently as connections come in,
+//! so a slow or stalled client can't hold up accepting the others.
+
+use crate::config::TlsConfig;
+use eyre::WrapErr;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io,
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        ServerConfig,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+/// Connections that haven't completed their handshake by then are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after a failed accept, typically out of file descriptors.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_secs(1);
+
+/// Read the certificate chain and private key of `config`.
+pub fn acceptor(config: &TlsConfig) -> eyre::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(&config.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .wrap_err_with(|| format!("invalid BLOB_TLS_CERT={}", config.cert.display()))?;
+    eyre::ensure!(
+        !certs.is_empty(),
+        "BLOB_TLS_CERT={}: no certificate found",
+        config.cert.display()
+    );
+    let key = PrivateKeyDer::from_pem_file(&config.key)
+        .wrap_err_with(|| format!("invalid BLOB_TLS_KEY={}", config.key.display()))?;
+
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .wrap_err("BLOB_TLS_KEY does not match BLOB_TLS_CERT")?;
+    // The server speaks HTTP/1.1 only
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// A TCP listener yielding connections once their TLS handshake succeeded.
+pub struct TlsListener {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    handshakes: JoinSet<Option<(TlsStream<TcpStream>, SocketAddr)>>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, acceptor: TlsAcceptor) -> Self {
+        Self {
+            listener,
+            acceptor,
+            handshakes: JoinSet::new(),
+        }
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        let handshake = self.acceptor.accept(stream);
+                        self.handshakes.spawn(async move {
+                            match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                                Ok(Ok(stream)) => Some((stream, addr)),
+                                Ok(Err(err)) => {
+                                    tracing::debug!(%addr, %err, "TLS handshake failed");
+                                    None
+                                }
+                                Err(_) => {
+                                    tracing::debug!(%addr, "TLS handshake timed out");
+                                    None
+                                }
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        tracing::warn!(%err, "failed to accept connection");
+                        tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                    }
+                },
+                // An empty set disables this branch until the next accept
+                Some(handshake) = self.handshakes.join_next() => {
+                    if let Ok(Some(connection)) = handshake {
+                        return connection;
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.listener.local_addr()
+    }
+}
@@ -0,0 +1,1202 @@
+//! Response bodies of the JSON API, for Rust consumers that want typed access
+//! instead of hand-rolled structs.
+//!
+//! These types follow the crate's semver: within a major version fields are
+//! only ever added, never renamed, removed or given another type. Every struct
+//! is `#[non_exhaustive]` so adding a field isn't a breaking change, and
+//! deserializing ignores unknown fields, so a client built against an older
+//! version keeps reading responses of a newer server.
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::sensitivity::{LaggedCorrelation, PriceSensitivity};
+
+/// `/api/stats`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Stats {
+    pub total_blocks: u64,
+    pub total_blobs: u64,
+    pub total_transactions: u64,
+    pub avg_blobs_per_block: f64,
+    pub latest_block: Option<u64>,
+    pub earliest_block: Option<u64>,
+    pub latest_gas_price: u128,
+    /// Beacon slots since the earliest block, counting missed slots as empty.
+    pub total_slots: u64,
+    pub missed_slots: u64,
+    pub avg_blobs_per_slot: f64,
+    pub throughput_windows: Vec<ThroughputWindow>,
+    /// Execution layer size of blob txs indexed with it.
+    pub total_el_bytes: u64,
+    /// Data availability size of the same txs.
+    pub total_da_bytes: u64,
+    /// Known for txs whose sidecar was seen.
+    pub total_payload_bytes: u64,
+    /// Blob versioned hash references by blob txs, and how many were distinct
+    /// within their UTC day. The difference counts same-day re-posts.
+    pub total_blob_refs: u64,
+    pub distinct_daily_blobs: u64,
+    /// Cutoffs of the demand regimes, for legends.
+    pub regimes: Vec<Regime>,
+}
+
+/// Per-block vs per-slot throughput over a trailing window ending at the
+/// latest block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ThroughputWindow {
+    /// `1h`, `24h` or `7d`.
+    pub window: String,
+    pub blocks: u64,
+    pub slots: u64,
+    pub missed_slots: u64,
+    pub blobs_per_block: f64,
+    pub blobs_per_slot: f64,
+    /// `None` if no block's parent is indexed.
+    pub block_intervals: Option<BlockIntervals>,
+    /// DA bandwidth in bytes per second, a blob counting as 128 KiB: averaged
+    /// over the window's slots, and of its busiest block over the interval
+    /// since its parent, `None` if no block's parent is indexed.
+    pub sustained_bytes_per_sec: f64,
+    pub peak_bytes_per_sec: Option<f64>,
+}
+
+/// Seconds between consecutive blocks, a multiple of the slot time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockIntervals {
+    pub avg_secs: f64,
+    pub p50_secs: u64,
+    pub p90_secs: u64,
+    pub p99_secs: u64,
+    pub max_secs: u64,
+}
+
+/// A blob transaction of a [`Block`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockTransaction {
+    pub tx_hash: String,
+    pub sender: String,
+    pub blob_count: u64,
+    pub blob_size: u64,
+    pub chain: String,
+    /// Owner of the sender, if known.
+    pub attributed_entity: Option<String>,
+    /// `manual`, `registry` or `heuristic`, `None` if unattributed.
+    pub label_source: Option<String>,
+    /// Permalink, `/api/txs/{tx_hash}`.
+    pub url: String,
+}
+
+/// `/api/blocks`, `/api/block`, `/api/blocks/range` and
+/// `/api/blocks/{number_or_hash}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Block {
+    pub block_number: u64,
+    /// `None` for blocks indexed before hashes were recorded.
+    pub block_hash: Option<String>,
+    /// Permalink, `/api/blocks/{block_hash}`, or `/api/blocks/{block_number}`
+    /// for blocks without a recorded hash.
+    pub url: String,
+    pub block_timestamp: u64,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub total_blob_size: u64,
+    pub gas_used: u64,
+    pub gas_price: u128,
+    pub excess_blob_gas: u64,
+    pub base_fee_per_gas: Option<u64>,
+    /// Execution layer context, `None` unless execution tracking is enabled.
+    pub non_blob_tx_count: Option<u64>,
+    pub non_blob_gas_used: Option<u64>,
+    /// Blob schedule in effect at the block.
+    pub blob_target: u64,
+    pub blob_max: u64,
+    /// Seconds since the parent block, `None` if it isn't indexed.
+    pub block_interval: Option<u64>,
+    /// Fee recipient, `None` for blocks indexed before it was recorded.
+    pub beneficiary: Option<String>,
+    /// Omitted by `/api/blocks/range` unless `include=txs`, and by `/api/sync`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<BlockTransaction>>,
+    /// Most blobs first.
+    pub by_chain: Vec<BlockChainTotals>,
+    pub target_utilization: f64,
+    /// Demand regime of the block's target utilization, see [`Regime`].
+    pub regime: String,
+    pub saturation_index: f64,
+    /// Blob gas used over the block's max blob gas, as in `eth_feeHistory`.
+    pub blob_gas_used_ratio: f64,
+    pub consistency: BlockConsistency,
+}
+
+/// A block's transactions of one chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockChainTotals {
+    pub chain: String,
+    pub tx_count: u64,
+    pub blobs: u64,
+    pub blob_size: u64,
+}
+
+/// Blob gas used as the block header states it against the sum over the
+/// indexed transactions. A mismatch means a blob tx was missed or miscounted,
+/// or an unusual block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockConsistency {
+    /// `None` for blocks indexed before the header value was recorded.
+    pub header_blob_gas_used: Option<u64>,
+    pub computed_blob_gas_used: u64,
+    /// `None` when the header value is unknown.
+    pub matches: Option<bool>,
+}
+
+/// `/api/senders`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Sender {
+    pub address: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub total_blob_size: u64,
+    pub chain: String,
+    /// `manual` or `registry`, `None` for unknown senders.
+    pub label_source: Option<String>,
+    /// `None` if no indexed tx backs the sender.
+    pub first_seen_block: Option<u64>,
+    pub last_seen_block: Option<u64>,
+    pub last_seen_timestamp: Option<u64>,
+}
+
+/// `/api/chart`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChartData {
+    pub labels: Vec<u64>,
+    pub blobs: Vec<u64>,
+    /// Gwei, `None` for blocks that aren't indexed if the gap fill has no
+    /// value for them.
+    pub gas_prices: Vec<Option<f64>>,
+    /// The same in wei, as decimal strings since they can exceed the integers
+    /// JavaScript represents exactly.
+    pub gas_prices_wei: Vec<Option<String>>,
+    /// Base 10 logarithm of the same in wei, for log scale charts: 0 at the
+    /// 1 wei minimum, 9 at 1 Gwei.
+    pub gas_prices_log10: Vec<Option<f64>>,
+    pub non_blob_tx_counts: Vec<Option<u64>>,
+    pub non_blob_gas_used: Vec<Option<u64>>,
+    /// Gwei.
+    pub base_fees: Vec<Option<f64>>,
+    /// Seconds since the parent block, `None` if unknown.
+    pub block_intervals: Vec<Option<u64>>,
+    /// DA bandwidth of each block in bytes per second, its blobs at 128 KiB
+    /// over the interval since its parent, `None` if that is unknown.
+    pub bandwidth_bytes_per_sec: Vec<Option<f64>>,
+    /// Annotations whose block is within the chart.
+    pub annotations: Vec<Annotation>,
+}
+
+/// An event marked by an operator, from `/api/annotations` or alongside chart
+/// data, to draw as a vertical marker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Annotation {
+    pub id: u64,
+    pub timestamp: u64,
+    /// `release`, `incident`, `fork` or any other kind operators use.
+    pub kind: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// First indexed block at or after `timestamp`, `None` until there is one.
+    /// In `/api/all-time-chart`, the first label at or after that block.
+    pub block_number: Option<u64>,
+    pub updated_at: u64,
+}
+
+/// The indexer process currently holding the writer lease.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Writer {
+    pub holder: String,
+    pub acquired_at: u64,
+    pub heartbeat_at: u64,
+    pub heartbeat_age_secs: u64,
+    /// `false` once the heartbeat is older than the lease timeout.
+    pub active: bool,
+    /// Canonical tip of the writer's node, as last reported.
+    pub node_tip: Option<u64>,
+}
+
+/// `/api/health`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Health {
+    /// `ok`, or `no_writer` without an active writer.
+    pub status: String,
+    pub latest_block: Option<u64>,
+    /// Node tip minus latest indexed block, `None` if either is unknown.
+    pub blocks_behind: Option<u64>,
+    pub writer: Option<Writer>,
+    /// Size of the database and of its WAL in bytes, `None` for in-memory
+    /// databases. A large WAL means checkpoints aren't completing.
+    pub db_size_bytes: Option<u64>,
+    pub wal_size_bytes: Option<u64>,
+    /// Latest verification of stored blocks against the node's canonical
+    /// chain, `None` until the writer has run one.
+    pub canonical: Option<CanonicalCheck>,
+    /// Latest check of sender stats against blob transactions, `None` until
+    /// the writer has run one.
+    pub senders: Option<SenderCheck>,
+}
+
+/// A round of the writer's check of stored sender stats against the ones
+/// derived from blob transactions, see `/api/sender-consistency` for details.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderCheck {
+    pub checked_at: u64,
+    pub drifted_senders: u64,
+    /// Transactions and blobs stored and derived counts differ by, summed
+    /// over drifted senders.
+    pub tx_count_drift: u64,
+    pub blob_drift: u64,
+    /// Whether stats were rebuilt in this round, after drifting past
+    /// `BLOB_SENDER_REPAIR_THRESHOLD`.
+    pub repaired: bool,
+    pub total_repairs: u64,
+    pub last_repair_at: Option<u64>,
+}
+
+/// A round of canonical chain verification by the writer, which samples
+/// stored blocks and repairs those its node no longer has as canonical.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CanonicalCheck {
+    pub checked_at: u64,
+    pub blocks_checked: u64,
+    /// Blocks found off the canonical chain and repaired in this round.
+    pub mismatches: u64,
+    /// Blocks repaired since verification first ran.
+    pub total_mismatches: u64,
+    pub last_mismatch_block: Option<u64>,
+    pub last_mismatch_at: Option<u64>,
+}
+
+/// Line sent on live streams when idle and right after connecting, so clients
+/// can show how far the data lags and detect stale connections.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Heartbeat {
+    /// Always `heartbeat`, other lines but [`Reorg`] have no type.
+    pub r#type: String,
+    pub timestamp: u64,
+    pub latest_block: Option<u64>,
+    pub node_tip: Option<u64>,
+    pub blocks_behind: Option<u64>,
+    /// `false` if the node tip is stale.
+    pub writer_active: bool,
+}
+
+/// Line sent on `/api/tail` when blocks it already streamed were reverted.
+/// Their transactions should be dropped, the blocks replacing them are streamed
+/// next.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Reorg {
+    /// Always `reorg`.
+    pub r#type: String,
+    pub first_block: u64,
+    pub last_block: u64,
+}
+
+/// `/api/blob-transactions`, `/api/txs/{tx_hash}` and the lines of
+/// `/api/tail`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlobTransaction {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub sender: String,
+    pub blob_count: u64,
+    pub blob_size: u64,
+    pub gas_price: u128,
+    pub chain: String,
+    /// Owner of the sender, if known.
+    pub attributed_entity: Option<String>,
+    /// `manual`, `registry` or `heuristic`, `None` if unattributed.
+    pub label_source: Option<String>,
+    /// EIP-2718 type, 3 for EIP-4844. `None` for txs archived before types
+    /// were recorded.
+    pub tx_type: Option<u8>,
+    pub blob_hashes: Vec<String>,
+    /// Payload size per blob, `None` if the sidecar wasn't seen.
+    pub blob_sizes: Vec<Option<u64>>,
+    /// Permalink, `/api/txs/{tx_hash}`.
+    pub url: String,
+}
+
+/// A page of the blobs a sender posted, oldest first,
+/// `/api/sender/{address}/blobs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderBlobs {
+    pub sender: String,
+    pub blobs: Vec<SenderBlob>,
+    /// Pass as `cursor` to get the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Blocks and blob transactions indexed after a height, lowest first,
+/// `/api/sync`. Mirrors replicate the index by passing `cursor` back until
+/// `has_more` is false, then poll with it for new blocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SyncPage {
+    /// Blocks of the page, without their transactions.
+    pub blocks: Vec<Block>,
+    /// Blob transactions of the page, by block then tx hash, with their blob
+    /// hashes.
+    pub transactions: Vec<BlobTransaction>,
+    /// Pass as `cursor` to continue after this page. Answered with 409 once
+    /// the block it ends at was reorged out.
+    pub cursor: String,
+    /// Whether blocks past this page are already indexed.
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderBlob {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub tx_hash: String,
+    /// Position of the blob within its transaction.
+    pub blob_index: u64,
+    /// Versioned hash.
+    pub blob_hash: String,
+    /// Payload size, `None` if the sidecar wasn't seen.
+    pub blob_size: Option<u64>,
+    /// Permalink of the transaction, `/api/txs/{tx_hash}`.
+    pub tx_url: String,
+}
+
+/// Blob transactions sent to one address, the inbox contract of rollups where
+/// many batcher keys may post to one inbox. `/api/inbox/{address}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Inbox {
+    pub address: String,
+    /// Window, ending now.
+    pub hours: u64,
+    pub total_transactions: u64,
+    pub total_blobs: u64,
+    /// Bytes, full blobs where the payload size is unknown.
+    pub total_blob_size: u64,
+    /// Most blobs first.
+    pub senders: Vec<InboxSender>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct InboxSender {
+    pub address: String,
+    pub chain: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub total_blob_size: u64,
+    pub first_block: u64,
+    pub last_block: u64,
+    /// Transactions whose receipt failed, where execution tracking saw them.
+    pub reverted_txs: u64,
+}
+
+/// `/api/all-time-chart`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AllTimeChartData {
+    /// Block numbers (sampled).
+    pub labels: Vec<u64>,
+    /// Smoothed blob counts.
+    pub blobs: Vec<f64>,
+    /// Smoothed gas prices in Gwei.
+    pub gas_prices: Vec<f64>,
+    /// The same in wei, rounded to whole wei, as decimal strings.
+    pub gas_prices_wei: Vec<String>,
+    /// Base 10 logarithm of the same in wei, for log scale charts.
+    pub gas_prices_log10: Vec<f64>,
+    pub timestamps: Vec<u64>,
+    /// Dynamic target at each point.
+    pub targets: Vec<u64>,
+    /// Dynamic max at each point.
+    pub maxes: Vec<u64>,
+    /// First block under the latest blob schedule entry (BPO2).
+    pub bpo2_block: Option<u64>,
+    /// Execution layer context, `None` where it wasn't recorded.
+    pub non_blob_tx_counts: Vec<Option<f64>>,
+    pub non_blob_gas_used: Vec<Option<f64>>,
+    /// Gwei.
+    pub base_fees: Vec<Option<f64>>,
+    /// Seconds since the parent block, downsampled like blobs.
+    pub block_intervals: Vec<Option<f64>>,
+    /// DA bandwidth in bytes per second, downsampled blobs at 128 KiB over
+    /// the downsampled interval.
+    pub bandwidth_bytes_per_sec: Vec<Option<f64>>,
+    /// Annotations whose block is within the chart.
+    pub annotations: Vec<Annotation>,
+}
+
+/// Blob parameters in effect from a given timestamp, `/api/blob-schedule`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlobScheduleEntry {
+    pub activation_timestamp: u64,
+    pub target: u64,
+    pub max: u64,
+    pub base_fee_update_fraction: u64,
+}
+
+/// Blocks built for the fee recipients of a window, `/api/builders`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Builders {
+    pub hours: u64,
+    /// Blocks of the window with a known fee recipient.
+    pub blocks: u64,
+    pub avg_blobs_per_block: f64,
+    /// Most blocks first.
+    pub builders: Vec<Builder>,
+}
+
+/// Blocks of one fee recipient. Most builders set themselves as the fee
+/// recipient, some the proposer's address instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Builder {
+    pub beneficiary: String,
+    pub blocks: u64,
+    /// Share of the window's blocks, in percent.
+    pub share_pct: f64,
+    pub total_blobs: u64,
+    pub avg_blobs_per_block: f64,
+    /// Blobs per block over the window's average, negative if fewer.
+    pub blobs_vs_avg: f64,
+    /// Average target utilization of the blocks, in percent.
+    pub avg_target_utilization: f64,
+    /// Blocks without blobs, and at the blob max.
+    pub empty_blocks: u64,
+    pub full_blocks: u64,
+}
+
+/// A demand regime blocks are classified into by target utilization,
+/// `/api/regimes`. From lowest to highest: `abundant`, `normal`, `pressured`,
+/// `congested` and `saturated`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Regime {
+    pub name: String,
+    /// Target utilization in percent, inclusive.
+    pub from_pct: f64,
+    /// Exclusive, `None` for `saturated`.
+    pub to_pct: Option<f64>,
+}
+
+/// A block whose blob target or max differ from its parent's, i.e. where a
+/// blob schedule change was observed to activate, `/api/fork-events`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ForkEvent {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub previous_target: u64,
+    pub previous_max: u64,
+    pub target: u64,
+    pub max: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PriorityFeeLevels {
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+}
+
+/// Priority fee competition for blob inclusion in a block,
+/// `/api/inclusion-market`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct InclusionMarketBlock {
+    pub block_number: u64,
+    pub tx_count: u64,
+    /// Fees paid by included blob txs (wei).
+    pub included: Option<PriorityFeeLevels>,
+    /// Pending blob txs left out (needs mempool tracking).
+    pub excluded_count: u64,
+    /// Fees bid by the left-out blob txs (wei).
+    pub excluded: Option<PriorityFeeLevels>,
+}
+
+/// Blob gas counterpart of `eth_feeHistory`, quantities are hex encoded like
+/// JSON-RPC. `/api/blob-fee-history`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct BlobFeeHistory {
+    pub oldest_block: String,
+    /// One per block, plus the fee of the block after the newest one.
+    pub base_fee_per_blob_gas: Vec<String>,
+    pub blob_gas_used_ratio: Vec<f64>,
+    /// Priority fee percentiles, weighted by blob count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<Vec<String>>>,
+}
+
+/// Blob activity by local day of week and/or hour of day, `/api/heatmap`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Heatmap {
+    pub days: u64,
+    pub tz_offset: i64,
+    /// `utilization`, `fee`, `blobs` or `tx_count`, what each cell's `value` is.
+    pub metric: String,
+    /// `both`, `hour_of_day` or `day_of_week`.
+    pub group: String,
+    /// Monday 00:00 first: 7 x 24 for `both`, 24 hours for `hour_of_day`, 7
+    /// days for `day_of_week`.
+    pub cells: Vec<HeatmapCell>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct HeatmapCell {
+    /// 0 = Monday, `None` grouped by hour of day only.
+    pub weekday: Option<u8>,
+    /// `None` grouped by day of week only.
+    pub hour: Option<u8>,
+    pub blocks: u64,
+    pub total_blobs: u64,
+    /// Blob transactions.
+    pub tx_count: u64,
+    pub avg_blobs_per_block: f64,
+    /// % of the blob target used, averaged over blocks.
+    pub target_utilization: f64,
+    /// Gwei.
+    pub avg_gas_price: f64,
+    /// The requested metric: `target_utilization`, `avg_gas_price`,
+    /// `avg_blobs_per_block` or blob transactions per block.
+    pub value: f64,
+}
+
+/// Excess blob gas over time, and how the blob fee market moved around
+/// target. `/api/excess-blob-gas`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ExcessBlobGas {
+    pub series: Vec<ExcessBlobGasPoint>,
+    pub blocks_above_target: u64,
+    pub blocks_at_target: u64,
+    pub blocks_below_target: u64,
+    pub secs_above_target: u64,
+    pub secs_below_target: u64,
+    pub largest_build_up: Option<BuildUp>,
+    pub fee_doublings: Vec<FeeDoubling>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ExcessBlobGasPoint {
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub excess_blob_gas: u64,
+    /// Change from the parent block, `None` if it isn't indexed.
+    pub delta: Option<i64>,
+    pub blob_gas_price: u128,
+}
+
+/// Longest run of consecutive blocks where excess blob gas kept growing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BuildUp {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub blocks: u64,
+    pub excess_blob_gas_increase: u64,
+}
+
+/// The blob gas price reaching twice its lowest value since the previous
+/// doubling.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct FeeDoubling {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub timestamp: u64,
+    pub from_price: u128,
+    pub to_price: u128,
+}
+
+/// Whether the blob target is sized right, over the last `days`.
+/// `/api/protocol-summary`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ProtocolSummary {
+    pub days: u64,
+    pub blocks: u64,
+    pub avg_blobs_per_block: f64,
+    /// Blob target averaged over blocks, as it may change with a fork.
+    pub avg_target: f64,
+    /// % of the blob target used, over all blocks.
+    pub target_utilization: f64,
+    /// % of blocks using all blobs allowed.
+    pub pct_blocks_at_max: f64,
+    /// Average blobs per block above (positive) or below the target, where the
+    /// blob fee holds steady at 0.
+    pub equilibrium_deviation: f64,
+    /// `None` without blocks.
+    pub fee_percentiles: Option<BlobFeePercentiles>,
+    /// Times the blob gas price doubled, see [`FeeDoubling`].
+    pub fee_doublings: u64,
+}
+
+/// When blob demand outgrows the blob target and max if it keeps growing as
+/// over the last `days`. `/api/capacity`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Capacity {
+    pub days: u64,
+    /// Blobs per block the projection is checked against.
+    pub target: f64,
+    pub max: f64,
+    pub avg_blobs_per_block: f64,
+    /// Blobs per block on the last day along the trend, `None` like
+    /// `daily_growth`.
+    pub trend_blobs_per_block: Option<f64>,
+    /// Compound growth of blobs per block per day, `None` with too few days
+    /// of demand to fit.
+    pub daily_growth: Option<f64>,
+    /// Linear change of blobs per block per day, split over `chains`.
+    pub trend_per_day: f64,
+    /// When average blobs per block reach `target` and `max`, `None` if they
+    /// never do at the current growth.
+    pub target_reached_at: Option<u64>,
+    pub max_reached_at: Option<u64>,
+    pub chains: Vec<ChainGrowth>,
+}
+
+/// A chain's share of blob demand growth, see [`Capacity`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainGrowth {
+    pub chain: String,
+    pub avg_blobs_per_block: f64,
+    /// Linear change of the chain's blobs per block per day. The chains' trends
+    /// add up to the total.
+    pub trend_per_day: f64,
+    pub daily_growth: Option<f64>,
+}
+
+/// Blob gas price percentiles over blocks, in wei.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlobFeePercentiles {
+    pub p10: u128,
+    pub p25: u128,
+    pub p50: u128,
+    pub p75: u128,
+    pub p90: u128,
+    pub p99: u128,
+}
+
+/// A block that failed to ingest and was skipped, to be re-processed.
+/// `/api/ingest-errors`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IngestError {
+    pub block_number: u64,
+    pub stage: String,
+    pub error: String,
+    pub attempts: u32,
+    pub failed_at: u64,
+}
+
+/// A notification processed by the ExEx, `/api/ingest-log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IngestLogEntry {
+    /// Pass as `before` to page back from this entry.
+    pub id: u64,
+    pub processed_at: u64,
+    /// "commit", "revert" or "reorg".
+    pub kind: String,
+    /// Inclusive block ranges, `None` if the notification had no such chain.
+    pub reverted: Option<BlockRange>,
+    pub committed: Option<BlockRange>,
+    /// Blob transactions in the reverted and committed blocks.
+    pub reverted_txs: u64,
+    pub committed_txs: u64,
+    pub duration_ms: u64,
+    /// "ok", "skipped" if a step gave up (see `/api/ingest-errors`), or
+    /// "failed" if processing stopped with `error`.
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockRange {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Stored sender stats vs the ones derived from indexed blob transactions,
+/// `/api/consistency/senders`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderConsistency {
+    pub drifted_senders: usize,
+    /// First drifted senders by address.
+    pub drift: Vec<SenderDrift>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderDrift {
+    pub address: String,
+    pub stored: SenderTotals,
+    pub derived: SenderTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderTotals {
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub total_blob_size: u64,
+}
+
+/// Blob market concentration over a time window, `/api/concentration`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Concentration {
+    pub window_secs: u64,
+    pub total_blobs: u64,
+    pub top: usize,
+    pub senders: ConcentrationMetrics,
+    /// Unlabelled senders count as their own chain.
+    pub chains: ConcentrationMetrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConcentrationMetrics {
+    pub participants: usize,
+    /// 0 = equal shares, towards 1 as few participants dominate.
+    pub gini: f64,
+    /// Herfindahl-Hirschman index on the 0-10,000 scale.
+    pub hhi: f64,
+    /// % of blobs posted by the `top` largest participants.
+    pub top_n_share: f64,
+}
+
+/// Blob fees paid by a chain versus posting the same data as calldata,
+/// `/api/blob-savings`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlobSavings {
+    pub chain: String,
+    pub tx_count: u64,
+    pub total_blobs: u64,
+    pub payload_bytes: u64,
+    pub blob_fees_eth: f64,
+    /// EIP-7623 floor pricing at the block's base fee.
+    pub calldata_cost_eth: f64,
+    pub savings_eth: f64,
+    pub savings_pct: f64,
+    /// Txs without a recorded base fee, excluded from the calldata cost.
+    pub unpriced_tx_count: u64,
+}
+
+/// How a chain's blob submission costs split between blob gas and EL gas,
+/// `/api/chain-cost-breakdown`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainCostBreakdown {
+    pub chain: String,
+    pub tx_count: u64,
+    /// Fee totals below cover the txs with a recorded execution fee only.
+    pub priced_blobs: u64,
+    pub blob_fees_eth: f64,
+    pub execution_fees_eth: f64,
+    pub total_cost_eth: f64,
+    /// % of the total cost paid for blob gas.
+    pub blob_fee_share_pct: f64,
+    pub execution_gas_used: u64,
+    /// Txs indexed without execution tracking, excluded from the fee totals.
+    pub unpriced_tx_count: u64,
+}
+
+/// How often blob txs needed more than one attempt at the same nonce, e.g.
+/// rebids with a higher blob fee or re-broadcasts after a reorg.
+/// `/api/resubmissions`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Resubmissions {
+    pub tx_count: u64,
+    pub resubmitted_tx_count: u64,
+    pub resubmission_rate: f64,
+    /// Attempts per included tx, 1.0 = never resubmitted.
+    pub avg_attempts: f64,
+    /// Same tx included again after its block was reorged out.
+    pub reincluded_tx_count: u64,
+    pub chains: Vec<ChainResubmissions>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainResubmissions {
+    pub chain: String,
+    pub tx_count: u64,
+    pub resubmitted_tx_count: u64,
+    pub resubmission_rate: f64,
+    pub avg_attempts: f64,
+}
+
+/// Chain behavior profile (also serves as chain stats), `/api/chain-profiles`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainProfile {
+    pub chain: String,
+    pub total_transactions: u64,
+    pub total_blobs: u64,
+    /// % of total blobs in the time window.
+    pub percentage: f64,
+    pub avg_blobs_per_tx: f64,
+    /// Average time between posts.
+    pub avg_posting_interval_secs: f64,
+    /// 24 hours, normalized 0-1.
+    pub hourly_activity: Vec<f64>,
+    /// Correlation of posting with the blob base fee.
+    pub price_sensitivity: PriceSensitivity,
+}
+
+/// Daily DA uptime per chain, as a day x chain matrix. `/api/chain-uptime`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainUptime {
+    /// Start of each UTC day, oldest first.
+    pub days: Vec<u64>,
+    pub chains: Vec<ChainDailyUptime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainDailyUptime {
+    pub chain: String,
+    /// Longer gaps between posts count as downtime.
+    pub gap_threshold_secs: u64,
+    /// Per day, 0-1, `None` before the chain's first post.
+    pub uptime: Vec<Option<f64>>,
+    pub avg_uptime: f64,
+}
+
+/// Posting and failed batches per chain over a window, `/api/chain-health`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainHealth {
+    pub hours: u64,
+    /// Most failed txs first.
+    pub chains: Vec<ChainHealthStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainHealthStatus {
+    pub chain: String,
+    pub txs: u64,
+    /// Reverted blob txs, which paid blob fees but delivered no batch. Only
+    /// counted with execution tracking enabled.
+    pub failed_txs: u64,
+    pub failure_rate: f64,
+    pub failed_blobs: u64,
+    pub failed_blob_fees_wei: u128,
+    pub last_post_timestamp: u64,
+    pub secs_since_last_post: u64,
+}
+
+/// All-time extremes, `None` until there is a block to hold them.
+/// `/api/records`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Records {
+    pub most_blobs: Option<BlockRecord>,
+    pub highest_blob_fee: Option<BlockRecord>,
+    pub longest_saturated_streak: Option<StreakRecord>,
+    pub largest_sender_day: Option<SenderDayRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockRecord {
+    pub block_number: u64,
+    /// Blobs, or the blob base fee in wei.
+    pub value: u128,
+    pub link: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct StreakRecord {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Consecutive blocks at the max blob count.
+    pub blocks: u64,
+    pub link: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SenderDayRecord {
+    pub sender: String,
+    pub chain: String,
+    /// Start of the UTC day.
+    pub day: u64,
+    pub blobs: u64,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// `None` if the blocks span more than a chart request may.
+    pub link: Option<String>,
+}
+
+/// Decoded OP Stack batches of one chain, `/api/op-batches`. Channels only
+/// count if all their frames came in one transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainOpBatches {
+    pub chain: String,
+    /// Blobs that decoded as OP Stack batcher data.
+    pub blobs: u64,
+    pub frames: u64,
+    pub avg_frame_size: f64,
+    pub channels: u64,
+    pub avg_frames_per_channel: f64,
+    /// Compressed, in bytes.
+    pub avg_channel_size: f64,
+    /// Decompressed / compressed, `None` if none decompressed.
+    pub compression_ratio: Option<f64>,
+}
+
+/// Stats of two time ranges, with the change from a to b.
+/// `/api/compare-periods`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ComparePeriods {
+    pub a: PeriodStats,
+    pub b: PeriodStats,
+    pub delta_pct: PeriodDeltas,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PeriodStats {
+    pub from: u64,
+    /// Exclusive.
+    pub to: u64,
+    pub blocks: u64,
+    pub slots: u64,
+    pub missed_slots: u64,
+    pub total_blobs: u64,
+    pub blobs_per_block: f64,
+    pub blobs_per_slot: f64,
+    pub blob_transactions: u64,
+    pub unique_senders: u64,
+    /// Wei, averaged over blocks.
+    pub avg_blob_base_fee: f64,
+    pub blob_fees_eth: f64,
+    /// Blocks at their fork's max blob count.
+    pub saturated_blocks_pct: f64,
+    /// Blobs over the sum of per-block targets.
+    pub target_utilization_pct: f64,
+}
+
+/// Percent change of each period stat from a to b, `None` where a is zero.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PeriodDeltas {
+    pub blocks: Option<f64>,
+    pub missed_slots: Option<f64>,
+    pub total_blobs: Option<f64>,
+    pub blobs_per_block: Option<f64>,
+    pub blobs_per_slot: Option<f64>,
+    pub blob_transactions: Option<f64>,
+    pub unique_senders: Option<f64>,
+    pub avg_blob_base_fee: Option<f64>,
+    pub blob_fees_eth: Option<f64>,
+    pub saturated_blocks_pct: Option<f64>,
+    pub target_utilization_pct: Option<f64>,
+}
+
+/// Expected blob demand per block over the next hour, `/api/demand-forecast`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DemandForecast {
+    pub generated_at: u64,
+    pub horizon_secs: u64,
+    pub target_blobs_per_block: u64,
+    pub max_blobs_per_block: u64,
+    /// Average over the whole horizon.
+    pub expected_blobs_per_block: f64,
+    /// Senders not attributed to a chain, at their recent rate.
+    pub background_blobs_per_block: f64,
+    pub buckets: Vec<DemandBucket>,
+    pub chains: Vec<ChainDemand>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DemandBucket {
+    pub start: u64,
+    pub expected_blobs_per_block: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainDemand {
+    pub chain: String,
+    pub cadence_secs: u64,
+    pub blobs_per_post: f64,
+    pub last_post: u64,
+    /// `None` if the chain is silent for too long to expect a post.
+    pub next_post: Option<u64>,
+    /// Over the whole horizon.
+    pub expected_blobs: f64,
+}
+
+/// Distribution of blob counts per transaction of one chain,
+/// `/api/blobs-per-tx`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlobsPerTx {
+    pub chain: String,
+    pub days: u64,
+    pub tx_count: u64,
+    /// `None` without transactions.
+    pub avg_blobs_per_tx: Option<f64>,
+    /// Every blob count up to the largest seen, including empty ones.
+    pub buckets: Vec<BlobCountBucket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlobCountBucket {
+    pub blob_count: u64,
+    pub tx_count: u64,
+    /// Of the chain's transactions, 0-1.
+    pub share: f64,
+}
+
+/// Head-to-head stats of the requested chains over the same UTC days,
+/// `/api/compare-chains`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainComparison {
+    /// Start of each UTC day, oldest first.
+    pub days: Vec<u64>,
+    /// In the order requested.
+    pub chains: Vec<ComparedChain>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ComparedChain {
+    pub chain: String,
+    pub total_transactions: u64,
+    pub total_blobs: u64,
+    /// Blob fees paid, in ETH.
+    pub blob_fees_eth: f64,
+    /// Average time between posts, `None` with fewer than two.
+    pub avg_posting_interval_secs: Option<f64>,
+    /// Share of blob space carrying payload, 0-1, over the transactions
+    /// whose payload size is known. `None` if there are none.
+    pub avg_fill: Option<f64>,
+    /// Per day, aligned with `days`.
+    pub daily_transactions: Vec<u64>,
+    pub daily_blobs: Vec<u64>,
+    pub daily_blob_fees_eth: Vec<f64>,
+}
+
+/// Blobs a chain posted per UTC day and the blob fees it paid, for a calendar
+/// heatmap, `/api/chain/{name}/calendar`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ChainCalendar {
+    pub chain: String,
+    /// Start of the first day, the arrays hold one entry per day from there
+    /// through today.
+    pub first_day: u64,
+    pub blobs: Vec<u64>,
+    pub blob_fees_eth: Vec<f64>,
+}
+
+/// Blob versioned hash references per UTC day, and how many distinct hashes
+/// they were, `/api/blob-hashes/daily`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DailyBlobHashes {
+    /// Start of the first day, the arrays hold one entry per day from there
+    /// through today.
+    pub first_day: u64,
+    pub blob_refs: Vec<u64>,
+    pub distinct_blobs: Vec<u64>,
+}
+
+/// How long blob txs waited in the mempool before inclusion,
+/// `/api/inclusion-latency`.
+///
+/// Waits are measured from the first block after which the mempool tracker saw
+/// a tx pending, so txs included before any snapshot saw them (sent privately,
+/// or included in the first block after reaching the mempool) aren't measured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct InclusionLatency {
+    pub window_secs: u64,
+    /// `None` for all chains.
+    pub chain: Option<String>,
+    pub tx_count: u64,
+    /// Txs seen pending before their inclusion, the ones measured.
+    pub tracked_tx_count: u64,
+    /// `None` without tracked txs.
+    pub latency: Option<LatencyPercentiles>,
+    /// Of tracked txs, included in the first block after they were seen.
+    pub first_eligible_share: Option<f64>,
+    /// By priority fee paid, lowest first.
+    pub fee_buckets: Vec<InclusionLatencyBucket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LatencyPercentiles {
+    pub p50_blocks: u64,
+    pub p90_blocks: u64,
+    pub p99_blocks: u64,
+    pub p50_secs: u64,
+    pub p90_secs: u64,
+    pub p99_secs: u64,
+}
+
+/// Inclusion latency of the tracked txs paying a priority fee in
+/// `min_priority_fee_gwei..max_priority_fee_gwei`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct InclusionLatencyBucket {
+    pub min_priority_fee_gwei: f64,
+    /// `None` for the highest bucket.
+    pub max_priority_fee_gwei: Option<f64>,
+    pub tracked_tx_count: u64,
+    pub latency: Option<LatencyPercentiles>,
+    pub first_eligible_share: Option<f64>,
+}
@@ -0,0 +1,328 @@
+//! Operator actions under `/admin`.
+
+use alloy_primitives::{address, Address};
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+    Router,
+};
+use blob_exex::{
+    admin, api,
+    db::{NewBlobTransaction, NewBlock},
+    events::EventBus,
+    Database,
+};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+const TOKEN: &str = "0123456789abcdef";
+
+/// Status of a request to `uri` carrying `token`, and its JSON body if it
+/// succeeded.
+async fn send(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> eyre::Result<(u16, Value)> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))?;
+    let response = router.clone().oneshot(request).await?;
+    let status = response.status().as_u16();
+    let body = response.into_body().collect().await?.to_bytes();
+    let body = if status == 200 {
+        serde_json::from_slice(&body)?
+    } else {
+        Value::Null
+    };
+    Ok((status, body))
+}
+
+#[tokio::test]
+async fn alert_thresholds_are_adjusted() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let condition = json!({ "kind": "blob_fee_above", "gwei": 10.0 });
+    let id = db.insert_alert_rule("0xowner", &condition.to_string(), r#"{"type":"stream"}"#)?;
+    db.set_alert_rule_firing(id, true)?;
+    let router = admin::router(db.clone(), TOKEN, None);
+
+    let uri = format!("/admin/alerts/rules/{id}/condition");
+    let raised = json!({ "kind": "blob_fee_above", "gwei": 25.0 });
+    let (status, _) = send(
+        &router,
+        Method::PUT,
+        &uri,
+        "wrong token",
+        Some(raised.clone()),
+    )
+    .await?;
+    assert_eq!(status, 401);
+    let invalid = json!({ "kind": "blob_fee_above", "gwei": -1.0 });
+    let (status, _) = send(&router, Method::PUT, &uri, TOKEN, Some(invalid)).await?;
+    assert_eq!(status, 400);
+    let (status, _) = send(&router, Method::PUT, &uri, TOKEN, Some(raised.clone())).await?;
+    assert_eq!(status, 204);
+    let missing = "/admin/alerts/rules/999/condition";
+    let (status, _) = send(&router, Method::PUT, missing, TOKEN, Some(raised.clone())).await?;
+    assert_eq!(status, 404);
+
+    let (status, rules) = send(&router, Method::GET, "/admin/alerts/rules", TOKEN, None).await?;
+    assert_eq!(status, 200);
+    assert_eq!(rules[0]["owner"], "0xowner");
+    assert_eq!(rules[0]["condition"], raised);
+    assert_eq!(rules[0]["firing"], false);
+
+    let log = db.get_admin_audit_log(10)?;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].action, "put_alert_condition");
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollups_and_senders_are_rebuilt() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let router = admin::router(db.clone(), TOKEN, None);
+
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/admin/rollups/recompute",
+        TOKEN,
+        None,
+    )
+    .await?;
+    assert_eq!(status, 204);
+    let (status, rebuilt) =
+        send(&router, Method::POST, "/admin/senders/rebuild", TOKEN, None).await?;
+    assert_eq!(status, 200);
+    assert_eq!(rebuilt, json!({ "senders": 0 }));
+    Ok(())
+}
+
+#[tokio::test]
+async fn reprocess_requests_are_queued_until_done() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let router = admin::router(db.clone(), TOKEN, None);
+
+    let inverted = json!({ "from_block": 5, "to_block": 3 });
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/admin/reprocess",
+        TOKEN,
+        Some(inverted),
+    )
+    .await?;
+    assert_eq!(status, 400);
+    let range = json!({ "from_block": 3, "to_block": 5 });
+    let (status, queued) = send(
+        &router,
+        Method::POST,
+        "/admin/reprocess",
+        TOKEN,
+        Some(range),
+    )
+    .await?;
+    assert_eq!(status, 200);
+    let id = queued["request_id"].as_u64().unwrap();
+
+    let request = db.next_reprocess_request()?.unwrap();
+    assert_eq!((request.id, request.next_block), (id, 3));
+    // Picked up where it was left after a restart
+    db.advance_reprocess_request(id, 5)?;
+    let request = db.next_reprocess_request()?.unwrap();
+    assert_eq!((request.id, request.next_block), (id, 5));
+    assert_eq!(request.completed_at, None);
+    db.advance_reprocess_request(id, 6)?;
+    assert!(db.next_reprocess_request()?.is_none());
+
+    let log = db.get_admin_audit_log(10)?;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].action, "reprocess");
+    Ok(())
+}
+
+/// Index block 1 with one blob tx from `sender`.
+fn index(db: &Database, sender: Address) -> eyre::Result<()> {
+    let timestamp = 1_767_747_683;
+    let tx_hash = format!("0x{:064x}", 1);
+    db.insert_blob_transaction(&NewBlobTransaction {
+        tx_hash: &tx_hash,
+        block_number: 1,
+        sender,
+        nonce: 0,
+        tx_type: 3,
+        blob_count: 1,
+        gas_price: 1,
+        priority_fee: 0,
+        created_at: timestamp,
+        el_size: 200,
+        payload_size: None,
+        to: None,
+    })?;
+    db.update_sender(&sender, 1, timestamp, 1, 131_072)?;
+    db.insert_block(&NewBlock {
+        block_number: 1,
+        block_timestamp: timestamp,
+        tx_count: 1,
+        total_blobs: 1,
+        gas_used: 131_072,
+        gas_price: 1,
+        excess_blob_gas: 0,
+        base_fee_per_gas: 7,
+        priority_fees: None,
+        blob_target: 14,
+        blob_max: 21,
+        header_blob_gas_used: Some(131_072),
+        block_hash: format!("{:#066x}", 1),
+        beneficiary: Address::repeat_byte(0x24),
+    })?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn labels_override_the_registry_until_removed() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    index(&db, base)?;
+    let router = admin::router(db.clone(), TOKEN, None);
+    let api = api::router(db.clone(), api::Limits::default(), EventBus::new());
+    let attribution = async || -> eyre::Result<(Value, Value)> {
+        let (_, txs) = send(&api, Method::GET, "/api/blob-transactions", "", None).await?;
+        let (_, senders) = send(&api, Method::GET, "/api/senders", "", None).await?;
+        assert_eq!(txs[0]["chain"], senders[0]["chain"]);
+        assert_eq!(txs[0]["label_source"], senders[0]["label_source"]);
+        Ok((txs[0]["chain"].clone(), txs[0]["label_source"].clone()))
+    };
+    assert_eq!(attribution().await?, (json!("Base"), json!("registry")));
+
+    let uri = format!("/admin/labels/{base}");
+    for (address, label) in [(&*uri, ""), ("/admin/labels/0x1234", "Acme")] {
+        let request = json!({ "label": label });
+        let (status, _) = send(&router, Method::PUT, address, TOKEN, Some(request)).await?;
+        assert_eq!(status, 400);
+    }
+    let request = json!({ "label": "Acme" });
+    let (status, _) = send(&router, Method::PUT, &uri, TOKEN, Some(request)).await?;
+    assert_eq!(status, 204);
+    let (_, labels) = send(&router, Method::GET, "/admin/labels", TOKEN, None).await?;
+    assert_eq!(labels[0]["address"], base.to_checksum(None));
+    assert_eq!(labels[0]["label"], "Acme");
+    assert_eq!(attribution().await?, (json!("Acme"), json!("manual")));
+
+    let (status, _) = send(&router, Method::DELETE, &uri, TOKEN, None).await?;
+    assert_eq!(status, 204);
+    let (status, _) = send(&router, Method::DELETE, &uri, TOKEN, None).await?;
+    assert_eq!(status, 404);
+    assert_eq!(attribution().await?, (json!("Base"), json!("registry")));
+
+    let actions: Vec<String> = db
+        .get_admin_audit_log(10)?
+        .into_iter()
+        .map(|entry| entry.action)
+        .collect();
+    assert_eq!(actions, ["delete_label", "put_label"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn annotations_are_edited_by_admins_and_marked_on_charts() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    index(&db, Address::repeat_byte(0x11))?;
+    let router = admin::router(db.clone(), TOKEN, None);
+    let api = api::router(db.clone(), api::Limits::default(), EventBus::new());
+
+    let release = json!({ "timestamp": 1_767_747_680, "kind": "release", "title": "v1.2" });
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/annotations",
+        "wrong token",
+        Some(release.clone()),
+    )
+    .await?;
+    assert_eq!(status, 401);
+    let untitled = json!({ "timestamp": 1_767_747_680, "kind": "release", "title": " " });
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/annotations",
+        TOKEN,
+        Some(untitled),
+    )
+    .await?;
+    assert_eq!(status, 400);
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/annotations",
+        TOKEN,
+        Some(release),
+    )
+    .await?;
+    assert_eq!(status, 201);
+    // After the last indexed block, so not on the chart yet
+    let incident = json!({ "timestamp": 1_767_750_000, "kind": "incident", "title": "Outage" });
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/annotations",
+        TOKEN,
+        Some(incident),
+    )
+    .await?;
+    assert_eq!(status, 201);
+
+    let (_, annotations) = send(&api, Method::GET, "/api/annotations", "", None).await?;
+    assert_eq!(annotations.as_array().map(Vec::len), Some(2));
+    assert_eq!(annotations[0]["block_number"], 1);
+    assert_eq!(annotations[1]["block_number"], Value::Null);
+    let (_, chart) = send(&api, Method::GET, "/api/chart", "", None).await?;
+    assert_eq!(chart["annotations"].as_array().map(Vec::len), Some(1));
+    assert_eq!(chart["annotations"][0]["title"], "v1.2");
+
+    let id = &annotations[0]["id"];
+    let uri = format!("/api/annotations/{id}");
+    let renamed = json!({ "timestamp": 1_767_747_680, "kind": "release", "title": "v1.2.1" });
+    let (status, _) = send(&router, Method::PUT, &uri, TOKEN, Some(renamed.clone())).await?;
+    assert_eq!(status, 204);
+    let (status, _) = send(
+        &router,
+        Method::PUT,
+        "/api/annotations/999",
+        TOKEN,
+        Some(renamed),
+    )
+    .await?;
+    assert_eq!(status, 404);
+    let (_, chart) = send(&api, Method::GET, "/api/chart", "", None).await?;
+    assert_eq!(chart["annotations"][0]["title"], "v1.2.1");
+    let (status, _) = send(&router, Method::DELETE, &uri, TOKEN, None).await?;
+    assert_eq!(status, 204);
+    let (status, _) = send(&router, Method::DELETE, &uri, TOKEN, None).await?;
+    assert_eq!(status, 404);
+    let (_, chart) = send(&api, Method::GET, "/api/chart", "", None).await?;
+    assert_eq!(chart["annotations"], json!([]));
+
+    let actions: Vec<String> = db
+        .get_admin_audit_log(10)?
+        .into_iter()
+        .map(|entry| entry.action)
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            "delete_annotation",
+            "put_annotation",
+            "post_annotation",
+            "post_annotation"
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! Alert rules, and the API keys operators issue for them.
+
+use alloy_primitives::Address;
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+    Router,
+};
+use blob_exex::{
+    admin, alerts,
+    db::NewBlock,
+    events::{Event, EventBus},
+    Database,
+};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "0123456789abcdef";
+
+fn router(db: &Database) -> Router {
+    admin::router(db.clone(), ADMIN_TOKEN, None).merge(alerts::router(db.clone(), EventBus::new()))
+}
+
+/// Status of a request to `uri` carrying `token`, and its JSON body if it
+/// succeeded.
+async fn send(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> eyre::Result<(u16, Value)> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))?;
+    let response = router.clone().oneshot(request).await?;
+    let status = response.status().as_u16();
+    let body = response.into_body().collect().await?.to_bytes();
+    let body = if (200..300).contains(&status) && !body.is_empty() {
+        serde_json::from_slice(&body)?
+    } else {
+        Value::Null
+    };
+    Ok((status, body))
+}
+
+/// A key issued to `name` through the admin routes.
+async fn issue_key(router: &Router, name: &str) -> eyre::Result<String> {
+    let (status, issued) = send(
+        router,
+        Method::POST,
+        "/admin/alerts/keys",
+        ADMIN_TOKEN,
+        Some(json!({ "name": name })),
+    )
+    .await?;
+    assert_eq!(status, 201);
+    Ok(issued["key"].as_str().unwrap().to_string())
+}
+
+fn rule(delivery: Value) -> Value {
+    json!({ "condition": { "kind": "fork_activated" }, "delivery": delivery })
+}
+
+#[tokio::test]
+async fn only_issued_keys_manage_rules() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let router = router(&db);
+    let stream = rule(json!({ "type": "stream" }));
+
+    // Long enough, but chosen by the caller
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/alerts/rules",
+        "a key nobody issued",
+        Some(stream.clone()),
+    )
+    .await?;
+    assert_eq!(status, 401);
+
+    let key = issue_key(&router, "rollup team").await?;
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/alerts/rules",
+        &key,
+        Some(stream),
+    )
+    .await?;
+    assert_eq!(status, 201);
+    let (status, rules) = send(&router, Method::GET, "/api/alerts/rules", &key, None).await?;
+    assert_eq!(status, 200);
+    assert_eq!(rules.as_array().unwrap().len(), 1);
+
+    let (_, keys) = send(
+        &router,
+        Method::GET,
+        "/admin/alerts/keys",
+        ADMIN_TOKEN,
+        None,
+    )
+    .await?;
+    assert_eq!(keys[0]["name"], "rollup team");
+    let owner = keys[0]["owner"].as_str().unwrap();
+    let uri = format!("/admin/alerts/keys/{owner}");
+    let (status, _) = send(&router, Method::DELETE, &uri, ADMIN_TOKEN, None).await?;
+    assert_eq!(status, 204);
+
+    let (status, _) = send(&router, Method::GET, "/api/alerts/rules", &key, None).await?;
+    assert_eq!(status, 401);
+    assert!(db.get_alert_rules(None)?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn webhooks_to_internal_addresses_are_refused() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let router = router(&db);
+    let key = issue_key(&router, "ops").await?;
+
+    for url in [
+        "http://169.254.169.254/latest/meta-data/",
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.0.0.1/hook",
+        "http://192.168.1.1/hook",
+        "http://100.64.0.1/hook",
+        "http://0.0.0.0/hook",
+        "http://[::1]/hook",
+        "http://[fe80::1]/hook",
+        "http://[fd00::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+        "ftp://1.1.1.1/hook",
+    ] {
+        let webhook = rule(json!({ "type": "webhook", "url": url }));
+        let (status, _) = send(
+            &router,
+            Method::POST,
+            "/api/alerts/rules",
+            &key,
+            Some(webhook),
+        )
+        .await?;
+        assert_eq!(status, 400, "{url}");
+    }
+
+    let webhook = rule(json!({ "type": "webhook", "url": "https://1.1.1.1/hook" }));
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/alerts/rules",
+        &key,
+        Some(webhook),
+    )
+    .await?;
+    assert_eq!(status, 201);
+    Ok(())
+}
+
+/// A block `age` seconds old under a blob schedule of `target` and `max`.
+fn block(block_number: u64, age: u64, target: u64, max: u64) -> eyre::Result<NewBlock> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(NewBlock {
+        block_number,
+        block_timestamp: now - age,
+        tx_count: 0,
+        total_blobs: 0,
+        gas_used: 0,
+        gas_price: 1,
+        excess_blob_gas: 0,
+        base_fee_per_gas: 7,
+        priority_fees: None,
+        blob_target: target,
+        blob_max: max,
+        header_blob_gas_used: Some(0),
+        block_hash: format!("{block_number:#066x}"),
+        beneficiary: Address::repeat_byte(0x24),
+    })
+}
+
+#[tokio::test]
+async fn observed_forks_fire_once() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let router = router(&db);
+    let key = issue_key(&router, "researchers").await?;
+    let stream = rule(json!({ "type": "stream" }));
+    let (status, _) = send(
+        &router,
+        Method::POST,
+        "/api/alerts/rules",
+        &key,
+        Some(stream),
+    )
+    .await?;
+    assert_eq!(status, 201);
+    let owner = db.get_alert_rules(None)?.remove(0).owner;
+    let bus = EventBus::new();
+    let mut events = bus.subscribe();
+
+    // Long enough ago to be history rather than news
+    db.insert_block(&block(1, 2 * alerts::FORK_ALERT_WINDOW, 3, 6)?)?;
+    db.insert_block(&block(2, 2 * alerts::FORK_ALERT_WINDOW - 12, 6, 9)?)?;
+    alerts::evaluate(&db, &bus).await?;
+    assert!(db.get_alert_events(&owner, 0, 10)?.is_empty());
+
+    db.insert_block(&block(3, 24, 6, 9)?)?;
+    db.insert_block(&block(4, 12, 14, 21)?)?;
+    alerts::evaluate(&db, &bus).await?;
+    alerts::evaluate(&db, &bus).await?;
+    let fired = db.get_alert_events(&owner, 0, 10)?;
+    assert_eq!(fired.len(), 1);
+    assert_eq!(
+        fired[0].message,
+        "blob schedule changed at block 4: target 6 -> 14, max 9 -> 21"
+    );
+    assert_eq!(events.try_recv()?, Event::AlertFired { id: fired[0].id });
+    assert!(events.try_recv().is_err());
+    Ok(())
+}
@@ -0,0 +1,2077 @@
+//! Responses of the public API, requested through its router.
+
+use alloy_eips::{eip4844::DATA_GAS_PER_BLOB, eip7840::BlobParams};
+use alloy_primitives::{address, Address};
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use blob_exex::{
+    api::{self, ApiError, Limits},
+    db::{
+        self, NewBlobTransaction, NewBlock, NewIngestLogEntry, NewPendingBlobTransaction,
+        PriorityFees,
+    },
+    events::{Event, EventBus},
+    maintenance,
+    regime::RegimeThresholds,
+    schedule::BlobScheduleEntry,
+    BlobSchedule, Database, DbError,
+};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tower::ServiceExt;
+
+const TIMESTAMP: u64 = 1_767_747_671;
+
+fn router(db: &Database) -> Router {
+    api::router(db.clone(), Limits::default(), EventBus::new())
+}
+
+/// Status and JSON body of a GET request to `uri`.
+async fn get(router: Router, uri: &str) -> eyre::Result<(u16, Value)> {
+    let response = router
+        .oneshot(Request::get(uri).body(Body::empty())?)
+        .await?;
+    let status = response.status().as_u16();
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok((status, serde_json::from_slice(&body)?))
+}
+
+/// A block numbered `block_number`, `blobs` of its max of 21 used.
+fn block(block_number: u64, blobs: u64) -> NewBlock {
+    NewBlock {
+        block_number,
+        block_timestamp: TIMESTAMP + block_number * 12,
+        tx_count: 0,
+        total_blobs: blobs,
+        gas_used: blobs as i64 * 131_072,
+        gas_price: 1,
+        excess_blob_gas: 0,
+        base_fee_per_gas: 7,
+        priority_fees: None,
+        blob_target: 14,
+        blob_max: 21,
+        header_blob_gas_used: Some(blobs * 131_072),
+        block_hash: format!("{block_number:#066x}"),
+        beneficiary: Address::repeat_byte(0x24),
+    }
+}
+
+/// Hash of the `i`th blob tx of a block.
+fn tx_hash(block_number: u64, i: usize) -> String {
+    format!("0x{block_number:032x}{i:032x}")
+}
+
+/// Index `block(block_number, ..)` the way the ExEx does, with one blob tx
+/// per entry of `txs`: its sender and the size of each of its blobs, `None`
+/// when the sidecar wasn't available. The `i`th tx pays a priority fee of `i`.
+fn index(db: &Database, block_number: u64, txs: &[(Address, &[Option<u64>])]) -> eyre::Result<()> {
+    index_block(db, block(block_number, 0), txs)
+}
+
+/// Index `block` like [`index`], its blob usage following from `txs`.
+fn index_block(
+    db: &Database,
+    block: NewBlock,
+    txs: &[(Address, &[Option<u64>])],
+) -> eyre::Result<()> {
+    let (block_number, timestamp) = (block.block_number, block.block_timestamp);
+    let mut blobs = 0;
+    for (i, (sender, sizes)) in txs.iter().enumerate() {
+        let tx_hash = tx_hash(block_number, i);
+        db.insert_blob_transaction(&NewBlobTransaction {
+            tx_hash: &tx_hash,
+            block_number,
+            sender: *sender,
+            nonce: block_number,
+            tx_type: 3,
+            blob_count: sizes.len() as i64,
+            gas_price: block.gas_price,
+            priority_fee: i as i64,
+            created_at: timestamp,
+            el_size: 200,
+            payload_size: sizes.iter().copied().sum(),
+            to: None,
+        })?;
+        for (blob_index, size) in sizes.iter().enumerate() {
+            let blob_hash = format!("0x01{block_number:030x}{i:016x}{blob_index:016x}");
+            db.insert_blob_hash(&tx_hash, &blob_hash, blob_index as i64, *size)?;
+        }
+        let blob_size = sizes.iter().map(|size| size.unwrap_or(131_072)).sum();
+        db.update_sender(
+            sender,
+            block_number,
+            timestamp,
+            sizes.len() as u64,
+            blob_size,
+        )?;
+        blobs += sizes.len() as u64;
+    }
+    db.insert_block(&NewBlock {
+        tx_count: txs.len() as u64,
+        total_blobs: blobs,
+        gas_used: blobs as i64 * 131_072,
+        header_blob_gas_used: Some(blobs * 131_072),
+        priority_fees: PriorityFees::from_sorted(&(0..txs.len() as u64).collect::<Vec<_>>()),
+        ..block
+    })?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn blob_fee_history_includes_the_next_blocks_fee() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    db.insert_block(&block(1, 0))?;
+    // Indexed under a smaller max than the current schedule's
+    db.insert_block(&NewBlock {
+        blob_max: 6,
+        ..block(2, 21)
+    })?;
+    db.insert_block(&NewBlock {
+        excess_blob_gas: 10_000_000,
+        gas_price: 3,
+        ..block(3, 21)
+    })?;
+
+    let (status, history) = get(router(&db), "/api/blob-fee-history?block_count=3").await?;
+    assert_eq!(status, 200);
+    assert_eq!(history["oldestBlock"], "0x1");
+
+    let params = BlobParams::bpo2();
+    let next_fee = params.calc_blob_fee(params.next_block_excess_blob_gas_osaka(
+        10_000_000,
+        21 * DATA_GAS_PER_BLOB,
+        7,
+    ));
+    assert_eq!(
+        history["baseFeePerBlobGas"],
+        json!(["0x1", "0x1", "0x3", format!("{next_fee:#x}")])
+    );
+    assert_eq!(history["blobGasUsedRatio"], json!([0.0, 3.5, 1.0]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn bad_parameters_get_json_errors_naming_them() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    db.insert_block(&block(1, 3))?;
+
+    for (uri, param) in [
+        ("/api/heatmap?tz_offset=15", Some("tz_offset")),
+        ("/api/heatmap?tz_offset=east", None),
+        ("/api/heatmap?metric=price", Some("metric")),
+        ("/api/heatmap?group=month", Some("group")),
+        ("/api/heatmap?days=0", Some("days")),
+        ("/api/concentration?window=forever", Some("window")),
+        ("/api/stats?windows=1h,soon", Some("window")),
+        (
+            "/api/blob-fee-history?newest_block=tip",
+            Some("newest_block"),
+        ),
+        (
+            "/api/blob-fee-history?reward_percentiles=50,10",
+            Some("reward_percentiles"),
+        ),
+        ("/api/blob-fee-history?block_count=-1", None),
+        ("/api/chart?blocks=0", Some("blocks")),
+        (
+            "/api/blocks/range?from=1&to=2&include=receipts",
+            Some("include"),
+        ),
+    ] {
+        let (status, body) = get(router(&db), uri).await?;
+        assert_eq!(status, 400, "{uri}");
+        assert!(
+            body["error"]
+                .as_str()
+                .is_some_and(|error| !error.is_empty()),
+            "{uri}"
+        );
+        assert_eq!(body["param"].as_str(), param, "{uri}");
+    }
+
+    let (status, body) = get(router(&db), "/api/blocks/42").await?;
+    assert_eq!(status, 404);
+    assert_eq!(body["error"], "block 42 is not indexed");
+    Ok(())
+}
+
+#[test]
+fn internal_database_errors_are_not_described() {
+    for err in [
+        DbError::Io("failed to open /var/lib/blobs/blobs.db".to_string()),
+        DbError::Schema("no such column: secret in SELECT secret FROM blocks".to_string()),
+        DbError::Corrupt("database disk image is malformed".to_string()),
+    ] {
+        let err = ApiError::from(err);
+        assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(err.error, "internal error");
+    }
+
+    let err = ApiError::from(DbError::InvalidInput("limit is too large".to_string()));
+    assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    assert_eq!(err.error, "invalid input: limit is too large");
+}
+
+#[tokio::test]
+async fn blob_sizes_fall_back_to_the_full_blob() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    index(
+        &db,
+        1,
+        &[(Address::repeat_byte(0x11), &[Some(1_000), None, Some(0)])],
+    )?;
+
+    let (status, tx) = get(router(&db), &format!("/api/txs/{}", tx_hash(1, 0))).await?;
+    assert_eq!(status, 200);
+    assert_eq!(tx["blob_sizes"], json!([1_000, null, 0]));
+    assert_eq!(tx["blob_size"], 1_000 + 131_072);
+
+    let (_, block) = get(router(&db), "/api/blocks/1").await?;
+    assert_eq!(block["total_blob_size"], 1_000 + 131_072);
+    Ok(())
+}
+
+#[tokio::test]
+async fn inclusion_market_compares_included_and_left_out_fees() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let sender = Address::repeat_byte(0x11);
+    index(
+        &db,
+        1,
+        &[(sender, &[None]), (sender, &[None]), (sender, &[None])],
+    )?;
+    index(&db, 2, &[(sender, &[None])])?;
+    for (i, priority_fee) in [5, 1].into_iter().enumerate() {
+        db.insert_pending_blob_transaction(&NewPendingBlobTransaction {
+            block_number: 1,
+            tx_hash: &tx_hash(100, i),
+            sender: Address::repeat_byte(0x22),
+            nonce: i as u64,
+            blob_count: 1,
+            priority_fee,
+            max_fee_per_blob_gas: 1,
+        })?;
+    }
+
+    let (status, market) = get(router(&db), "/api/inclusion-market?blocks=2").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        market,
+        json!([
+            {
+                "block_number": 2,
+                "tx_count": 1,
+                "included": {"min": 0, "median": 0, "max": 0},
+                "excluded_count": 0,
+                "excluded": null,
+            },
+            {
+                "block_number": 1,
+                "tx_count": 3,
+                "included": {"min": 0, "median": 1, "max": 2},
+                "excluded_count": 2,
+                "excluded": {"min": 1, "median": 3, "max": 5},
+            },
+        ])
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn blocks_follow_the_stored_blob_schedule() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    for block_number in 1..=3 {
+        db.insert_block(&block(block_number, 3))?;
+    }
+    let entries = [
+        BlobScheduleEntry {
+            activation_timestamp: 0,
+            target: 3,
+            max: 6,
+            base_fee_update_fraction: 3_338_477,
+        },
+        BlobScheduleEntry {
+            activation_timestamp: TIMESTAMP + 24,
+            target: 6,
+            max: 9,
+            base_fee_update_fraction: 5_007_716,
+        },
+    ];
+    db.replace_blob_schedule(&BlobSchedule::new(entries.to_vec()).unwrap())?;
+
+    let (status, schedule) = get(router(&db), "/api/blob-schedule").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        schedule,
+        json!([
+            {"activation_timestamp": 0, "target": 3, "max": 6, "base_fee_update_fraction": 3_338_477},
+            {
+                "activation_timestamp": TIMESTAMP + 24,
+                "target": 6,
+                "max": 9,
+                "base_fee_update_fraction": 5_007_716,
+            },
+        ])
+    );
+    for (block_number, target, max) in [(1, 3, 6), (2, 6, 9), (3, 6, 9)] {
+        let (_, block) = get(router(&db), &format!("/api/blocks/{block_number}")).await?;
+        assert_eq!(
+            (&block["blob_target"], &block["blob_max"]),
+            (&json!(target), &json!(max))
+        );
+    }
+
+    // Saturated from the max of the latest entry
+    let (_, regimes) = get(router(&db), "/api/regimes").await?;
+    assert_eq!(regimes[4]["name"], "saturated");
+    assert_eq!(regimes[4]["from_pct"], 150.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn stored_regime_thresholds_classify_blocks() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    // 12 of a target of 14, about 86%
+    db.insert_block(&block(1, 12))?;
+    let (_, block) = get(router(&db), "/api/blocks/1").await?;
+    assert_eq!(block["regime"], "normal");
+
+    db.replace_regime_thresholds(&RegimeThresholds::parse("10, 20, 80, 95")?)?;
+    let (_, block) = get(router(&db), "/api/blocks/1").await?;
+    assert_eq!(block["regime"], "congested");
+    let (_, regimes) = get(router(&db), "/api/regimes").await?;
+    assert_eq!(
+        regimes[3],
+        json!({ "name": "congested", "from_pct": 80.0, "to_pct": 95.0 })
+    );
+    let (_, stats) = get(router(&db), "/api/stats").await?;
+    assert_eq!(stats["regimes"], regimes);
+
+    assert!(RegimeThresholds::parse("10,80,20,95").is_err());
+    assert!(RegimeThresholds::parse("10,20,80").is_err());
+    Ok(())
+}
+
+/// 10:00 UTC two days ago.
+fn recent_hour() -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    db::group_by_day(now - 2 * 86400, 0) + 10 * 3600
+}
+
+#[tokio::test]
+async fn heatmap_cells_are_in_local_time() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let hour = recent_hour();
+    for (block_number, block_timestamp, blobs) in
+        [(1, hour, 3), (2, hour + 12, 4), (3, hour + 3600, 7)]
+    {
+        db.insert_block(&NewBlock {
+            block_timestamp,
+            ..block(block_number, blobs)
+        })?;
+    }
+
+    let (status, heatmap) = get(
+        router(&db),
+        "/api/heatmap?days=7&tz_offset=-3&group=hour_of_day&metric=blobs",
+    )
+    .await?;
+    assert_eq!(status, 200);
+    let cells = heatmap["cells"].as_array().unwrap();
+    assert_eq!(cells.len(), 24);
+    assert_eq!(
+        (&cells[7]["hour"], &cells[7]["blocks"]),
+        (&json!(7), &json!(2))
+    );
+    assert_eq!(cells[7]["value"], 3.5);
+    assert_eq!(cells[8]["value"], 7.0);
+    assert_eq!(
+        cells
+            .iter()
+            .map(|cell| cell["blocks"].as_u64().unwrap())
+            .sum::<u64>(),
+        3
+    );
+
+    let (_, heatmap) = get(router(&db), "/api/heatmap?days=7&tz_offset=-3").await?;
+    let cell = &heatmap["cells"][db::weekday(hour, -3 * 3600) * 24 + 7];
+    assert_eq!(
+        (&cell["hour"], &cell["total_blobs"]),
+        (&json!(7), &json!(7))
+    );
+    assert_eq!(cell["value"], 7.0 / (2.0 * 14.0) * 100.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn senders_are_attributed_and_returned_checksummed() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    index(&db, 1, &[(base, &[None])])?;
+
+    let (status, senders) = get(router(&db), "/api/senders").await?;
+    assert_eq!(status, 200);
+    assert_eq!(senders.as_array().map(Vec::len), Some(1));
+    assert_eq!(senders[0]["address"], base.to_checksum(None));
+    assert_eq!(senders[0]["chain"], "Base");
+
+    // Either casing finds the sender
+    for address in [base.to_checksum(None), base.to_string().to_lowercase()] {
+        let (_, blobs) = get(router(&db), &format!("/api/sender/{address}/blobs")).await?;
+        assert_eq!(blobs["sender"], base.to_checksum(None));
+        assert_eq!(blobs["blobs"].as_array().map(Vec::len), Some(1));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn tail_streams_new_transactions_as_ndjson() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let sender = Address::repeat_byte(0x11);
+    index(&db, 1, &[(sender, &[None])])?;
+    let bus = EventBus::new();
+    let router = api::router(db.clone(), Limits::default(), bus.clone());
+
+    let response = router
+        .oneshot(Request::get("/api/tail?format=ndjson").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let mut body = response.into_body();
+    let mut next_lines = async || -> eyre::Result<Vec<Value>> {
+        let frame = body.frame().await.expect("stream is open")?;
+        let data = frame.into_data().expect("a data frame");
+        let lines = std::str::from_utf8(&data)?.lines();
+        Ok(lines.map(serde_json::from_str).collect::<Result<_, _>>()?)
+    };
+
+    // Already indexed blocks aren't replayed
+    let heartbeat = next_lines().await?;
+    assert_eq!(heartbeat[0]["type"], "heartbeat");
+    assert_eq!(heartbeat[0]["latest_block"], 1);
+
+    index(&db, 2, &[(sender, &[None]), (sender, &[None, None])])?;
+    bus.publish(Event::BlockIndexed { block_number: 2 });
+    let txs = next_lines().await?;
+    assert_eq!(txs.len(), 2);
+    assert_eq!(txs[0]["tx_hash"], tx_hash(2, 0));
+    assert_eq!(txs[1]["blob_count"], 2);
+
+    bus.publish(Event::ReorgDetected {
+        first_block: 2,
+        last_block: 2,
+    });
+    let reorg = next_lines().await?;
+    assert_eq!(
+        reorg,
+        [json!({"type": "reorg", "first_block": 2, "last_block": 2})]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn concentration_counts_senders_and_chains() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let base_alt = address!("0xff00000000000000000000000000000000008453");
+    let other = Address::repeat_byte(0x11);
+    index(
+        &db,
+        1,
+        &[
+            (base, &[None, None, None]),
+            (base_alt, &[None]),
+            (other, &[None, None, None, None]),
+        ],
+    )?;
+
+    let (status, concentration) = get(router(&db), "/api/concentration?window=5200w&top=1").await?;
+    assert_eq!(status, 200);
+    assert_eq!(concentration["total_blobs"], 8);
+    let close = |value: &Value, expected: f64| (value.as_f64().unwrap() - expected).abs() < 1e-9;
+
+    let senders = &concentration["senders"];
+    assert_eq!(senders["participants"], 3);
+    assert!(close(&senders["gini"], 0.25), "{senders}");
+    assert!(close(
+        &senders["hhi"],
+        12.5f64.powi(2) + 37.5f64.powi(2) + 2500.0
+    ));
+    assert!(close(&senders["top_n_share"], 50.0));
+
+    // Both Base senders count as one chain
+    let chains = &concentration["chains"];
+    assert_eq!(chains["participants"], 2);
+    assert!(close(&chains["gini"], 0.0), "{chains}");
+    assert!(close(&chains["hhi"], 5000.0));
+    assert!(close(&chains["top_n_share"], 50.0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn blob_savings_price_payloads_as_floor_calldata() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    index(&db, 1, &[(base, &[Some(1_000), None])])?;
+
+    let (status, savings) = get(router(&db), "/api/blob-savings?window=5200w").await?;
+    assert_eq!(status, 200);
+    let savings = &savings[0];
+    assert_eq!(savings["chain"], "Base");
+    assert_eq!(savings["payload_bytes"], 1_000 + 131_072);
+    assert_eq!(savings["unpriced_tx_count"], 0);
+    // 40 gas per byte at the block's base fee of 7 wei, against 1 wei per blob gas
+    let calldata_wei = (1_000 + 131_072) * 40 * 7;
+    let blob_wei = 2 * DATA_GAS_PER_BLOB;
+    let close = |value: &Value, expected: f64| (value.as_f64().unwrap() - expected).abs() < 1e-12;
+    assert!(close(
+        &savings["calldata_cost_eth"],
+        calldata_wei as f64 / 1e18
+    ));
+    assert!(close(&savings["blob_fees_eth"], blob_wei as f64 / 1e18));
+    assert!(close(
+        &savings["savings_eth"],
+        (calldata_wei - blob_wei) as f64 / 1e18
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn throughput_counts_missed_slots_as_empty() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    // The slot before block 3 was missed
+    for (block_number, block_timestamp, blobs) in [
+        (1, TIMESTAMP, 3),
+        (2, TIMESTAMP + 12, 6),
+        (3, TIMESTAMP + 36, 3),
+    ] {
+        db.insert_block(&NewBlock {
+            block_timestamp,
+            ..block(block_number, blobs)
+        })?;
+    }
+
+    let (status, stats) = get(router(&db), "/api/stats?windows=1h").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        (&stats["total_slots"], &stats["missed_slots"]),
+        (&json!(4), &json!(1))
+    );
+    assert_eq!(stats["avg_blobs_per_slot"], 3.0);
+    let window = &stats["throughput_windows"][0];
+    assert_eq!(window["window"], "1h");
+    assert_eq!(
+        (&window["slots"], &window["missed_slots"]),
+        (&json!(4), &json!(1))
+    );
+    assert_eq!(window["blobs_per_block"], 4.0);
+    assert_eq!(window["blobs_per_slot"], 3.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bandwidth_is_blobs_over_block_time() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    // The slot before block 3 was missed
+    for (block_number, block_timestamp, blobs) in [
+        (1, TIMESTAMP, 3),
+        (2, TIMESTAMP + 12, 6),
+        (3, TIMESTAMP + 36, 3),
+    ] {
+        db.insert_block(&NewBlock {
+            block_timestamp,
+            ..block(block_number, blobs)
+        })?;
+    }
+
+    let (_, chart) = get(router(&db), "/api/chart?blocks=3").await?;
+    let bandwidth: Vec<(u64, Option<f64>)> = chart["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(chart["bandwidth_bytes_per_sec"].as_array().unwrap())
+        .map(|(label, bandwidth)| (label.as_u64().unwrap(), bandwidth.as_f64()))
+        .collect();
+    // Block 1's parent isn't indexed
+    assert!(bandwidth.contains(&(1, None)));
+    assert!(bandwidth.contains(&(2, Some(6.0 * 131_072.0 / 12.0))));
+    assert!(bandwidth.contains(&(3, Some(3.0 * 131_072.0 / 24.0))));
+
+    let (_, stats) = get(router(&db), "/api/stats?windows=1h").await?;
+    let window = &stats["throughput_windows"][0];
+    assert_eq!(window["sustained_bytes_per_sec"], 3.0 * 131_072.0 / 12.0);
+    assert_eq!(window["peak_bytes_per_sec"], 6.0 * 131_072.0 / 12.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reposted_blobs_count_once_a_day() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let hour = recent_hour();
+    let alpha = Address::repeat_byte(0x11);
+    for (block_number, block_timestamp) in [(1, hour), (2, hour + 12)] {
+        let block = NewBlock {
+            block_timestamp,
+            ..block(block_number, 0)
+        };
+        index_block(&db, block, &[(alpha, &[None])])?;
+    }
+    // Block 2's tx posts block 1's blob again, then the same reference is
+    // stored twice
+    let reposted = format!("0x01{:030x}{:016x}{:016x}", 1, 0, 0);
+    for _ in 0..2 {
+        db.insert_blob_hash(&tx_hash(2, 0), &reposted, 1, None)?;
+    }
+
+    let (_, stats) = get(router(&db), "/api/stats").await?;
+    assert_eq!(
+        (&stats["total_blob_refs"], &stats["distinct_daily_blobs"]),
+        (&json!(3), &json!(2))
+    );
+    let (status, daily) = get(router(&db), "/api/blob-hashes/daily?days=7").await?;
+    assert_eq!(status, 200);
+    assert_eq!(daily["blob_refs"], json!([0, 0, 0, 0, 3, 0, 0]));
+    assert_eq!(daily["distinct_blobs"], json!([0, 0, 0, 0, 2, 0, 0]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn mirrors_sync_page_by_page_until_a_reorg() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let alpha = Address::repeat_byte(0x11);
+    for block_number in 1..=3 {
+        index(&db, block_number, &[(alpha, &[None])])?;
+    }
+
+    let (status, page) = get(router(&db), "/api/sync?limit=2").await?;
+    assert_eq!(status, 200);
+    assert_eq!(page["blocks"].as_array().map(Vec::len), Some(2));
+    assert_eq!(page["transactions"][1]["tx_hash"], tx_hash(2, 0));
+    assert_eq!(page["cursor"], format!("2-{:#066x}", 2));
+    assert_eq!(page["has_more"], true);
+    let cursor = page["cursor"].as_str().unwrap().to_string();
+    let (_, page) = get(router(&db), &format!("/api/sync?limit=2&cursor={cursor}")).await?;
+    assert_eq!(page["blocks"][0]["block_number"], 3);
+    assert_eq!(page["has_more"], false);
+    let (_, caught_up) = get(router(&db), "/api/sync?since_block=3").await?;
+    assert_eq!(caught_up["blocks"], json!([]));
+    assert_eq!(caught_up["cursor"], format!("3-{:#066x}", 3));
+
+    // Block 2 is replaced by a sibling the mirror hasn't seen
+    db.delete_block(2)?;
+    let sibling = NewBlock {
+        block_hash: format!("{:#066x}", 0x22),
+        ..block(2, 0)
+    };
+    index_block(&db, sibling, &[(alpha, &[None])])?;
+    let (status, _) = get(router(&db), &format!("/api/sync?cursor={cursor}")).await?;
+    assert_eq!(status, 409);
+    Ok(())
+}
+
+#[tokio::test]
+async fn builders_are_ranked_by_blocks_built() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let hour = recent_hour();
+    let (alpha, beta) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb));
+    for (block_number, beneficiary, blobs) in [(1, alpha, 0), (2, beta, 6), (3, alpha, 21)] {
+        db.insert_block(&NewBlock {
+            block_timestamp: hour + block_number * 12,
+            beneficiary,
+            ..block(block_number, blobs)
+        })?;
+    }
+
+    let (_, block) = get(router(&db), "/api/blocks/2").await?;
+    assert_eq!(block["beneficiary"], beta.to_checksum(None));
+    let (status, builders) = get(router(&db), "/api/builders").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        (&builders["blocks"], &builders["avg_blobs_per_block"]),
+        (&json!(3), &json!(9.0))
+    );
+    let top = &builders["builders"][0];
+    assert_eq!(top["beneficiary"], alpha.to_checksum(None));
+    assert_eq!(
+        (&top["blocks"], &top["total_blobs"]),
+        (&json!(2), &json!(21))
+    );
+    assert!((top["share_pct"].as_f64().unwrap() - 200.0 / 3.0).abs() < 1e-9);
+    assert_eq!(top["blobs_vs_avg"], 1.5);
+    assert_eq!(
+        (&top["empty_blocks"], &top["full_blocks"]),
+        (&json!(1), &json!(1))
+    );
+    assert_eq!(builders["builders"][1]["blobs_vs_avg"], -3.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reindexed_blocks_are_not_counted_twice() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (alpha, beta) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    index(&db, 1, &[(alpha, &[None])])?;
+    index(&db, 2, &[(alpha, &[Some(1_000), None]), (beta, &[None])])?;
+    let (_, senders) = get(router(&db), "/api/senders").await?;
+
+    // Reverted by a reorg, then indexed again
+    db.delete_block(2)?;
+    let (_, reverted) = get(router(&db), "/api/senders").await?;
+    assert_eq!(reverted.as_array().map(Vec::len), Some(1));
+    assert_eq!(reverted[0]["tx_count"], 1);
+    index(&db, 2, &[(alpha, &[Some(1_000), None]), (beta, &[None])])?;
+    assert_eq!(get(router(&db), "/api/senders").await?.1, senders);
+
+    let (status, consistency) = get(router(&db), "/api/consistency/senders").await?;
+    assert_eq!(status, 200);
+    assert_eq!(consistency, json!({"drifted_senders": 0, "drift": []}));
+
+    // A blind increment is reported as drift
+    db.update_sender(&beta, 2, TIMESTAMP + 24, 1, 131_072)?;
+    let (_, consistency) = get(router(&db), "/api/consistency/senders").await?;
+    assert_eq!(consistency["drifted_senders"], 1);
+    let drift = &consistency["drift"][0];
+    assert_eq!(drift["address"], beta.to_checksum(None));
+    assert_eq!(drift["stored"]["tx_count"], 2);
+    assert_eq!(drift["derived"]["tx_count"], 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn resubmissions_count_every_attempt_at_a_nonce() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let sender = Address::repeat_byte(0x11);
+    index(&db, 1, &[(sender, &[None])])?;
+    // Reorged out and included again, after a rebid was seen pending
+    index(&db, 2, &[(sender, &[None])])?;
+    db.archive_reverted_block(2)?;
+    db.delete_block(2)?;
+    db.insert_pending_blob_transaction(&NewPendingBlobTransaction {
+        block_number: 2,
+        tx_hash: &tx_hash(2, 1),
+        sender,
+        nonce: 2,
+        blob_count: 1,
+        priority_fee: 2,
+        max_fee_per_blob_gas: 1,
+    })?;
+    index(&db, 2, &[(sender, &[None])])?;
+
+    let (status, resubmissions) = get(router(&db), "/api/resubmissions?window=5200w").await?;
+    assert_eq!(status, 200);
+    assert_eq!(resubmissions["tx_count"], 2);
+    assert_eq!(resubmissions["resubmitted_tx_count"], 1);
+    assert_eq!(resubmissions["resubmission_rate"], 0.5);
+    assert_eq!(resubmissions["avg_attempts"], 1.5);
+    assert_eq!(resubmissions["reincluded_tx_count"], 1);
+    assert_eq!(resubmissions["chains"][0]["chain"], "Other");
+    Ok(())
+}
+
+#[tokio::test]
+async fn excess_blob_gas_build_ups_and_fee_doublings() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let hour = recent_hour();
+    for (block_number, excess_blob_gas, gas_price, blobs) in [
+        (1, 0, 1, 14),
+        (2, 100, 1, 21),
+        (3, 300, 2, 21),
+        (4, 200, 2, 0),
+        (5, 400, 3, 21),
+        (6, 500, 4, 21),
+    ] {
+        db.insert_block(&NewBlock {
+            block_timestamp: hour + block_number * 12,
+            excess_blob_gas,
+            gas_price,
+            ..block(block_number, blobs)
+        })?;
+    }
+
+    let (status, excess) = get(router(&db), "/api/excess-blob-gas?hours=72").await?;
+    assert_eq!(status, 200);
+    let deltas: Vec<&Value> = excess["series"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| &point["delta"])
+        .collect();
+    assert_eq!(
+        deltas,
+        [
+            &json!(null),
+            &json!(100),
+            &json!(200),
+            &json!(-100),
+            &json!(200),
+            &json!(100)
+        ]
+    );
+    assert_eq!(
+        (
+            &excess["blocks_above_target"],
+            &excess["blocks_at_target"],
+            &excess["blocks_below_target"]
+        ),
+        (&json!(4), &json!(1), &json!(1))
+    );
+    assert_eq!(excess["secs_above_target"], 48);
+    // The first of the longest runs of growth
+    assert_eq!(
+        excess["largest_build_up"],
+        json!({"from_block": 2, "to_block": 3, "blocks": 2, "excess_blob_gas_increase": 300})
+    );
+    let doublings: Vec<(u64, u64)> = excess["fee_doublings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| {
+            (
+                d["from_block"].as_u64().unwrap(),
+                d["to_block"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(doublings, [(1, 3), (3, 6)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn stats_split_execution_and_blob_bytes() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let sender = Address::repeat_byte(0x11);
+    index(
+        &db,
+        1,
+        &[(sender, &[Some(1_000), Some(500)]), (sender, &[None])],
+    )?;
+
+    let (status, stats) = get(router(&db), "/api/stats").await?;
+    assert_eq!(status, 200);
+    assert_eq!(stats["total_el_bytes"], 2 * 200);
+    assert_eq!(stats["total_da_bytes"], 3 * 131_072);
+    // Only the tx whose whole sidecar was seen
+    assert_eq!(stats["total_payload_bytes"], 1_500);
+    Ok(())
+}
+
+#[tokio::test]
+async fn chains_are_down_once_they_stop_posting_at_their_cadence() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    // Every 10 minutes from midnight yesterday until noon
+    let yesterday = recent_hour() - 10 * 3600 + 86400;
+    for post in 0..=72 {
+        let block = NewBlock {
+            block_timestamp: yesterday + post * 600,
+            ..block(post + 1, 0)
+        };
+        index_block(&db, block, &[(base, &[None])])?;
+    }
+
+    let (status, uptime) = get(router(&db), "/api/chain-uptime?days=3").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        uptime["days"],
+        json!([yesterday - 86400, yesterday, yesterday + 86400])
+    );
+    let chains = uptime["chains"].as_array().unwrap();
+    assert_eq!(chains.len(), 1);
+    assert_eq!(chains[0]["chain"], "Base");
+    // Three times the usual gap
+    assert_eq!(chains[0]["gap_threshold_secs"], 1800);
+    // Down from half an hour after the last post at noon
+    let up_yesterday = 1.0 - (12.0 * 3600.0 - 1800.0) / 86400.0;
+    assert_eq!(chains[0]["uptime"], json!([null, up_yesterday, 0.0]));
+    assert_eq!(chains[0]["avg_uptime"], up_yesterday / 2.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn block_ranges_are_read_at_once() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (alpha, beta) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    index(&db, 1, &[(alpha, &[None])])?;
+    index(&db, 2, &[(alpha, &[None]), (beta, &[None, None])])?;
+    index(&db, 3, &[])?;
+    index(&db, 5, &[(beta, &[None])])?;
+
+    let (status, blocks) = get(router(&db), "/api/blocks/range?from=2&to=5").await?;
+    assert_eq!(status, 200);
+    let numbers: Vec<&Value> = blocks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| &block["block_number"])
+        .collect();
+    assert_eq!(numbers, [&json!(2), &json!(3), &json!(5)]);
+    assert_eq!(blocks[0]["total_blobs"], 3);
+    assert!(blocks[0].get("transactions").is_none());
+
+    let (_, blocks) = get(router(&db), "/api/blocks/range?from=2&to=3&include=txs").await?;
+    let hashes: Vec<&Value> = blocks[0]["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tx| &tx["tx_hash"])
+        .collect();
+    assert_eq!(hashes, [&json!(tx_hash(2, 0)), &json!(tx_hash(2, 1))]);
+    assert_eq!(blocks[1]["transactions"], json!([]));
+
+    let (status, _) = get(router(&db), "/api/blocks/range?from=5&to=2").await?;
+    assert_eq!(status, 400);
+    let (status, _) = get(router(&db), "/api/blocks/range?from=1&to=10000").await?;
+    assert_eq!(status, 200);
+    let (status, _) = get(router(&db), "/api/blocks/range?from=1&to=10001").await?;
+    assert_eq!(status, 413);
+    Ok(())
+}
+
+#[tokio::test]
+async fn records_are_recomputed_after_a_revert() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (alice, bob) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    let full: &[Option<u64>] = &[None; 21];
+    index(&db, 1, &[(alice, full)])?;
+    index(&db, 2, &[(alice, full)])?;
+    index(&db, 3, &[(bob, &[None; 3])])?;
+    index(&db, 4, &[(bob, full)])?;
+    index(&db, 5, &[(bob, full)])?;
+    index_block(
+        &db,
+        NewBlock {
+            gas_price: 10,
+            ..block(6, 0)
+        },
+        &[(bob, full)],
+    )?;
+
+    let (status, records) = get(router(&db), "/api/records").await?;
+    assert_eq!(status, 200);
+    // Ties keep the earlier block
+    assert_eq!(records["most_blobs"]["block_number"], 1);
+    assert_eq!(records["most_blobs"]["value"], 21);
+    assert_eq!(records["highest_blob_fee"]["block_number"], 6);
+    assert_eq!(records["highest_blob_fee"]["value"], 10);
+    let streak = &records["longest_saturated_streak"];
+    assert_eq!(
+        (&streak["from_block"], &streak["to_block"]),
+        (&json!(4), &json!(6))
+    );
+    assert_eq!(streak["blocks"], 3);
+    let sender_day = &records["largest_sender_day"];
+    assert_eq!(sender_day["sender"], bob.to_checksum(None));
+    assert_eq!(sender_day["blobs"], 66);
+    assert_eq!(
+        sender_day["link"],
+        "/api/blocks/range?from=3&to=6&include=txs"
+    );
+
+    // Every record but the most blobs was set by block 6
+    db.archive_reverted_block(6)?;
+    db.delete_block(6)?;
+    let (_, records) = get(router(&db), "/api/records").await?;
+    assert_eq!(records["most_blobs"]["block_number"], 1);
+    assert_eq!(records["highest_blob_fee"]["block_number"], 1);
+    assert_eq!(records["highest_blob_fee"]["value"], 1);
+    let streak = &records["longest_saturated_streak"];
+    assert_eq!(
+        (&streak["from_block"], &streak["to_block"]),
+        (&json!(1), &json!(2))
+    );
+    assert_eq!(records["largest_sender_day"]["blobs"], 45);
+    assert_eq!(records["largest_sender_day"]["to_block"], 5);
+
+    // The replacement extends the streak left at the tip
+    index(&db, 6, &[(bob, full)])?;
+    let (_, records) = get(router(&db), "/api/records").await?;
+    let streak = &records["longest_saturated_streak"];
+    assert_eq!(
+        (&streak["from_block"], &streak["to_block"]),
+        (&json!(4), &json!(6))
+    );
+    assert_eq!(records["largest_sender_day"]["blobs"], 66);
+    Ok(())
+}
+
+#[tokio::test]
+async fn periods_are_compared_side_by_side() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (alice, bob) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    index(&db, 1, &[(alice, &[None; 3])])?;
+    index(&db, 2, &[(alice, &[None; 21])])?;
+    index(&db, 3, &[(alice, &[None; 6]), (bob, &[None; 6])])?;
+    // The slot of block 4 was missed
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: TIMESTAMP + 60,
+            ..block(4, 0)
+        },
+        &[(bob, &[None; 12])],
+    )?;
+
+    let t = TIMESTAMP;
+    let uri = |a: (u64, u64), b: (u64, u64)| {
+        format!(
+            "/api/compare-periods?a_from={}&a_to={}&b_from={}&b_to={}",
+            a.0, a.1, b.0, b.1
+        )
+    };
+    let (status, compared) = get(router(&db), &uri((t, t + 36), (t + 36, t + 72))).await?;
+    assert_eq!(status, 200);
+    let (a, b) = (&compared["a"], &compared["b"]);
+    assert_eq!((&a["blocks"], &b["blocks"]), (&json!(2), &json!(2)));
+    assert_eq!((&a["slots"], &b["slots"]), (&json!(2), &json!(3)));
+    assert_eq!(b["missed_slots"], 1);
+    assert_eq!(
+        (&a["total_blobs"], &b["total_blobs"]),
+        (&json!(24), &json!(24))
+    );
+    assert_eq!(b["blobs_per_slot"], 8.0);
+    assert_eq!(
+        (&a["unique_senders"], &b["unique_senders"]),
+        (&json!(1), &json!(2))
+    );
+    assert_eq!(a["saturated_blocks_pct"], 50.0);
+    assert_eq!(a["target_utilization_pct"], 24.0 / 28.0 * 100.0);
+
+    let delta = &compared["delta_pct"];
+    assert_eq!(delta["total_blobs"], 0.0);
+    assert_eq!(delta["blob_transactions"], 50.0);
+    assert_eq!(delta["unique_senders"], 100.0);
+    assert_eq!(delta["saturated_blocks_pct"], -100.0);
+    // No change from nothing
+    assert_eq!(delta["missed_slots"], Value::Null);
+
+    let (status, _) = get(router(&db), &uri((t + 36, t), (t, t + 36))).await?;
+    assert_eq!(status, 400);
+    let (status, _) = get(router(&db), &uri((t, t + 36), (t, t + 721 * 3600))).await?;
+    assert_eq!(status, 413);
+    Ok(())
+}
+
+#[tokio::test]
+async fn utilization_is_relative_to_the_blocks_own_schedule() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    // Indexed before a fork raised the target
+    db.insert_block(&NewBlock {
+        blob_target: 6,
+        blob_max: 9,
+        ..block(1, 9)
+    })?;
+    db.insert_block(&block(2, 9))?;
+
+    let (_, before) = get(router(&db), "/api/blocks/1").await?;
+    assert_eq!(before["target_utilization"], 150.0);
+    assert_eq!(before["saturation_index"], 100.0);
+    let (_, after) = get(router(&db), "/api/blocks/2").await?;
+    assert_eq!(after["target_utilization"], 9.0 / 14.0 * 100.0);
+    assert_eq!(after["saturation_index"], 9.0 / 21.0 * 100.0);
+
+    let uri = format!(
+        "/api/compare-periods?a_from={}&a_to={}&b_from={}&b_to={}",
+        TIMESTAMP,
+        TIMESTAMP + 24,
+        TIMESTAMP + 24,
+        TIMESTAMP + 36
+    );
+    let (_, compared) = get(router(&db), &uri).await?;
+    assert_eq!(compared["a"]["saturated_blocks_pct"], 100.0);
+    assert_eq!(compared["b"]["saturated_blocks_pct"], 0.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn health_tells_how_far_the_index_is_behind() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    index(&db, 4, &[])?;
+
+    let (status, health) = get(router(&db), "/api/health").await?;
+    assert_eq!(status, 200);
+    assert_eq!(health["status"], "no_writer");
+    assert_eq!(health["blocks_behind"], Value::Null);
+
+    db.acquire_writer_lease("node-a", 60)?;
+    db.set_node_tip(10)?;
+    let (_, health) = get(router(&db), "/api/health").await?;
+    assert_eq!(health["status"], "ok");
+    assert_eq!(health["latest_block"], 4);
+    assert_eq!(health["writer"]["node_tip"], 10);
+    assert_eq!(health["blocks_behind"], 6);
+    Ok(())
+}
+
+#[tokio::test]
+async fn blobs_per_tx_are_counted_per_chain() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let other = Address::repeat_byte(0x11);
+    let hour = recent_hour();
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: hour,
+            ..block(1, 0)
+        },
+        &[(base, &[None]), (other, &[None; 2]), (base, &[None; 3])],
+    )?;
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: hour + 12,
+            ..block(2, 0)
+        },
+        &[(base, &[None; 3])],
+    )?;
+
+    let (status, histogram) = get(router(&db), "/api/blobs-per-tx?chain=base").await?;
+    assert_eq!(status, 200);
+    assert_eq!(histogram["chain"], "Base");
+    assert_eq!(histogram["days"], 7);
+    assert_eq!(histogram["tx_count"], 3);
+    assert_eq!(histogram["avg_blobs_per_tx"], 7.0 / 3.0);
+    let buckets: Vec<_> = histogram["buckets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bucket| (bucket["blob_count"].clone(), bucket["tx_count"].clone()))
+        .collect();
+    assert_eq!(
+        buckets,
+        [
+            (json!(1), json!(1)),
+            (json!(2), json!(0)),
+            (json!(3), json!(2))
+        ]
+    );
+
+    let (_, histogram) = get(router(&db), "/api/blobs-per-tx?chain=Scroll").await?;
+    assert_eq!(histogram["tx_count"], 0);
+    assert_eq!(histogram["avg_blobs_per_tx"], Value::Null);
+    let (status, _) = get(router(&db), "/api/blobs-per-tx?chain=Base&days=31").await?;
+    assert_eq!(status, 413);
+    Ok(())
+}
+
+#[tokio::test]
+async fn block_transactions_are_totalled_per_chain() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let (alice, bob) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    index(
+        &db,
+        1,
+        &[
+            (alice, &[Some(100)]),
+            (base, &[Some(1_000), None]),
+            (bob, &[Some(200)]),
+            (base, &[Some(2_000)]),
+        ],
+    )?;
+
+    let expected = json!([
+        { "chain": "Base", "tx_count": 2, "blobs": 3, "blob_size": 134_072 },
+        { "chain": "Other", "tx_count": 2, "blobs": 2, "blob_size": 300 },
+    ]);
+    let (_, block) = get(router(&db), "/api/blocks/1").await?;
+    assert_eq!(block["by_chain"], expected);
+    // Without the transactions themselves too
+    let (_, blocks) = get(router(&db), "/api/blocks/range?from=1&to=1").await?;
+    assert_eq!(blocks[0]["by_chain"], expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn block_intervals_are_measured_from_the_parent() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    // The slot after block 2 was missed, and block 4 arrives after its child
+    for (block_number, offset) in [(1, 0), (2, 12), (3, 36), (5, 60), (4, 48)] {
+        db.insert_block(&NewBlock {
+            block_timestamp: TIMESTAMP + offset,
+            ..block(block_number, 3)
+        })?;
+    }
+
+    let (_, blocks) = get(router(&db), "/api/blocks/range?from=1&to=5").await?;
+    let intervals: Vec<_> = blocks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| block["block_interval"].clone())
+        .collect();
+    assert_eq!(
+        intervals,
+        [Value::Null, json!(12), json!(24), json!(12), json!(12)]
+    );
+
+    let intervals = db.get_block_intervals(TIMESTAMP)?.unwrap();
+    assert_eq!(intervals.blocks, 4);
+    assert_eq!(intervals.avg_secs, 15.0);
+    assert_eq!((intervals.p50_secs, intervals.p90_secs), (12, 24));
+    assert_eq!(intervals.max_secs, 24);
+    assert!(db.get_block_intervals(TIMESTAMP + 61)?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn senders_are_first_and_last_seen_in_their_blocks() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (alice, bob) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    index(&db, 1, &[(alice, &[None; 3])])?;
+    index(&db, 2, &[(alice, &[None; 2])])?;
+    index(&db, 4, &[(alice, &[None]), (bob, &[None])])?;
+
+    let seen = async || -> eyre::Result<Vec<Value>> {
+        let (_, senders) = get(router(&db), "/api/senders").await?;
+        Ok(senders
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|sender| {
+                json!([
+                    sender["first_seen_block"],
+                    sender["last_seen_block"],
+                    sender["last_seen_timestamp"],
+                ])
+            })
+            .collect())
+    };
+    assert_eq!(
+        seen().await?,
+        [json!([1, 4, TIMESTAMP + 48]), json!([4, 4, TIMESTAMP + 48])]
+    );
+
+    // Senders only seen in the reverted block are gone
+    db.delete_block(4)?;
+    assert_eq!(seen().await?, [json!([1, 2, TIMESTAMP + 24])]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn inclusion_latency_is_counted_from_first_sight() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let alice = Address::repeat_byte(0x11);
+    let hour = recent_hour();
+    // Pending txs by the block they were seen in and the one they made it into
+    for (seen_in, included_in) in [(1, 2), (1, 3), (2, 3), (3, 4)] {
+        db.insert_pending_blob_transaction(&NewPendingBlobTransaction {
+            block_number: seen_in,
+            tx_hash: &tx_hash(included_in, 0),
+            sender: alice,
+            nonce: included_in,
+            blob_count: 1,
+            priority_fee: 0,
+            max_fee_per_blob_gas: 1,
+        })?;
+    }
+    let block = |block_number| NewBlock {
+        block_timestamp: hour + block_number * 12,
+        ..block(block_number, 0)
+    };
+    index_block(&db, block(1), &[])?;
+    index_block(&db, block(2), &[(alice, &[None])])?;
+    index_block(&db, block(3), &[(alice, &[None]), (base, &[None])])?;
+    index_block(&db, block(4), &[(alice, &[None])])?;
+
+    let (status, latency) = get(router(&db), "/api/inclusion-latency?window=7d").await?;
+    assert_eq!(status, 200);
+    assert_eq!(latency["tx_count"], 4);
+    assert_eq!(latency["tracked_tx_count"], 3);
+    assert_eq!(latency["latency"]["p50_blocks"], 1);
+    assert_eq!(latency["latency"]["p90_blocks"], 2);
+    assert_eq!(latency["latency"]["p90_secs"], 24);
+    assert_eq!(latency["first_eligible_share"], 2.0 / 3.0);
+    assert_eq!(latency["fee_buckets"][0]["tracked_tx_count"], 3);
+    assert_eq!(latency["fee_buckets"][1]["latency"], Value::Null);
+
+    let (_, latency) = get(router(&db), "/api/inclusion-latency?window=7d&chain=base").await?;
+    assert_eq!(latency["chain"], "Base");
+    assert_eq!(latency["tx_count"], 1);
+    assert_eq!(latency["tracked_tx_count"], 0);
+    let (status, _) = get(router(&db), "/api/inclusion-latency?window=soon").await?;
+    assert_eq!(status, 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn header_blob_gas_is_checked_against_the_transactions() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    db.insert_block(&block(1, 7))?;
+    db.insert_block(&NewBlock {
+        header_blob_gas_used: Some(8 * DATA_GAS_PER_BLOB),
+        ..block(2, 7)
+    })?;
+    // Indexed before the header value was recorded
+    db.insert_block(&NewBlock {
+        header_blob_gas_used: None,
+        ..block(3, 7)
+    })?;
+
+    let (_, blocks) = get(router(&db), "/api/blocks/range?from=1&to=3").await?;
+    let consistency: Vec<_> = blocks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| block["consistency"].clone())
+        .collect();
+    let computed = 7 * DATA_GAS_PER_BLOB;
+    assert_eq!(
+        consistency,
+        [
+            json!({ "header_blob_gas_used": computed, "computed_blob_gas_used": computed, "matches": true }),
+            json!({ "header_blob_gas_used": 8 * DATA_GAS_PER_BLOB, "computed_blob_gas_used": computed, "matches": false }),
+            json!({ "header_blob_gas_used": null, "computed_blob_gas_used": computed, "matches": null }),
+        ]
+    );
+    // The ratio trusts the header
+    for (block, blobs) in blocks.as_array().unwrap().iter().zip([7.0, 8.0, 7.0]) {
+        let ratio = block["blob_gas_used_ratio"].as_f64().unwrap();
+        assert!((ratio - blobs / 21.0).abs() < 1e-12, "{ratio}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn listed_blocks_and_txs_link_to_their_permalinks() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    index(&db, 1, &[(Address::repeat_byte(0x11), &[None])])?;
+
+    let (_, blocks) = get(router(&db), "/api/blocks").await?;
+    let block_url = blocks[0]["url"].as_str().unwrap().to_string();
+    assert_eq!(block_url, format!("/api/blocks/{:#066x}", 1));
+    let (status, linked) = get(router(&db), &block_url).await?;
+    assert_eq!(status, 200);
+    assert_eq!(linked["block_number"], 1);
+
+    let (_, txs) = get(router(&db), "/api/blob-transactions").await?;
+    let tx_url = txs[0]["url"].as_str().unwrap().to_string();
+    assert_eq!(tx_url, format!("/api/txs/{}", tx_hash(1, 0)));
+    assert_eq!(blocks[0]["transactions"][0]["url"], tx_url);
+
+    // A block replacing it in a reorg has a permalink of its own
+    db.delete_block(1)?;
+    index_block(
+        &db,
+        NewBlock {
+            block_hash: format!("{:#066x}", 0x101),
+            ..block(1, 0)
+        },
+        &[],
+    )?;
+    let (status, _) = get(router(&db), &block_url).await?;
+    assert_eq!(status, 404);
+    let (status, _) = get(router(&db), &tx_url).await?;
+    assert_eq!(status, 404);
+    let (_, blocks) = get(router(&db), "/api/blocks").await?;
+    assert_eq!(blocks[0]["url"], format!("/api/blocks/{:#066x}", 0x101));
+
+    for uri in ["/api/blocks/latest", "/api/blocks/0x12", "/api/txs/42"] {
+        let (status, _) = get(router(&db), uri).await?;
+        assert_eq!(status, 400, "{uri}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn sender_blobs_are_paged_with_a_cursor() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (alice, bob) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    index(&db, 1, &[(alice, &[Some(10), None]), (alice, &[Some(30)])])?;
+    index(&db, 2, &[(bob, &[None])])?;
+    index(&db, 3, &[(bob, &[None]), (alice, &[None, None])])?;
+
+    let mut pages = Vec::new();
+    let mut uri = format!("/api/sender/{alice}/blobs?limit=2");
+    loop {
+        let (status, page) = get(router(&db), &uri).await?;
+        assert_eq!(status, 200);
+        assert_eq!(page["sender"], alice.to_checksum(None));
+        let blobs: Vec<_> = page["blobs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|blob| (blob["tx_hash"].clone(), blob["blob_index"].clone()))
+            .collect();
+        pages.push(blobs);
+        match page["next_cursor"].as_str() {
+            Some(cursor) => uri = format!("/api/sender/{alice}/blobs?limit=2&cursor={cursor}"),
+            None => break,
+        }
+    }
+    let blob = |block_number, i, blob_index| (json!(tx_hash(block_number, i)), json!(blob_index));
+    assert_eq!(
+        pages,
+        [
+            vec![blob(1, 0, 0), blob(1, 0, 1)],
+            vec![blob(1, 1, 0), blob(3, 1, 0)],
+            vec![blob(3, 1, 1)],
+        ]
+    );
+
+    let (_, page) = get(router(&db), &format!("/api/sender/{alice}/blobs?from=2")).await?;
+    assert_eq!(page["blobs"].as_array().unwrap().len(), 2);
+    assert_eq!(page["blobs"][0]["block_number"], 3);
+    assert_eq!(page["next_cursor"], Value::Null);
+    for uri in [
+        format!("/api/sender/{alice}/blobs?cursor=latest"),
+        "/api/sender/0x1234/blobs".to_string(),
+    ] {
+        let (status, _) = get(router(&db), &uri).await?;
+        assert_eq!(status, 400, "{uri}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn fork_events_follow_the_blocks_schedules() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let schedule = |block_number, blob_target, blob_max| NewBlock {
+        blob_target,
+        blob_max,
+        ..block(block_number, 0)
+    };
+    db.insert_block(&schedule(1, 6, 9))?;
+    db.insert_block(&schedule(2, 6, 9))?;
+    // Indexed ahead of its parent, so only compared once that arrives
+    db.insert_block(&schedule(4, 14, 21))?;
+    let (_, events) = get(router(&db), "/api/fork-events").await?;
+    assert_eq!(events, json!([]));
+
+    db.insert_block(&schedule(3, 14, 21))?;
+    let (status, events) = get(router(&db), "/api/fork-events").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        events,
+        json!([{
+            "block_number": 3,
+            "block_timestamp": TIMESTAMP + 36,
+            "previous_target": 6,
+            "previous_max": 9,
+            "target": 14,
+            "max": 21,
+        }])
+    );
+
+    // Reorged into a block that kept the old schedule
+    db.delete_block(3)?;
+    db.insert_block(&schedule(3, 6, 9))?;
+    let (_, events) = get(router(&db), "/api/fork-events").await?;
+    let forks: Vec<_> = events
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["block_number"].clone())
+        .collect();
+    assert_eq!(forks, [json!(4)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn heavy_endpoints_answer_in_the_format_asked() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    index(
+        &db,
+        1,
+        &[(Address::repeat_byte(0x11), &[Some(1_000), None])],
+    )?;
+    index(&db, 2, &[])?;
+    let uri = "/api/blocks/range?from=1&to=2";
+    let fetch = |uri: String, accept: &'static str| {
+        let router = router(&db);
+        async move {
+            let request = Request::get(uri).header(header::ACCEPT, accept);
+            let response = router.oneshot(request.body(Body::empty())?).await?;
+            let status = response.status().as_u16();
+            let content_type = response.headers()[header::CONTENT_TYPE]
+                .to_str()?
+                .to_string();
+            let body = response.into_body().collect().await?.to_bytes();
+            eyre::Ok((status, content_type, body))
+        }
+    };
+
+    let (_, json) = get(router(&db), uri).await?;
+    let (status, content_type, body) = fetch(format!("{uri}&format=msgpack"), "*/*").await?;
+    assert_eq!(
+        (status, content_type.as_str()),
+        (200, "application/msgpack")
+    );
+    let msgpack: Value = rmp_serde::from_slice(&body)?;
+    let accept = "text/html, application/cbor;q=0.9";
+    let (status, content_type, body) = fetch(uri.to_string(), accept).await?;
+    assert_eq!((status, content_type.as_str()), (200, "application/cbor"));
+    let cbor: Value = ciborium::from_reader(&body[..])?;
+    assert_eq!(msgpack, cbor);
+    assert_eq!(msgpack.as_array().unwrap().len(), 2);
+    assert_eq!(msgpack[0]["block_number"], json[0]["block_number"]);
+    assert_eq!(msgpack[0]["total_blobs"], 2);
+
+    // The query wins over the header, and has to name a known format
+    let (_, content_type, _) = fetch(format!("{uri}&format=json"), "application/cbor").await?;
+    assert!(content_type.starts_with("application/json"));
+    let (status, _, _) = fetch(format!("{uri}&format=xml"), "*/*").await?;
+    assert_eq!(status, 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn protocol_summary_sizes_up_the_target() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (_, empty) = get(router(&db), "/api/protocol-summary").await?;
+    assert_eq!(empty["blocks"], 0);
+    assert_eq!(empty["fee_percentiles"], Value::Null);
+
+    // Long before the window
+    db.insert_block(&block(1, 21))?;
+    let hour = recent_hour();
+    for (block_number, blobs, gas_price) in [(2, 7, 4), (3, 21, 2), (4, 14, 5), (5, 21, 10)] {
+        db.insert_block(&NewBlock {
+            block_timestamp: hour + block_number * 12,
+            gas_price,
+            ..block(block_number, blobs)
+        })?;
+    }
+
+    let (status, summary) = get(router(&db), "/api/protocol-summary?days=30").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        summary,
+        json!({
+            "days": 30,
+            "blocks": 4,
+            "avg_blobs_per_block": 15.75,
+            "avg_target": 14.0,
+            "target_utilization": 112.5,
+            "pct_blocks_at_max": 50.0,
+            "equilibrium_deviation": 1.75,
+            "fee_percentiles": { "p10": 2, "p25": 2, "p50": 4, "p75": 5, "p90": 10, "p99": 10 },
+            // 2 to 5, then 5 to 10
+            "fee_doublings": 2,
+        })
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn chain_profiles_merge_the_hours_of_each_chain() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let other = Address::repeat_byte(0x11);
+    let hour = recent_hour();
+    for (block_number, offset, txs) in [
+        (1, 12, &[(base, &[None; 2][..]), (other, &[None][..])][..]),
+        (2, 600, &[(base, &[None][..])][..]),
+        (3, 3700, &[(base, &[None; 3][..])][..]),
+    ] {
+        let block = NewBlock {
+            block_timestamp: hour + offset,
+            ..block(block_number, 0)
+        };
+        index_block(&db, block, txs)?;
+    }
+    let profile = |profiles: &Value, i: usize| {
+        let profile = &profiles[i];
+        (
+            profile["chain"].clone(),
+            profile["total_transactions"].clone(),
+            profile["total_blobs"].clone(),
+            profile["avg_posting_interval_secs"].clone(),
+            profile["hourly_activity"][10].clone(),
+            profile["hourly_activity"][11].clone(),
+        )
+    };
+
+    let (status, profiles) = get(router(&db), "/api/chain-profiles?hours=72").await?;
+    assert_eq!(status, 200);
+    assert_eq!(profiles.as_array().unwrap().len(), 2);
+    assert_eq!(
+        profile(&profiles, 0),
+        (
+            json!("Base"),
+            json!(3),
+            json!(6),
+            json!(1844.0),
+            json!(1.0),
+            json!(0.5)
+        )
+    );
+    assert_eq!(profiles[0]["avg_blobs_per_tx"], 2.0);
+    assert_eq!(profile(&profiles, 1).2, json!(1));
+
+    // The rollups follow a revert
+    db.delete_block(3)?;
+    let (_, profiles) = get(router(&db), "/api/chain-profiles?hours=72").await?;
+    assert_eq!(
+        profile(&profiles, 0),
+        (
+            json!("Base"),
+            json!(2),
+            json!(3),
+            json!(588.0),
+            json!(1.0),
+            json!(0.0)
+        )
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn reverted_batches_count_against_their_chain() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let (other, labeled) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    db.set_sender_label(&labeled, "Acme")?;
+    let hour = recent_hour();
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: hour,
+            ..block(1, 0)
+        },
+        &[(base, &[None; 2]), (other, &[None]), (labeled, &[None])],
+    )?;
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: hour + 12,
+            ..block(2, 0)
+        },
+        &[(base, &[None]), (labeled, &[None])],
+    )?;
+    db.record_blob_transaction_status(&tx_hash(1, 0), false)?;
+    db.record_blob_transaction_status(&tx_hash(1, 2), true)?;
+    // Not indexed
+    db.record_blob_transaction_status(&tx_hash(3, 0), false)?;
+
+    let (status, health) = get(router(&db), "/api/chain-health?hours=72").await?;
+    assert_eq!(status, 200);
+    assert_eq!(health["hours"], 72);
+    // Unknown senders have no chain to be healthy
+    let chains: Vec<_> = health["chains"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chain| {
+            (
+                chain["chain"].clone(),
+                chain["txs"].clone(),
+                chain["failed_txs"].clone(),
+                chain["failure_rate"].clone(),
+                chain["failed_blobs"].clone(),
+                chain["failed_blob_fees_wei"].clone(),
+                chain["last_post_timestamp"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        chains,
+        [
+            (
+                json!("Base"),
+                json!(2),
+                json!(1),
+                json!(0.5),
+                json!(2),
+                json!(2 * DATA_GAS_PER_BLOB),
+                json!(hour + 12)
+            ),
+            (
+                json!("Acme"),
+                json!(2),
+                json!(0),
+                json!(0.0),
+                json!(0),
+                json!(0),
+                json!(hour + 12)
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn capacity_is_projected_from_each_chains_growth() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let other = Address::repeat_byte(0x11);
+    let today = db::group_by_day(recent_hour() + 2 * 86400, 0);
+    // A block a day over the last 8 whole days, Base doubling its blobs
+    for day in 0..8u64 {
+        let blobs = vec![None; 1 << day];
+        index_block(
+            &db,
+            NewBlock {
+                block_timestamp: today - (8 - day) * 86400 + 3600,
+                ..block(day + 1, 0)
+            },
+            &[(base, &blobs), (other, &[None])],
+        )?;
+    }
+
+    let uri = "/api/capacity?days=8&target=14&max=100000";
+    let (status, capacity) = get(router(&db), uri).await?;
+    assert_eq!(status, 200);
+    assert_eq!(capacity["avg_blobs_per_block"], 255.0 / 8.0 + 1.0);
+    assert_eq!(capacity["target_reached_at"], today);
+    let max_reached_at = capacity["max_reached_at"].as_u64().unwrap();
+    assert!(max_reached_at > today);
+
+    let chains = capacity["chains"].as_array().unwrap();
+    assert_eq!(chains.len(), 2);
+    assert_eq!(chains[0]["chain"], "Base");
+    assert!((chains[0]["daily_growth"].as_f64().unwrap() - 1.0).abs() < 1e-9);
+    assert_eq!(chains[1]["trend_per_day"], 0.0);
+    assert_eq!(chains[1]["daily_growth"], 0.0);
+    let total = capacity["trend_per_day"].as_f64().unwrap();
+    assert!((chains[0]["trend_per_day"].as_f64().unwrap() - total).abs() < 1e-9);
+
+    let (status, _) = get(router(&db), "/api/capacity?target=0").await?;
+    assert_eq!(status, 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn chain_profiles_export_as_csv() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let labeled = Address::repeat_byte(0x22);
+    db.set_sender_label(&labeled, r#"Acme "L2", Inc"#)?;
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: recent_hour(),
+            ..block(1, 0)
+        },
+        &[(base, &[None; 2]), (labeled, &[None])],
+    )?;
+
+    let response = router(&db)
+        .oneshot(Request::get("/api/chain-profiles?hours=72&format=csv").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let body = response.into_body().collect().await?.to_bytes();
+    let csv = String::from_utf8(body.to_vec())?;
+    let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+    assert_eq!(lines.len(), 3);
+
+    // Nested fields flattened into dotted columns, in field order
+    let columns: Vec<&str> = lines[0].split(',').collect();
+    assert_eq!(
+        columns[..5],
+        [
+            "chain",
+            "total_transactions",
+            "total_blobs",
+            "percentage",
+            "avg_blobs_per_tx"
+        ]
+    );
+    assert!(columns.contains(&"hourly_activity.23"));
+    assert!(columns.contains(&"price_sensitivity.correlation"));
+    assert!(lines[1].starts_with("Base,1,2,"));
+    assert!(lines[2].starts_with(r#""Acme ""L2"", Inc",1,1,"#));
+    Ok(())
+}
+
+#[tokio::test]
+async fn inboxes_gather_the_senders_posting_to_them() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let inbox = Address::repeat_byte(0xff);
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let other = Address::repeat_byte(0x11);
+    let hour = recent_hour();
+    // Sender, recipient, blobs and payload size of each tx, one per block
+    let txs = [
+        (base, Some(inbox), 2, Some(1_000)),
+        (other, Some(inbox), 1, None),
+        (base, Some(inbox), 3, Some(2_000)),
+        (base, None, 6, None),
+        (other, Some(Address::repeat_byte(0xee)), 1, None),
+    ];
+    for (i, (sender, to, blob_count, payload_size)) in txs.into_iter().enumerate() {
+        let block_number = i as u64 + 1;
+        db.insert_blob_transaction(&NewBlobTransaction {
+            tx_hash: &tx_hash(block_number, 0),
+            block_number,
+            sender,
+            nonce: block_number,
+            tx_type: 3,
+            blob_count,
+            gas_price: 1,
+            priority_fee: 0,
+            created_at: hour + block_number * 12,
+            el_size: 200,
+            payload_size,
+            to,
+        })?;
+        db.insert_block(&NewBlock {
+            block_timestamp: hour + block_number * 12,
+            tx_count: 1,
+            ..block(block_number, blob_count as u64)
+        })?;
+    }
+    db.record_blob_transaction_status(&tx_hash(2, 0), false)?;
+
+    let uri = format!("/api/inbox/{}?hours=72", inbox.to_string().to_lowercase());
+    let (status, summary) = get(router(&db), &uri).await?;
+    assert_eq!(status, 200);
+    assert_eq!(summary["address"], inbox.to_checksum(None));
+    assert_eq!(summary["total_transactions"], 3);
+    assert_eq!(summary["total_blobs"], 6);
+    assert_eq!(summary["total_blob_size"], 3_000 + 131_072);
+    assert_eq!(
+        summary["senders"],
+        json!([
+            {
+                "address": base.to_checksum(None),
+                "chain": "Base",
+                "tx_count": 2,
+                "total_blobs": 5,
+                "total_blob_size": 3_000,
+                "first_block": 1,
+                "last_block": 3,
+                "reverted_txs": 0,
+            },
+            {
+                "address": other.to_checksum(None),
+                "chain": "Other",
+                "tx_count": 1,
+                "total_blobs": 1,
+                "total_blob_size": 131_072,
+                "first_block": 2,
+                "last_block": 2,
+                "reverted_txs": 1,
+            },
+        ])
+    );
+
+    let (status, _) = get(router(&db), "/api/inbox/inbox.eth").await?;
+    assert_eq!(status, 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn the_ingest_log_pages_back_from_the_latest_entry() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let entry = |kind, reverted, committed, outcome, error: Option<&str>| NewIngestLogEntry {
+        kind,
+        reverted,
+        committed,
+        reverted_txs: 1,
+        committed_txs: 4,
+        duration: Duration::from_millis(25),
+        outcome,
+        error: error.map(str::to_string),
+    };
+    db.append_ingest_log(&entry("commit", None, Some(1..=3), "ok", None))?;
+    db.append_ingest_log(&entry("reorg", Some(3..=3), Some(3..=4), "ok", None))?;
+    db.append_ingest_log(&entry(
+        "commit",
+        None,
+        Some(5..=5),
+        "failed",
+        Some("disk full"),
+    ))?;
+
+    let (status, page) = get(router(&db), "/api/ingest-log?limit=2").await?;
+    assert_eq!(status, 200);
+    let entries = page.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["outcome"], "failed");
+    assert_eq!(entries[0]["error"], "disk full");
+    assert_eq!(entries[0]["reverted"], Value::Null);
+    assert_eq!(entries[1]["kind"], "reorg");
+    assert_eq!(entries[1]["reverted"], json!({ "from": 3, "to": 3 }));
+    assert_eq!(entries[1]["committed"], json!({ "from": 3, "to": 4 }));
+    assert_eq!(entries[1]["duration_ms"], 25);
+
+    let uri = format!("/api/ingest-log?limit=2&before={}", entries[1]["id"]);
+    let (_, page) = get(router(&db), &uri).await?;
+    assert_eq!(page.as_array().unwrap().len(), 1);
+    assert_eq!(page[0]["committed"], json!({ "from": 1, "to": 3 }));
+
+    // Retention is by age
+    let processed_at = page[0]["processed_at"].as_u64().unwrap();
+    assert_eq!(db.prune_ingest_log(processed_at)?, 0);
+    assert_eq!(db.prune_ingest_log(processed_at + 3600)?, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn chart_fees_stay_exact_at_one_wei() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let huge = 10u128.pow(21);
+    // Block 2 isn't indexed
+    db.insert_block(&block(1, 0))?;
+    db.insert_block(&NewBlock {
+        gas_price: huge,
+        ..block(3, 0)
+    })?;
+
+    let (status, chart) = get(router(&db), "/api/chart?blocks=3&gap_fill=null").await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        chart["gas_prices_wei"],
+        json!(["1", null, huge.to_string()])
+    );
+    assert_eq!(chart["gas_prices"][0], 1e-9);
+    assert_eq!(chart["gas_prices"][1], Value::Null);
+    let log10 = &chart["gas_prices_log10"];
+    assert_eq!(log10[0], 0.0);
+    assert_eq!(log10[1], Value::Null);
+    assert!((log10[2].as_f64().unwrap() - 21.0).abs() < 1e-9);
+
+    let (_, chart) = get(router(&db), "/api/all-time-chart?strategy=max").await?;
+    let wei = chart["gas_prices_wei"].as_array().unwrap();
+    assert_eq!(wei.first(), Some(&json!("1")));
+    assert_eq!(wei.last(), Some(&json!(huge.to_string())));
+    Ok(())
+}
+
+#[tokio::test]
+async fn chains_are_compared_over_the_same_days() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let labeled = Address::repeat_byte(0x22);
+    db.set_sender_label(&labeled, "Acme")?;
+    let hour = recent_hour();
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: hour,
+            ..block(1, 0)
+        },
+        &[(base, &[Some(65_536); 2]), (labeled, &[None])],
+    )?;
+    index_block(
+        &db,
+        NewBlock {
+            block_timestamp: hour + 600,
+            ..block(2, 0)
+        },
+        &[(base, &[None])],
+    )?;
+
+    let uri = "/api/compare-chains?days=3&chains=base,%20ACME%20,,Nope,Base";
+    let (status, comparison) = get(router(&db), uri).await?;
+    assert_eq!(status, 200);
+    let first_day = db::group_by_day(hour, 0);
+    assert_eq!(
+        comparison["days"],
+        json!([first_day, first_day + 86400, first_day + 2 * 86400])
+    );
+    let chains = comparison["chains"].as_array().unwrap();
+    let names: Vec<_> = chains.iter().map(|chain| chain["chain"].clone()).collect();
+    assert_eq!(names, ["Base", "Acme", "Nope"]);
+
+    assert_eq!(chains[0]["daily_transactions"], json!([2, 0, 0]));
+    assert_eq!(chains[0]["daily_blobs"], json!([3, 0, 0]));
+    assert_eq!(chains[0]["avg_posting_interval_secs"], 600.0);
+    // Only blobs of known payload size count towards the fill
+    assert_eq!(chains[0]["avg_fill"], 0.5);
+    let fees = chains[0]["blob_fees_eth"].as_f64().unwrap();
+    assert!((fees - 3.0 * 131_072.0 / 1e18).abs() < 1e-24);
+    assert_eq!(chains[1]["total_blobs"], 1);
+    assert_eq!(chains[1]["avg_posting_interval_secs"], Value::Null);
+    assert_eq!(chains[2]["total_transactions"], 0);
+    assert_eq!(chains[2]["avg_fill"], Value::Null);
+
+    let (status, _) = get(router(&db), "/api/compare-chains?chains=,").await?;
+    assert_eq!(status, 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn drifted_sender_stats_are_reported_and_repaired() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let alice = Address::repeat_byte(0x11);
+    index(&db, 1, &[(alice, &[None, None])])?;
+    // Counted twice, as by a retried step
+    db.update_sender(&alice, 1, TIMESTAMP + 12, 2, 2 * 131_072)?;
+    // The first round runs right away, the next an hour later
+    let round = |repair_above| {
+        tokio::time::timeout(
+            Duration::from_millis(200),
+            maintenance::check_senders(&db, Some(repair_above)),
+        )
+    };
+    let check = || async {
+        let (_, health) = get(router(&db), "/api/health").await?;
+        let check = &health["senders"];
+        eyre::Ok((
+            check["drifted_senders"].clone(),
+            check["tx_count_drift"].clone(),
+            check["blob_drift"].clone(),
+            check["repaired"].clone(),
+            check["total_repairs"].clone(),
+        ))
+    };
+
+    let (_, health) = get(router(&db), "/api/health").await?;
+    assert_eq!(health["senders"], Value::Null);
+    assert!(round(1).await.is_err());
+    assert_eq!(
+        check().await?,
+        (json!(1), json!(1), json!(2), json!(false), json!(0))
+    );
+
+    assert!(round(0).await.is_err());
+    assert_eq!(
+        check().await?,
+        (json!(1), json!(1), json!(2), json!(true), json!(1))
+    );
+    assert!(db.get_sender_drift()?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn chain_calendars_have_a_day_per_cell() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    let hour = recent_hour();
+    for (block_number, block_timestamp, blobs) in [
+        (1, hour, &[None; 2][..]),
+        (2, hour + 3600, &[None][..]),
+        (3, hour - 86400, &[None; 3][..]),
+    ] {
+        let block = NewBlock {
+            block_timestamp,
+            ..block(block_number, 0)
+        };
+        index_block(
+            &db,
+            block,
+            &[(base, blobs), (Address::repeat_byte(0x11), &[None])],
+        )?;
+    }
+
+    let (status, calendar) = get(router(&db), "/api/chain/base/calendar?days=4").await?;
+    assert_eq!(status, 200);
+    // As labeled rather than as requested
+    assert_eq!(calendar["chain"], "Base");
+    assert_eq!(calendar["first_day"], db::group_by_day(hour, 0) - 86400);
+    assert_eq!(calendar["blobs"], json!([3, 3, 0, 0]));
+    let fees = calendar["blob_fees_eth"].as_array().unwrap();
+    assert!((fees[1].as_f64().unwrap() - 3.0 * 131_072.0 / 1e18).abs() < 1e-24);
+    assert_eq!(fees[2], 0.0);
+
+    let (_, calendar) = get(router(&db), "/api/chain/Nope/calendar?days=2").await?;
+    assert_eq!(calendar["chain"], "Nope");
+    assert_eq!(calendar["blobs"], json!([0, 0]));
+    let (status, _) = get(router(&db), "/api/chain/base/calendar?days=367").await?;
+    assert_eq!(status, 413);
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Chart series, sampled and bucketed from indexed blocks.
+
+use alloy_primitives::Address;
+use blob_exex::{
+    db::{Downsample, GapFill, NewBlock},
+    BlobSchedule, Database,
+};
+
+const TIMESTAMP: u64 = 1_767_747_671;
+
+/// A block numbered `block_number`, `blobs` of its max of 21 used.
+fn block(block_number: u64, blobs: u64) -> NewBlock {
+    NewBlock {
+        block_number,
+        block_timestamp: TIMESTAMP + block_number * 12,
+        tx_count: 0,
+        total_blobs: blobs,
+        gas_used: blobs as i64 * 131_072,
+        gas_price: 1,
+        excess_blob_gas: 0,
+        base_fee_per_gas: 7,
+        priority_fees: None,
+        blob_target: 14,
+        blob_max: 21,
+        header_blob_gas_used: Some(blobs * 131_072),
+        block_hash: format!("{block_number:#066x}"),
+        beneficiary: Address::repeat_byte(0x24),
+    }
+}
+
+#[test]
+fn all_time_chart_downsamples_windows_of_blocks() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    for (block_number, blobs) in [(1, 3), (2, 1), (3, 6), (4, 2)] {
+        db.insert_block(&block(block_number, blobs))?;
+    }
+
+    let schedule = BlobSchedule::default();
+    for (strategy, labels, blobs) in [
+        (Downsample::Mean, [1, 3], [2.0, 4.0]),
+        (Downsample::Max, [1, 3], [3.0, 6.0]),
+        (Downsample::Last, [2, 4], [1.0, 2.0]),
+    ] {
+        let chart = db.get_all_time_chart_data(2, &schedule, strategy)?;
+        assert_eq!(chart.labels, labels, "{strategy:?}");
+        assert_eq!(chart.blobs, blobs, "{strategy:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn chart_gaps_are_priced_as_asked() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let excess_blob_gas: u64 = 100 * 131_072 * 14;
+    let schedule = BlobSchedule::default();
+    let params = schedule.params_at(TIMESTAMP).blob_params();
+    let fee = |excess_blob_gas: u64| params.calc_blob_fee(excess_blob_gas) as f64 / 1e9;
+    // Blocks 2 and 3 aren't indexed
+    db.insert_block(&NewBlock {
+        excess_blob_gas: excess_blob_gas as i64,
+        gas_price: params.calc_blob_fee(excess_blob_gas),
+        ..block(1, 0)
+    })?;
+    db.insert_block(&block(4, 0))?;
+
+    let chart = db.get_chart_data(4, GapFill::Previous, &schedule)?;
+    assert_eq!(chart.labels, [1, 2, 3, 4]);
+    let previous = Some(fee(excess_blob_gas));
+    assert_eq!(chart.gas_prices, [previous, previous, previous, Some(1e-9)]);
+
+    // Empty blocks take the excess down by a target each
+    let chart = db.get_chart_data(4, GapFill::ExcessBlobGas, &schedule)?;
+    let target_gas = 14 * 131_072;
+    assert_eq!(
+        chart.gas_prices[1..3],
+        [
+            Some(fee(excess_blob_gas - target_gas)),
+            Some(fee(excess_blob_gas - 2 * target_gas)),
+        ]
+    );
+    assert!(chart.gas_prices[2] < chart.gas_prices[1]);
+
+    let chart = db.get_chart_data(4, GapFill::Null, &schedule)?;
+    assert_eq!(chart.gas_prices[1..3], [None, None]);
+    assert_eq!(chart.blobs, [0, 0, 0, 0]);
+
+    // A window starting in a gap continues from the block before it
+    db.insert_block(&block(5, 0))?;
+    db.insert_block(&block(6, 0))?;
+    let chart = db.get_chart_data(5, GapFill::Previous, &schedule)?;
+    assert_eq!(chart.labels, [2, 3, 4, 5, 6]);
+    assert_eq!(chart.gas_prices[..2], [previous, previous]);
+    Ok(())
+}
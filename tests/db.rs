@@ -0,0 +1,785 @@
+//! Behavior of the database wrapper itself, independent of what's indexed.
+
+use alloy_primitives::Address;
+use blob_exex::{
+    chains::REGISTRY_VERSION,
+    config::EntityAddress,
+    db::{NewBlobTransaction, NewBlock, SCHEMA_VERSION},
+    self_test, Database, DbError,
+};
+use rusqlite::Connection;
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn writes_from_other_threads_outlive_rolled_back_transactions() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+
+    let mut action = None;
+    let result: Result<(), DbError> = db.transaction(|| {
+        let writer = db.clone();
+        action = Some(thread::spawn(move || {
+            writer.record_admin_action("reprocess", "{}")
+        }));
+        // Give the other thread time to write into the transaction, if it could
+        thread::sleep(Duration::from_millis(100));
+        Err(DbError::InvalidInput("roll back".to_string()))
+    });
+    assert!(result.is_err());
+
+    action.expect("action thread was spawned").join().unwrap()?;
+    let log = db.get_admin_audit_log(10)?;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].action, "reprocess");
+    Ok(())
+}
+
+#[test]
+fn panicking_transactions_are_rolled_back() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        db.transaction(|| {
+            db.record_admin_action("reprocess", "{}")?;
+            panic!("indexing failed");
+            #[allow(unreachable_code)]
+            Ok::<_, DbError>(())
+        })
+    }));
+    assert!(panicked.is_err());
+
+    // Other threads aren't locked out, and nothing was left behind
+    let writer = db.clone();
+    thread::spawn(move || writer.record_admin_action("prune", "{}"))
+        .join()
+        .unwrap()?;
+    let log = db.get_admin_audit_log(10)?;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].action, "prune");
+    Ok(())
+}
+
+#[test]
+fn nested_transactions_roll_back_on_their_own() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let label = Address::repeat_byte(0x42);
+
+    db.transaction(|| {
+        db.record_admin_action("outer", "{}")?;
+        // Helpers with transactions of their own work inside one
+        db.set_sender_label(&label, "Base")?;
+        let inner: Result<(), DbError> = db.transaction(|| {
+            db.record_admin_action("inner", "{}")?;
+            Err(DbError::InvalidInput("roll back".to_string()))
+        });
+        assert!(inner.is_err());
+        db.transaction(|| db.record_admin_action("committed", "{}"))
+    })?;
+
+    let actions: Vec<String> = db
+        .get_admin_audit_log(10)?
+        .into_iter()
+        .map(|action| action.action)
+        .collect();
+    assert_eq!(actions.len(), 2);
+    assert!(actions.contains(&"outer".to_string()));
+    assert!(actions.contains(&"committed".to_string()));
+    assert_eq!(db.get_sender_labels()?.len(), 1);
+
+    // Rolling back the outer transaction undoes what the helpers wrote
+    let result: Result<(), DbError> = db.transaction(|| {
+        db.delete_sender_label(&label)?;
+        Err(DbError::InvalidInput("roll back".to_string()))
+    });
+    assert!(result.is_err());
+    assert_eq!(db.get_sender_labels()?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn only_senders_with_a_changed_entity_are_reattributed() -> eyre::Result<()> {
+    let file = TempDb::new("entity-addresses");
+    let db = Database::new(file.path())?;
+    let (alpha, beta) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    for (i, sender) in [alpha, beta].into_iter().enumerate() {
+        db.insert_blob_transaction(&NewBlobTransaction {
+            tx_hash: &format!("0x{i:064x}"),
+            block_number: 1,
+            sender,
+            nonce: 0,
+            tx_type: 3,
+            blob_count: 1,
+            gas_price: 1,
+            priority_fee: 0,
+            created_at: 1_767_747_683,
+            el_size: 200,
+            payload_size: None,
+            to: None,
+        })?;
+    }
+    let entity = |name: &str| EntityAddress {
+        entity: name.to_string(),
+        from_block: None,
+        to_block: None,
+    };
+    let conn = Connection::open(file.path())?;
+    // Entity and whether it was attributed since the last call, per tx
+    let attributions = || -> eyre::Result<Vec<(Option<String>, bool)>> {
+        let attributions = conn
+            .prepare(
+                "SELECT attributed_entity, registry_version IS NOT NULL
+                 FROM blob_transactions ORDER BY tx_hash",
+            )?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        conn.execute("UPDATE blob_transactions SET registry_version = NULL", ())?;
+        Ok(attributions)
+    };
+
+    db.replace_entity_addresses(&HashMap::from([(alpha, entity("Alpha"))]))?;
+    assert_eq!(
+        attributions()?,
+        [(Some("Alpha".to_string()), true), (None, true)]
+    );
+
+    db.replace_entity_addresses(&HashMap::from([
+        (alpha, entity("Alpha")),
+        (beta, entity("Beta")),
+    ]))?;
+    assert_eq!(
+        attributions()?,
+        [
+            (Some("Alpha".to_string()), false),
+            (Some("Beta".to_string()), true)
+        ]
+    );
+
+    // Unchanged, as on most restarts
+    db.replace_entity_addresses(&HashMap::from([
+        (alpha, entity("Alpha")),
+        (beta, entity("Beta")),
+    ]))?;
+    assert_eq!(
+        attributions()?,
+        [
+            (Some("Alpha".to_string()), false),
+            (Some("Beta".to_string()), false)
+        ]
+    );
+
+    db.replace_entity_addresses(&HashMap::new())?;
+    assert_eq!(attributions()?, [(None, true), (None, true)]);
+    Ok(())
+}
+
+/// Index blocks 1 to 8, an hour and a half apart, with one to three blob txs
+/// from two senders and a blob schedule change at block 5, the way the indexer
+/// does: txs and their blob hashes first, then the block.
+fn ingest(db: &Database) -> eyre::Result<()> {
+    for block_number in 1..=8u64 {
+        let timestamp = 1_767_747_671 + block_number * 5400;
+        let tx_count = block_number % 3 + 1;
+        for i in 0..tx_count {
+            let tx_hash = format!("0x{block_number:032x}{i:032x}");
+            db.insert_blob_transaction(&NewBlobTransaction {
+                tx_hash: &tx_hash,
+                block_number,
+                sender: Address::repeat_byte(0x10 + i as u8 % 2),
+                nonce: block_number * 10 + i,
+                tx_type: 3,
+                blob_count: i as i64 + 1,
+                gas_price: block_number as u128 * 1_000,
+                priority_fee: i as i64,
+                created_at: timestamp,
+                el_size: 200,
+                payload_size: None,
+                to: None,
+            })?;
+            for blob_index in 0..=i as i64 {
+                let blob_hash = format!("0x{block_number:030x}{i:017x}{blob_index:017x}");
+                db.insert_blob_hash(&tx_hash, &blob_hash, blob_index, Some(100_000))?;
+            }
+        }
+        let blobs = tx_count * (tx_count + 1) / 2;
+        let (blob_target, blob_max) = if block_number < 5 { (6, 9) } else { (10, 15) };
+        db.insert_block(&NewBlock {
+            block_number,
+            block_timestamp: timestamp,
+            tx_count,
+            total_blobs: blobs,
+            gas_used: blobs as i64 * 131_072,
+            gas_price: block_number as u128 * 1_000,
+            excess_blob_gas: 0,
+            base_fee_per_gas: 7,
+            priority_fees: None,
+            blob_target,
+            blob_max,
+            header_blob_gas_used: Some(blobs * 131_072),
+            block_hash: format!("{block_number:#066x}"),
+            beneficiary: Address::repeat_byte(0x24),
+        })?;
+    }
+    Ok(())
+}
+
+/// Every row of the tables derived from blocks and txs, sorted.
+fn derived_tables(path: &str) -> eyre::Result<Vec<String>> {
+    let conn = Connection::open(path)?;
+    let mut rows = Vec::new();
+    for table in [
+        "hourly_blob_stats",
+        "hourly_chain_stats",
+        "daily_blob_hashes",
+        "records",
+        "fork_events",
+    ] {
+        let mut stmt = conn.prepare(&format!("SELECT * FROM {table}"))?;
+        let columns = stmt.column_count();
+        let table_rows = stmt
+            .query_map([], |row| {
+                (0..columns)
+                    .map(|i| row.get::<_, rusqlite::types::Value>(i))
+                    .collect::<Result<Vec<_>, _>>()
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!table_rows.is_empty(), "{table} is empty");
+        rows.extend(table_rows.iter().map(|row| format!("{table} {row:?}")));
+    }
+    rows.sort();
+    Ok(rows)
+}
+
+#[test]
+fn bulk_ingest_derives_the_same_tables() -> eyre::Result<()> {
+    let (normal, bulk) = (TempDb::new("normal-ingest"), TempDb::new("bulk-ingest"));
+    ingest(&Database::new(normal.path())?)?;
+    let db = Database::new(bulk.path())?;
+    db.begin_bulk_ingest()?;
+    ingest(&db)?;
+    db.end_bulk_ingest()?;
+    drop(db);
+
+    assert_eq!(derived_tables(bulk.path())?, derived_tables(normal.path())?);
+    Ok(())
+}
+
+#[test]
+fn rebuilds_after_archiving_keep_the_archived_days() -> eyre::Result<()> {
+    let file = TempDb::new("archive-rebuilds");
+    let dir = std::env::temp_dir().join(format!("blob-exex-{}-archives", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let db = Database::new(file.path())?;
+    ingest(&db)?;
+    db.rebuild_sender_stats()?;
+    let senders = db.get_top_senders(10)?;
+    let rollups = derived_tables(file.path())?;
+
+    // Every tx was indexed on the same day
+    let archived = db.archive(1_767_747_671 + 2 * 86400, &dir)?;
+    assert_eq!(archived.iter().map(|a| a.tx_count).sum::<u64>(), 17);
+    drop(db);
+
+    let db = Database::new(file.path())?;
+    assert_eq!(db.get_sender_drift()?.len(), 0);
+    db.rebuild_sender_stats()?;
+    db.reaggregate(1, 8)?;
+    db.begin_bulk_ingest()?;
+    db.end_bulk_ingest()?;
+    drop(db);
+
+    let db = Database::new(file.path())?;
+    assert_eq!(
+        format!("{:?}", db.get_top_senders(10)?),
+        format!("{senders:?}")
+    );
+    assert_eq!(derived_tables(file.path())?, rollups);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// `SCHEMA_VERSION` and the fingerprint of the schema it stands for. A change
+/// to the schema changes the fingerprint, and has to come with a new version.
+const SCHEMA: (u32, u64) = (28, 0xe3d91784ba12377f);
+
+/// A database file unique to a test, removed again when dropped.
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn new(name: &str) -> Self {
+        let name = format!("blob-exex-{}-{name}.db", std::process::id());
+        Self(std::env::temp_dir().join(name))
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().expect("temp dir is valid UTF-8")
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", self.path()));
+        }
+    }
+}
+
+/// FNV-1a over every table and index definition, whitespace normalized.
+fn schema_fingerprint(conn: &Connection) -> eyre::Result<u64> {
+    let mut stmt = conn.prepare(
+        "SELECT type, name, sql FROM sqlite_master
+         WHERE name NOT LIKE 'sqlite_%' AND sql IS NOT NULL
+         ORDER BY type, name",
+    )?;
+    let definitions = stmt
+        .query_map([], |row| {
+            let (kind, name, sql): (String, String, String) =
+                (row.get(0)?, row.get(1)?, row.get(2)?);
+            Ok(format!(
+                "{kind} {name} {}\n",
+                sql.split_whitespace().collect::<Vec<_>>().join(" ")
+            ))
+        })?
+        .collect::<Result<String, _>>()?;
+
+    Ok(definitions
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        }))
+}
+
+#[test]
+fn schema_changes_bump_the_version() -> eyre::Result<()> {
+    let file = TempDb::new("schema-version");
+    // Reopened, so migrating an up to date database is covered too
+    drop(Database::new(file.path())?);
+    let _db = Database::new(file.path())?;
+    let conn = Connection::open(file.path())?;
+
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    assert_eq!(version, SCHEMA_VERSION);
+    let fingerprint = schema_fingerprint(&conn)?;
+    assert_eq!(
+        (SCHEMA_VERSION, fingerprint),
+        SCHEMA,
+        "the schema changed, bump SCHEMA_VERSION and set SCHEMA to ({}, {fingerprint:#x})",
+        SCHEMA.0 + 1,
+    );
+    Ok(())
+}
+
+#[test]
+fn newer_schema_versions_are_refused() -> eyre::Result<()> {
+    let file = TempDb::new("newer-schema");
+    drop(Database::new(file.path())?);
+    Connection::open(file.path())?.pragma_update(None, "user_version", SCHEMA_VERSION + 1)?;
+
+    assert!(matches!(
+        Database::new(file.path()),
+        Err(DbError::Schema(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn duplicate_blob_hashes_are_merged_by_the_migration() -> eyre::Result<()> {
+    let file = TempDb::new("duplicate-blob-hashes");
+    drop(Database::new(file.path())?);
+    // Back to version 1, which had no unique index and added a blob's row
+    // again whenever its block was reprocessed
+    Connection::open(file.path())?.execute_batch(
+        "DROP INDEX idx_blob_hashes_tx_index;
+         INSERT INTO blob_hashes (tx_hash, blob_hash, blob_index, blob_size) VALUES
+             ('0x01', '0xaa', 0, 1000),
+             ('0x01', '0xaa', 0, NULL),
+             ('0x01', '0xbb', 1, NULL),
+             ('0x01', '0xbb', 1, 2000),
+             ('0x02', '0xcc', 0, NULL);
+         PRAGMA user_version = 1;",
+    )?;
+
+    drop(Database::new(file.path())?);
+    let conn = Connection::open(file.path())?;
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    assert_eq!(version, SCHEMA_VERSION);
+
+    // One row per blob, keeping the known sizes
+    let rows = conn
+        .prepare(
+            "SELECT tx_hash, blob_index, blob_size FROM blob_hashes
+             ORDER BY tx_hash, blob_index",
+        )?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<(String, u64, Option<u64>)>, _>>()?;
+    assert_eq!(
+        rows,
+        [
+            ("0x01".to_string(), 0, Some(1000)),
+            ("0x01".to_string(), 1, Some(2000)),
+            ("0x02".to_string(), 0, None),
+        ]
+    );
+    assert!(conn
+        .execute(
+            "INSERT INTO blob_hashes (tx_hash, blob_hash, blob_index) VALUES ('0x02', '0xcc', 0)",
+            (),
+        )
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn checksummed_addresses_are_lowercased_by_the_migration() -> eyre::Result<()> {
+    let file = TempDb::new("checksummed-addresses");
+    let db = Database::new(file.path())?;
+    let sender = Address::repeat_byte(0xab);
+    for block_number in 1..=2 {
+        db.insert_blob_transaction(&NewBlobTransaction {
+            tx_hash: &format!("0x{block_number:064x}"),
+            block_number,
+            sender,
+            nonce: block_number,
+            tx_type: 3,
+            blob_count: 1,
+            gas_price: 1,
+            priority_fee: 0,
+            created_at: 1_767_747_671 + block_number * 12,
+            el_size: 200,
+            payload_size: None,
+            to: None,
+        })?;
+    }
+    db.update_sender(&sender, 2, 1_767_747_695, 1, 131_072)?;
+    drop(db);
+    // Older versions stored the EIP-55 form, so the same sender could end up
+    // with a row per casing
+    let checksummed = sender.to_checksum(None);
+    let conn = Connection::open(file.path())?;
+    conn.execute("UPDATE blob_transactions SET sender = ?", [&checksummed])?;
+    conn.execute(
+        "INSERT INTO senders (
+             address, tx_count, total_blobs, total_blob_size,
+             first_seen_block, last_seen_block, last_seen_timestamp
+         ) VALUES (?, 1, 1, 131072, 1, 1, 1767747683)",
+        [&checksummed],
+    )?;
+
+    let db = Database::new(file.path())?;
+    let senders = db.get_top_senders(10)?;
+    assert_eq!(senders.len(), 1);
+    let lowercase = checksummed.to_lowercase();
+    assert_eq!(senders[0].address, lowercase);
+    assert_eq!((senders[0].tx_count, senders[0].total_blobs), (2, 2));
+    assert_eq!(
+        (senders[0].first_seen_block, senders[0].last_seen_block),
+        (Some(1), Some(2))
+    );
+    let tx_senders = conn
+        .prepare("SELECT DISTINCT sender FROM blob_transactions")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    assert_eq!(tx_senders, [lowercase]);
+    Ok(())
+}
+
+#[test]
+fn errors_are_classified_by_cause() -> eyre::Result<()> {
+    let file = TempDb::new("error-classes");
+    let db = Database::new(file.path())?;
+
+    // Another writer holding the lock is worth a retry
+    let conn = Connection::open(file.path())?;
+    conn.execute_batch("BEGIN EXCLUSIVE")?;
+    let err = db.record_admin_action("prune", "{}").unwrap_err();
+    assert!(matches!(err, DbError::Busy(_)), "{err:?}");
+    assert!(err.is_transient());
+    conn.execute_batch("COMMIT")?;
+    db.record_admin_action("prune", "{}")?;
+
+    let missing = std::env::temp_dir().join("blob-exex-missing-dir/blobs.db");
+    let err = Database::new(missing.to_str().unwrap()).unwrap_err();
+    assert!(matches!(err, DbError::Io(_)), "{err:?}");
+
+    let garbage = TempDb::new("error-classes-garbage");
+    std::fs::write(&garbage.0, [0x42; 4096])?;
+    let err = Database::new(garbage.path()).unwrap_err();
+    assert!(matches!(err, DbError::Corrupt(_)), "{err:?}");
+    assert!(!err.is_transient());
+    Ok(())
+}
+
+#[test]
+fn the_wal_is_truncated_once_no_reader_uses_it() -> eyre::Result<()> {
+    assert!(Database::new(":memory:")?.file_sizes()?.is_none());
+
+    let file = TempDb::new("wal-checkpoint");
+    let db = Database::new(file.path())?;
+    ingest(&db)?;
+    assert!(db.file_sizes()?.unwrap().wal_bytes > 0);
+
+    // A reader in another process keeps the WAL from being reset
+    let reader = Connection::open(file.path())?;
+    reader.execute_batch("BEGIN; SELECT COUNT(*) FROM blocks;")?;
+    assert!(!db.checkpoint_wal()?.complete);
+    reader.execute_batch("COMMIT")?;
+
+    let checkpoint = db.checkpoint_wal()?;
+    assert!(checkpoint.complete);
+    assert_eq!(checkpoint.wal_frames, 0);
+    let sizes = db.file_sizes()?.unwrap();
+    assert_eq!(sizes.wal_bytes, 0);
+    assert_eq!(sizes.db_bytes, std::fs::metadata(&file.0)?.len());
+    Ok(())
+}
+
+#[test]
+fn relabel_jobs_reattribute_their_range_in_steps() -> eyre::Result<()> {
+    let file = TempDb::new("relabel");
+    let db = Database::new(file.path())?;
+    ingest(&db)?;
+    // Attributed by an older registry
+    let conn = Connection::open(file.path())?;
+    conn.execute(
+        "UPDATE blob_transactions SET attributed_entity = 'Stale', registry_version = 0",
+        (),
+    )?;
+    let stale = |block_number: u64| -> eyre::Result<(Option<String>, u64)> {
+        Ok(conn.query_row(
+            "SELECT MAX(attributed_entity), MIN(registry_version) FROM blob_transactions
+             WHERE block_number = ?",
+            [block_number],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    };
+
+    assert!(matches!(
+        db.request_relabel(5, 2),
+        Err(DbError::InvalidInput(_))
+    ));
+    let id = db.request_relabel(2, 5)?;
+    let job = db.next_relabel_job()?.unwrap();
+    assert_eq!(
+        (job.id, job.next_block, job.registry_version),
+        (id, 2, None)
+    );
+
+    // Blocks 2 and 3 hold 3 and 1 txs, 4 and 5 hold 2 and 3
+    assert_eq!(db.relabel_blocks(id, 2, 3)?, 4);
+    let job = db.next_relabel_job()?.unwrap();
+    assert_eq!((job.next_block, job.relabeled), (4, 4));
+    assert_eq!(job.registry_version, Some(REGISTRY_VERSION));
+    assert_eq!(db.relabel_blocks(id, 4, 5)?, 5);
+    assert!(db.next_relabel_job()?.is_none());
+    let job = db.get_relabel_jobs(10)?.remove(0);
+    assert_eq!(job.relabeled, 9);
+    assert!(job.completed_at.is_some());
+
+    for block_number in 2..=5 {
+        assert_eq!(stale(block_number)?, (None, REGISTRY_VERSION));
+    }
+    for block_number in [1, 6] {
+        assert_eq!(stale(block_number)?, (Some("Stale".to_string()), 0));
+    }
+    Ok(())
+}
+
+#[test]
+fn reaggregating_a_range_rebuilds_its_rollups() -> eyre::Result<()> {
+    let file = TempDb::new("reaggregate");
+    let db = Database::new(file.path())?;
+    ingest(&db)?;
+    db.rebuild_sender_stats()?;
+    let conn = Connection::open(file.path())?;
+    let senders = || -> eyre::Result<Vec<(String, u64, u64, u64)>> {
+        let mut stmt = conn.prepare(
+            "SELECT address, tx_count, total_blobs, last_seen_block FROM senders ORDER BY 1",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    };
+    let (rollups, totals) = (derived_tables(file.path())?, senders()?);
+
+    // An aggregation bug left every hour and sender wrong
+    conn.execute_batch(
+        "UPDATE hourly_blob_stats SET total_blobs = 0;
+         UPDATE senders SET tx_count = 0, total_blobs = 0;",
+    )?;
+    assert!(matches!(
+        db.reaggregate(6, 3),
+        Err(DbError::InvalidInput(_))
+    ));
+    // Blocks are an hour and a half apart, so each has an hour of its own
+    let rebuilt = db.reaggregate(3, 6)?;
+    assert_eq!((rebuilt.hours, rebuilt.senders), (4, 2));
+    assert_eq!(senders()?, totals);
+    let blobs: Vec<u64> = conn
+        .prepare("SELECT total_blobs FROM hourly_blob_stats ORDER BY hour_start")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(blobs, [0, 0, 1, 3, 6, 1, 0, 0]);
+
+    db.reaggregate(1, 8)?;
+    assert_eq!(derived_tables(file.path())?, rollups);
+    Ok(())
+}
+
+#[test]
+fn canonical_checks_sample_settled_blocks() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    assert!(db.sample_block_hashes(2, 3, 5)?.is_empty());
+    ingest(&db)?;
+
+    // The 3 latest blocks 2 below the tip, and older ones picked at random
+    let samples = db.sample_block_hashes(2, 3, 5)?;
+    let blocks: Vec<u64> = samples
+        .iter()
+        .map(|(block_number, _)| *block_number)
+        .collect();
+    assert!(blocks.ends_with(&[4, 5, 6]), "{blocks:?}");
+    assert!(blocks.len() <= 6);
+    assert!(blocks.windows(2).all(|pair| pair[0] < pair[1]));
+    for (block_number, block_hash) in &samples {
+        assert_eq!(*block_hash, format!("{block_number:#066x}"));
+    }
+    assert!(db.sample_block_hashes(8, 3, 5)?.is_empty());
+    assert_eq!(db.sample_block_hashes(5, 3, 5)?.len(), 3);
+
+    assert!(db.get_canonical_check()?.is_none());
+    db.record_canonical_check(6, &[3, 4])?;
+    db.record_canonical_check(5, &[])?;
+    let check = db.get_canonical_check()?.unwrap();
+    assert_eq!(
+        (
+            check.blocks_checked,
+            check.mismatches,
+            check.total_mismatches
+        ),
+        (5, 0, 2)
+    );
+    assert_eq!(check.last_mismatch_block, Some(4));
+    assert!(check
+        .last_mismatch_at
+        .is_some_and(|at| at <= check.checked_at));
+    Ok(())
+}
+
+#[test]
+fn dated_entities_only_attribute_their_blocks() -> eyre::Result<()> {
+    let file = TempDb::new("dated-entities");
+    let db = Database::new(file.path())?;
+    ingest(&db)?;
+    let entities: HashMap<String, EntityAddress> = serde_json::from_str(
+        r#"{
+            "0x1010101010101010101010101010101010101010": { "entity": "Rotated", "to_block": 4 },
+            "0x1111111111111111111111111111111111111111": { "entity": "Joined", "from_block": 5 }
+        }"#,
+    )?;
+    let entities = entities
+        .into_iter()
+        .map(|(address, entity)| Ok((address.parse()?, entity)))
+        .collect::<eyre::Result<_>>()?;
+    db.replace_entity_addresses(&entities)?;
+
+    let conn = Connection::open(file.path())?;
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT block_number <= 4, sender, attributed_entity FROM blob_transactions
+         ORDER BY 1 DESC, 2",
+    )?;
+    let attributions: Vec<(bool, String, Option<String>)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+    let (rotated, joined) = (
+        "0x1010101010101010101010101010101010101010".to_string(),
+        "0x1111111111111111111111111111111111111111".to_string(),
+    );
+    assert_eq!(
+        attributions,
+        [
+            (true, rotated.clone(), Some("Rotated".to_string())),
+            (true, joined.clone(), None),
+            (false, rotated, None),
+            (false, joined, Some("Joined".to_string())),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn the_self_test_catches_changed_indexes() -> eyre::Result<()> {
+    let file = TempDb::new("self-test");
+    let db = Database::new(file.path())?;
+    db.round_trip()?;
+    assert_eq!(db.schema_problems()?, Vec::<String>::new());
+    self_test::run(file.path(), None)?;
+
+    // Opening the database recreates missing indexes, but not changed ones
+    Connection::open(file.path())?.execute_batch(
+        "DROP INDEX idx_ingest_log_processed;
+         CREATE INDEX idx_ingest_log_processed ON ingest_log(kind);",
+    )?;
+    let problems = db.schema_problems()?;
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("index idx_ingest_log_processed is on"));
+    assert!(self_test::run(file.path(), None).is_err());
+
+    let missing = std::env::temp_dir()
+        .join("blob-exex-missing")
+        .join("blob_stats.db");
+    assert!(self_test::run(missing.to_str().unwrap(), None).is_err());
+    Ok(())
+}
+
+#[test]
+fn blob_txs_keep_their_type() -> eyre::Result<()> {
+    let file = TempDb::new("tx-types");
+    let db = Database::new(file.path())?;
+    // A blob-carrying type of a future fork
+    db.insert_blob_transaction(&NewBlobTransaction {
+        tx_hash: &format!("0x{:064x}", 1),
+        block_number: 1,
+        sender: Address::repeat_byte(0x11),
+        nonce: 0,
+        tx_type: 5,
+        blob_count: 1,
+        gas_price: 1,
+        priority_fee: 0,
+        created_at: 1_767_747_683,
+        el_size: 200,
+        payload_size: None,
+        to: None,
+    })?;
+    let tx = db.get_blob_transaction(&format!("0x{:064x}", 1))?.unwrap();
+    assert_eq!(tx.tx_type, Some(5));
+    drop(db);
+
+    // Indexed before types were recorded, when every blob tx was EIP-4844
+    Connection::open(file.path())?
+        .execute("ALTER TABLE blob_transactions DROP COLUMN tx_type", ())?;
+    let db = Database::new(file.path())?;
+    let tx = db.get_blob_transaction(&format!("0x{:064x}", 1))?.unwrap();
+    assert_eq!(tx.tx_type, Some(3));
+    Ok(())
+}
+
+#[test]
+fn raw_envelopes_are_reverted_with_their_block() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let (first, second) = (format!("0x{:064x}", 1), format!("0x{:064x}", 2));
+    db.insert_raw_transaction(&first, 1, &[0x03, 0xf8])?;
+    // Indexed again after a restart
+    db.insert_raw_transaction(&first, 1, &[0x03, 0xf9])?;
+    db.insert_raw_transaction(&second, 2, &[0x03, 0xfa])?;
+    assert_eq!(db.get_raw_transaction(&first)?, Some(vec![0x03, 0xf9]));
+
+    db.delete_raw_transactions(2)?;
+    assert_eq!(db.get_raw_transaction(&second)?, None);
+    assert_eq!(db.get_raw_transaction(&first)?, Some(vec![0x03, 0xf9]));
+    Ok(())
+}
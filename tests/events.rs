@@ -0,0 +1,89 @@
+//! Changes written by another process reach the bus, announced ones only once.
+
+use alloy_primitives::Address;
+use blob_exex::{
+    db::{NewBlobTransaction, NewBlock},
+    events::{self, Event, EventBus},
+    Database,
+};
+use std::time::Duration;
+use tokio::sync::broadcast::Receiver;
+
+fn index(db: &Database, block_number: u64) -> eyre::Result<()> {
+    let tx_hash = format!("0x{block_number:064x}");
+    db.insert_blob_transaction(&NewBlobTransaction {
+        tx_hash: &tx_hash,
+        block_number,
+        sender: Address::repeat_byte(0x11),
+        nonce: block_number,
+        tx_type: 3,
+        blob_count: 1,
+        gas_price: 1,
+        priority_fee: 0,
+        created_at: 1_767_747_671 + block_number * 12,
+        el_size: 200,
+        payload_size: None,
+        to: None,
+    })?;
+    db.insert_block(&NewBlock {
+        block_number,
+        block_timestamp: 1_767_747_671 + block_number * 12,
+        tx_count: 1,
+        total_blobs: 1,
+        gas_used: 131_072,
+        gas_price: 1,
+        excess_blob_gas: 0,
+        base_fee_per_gas: 7,
+        priority_fees: None,
+        blob_target: 14,
+        blob_max: 21,
+        header_blob_gas_used: Some(131_072),
+        block_hash: format!("{block_number:#066x}"),
+        beneficiary: Address::repeat_byte(0x24),
+    })?;
+    Ok(())
+}
+
+async fn next(events: &mut Receiver<Event>) -> eyre::Result<Event> {
+    let wait = events::WATCH_INTERVAL * 3;
+    Ok(tokio::time::timeout(wait, events.recv()).await??)
+}
+
+#[tokio::test]
+async fn database_changes_nobody_announced_are_published() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let bus = EventBus::new();
+    let mut events = bus.subscribe();
+    tokio::spawn(events::watch_database(db.clone(), bus.clone()));
+    // Let the watcher read where the database is at
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    index(&db, 1)?;
+    assert_eq!(
+        next(&mut events).await?,
+        Event::BlockIndexed { block_number: 1 }
+    );
+
+    // Announced by the indexer, so not again by the watcher
+    index(&db, 2)?;
+    bus.publish(Event::BlockIndexed { block_number: 2 });
+    assert_eq!(
+        next(&mut events).await?,
+        Event::BlockIndexed { block_number: 2 }
+    );
+
+    db.archive_reverted_block(2)?;
+    db.delete_block(2)?;
+    assert_eq!(
+        next(&mut events).await?,
+        Event::ReorgDetected {
+            first_block: 2,
+            last_block: 2
+        }
+    );
+    assert_eq!(
+        next(&mut events).await?,
+        Event::BlockIndexed { block_number: 1 }
+    );
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Blob fees above `i64::MAX` wei must survive the database exactly.
+
+use alloy_eips::eip4844::DATA_GAS_PER_BLOB;
+use alloy_primitives::Address;
+use blob_exex::{
+    db::{Downsample, GapFill, NewBlobTransaction, NewBlock},
+    BlobSchedule, Database,
+};
+
+const TIMESTAMP: u64 = 1_767_747_671;
+
+/// Fees around the INTEGER boundary, ascending.
+const FEES: [u128; 5] = [0, 1, i64::MAX as u128, i64::MAX as u128 + 1, u128::MAX];
+
+/// A database with one block per entry of [`FEES`], numbered from 1, each with
+/// a single blob transaction paying that fee.
+fn setup() -> eyre::Result<Database> {
+    let db = Database::new(":memory:")?;
+    for (i, &fee) in FEES.iter().enumerate() {
+        let block_number = i as u64 + 1;
+        let tx_hash = format!("0x{block_number:064x}");
+        db.insert_blob_transaction(&NewBlobTransaction {
+            tx_hash: &tx_hash,
+            block_number,
+            sender: Address::repeat_byte(0x42),
+            nonce: block_number,
+            tx_type: 3,
+            blob_count: 1,
+            gas_price: fee,
+            priority_fee: 0,
+            created_at: TIMESTAMP + block_number * 12,
+            el_size: 200,
+            payload_size: None,
+            to: None,
+        })?;
+        db.insert_block(&NewBlock {
+            block_number,
+            block_timestamp: TIMESTAMP + block_number * 12,
+            tx_count: 1,
+            total_blobs: 1,
+            gas_used: 131_072,
+            gas_price: fee,
+            excess_blob_gas: 0,
+            base_fee_per_gas: 7,
+            priority_fees: None,
+            blob_target: 14,
+            blob_max: 21,
+            header_blob_gas_used: Some(131_072),
+            block_hash: format!("{:#066x}", block_number),
+            beneficiary: Address::repeat_byte(0x24),
+        })?;
+    }
+    Ok(db)
+}
+
+#[test]
+fn extreme_fees_round_trip() -> eyre::Result<()> {
+    let db = setup()?;
+
+    for (i, &fee) in FEES.iter().enumerate() {
+        let block = db.get_block(i as u64 + 1)?.expect("block is indexed");
+        assert_eq!(block.gas_price, fee);
+    }
+
+    let txs = db.get_blob_transactions_in_range(1, FEES.len() as u64)?;
+    let tx_fees: Vec<u128> = txs.iter().map(|tx| tx.gas_price).collect();
+    assert_eq!(tx_fees, FEES);
+
+    let blocks = db.get_blocks_in_range(1, FEES.len() as u64)?;
+    let block_fees: Vec<u128> = blocks.iter().map(|block| block.gas_price).collect();
+    assert_eq!(block_fees, FEES);
+
+    let history = db.get_fee_history(None, FEES.len() as u64)?;
+    let history_fees: Vec<u128> = history.iter().map(|block| block.gas_price).collect();
+    assert_eq!(history_fees, FEES);
+
+    let chart = db.get_chart_data(FEES.len() as u64, GapFill::Null, &BlobSchedule::default())?;
+    assert_eq!(chart.gas_prices_wei, FEES.map(Some));
+
+    assert_eq!(db.get_stats()?.latest_gas_price, u128::MAX);
+    Ok(())
+}
+
+#[test]
+fn extreme_fees_aggregate_without_wrapping() -> eyre::Result<()> {
+    let db = setup()?;
+    let mean = FEES.iter().map(|&fee| fee as f64).sum::<f64>() / FEES.len() as f64;
+
+    let series = db.get_block_series(0, i64::MAX as u64, i64::MAX as u64)?;
+    assert_eq!(series.len(), 1);
+    assert!((series[0].avg_gas_price - mean).abs() <= mean * 1e-9);
+
+    let hourly = db.get_hourly_stats(0)?;
+    let total: f64 = hourly.iter().map(|hour| hour.gas_price_sum).sum();
+    assert!((total - mean * FEES.len() as f64).abs() <= total * 1e-9);
+
+    let chart = db.get_all_time_chart_data(1, &BlobSchedule::default(), Downsample::Max)?;
+    assert_eq!(chart.gas_prices, vec![u128::MAX as f64 / 1e9]);
+    Ok(())
+}
+
+#[test]
+fn cost_breakdown_splits_blob_and_execution_fees() -> eyre::Result<()> {
+    let db = setup()?;
+    // Block 2 pays 1 wei per blob gas, execution tracking saw its tx only
+    db.record_blob_transaction_execution(&format!("0x{:064x}", 2), 21_000, 21_000 * 7)?;
+    db.record_blob_transaction_execution(&format!("0x{:064x}", 99), 21_000, 1)?;
+
+    let breakdown = db.get_sender_cost_breakdown(0)?;
+    assert_eq!(breakdown.len(), 1);
+    let costs = &breakdown[0];
+    assert_eq!(costs.tx_count, FEES.len() as u64);
+    assert_eq!(costs.unpriced_tx_count, FEES.len() as u64 - 1);
+    assert_eq!(costs.priced_blobs, 1);
+    assert_eq!(costs.blob_fees_wei, 131_072.0);
+    assert_eq!(costs.execution_fees_wei, 147_000.0);
+    assert_eq!(costs.execution_gas_used, 21_000);
+    Ok(())
+}
+
+#[test]
+fn blob_fees_are_charged_per_blob_gas() -> eyre::Result<()> {
+    let db = setup()?;
+    // Blocks 1 and 2 pay 0 and 1 wei per blob gas
+    let stats = db.get_period_stats(TIMESTAMP, TIMESTAMP + 3 * 12)?;
+    assert_eq!(stats.blob_fees_wei, DATA_GAS_PER_BLOB as f64);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Chains are forecast to keep posting at their cadence until they go quiet.
+
+use blob_exex::forecast::{self, CadenceModel, GrowthTrend};
+
+#[test]
+fn cadence_is_the_median_gap_between_posts() {
+    assert_eq!(CadenceModel::fit(&[(0, 1), (60, 1)]), None);
+
+    let model = CadenceModel::fit(&[(0, 2), (60, 2), (120, 2), (200, 6)]).unwrap();
+    assert_eq!(
+        model,
+        CadenceModel {
+            cadence_secs: 60,
+            blobs_per_post: 3.0,
+            last_post: 200,
+        }
+    );
+
+    // Never faster than a slot
+    let model = CadenceModel::fit(&[(0, 1), (1, 1), (2, 1)]).unwrap();
+    assert_eq!(model.cadence_secs, 12);
+}
+
+#[test]
+fn posts_are_projected_until_the_chain_goes_quiet() {
+    let model = CadenceModel {
+        cadence_secs: 60,
+        blobs_per_post: 3.0,
+        last_post: 200,
+    };
+
+    let posts: Vec<u64> = model.next_posts(230, 500).collect();
+    assert_eq!(posts, [260, 320, 380, 440]);
+
+    // Overdue, but not for three cadences yet
+    let posts: Vec<u64> = model.next_posts(350, 500).collect();
+    assert_eq!(posts, [350, 410, 470]);
+
+    assert_eq!(model.next_posts(400, 500).count(), 0);
+}
+
+#[test]
+fn demand_growth_is_compounded_daily() {
+    let growing: Vec<(f64, f64)> = (0..8)
+        .map(|day| (day as f64, 2.0 * 1.1f64.powi(day)))
+        .collect();
+    let trend = GrowthTrend::fit(&growing).unwrap();
+    assert!((trend.daily_growth - 0.1).abs() < 1e-9);
+    assert!((trend.level - 2.0 * 1.1f64.powi(7)).abs() < 1e-9);
+    let days = trend.days_until(trend.level * 1.21).unwrap();
+    assert!((days - 2.0).abs() < 1e-9);
+    assert_eq!(trend.days_until(trend.level / 2.0), Some(0.0));
+
+    // Days without demand don't count towards the fit
+    let sparse: Vec<(f64, f64)> = growing
+        .iter()
+        .map(|&(day, value)| (day, if day < 2.0 { 0.0 } else { value }))
+        .collect();
+    assert_eq!(GrowthTrend::fit(&sparse), None);
+
+    let shrinking: Vec<(f64, f64)> = growing.iter().map(|&(day, value)| (-day, value)).collect();
+    let trend = GrowthTrend::fit(&shrinking).unwrap();
+    assert!(trend.daily_growth < 0.0);
+    assert_eq!(trend.days_until(100.0), None);
+
+    assert_eq!(
+        forecast::linear_slope(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]),
+        2.0
+    );
+    assert_eq!(forecast::linear_slope(&[(4.0, 1.0)]), 0.0);
+}
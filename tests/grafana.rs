@@ -0,0 +1,149 @@
+//! The Grafana JSON datasource under `/grafana`.
+
+use alloy_primitives::{address, Address};
+use axum::{
+    body::Body,
+    http::{header, Request},
+    Router,
+};
+use blob_exex::{
+    db::{NewBlobTransaction, NewBlock},
+    grafana, Database,
+};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+/// 2026-01-07T00:00:00Z, an hour before mainnet's BPO2 activated.
+const HOUR: u64 = 1_767_744_000;
+
+/// Status and JSON body of a POST of `body` to `uri`.
+async fn post(router: &Router, uri: &str, body: Value) -> eyre::Result<(u16, Value)> {
+    let request = Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))?;
+    let response = router.clone().oneshot(request).await?;
+    let status = response.status().as_u16();
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok((status, serde_json::from_slice(&body)?))
+}
+
+/// Two blocks in the hour: one with 3 blobs posted by Base, one with a blob
+/// from an unlabelled sender.
+fn setup() -> eyre::Result<Database> {
+    let db = Database::new(":memory:")?;
+    let base = address!("0x5050F69a9786F081509234F1a7F4684b5E5b76C9");
+    for (block_number, sender, blobs) in [(1, base, 3), (2, Address::repeat_byte(0x11), 1)] {
+        let block_timestamp = HOUR + 100 + block_number * 12;
+        db.insert_blob_transaction(&NewBlobTransaction {
+            tx_hash: &format!("0x{block_number:064x}"),
+            block_number,
+            sender,
+            nonce: 0,
+            tx_type: 3,
+            blob_count: blobs,
+            gas_price: 2_000_000_000,
+            priority_fee: 0,
+            created_at: block_timestamp,
+            el_size: 200,
+            payload_size: None,
+            to: None,
+        })?;
+        db.update_sender(&sender, block_number, block_timestamp, blobs as u64, 0)?;
+        db.insert_block(&NewBlock {
+            block_number,
+            block_timestamp,
+            tx_count: 1,
+            total_blobs: blobs as u64,
+            gas_used: blobs * 131_072,
+            gas_price: 2_000_000_000,
+            excess_blob_gas: 0,
+            base_fee_per_gas: 7,
+            priority_fees: None,
+            blob_target: 14,
+            blob_max: 21,
+            header_blob_gas_used: Some(blobs as u64 * 131_072),
+            block_hash: format!("{block_number:#066x}"),
+            beneficiary: Address::repeat_byte(0x24),
+        })?;
+    }
+    Ok(db)
+}
+
+fn range() -> Value {
+    json!({ "from": "2026-01-07T00:00:00.000Z", "to": "2026-01-07T02:00:00.000Z" })
+}
+
+#[tokio::test]
+async fn metrics_are_listed_per_chain() -> eyre::Result<()> {
+    let router = grafana::router(setup()?);
+
+    let (status, metrics) = post(&router, "/grafana/search", json!({ "target": "" })).await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        metrics,
+        json!([
+            "blobs_per_block",
+            "blob_txs_per_block",
+            "blob_base_fee_gwei",
+            "chain_blobs:Base",
+            "chain_blobs:Other",
+        ])
+    );
+    let (_, metrics) = post(&router, "/grafana/search", json!({ "target": "CHAIN" })).await?;
+    assert_eq!(metrics, json!(["chain_blobs:Base", "chain_blobs:Other"]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn series_are_bucketed_by_interval() -> eyre::Result<()> {
+    let router = grafana::router(setup()?);
+
+    let request = json!({
+        "range": range(),
+        "intervalMs": 3_600_000,
+        "targets": [
+            { "target": "blobs_per_block" },
+            { "target": "blob_base_fee_gwei" },
+            { "target": "chain_blobs:Base" },
+        ],
+    });
+    let (status, series) = post(&router, "/grafana/query", request).await?;
+    assert_eq!(status, 200);
+    let ms = HOUR * 1000;
+    assert_eq!(
+        series,
+        json!([
+            { "target": "blobs_per_block", "datapoints": [[2.0, ms]] },
+            { "target": "blob_base_fee_gwei", "datapoints": [[2.0, ms]] },
+            { "target": "chain_blobs:Base", "datapoints": [[3.0, ms]] },
+        ])
+    );
+
+    let unknown = json!({ "range": range(), "targets": [{ "target": "gas" }] });
+    let (status, body) = post(&router, "/grafana/query", unknown).await?;
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "unknown metric: gas");
+    Ok(())
+}
+
+#[tokio::test]
+async fn blob_schedule_changes_are_annotated() -> eyre::Result<()> {
+    let router = grafana::router(setup()?);
+
+    let request = json!({ "range": range(), "annotation": { "name": "forks" } });
+    let (status, annotations) = post(&router, "/grafana/annotations", request).await?;
+    assert_eq!(status, 200);
+    assert_eq!(annotations.as_array().map(Vec::len), Some(1));
+    assert_eq!(annotations[0]["time"], 1_767_747_671_000u64);
+    assert_eq!(annotations[0]["title"], "Blob target 14 / max 21");
+    assert_eq!(annotations[0]["annotation"]["name"], "forks");
+
+    let inverted = json!({
+        "range": { "from": "2026-01-07T02:00:00Z", "to": "2026-01-07T00:00:00Z" },
+        "annotation": { "name": "forks" },
+    });
+    let (status, _) = post(&router, "/grafana/annotations", inverted).await?;
+    assert_eq!(status, 400);
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! Drives the blob ExEx with synthetic notifications against an in-memory database.
+
+use alloy_consensus::{transaction::SignerRecoverable, Header, Transaction as _, TxEip4844};
+use alloy_eips::eip4844::DATA_GAS_PER_BLOB;
+use alloy_primitives::{Address, B256};
+use blob_exex::{
+    config::Verbosity, indexer, regime::RegimeThresholds, replay, telemetry::BlockLog,
+    BlobSchedule, Database,
+};
+use reth::chainspec::MAINNET;
+use reth_execution_types::{Chain, ExecutionOutcome};
+use reth_exex::{ExExNotification, Wal};
+use reth_exex_test_utils::{test_exex_context, PollOnce, TestExExHandle};
+use reth_primitives::{
+    Block, BlockBody, EthPrimitives, RecoveredBlock, SealedBlock, Transaction, TransactionSigned,
+};
+use reth_testing_utils::generators::{self, sign_tx_with_random_key_pair};
+use std::{future::Future, pin::pin, sync::Arc};
+
+/// A timestamp after BPO2 activation on mainnet.
+const TIMESTAMP: u64 = 1_767_747_671 + 12;
+
+/// Sign an EIP-4844 transaction carrying `num_blobs` blobs.
+fn blob_tx(nonce: u64, num_blobs: usize) -> TransactionSigned {
+    let tx = TxEip4844 {
+        chain_id: 1,
+        nonce,
+        gas_limit: 21_000,
+        max_fee_per_gas: 100,
+        max_priority_fee_per_gas: 2,
+        to: Address::repeat_byte(0x42),
+        blob_versioned_hashes: (0..num_blobs)
+            .map(|i| B256::with_last_byte(nonce as u8 * 16 + i as u8))
+            .collect(),
+        max_fee_per_blob_gas: 1_000,
+        ..Default::default()
+    };
+    sign_tx_with_random_key_pair(&mut generators::rng(), Transaction::Eip4844(tx))
+}
+
+/// Build a recovered block at `number` containing `transactions`.
+fn block(number: u64, transactions: Vec<TransactionSigned>) -> RecoveredBlock<Block> {
+    let blob_gas_used = transactions
+        .iter()
+        .filter_map(|tx| tx.blob_versioned_hashes())
+        .map(|hashes| hashes.len() as u64 * DATA_GAS_PER_BLOB)
+        .sum();
+    let header = Header {
+        number,
+        timestamp: TIMESTAMP + number * 12,
+        base_fee_per_gas: Some(7),
+        excess_blob_gas: Some(0),
+        blob_gas_used: Some(blob_gas_used),
+        ..Default::default()
+    };
+    let body = BlockBody {
+        transactions,
+        ..Default::default()
+    };
+    SealedBlock::seal_slow(Block::new(header, body))
+        .try_recover()
+        .expect("failed to recover block senders")
+}
+
+fn chain(blocks: Vec<RecoveredBlock<Block>>) -> Chain {
+    Chain::new(blocks, ExecutionOutcome::default(), None)
+}
+
+/// Start the ExEx against a fresh in-memory database.
+async fn setup() -> eyre::Result<(
+    Database,
+    impl Future<Output = eyre::Result<()>> + Send,
+    TestExExHandle,
+)> {
+    let db = Database::new(":memory:")?;
+    let (ctx, handle) = test_exex_context().await?;
+    let exex = indexer::init(
+        ctx,
+        db.clone(),
+        BlobSchedule::mainnet(),
+        Vec::new(),
+        BlockLog::new(Verbosity::default()),
+        None,
+    )
+    .await?;
+    Ok((db, exex, handle))
+}
+
+#[tokio::test]
+async fn indexes_committed_chain() -> eyre::Result<()> {
+    let (db, exex, mut handle) = setup().await?;
+    let mut exex = pin!(exex);
+
+    let tx = blob_tx(0, 2);
+    let block = block(1, vec![tx.clone()]);
+    let num_hash = block.num_hash();
+
+    handle
+        .send_notification_chain_committed(chain(vec![block]))
+        .await?;
+    exex.poll_once().await?;
+
+    let stored = db.get_block(1)?.expect("block 1 should be indexed");
+    assert_eq!(stored.tx_count, 1);
+    assert_eq!(stored.total_blobs, 2);
+    assert_eq!(stored.gas_used, 2 * 131072);
+    assert_eq!(stored.header_blob_gas_used, Some(stored.gas_used));
+    assert_eq!(stored.transactions.len(), 1);
+    assert_eq!(stored.transactions[0].tx_hash, tx.tx_hash().to_string());
+    assert_eq!(stored.transactions[0].blob_count, 2);
+
+    let txs = db.get_blob_transactions(10)?;
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].blob_hashes.len(), 2);
+
+    let senders = db.get_top_senders(10)?;
+    assert_eq!(senders.len(), 1);
+    assert_eq!(senders[0].address, format!("{:#x}", tx.recover_signer()?));
+    assert_eq!(senders[0].total_blobs, 2);
+
+    handle.assert_event_finished_height(num_hash)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn reverted_chain_is_removed() -> eyre::Result<()> {
+    let (db, exex, mut handle) = setup().await?;
+    let mut exex = pin!(exex);
+
+    let committed = chain(vec![block(1, vec![blob_tx(0, 1)])]);
+
+    handle
+        .send_notification_chain_committed(committed.clone())
+        .await?;
+    exex.poll_once().await?;
+    assert!(db.get_block(1)?.is_some());
+
+    handle.send_notification_chain_reverted(committed).await?;
+    exex.poll_once().await?;
+
+    assert!(db.get_block(1)?.is_none());
+    assert!(db.get_blob_transactions(10)?.is_empty());
+    assert_eq!(db.get_stats()?.total_blocks, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reorged_chain_replaces_old_blocks() -> eyre::Result<()> {
+    let (db, exex, mut handle) = setup().await?;
+    let mut exex = pin!(exex);
+
+    let old_tx = blob_tx(0, 1);
+    let new_tx = blob_tx(1, 3);
+    let old = chain(vec![block(1, vec![old_tx.clone()])]);
+    let new_block = block(1, vec![new_tx.clone()]);
+    let new_num_hash = new_block.num_hash();
+    let new = chain(vec![new_block]);
+
+    handle
+        .send_notification_chain_committed(old.clone())
+        .await?;
+    exex.poll_once().await?;
+    handle.assert_event_finished_height(old.tip().num_hash())?;
+
+    handle.send_notification_chain_reorged(old, new).await?;
+    exex.poll_once().await?;
+
+    let stored = db.get_block(1)?.expect("block 1 should be indexed");
+    assert_eq!(stored.total_blobs, 3);
+    assert_eq!(stored.transactions.len(), 1);
+    assert_eq!(stored.transactions[0].tx_hash, new_tx.tx_hash().to_string());
+
+    let txs = db.get_blob_transactions(10)?;
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].tx_hash, new_tx.tx_hash().to_string());
+    assert_eq!(txs[0].blob_hashes.len(), 3);
+
+    // The old block's sender stats are undone rather than double-counted
+    let senders = db.get_top_senders(10)?;
+    assert_eq!(senders.len(), 1);
+    assert_eq!(
+        senders[0].address,
+        format!("{:#x}", new_tx.recover_signer()?)
+    );
+    assert_eq!(senders[0].total_blobs, 3);
+    assert!(db.get_sender_drift()?.is_empty());
+
+    handle.assert_event_finished_height(new_num_hash)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn blocks_without_blobs_are_recorded() -> eyre::Result<()> {
+    let (db, exex, mut handle) = setup().await?;
+    let mut exex = pin!(exex);
+
+    handle
+        .send_notification_chain_committed(chain(vec![block(1, vec![]), block(2, vec![])]))
+        .await?;
+    exex.poll_once().await?;
+
+    let stats = db.get_stats()?;
+    assert_eq!(stats.total_blocks, 2);
+    assert_eq!(stats.total_blobs, 0);
+    assert_eq!(stats.latest_block, Some(2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_indexes_with_the_seeded_chain_params() -> eyre::Result<()> {
+    let wal_dir = std::env::temp_dir().join(format!("blob-exex-replay-{}", std::process::id()));
+    let wal = Wal::<EthPrimitives>::new(&wal_dir)?;
+    wal.commit(&ExExNotification::ChainCommitted {
+        new: Arc::new(chain(vec![block(1, vec![blob_tx(0, 2)])])),
+    })?;
+    drop(wal);
+
+    let db = Database::new(":memory:")?;
+    let options = replay::Options {
+        wal: wal_dir.clone(),
+        chain: MAINNET.clone(),
+    };
+    std::env::set_var("BLOB_REGIME_THRESHOLDS", "10,20,30,40");
+    let replayed = replay::run(db.clone(), options, BlockLog::new(Verbosity::default())).await;
+    std::env::remove_var("BLOB_REGIME_THRESHOLDS");
+    std::fs::remove_dir_all(&wal_dir)?;
+    replayed?;
+
+    // Stored rather than read from the env var, which the web server lacks
+    assert_eq!(
+        db.get_regime_thresholds()?,
+        RegimeThresholds::parse("10,20,30,40")?
+    );
+    assert_eq!(db.get_blob_schedule()?, indexer::blob_schedule(&MAINNET));
+    let stored = db.get_block(1)?.expect("block 1 should be replayed");
+    assert_eq!(stored.total_blobs, 2);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! The single writer lease and the writes it fences.
+
+use blob_exex::{
+    lease::{self, LEASE_TIMEOUT},
+    Database, DbError,
+};
+use rusqlite::Connection;
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// A database file unique to a test, removed again when dropped.
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn new(name: &str) -> Self {
+        let name = format!("blob-exex-{}-{name}.db", std::process::id());
+        Self(std::env::temp_dir().join(name))
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().expect("temp dir is valid UTF-8")
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", self.path()));
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[tokio::test]
+async fn transactions_fail_once_the_lease_is_lost() -> eyre::Result<()> {
+    let file = TempDb::new("lease-fence");
+    let db = Database::new(file.path())?;
+    lease::acquire(&db, "writer").await?;
+    db.transaction(|| db.record_admin_action("before", "{}"))?;
+
+    // Another writer takes over while this one stalls
+    Connection::open(file.path())?.execute(
+        "UPDATE writer_lease SET holder = 'standby' WHERE id = 1",
+        (),
+    )?;
+    let result = db.transaction(|| db.record_admin_action("after", "{}"));
+    assert!(matches!(result, Err(DbError::Busy(_))));
+
+    let log = db.get_admin_audit_log(10)?;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].action, "before");
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_leases_are_waited_out() -> eyre::Result<()> {
+    let file = TempDb::new("lease-stale");
+    let db = Database::new(file.path())?;
+    db.acquire_writer_lease("stopped", LEASE_TIMEOUT.as_secs())?;
+    // Its last heartbeat went out just under the timeout ago
+    Connection::open(file.path())?.execute(
+        "UPDATE writer_lease SET heartbeat_at = ? WHERE id = 1",
+        [now() - LEASE_TIMEOUT.as_secs() + 2],
+    )?;
+
+    let started = Instant::now();
+    lease::acquire(&db, "restarted").await?;
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(lease::active_writer(&db)?.as_deref(), Some("restarted"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn live_leases_are_refused() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    db.acquire_writer_lease("alive", LEASE_TIMEOUT.as_secs())?;
+    let heartbeats = {
+        let db = db.clone();
+        thread::spawn(move || {
+            for _ in 0..10 {
+                thread::sleep(Duration::from_millis(500));
+                db.renew_writer_lease("alive").unwrap();
+            }
+        })
+    };
+
+    let started = Instant::now();
+    let result = lease::acquire(&db, "second").await;
+    assert!(matches!(result, Err(DbError::Busy(_))));
+    assert!(started.elapsed() < LEASE_TIMEOUT);
+    heartbeats.join().unwrap();
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Metrics of the web server's own process, as served on `/metrics`.
+
+use axum::{body::Body, http::Request, Router};
+use blob_exex::{
+    api::Limits,
+    config::{Profile, WebConfig},
+    events::EventBus,
+    server, telemetry, Database,
+};
+use http_body_util::BodyExt;
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// Status and body of a GET request to `uri`.
+async fn get(app: &Router, uri: &str) -> eyre::Result<(u16, String)> {
+    let response = app
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty())?)
+        .await?;
+    let status = response.status().as_u16();
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok((status, String::from_utf8(body.to_vec())?))
+}
+
+/// Value of the sample of `metric` carrying every one of `labels`.
+fn sample(metrics: &str, metric: &str, labels: &[&str]) -> Option<f64> {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with(&format!("{metric}{{")) || line.starts_with(&format!("{metric} "))
+        })
+        .find(|line| labels.iter().all(|label| line.contains(label)))
+        .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+}
+
+#[tokio::test]
+async fn requests_and_slow_queries_are_counted() -> eyre::Result<()> {
+    // The recorder is installed once per process, so this is the only test here
+    let _guard = telemetry::init("blob-exex-test")?;
+    let db = Database::new(":memory:")?;
+    db.log_slow_queries(Duration::ZERO)?;
+    db.record_admin_action("prune", "{}")?;
+    let config = WebConfig {
+        addr: "127.0.0.1:0".parse().unwrap(),
+        static_dir: "web/dist".into(),
+        admin_token: None,
+        query_token: None,
+        limits: Limits::default(),
+        profile: Profile::Internal,
+        cors_origins: Vec::new(),
+        cache_max_age: 12,
+        tls: None,
+        trusted_proxies: Vec::new(),
+    };
+    let app = server::app(db.clone(), EventBus::new(), None, &config);
+
+    for uri in [
+        "/api/stats",
+        "/api/stats",
+        "/api/blocks/latest",
+        "/wp-login.php",
+    ] {
+        get(&app, uri).await?;
+    }
+    let (status, metrics) = get(&app, "/metrics").await?;
+    assert_eq!(status, 200);
+
+    let requests = |labels: &[&str]| sample(&metrics, "blob_web_requests_total", labels);
+    assert_eq!(
+        requests(&[r#"route="/api/stats""#, r#"status="200""#]),
+        Some(2.0)
+    );
+    assert_eq!(
+        requests(&[r#"route="/api/blocks/{id}""#, r#"method="GET""#]),
+        Some(1.0)
+    );
+    // Scanned paths don't each get a series
+    assert_eq!(
+        requests(&[r#"route="unmatched""#, r#"status="404""#]),
+        Some(1.0)
+    );
+    assert!(!metrics.contains("wp-login"));
+    // Every statement is slow with a threshold of zero
+    assert!(sample(&metrics, "blob_db_slow_queries_total", &[]).is_some_and(|count| count > 0.0));
+    Ok(())
+}
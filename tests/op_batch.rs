@@ -0,0 +1,114 @@
+//! OP Stack batcher blobs decode to their channels, other blobs don't.
+
+use alloy_eips::eip4844::BYTES_PER_BLOB;
+use blob_exex::op_batch::{
+    complete_channels, decode_blob, decompressed_size, parse_frames, Compression,
+};
+use flate2::{write::ZlibEncoder, Compression as Level};
+use std::io::Write;
+
+/// Encode `data` into a blob the way OP Stack batchers do: rounds of 127
+/// bytes, the version and length leading the first, over four field elements.
+fn encode_blob(data: &[u8]) -> Vec<u8> {
+    let len = data.len() as u32;
+    let mut input = vec![0, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+    input.extend_from_slice(data);
+
+    let mut blob = vec![0u8; BYTES_PER_BLOB];
+    for (round, chunk) in input.chunks(127).enumerate() {
+        let mut bytes = [0u8; 127];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let (x, y, z) = (bytes[31], bytes[63], bytes[95]);
+        let firsts = [
+            x & 0b0011_1111,
+            (y & 0b0000_1111) | ((x & 0b1100_0000) >> 2),
+            z & 0b0011_1111,
+            ((z & 0b1100_0000) >> 2) | ((y & 0b1111_0000) >> 4),
+        ];
+        for (i, first) in firsts.into_iter().enumerate() {
+            let at = round * 128 + i * 32;
+            blob[at] = first;
+            blob[at + 1..at + 32].copy_from_slice(&bytes[i * 32..i * 32 + 31]);
+        }
+    }
+    blob
+}
+
+fn frame(channel_id: u8, frame_number: u16, data: &[u8], is_last: bool) -> Vec<u8> {
+    let mut frame = vec![channel_id; 16];
+    frame.extend_from_slice(&frame_number.to_be_bytes());
+    frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    frame.extend_from_slice(data);
+    frame.push(is_last as u8);
+    frame
+}
+
+/// Bytes that don't compress, so the channel spans many rounds.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn batcher_blobs_decode_to_their_channels() -> eyre::Result<()> {
+    let batches = noise(1_000);
+    let mut encoder = ZlibEncoder::new(Vec::new(), Level::default());
+    encoder.write_all(&batches)?;
+    let compressed = encoder.finish()?;
+    let (head, tail) = compressed.split_at(600);
+
+    // Channel 0xaa spans two frames, 0xbb continues in a later transaction
+    let mut data = vec![0];
+    data.extend(frame(0xaa, 1, tail, true));
+    data.extend(frame(0xbb, 0, b"rest later", false));
+    data.extend(frame(0xaa, 0, head, false));
+
+    assert_eq!(decode_blob(&encode_blob(&data)).as_deref(), Some(&data[..]));
+    let frames = parse_frames(&data).unwrap();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[1].channel_id, [0xbb; 16]);
+    assert!(!frames[1].is_last);
+
+    let channels = complete_channels(&frames);
+    assert_eq!(channels.len(), 1);
+    assert_eq!(channels[0].id, [0xaa; 16]);
+    assert_eq!(channels[0].frame_count, 2);
+    assert_eq!(channels[0].data, compressed);
+    assert_eq!(
+        decompressed_size(&channels[0].data),
+        Some((Compression::Zlib, batches.len() as u64))
+    );
+    Ok(())
+}
+
+#[test]
+fn other_blobs_are_not_decoded() {
+    let data = [&[0][..], &frame(0xaa, 0, b"batch", true)].concat();
+    let blob = encode_blob(&data);
+
+    let mut version = blob.clone();
+    version[1] = 1;
+    assert_eq!(decode_blob(&version), None);
+    let mut padding = blob.clone();
+    padding[BYTES_PER_BLOB - 1] = 1;
+    assert_eq!(decode_blob(&padding), None);
+    let mut field_element = blob;
+    field_element[32] |= 0b1000_0000;
+    assert_eq!(decode_blob(&field_element), None);
+
+    // Frames must be well formed to the last byte
+    assert_eq!(parse_frames(&data[..data.len() - 1]), None);
+    assert_eq!(parse_frames(&[&data[..], &[0]].concat()), None);
+    let mut is_last = data.clone();
+    *is_last.last_mut().unwrap() = 2;
+    assert_eq!(parse_frames(&is_last), None);
+    assert_eq!(parse_frames(&[&[1][..], &data[1..]].concat()), None);
+    assert_eq!(decompressed_size(b"\x42 not compressed"), None);
+}
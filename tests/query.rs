@@ -0,0 +1,159 @@
+//! Ad-hoc queries may only read public tables, and only for so long.
+
+use blob_exex::{
+    db::{self, PRIVATE_TABLES},
+    Database, DbError,
+};
+use rusqlite::types::Value;
+use std::{path::PathBuf, time::Duration};
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A database file unique to a test, removed again when dropped.
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn new(name: &str) -> Self {
+        let name = format!("blob-exex-{}-{name}.db", std::process::id());
+        Self(std::env::temp_dir().join(name))
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().expect("temp dir is valid UTF-8")
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", self.path()));
+        }
+    }
+}
+
+fn query(db: &Database, sql: &str) -> Result<Vec<Vec<Value>>, DbError> {
+    db.query_read_only(sql, 100, TIMEOUT)
+        .map(|result| result.rows)
+}
+
+#[test]
+fn queries_cannot_write_attach_or_pragma() -> eyre::Result<()> {
+    let file = TempDb::new("query-writes");
+    let db = Database::new(file.path())?;
+    db.record_admin_action("prune", "{}")?;
+    let attached = TempDb::new("query-attached");
+
+    for sql in [
+        "INSERT INTO admin_audit_log (action, params, created_at) VALUES ('x', '{}', 0)",
+        "DELETE FROM blocks",
+        "UPDATE blocks SET gas_price = 0",
+        "CREATE TABLE extra (id INTEGER)",
+        "DROP TABLE blocks",
+        &format!("ATTACH DATABASE '{}' AS other", attached.path()),
+        "PRAGMA user_version",
+        "PRAGMA journal_mode = DELETE",
+        "SELECT * FROM pragma_table_info('blocks')",
+    ] {
+        assert!(query(&db, sql).is_err(), "{sql} was allowed");
+    }
+    assert!(!attached.0.exists());
+    assert_eq!(db.get_admin_audit_log(10)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn queries_cannot_load_extensions() -> eyre::Result<()> {
+    let file = TempDb::new("query-extensions");
+    let db = Database::new(file.path())?;
+
+    let err = query(&db, "SELECT load_extension('/tmp/missing')").unwrap_err();
+    assert!(matches!(err, DbError::InvalidInput(_)), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn queries_cannot_read_private_tables() -> eyre::Result<()> {
+    let file = TempDb::new("query-private");
+    let db = Database::new(file.path())?;
+    db.record_admin_action("prune", "{}")?;
+
+    for table in PRIVATE_TABLES {
+        for sql in [
+            format!("SELECT * FROM {table}"),
+            format!("SELECT COUNT(*) FROM {table}"),
+            format!("SELECT 1 WHERE EXISTS (SELECT 1 FROM {table})"),
+        ] {
+            assert!(query(&db, &sql).is_err(), "{sql} was allowed");
+        }
+    }
+    assert_eq!(
+        query(&db, "SELECT COUNT(*) FROM blocks")?,
+        [[Value::Integer(0)]]
+    );
+    Ok(())
+}
+
+#[test]
+fn long_queries_are_interrupted() -> eyre::Result<()> {
+    let file = TempDb::new("query-timeout");
+    let db = Database::new(file.path())?;
+
+    let err = query(
+        &db,
+        "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n)
+         SELECT COUNT(*) FROM n",
+    )
+    .unwrap_err();
+    assert!(
+        matches!(&err, DbError::InvalidInput(message) if message.contains("longer than")),
+        "{err:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn queries_return_at_most_the_row_limit() -> eyre::Result<()> {
+    let file = TempDb::new("query-limit");
+    let db = Database::new(file.path())?;
+    let sql = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 10)
+               SELECT i FROM n";
+
+    let result = db.query_read_only(sql, 3, TIMEOUT)?;
+    assert_eq!(result.columns, ["i"]);
+    assert_eq!(result.rows, [1, 2, 3].map(|i| vec![Value::Integer(i)]));
+    assert!(result.truncated);
+
+    let result = db.query_read_only(sql, 10, TIMEOUT)?;
+    assert_eq!(result.rows.len(), 10);
+    assert!(!result.truncated);
+    Ok(())
+}
+
+#[test]
+fn queries_bucket_like_the_helpers() -> eyre::Result<()> {
+    let file = TempDb::new("query-buckets");
+    let db = Database::new(file.path())?;
+    let timestamp = 1_767_747_671;
+
+    for utc_offset in [0, 5 * 3600, 19_800, -34_200] {
+        let rows = query(
+            &db,
+            &format!(
+                "SELECT group_by_hour({timestamp}, {utc_offset}),
+                        group_by_day({timestamp}, {utc_offset}),
+                        group_by_day(NULL, 0)"
+            ),
+        )?;
+        assert_eq!(
+            rows,
+            [[
+                Value::Integer(db::group_by_hour(timestamp, utc_offset) as i64),
+                Value::Integer(db::group_by_day(timestamp, utc_offset) as i64),
+                Value::Null,
+            ]]
+        );
+    }
+    // Half hour offsets move hour boundaries
+    assert_eq!(db::group_by_hour(timestamp, 19_800) % 3600, 1800);
+    Ok(())
+}
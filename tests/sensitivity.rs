@@ -0,0 +1,45 @@
+//! Correlations between blob fees and how much chains post.
+
+use blob_exex::sensitivity::{pearson, price_sensitivity};
+
+#[test]
+fn chains_holding_back_after_spikes_correlate_negatively_later() {
+    // Fee spikes every other interval, and the chain posts in the interval
+    // after each calm one
+    let fees = [1.0, 5.0]
+        .repeat(5)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    let blobs = [1.0, 6.0].repeat(5);
+
+    let sensitivity = price_sensitivity(&fees, &blobs);
+    let correlation = |value: Option<f64>| value.map(|value| (value * 1e9).round() / 1e9);
+    assert_eq!(correlation(sensitivity.correlation), Some(1.0));
+    let lagged: Vec<(u64, Option<f64>)> = sensitivity
+        .lagged
+        .iter()
+        .map(|lagged| (lagged.lag_minutes, correlation(lagged.correlation)))
+        .collect();
+    // 10, 30 and 60 minutes are 1, 3 and 6 intervals later
+    assert_eq!(
+        lagged,
+        [(10, Some(-1.0)), (30, Some(-1.0)), (60, Some(1.0))]
+    );
+}
+
+#[test]
+fn intervals_without_blocks_or_variation_are_not_correlated() {
+    // Intervals without blocks have no fee and are left out
+    let fees = [Some(1.0), None, Some(2.0), None, Some(3.0)];
+    let blobs = [1.0, 100.0, 2.0, -100.0, 3.0];
+    let sensitivity = price_sensitivity(&fees, &blobs);
+    assert!(sensitivity
+        .correlation
+        .is_some_and(|c| (c - 1.0).abs() < 1e-9));
+    // Fewer than three pairs are left 10 minutes later
+    assert_eq!(sensitivity.lagged[0].correlation, None);
+
+    assert_eq!(pearson(&[1.0, 2.0, 3.0], &[4.0, 4.0, 4.0]), None);
+    assert_eq!(pearson(&[1.0, 2.0], &[1.0, 2.0]), None);
+}
@@ -0,0 +1,140 @@
+//! The web app as served, with the layers around the API.
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Request},
+    Router,
+};
+use blob_exex::{
+    api::Limits,
+    config::{Profile, WebConfig},
+    events::EventBus,
+    server, Database,
+};
+use http_body_util::BodyExt;
+use serde_json::Value;
+use tower::ServiceExt;
+
+fn config(profile: Profile, limits: Limits) -> WebConfig {
+    WebConfig {
+        addr: "127.0.0.1:0".parse().unwrap(),
+        static_dir: "web/dist".into(),
+        admin_token: None,
+        query_token: None,
+        limits,
+        profile,
+        cors_origins: Vec::new(),
+        cache_max_age: 12,
+        tls: None,
+        trusted_proxies: Vec::new(),
+    }
+}
+
+fn app(db: &Database, profile: Profile, limits: Limits) -> Router {
+    server::app(db.clone(), EventBus::new(), None, &config(profile, limits))
+}
+
+/// Status and headers of a GET request to `uri` with `headers`, and its body
+/// if it's JSON.
+async fn get(
+    app: &Router,
+    uri: &str,
+    headers: &[(header::HeaderName, &str)],
+) -> eyre::Result<(u16, HeaderMap, Option<Value>)> {
+    let mut request = Request::get(uri);
+    for (name, value) in headers {
+        request = request.header(name, *value);
+    }
+    let response = app.clone().oneshot(request.body(Body::empty())?).await?;
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let json = headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value == "application/json")
+        && !headers.contains_key(header::CONTENT_ENCODING);
+    let body = if json {
+        let body = response.into_body().collect().await?.to_bytes();
+        Some(serde_json::from_slice(&body)?)
+    } else {
+        None
+    };
+    Ok((status, headers, body))
+}
+
+#[tokio::test]
+async fn responses_are_compressed_and_large_requests_refused() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let app = app(&db, Profile::Internal, Limits::default());
+    let gzip = [(header::ACCEPT_ENCODING, "gzip")];
+
+    let (status, headers, _) = get(&app, "/api/stats", &gzip).await?;
+    assert_eq!(status, 200);
+    assert_eq!(headers[header::CONTENT_ENCODING], "gzip");
+    // Streamed lines aren't held back in the encoder
+    let (status, headers, _) = get(&app, "/api/tail", &gzip).await?;
+    assert_eq!(status, 200);
+    assert!(!headers.contains_key(header::CONTENT_ENCODING));
+
+    let (status, _, body) = get(&app, "/api/chart?blocks=10000000", &[]).await?;
+    assert_eq!(status, 413);
+    assert_eq!(
+        body.unwrap()["error"],
+        "blocks must be at most 10000, got 10000000"
+    );
+    let (status, _, _) = get(&app, "/api/chart?blocks=10000", &[]).await?;
+    assert_eq!(status, 200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn the_public_profile_only_serves_cacheable_reads() -> eyre::Result<()> {
+    let db = Database::new(":memory:")?;
+    let limits = Limits::default().capped(Limits::PUBLIC);
+    let internal = server::app(
+        db.clone(),
+        EventBus::new(),
+        None,
+        &WebConfig {
+            admin_token: Some("secret".to_string()),
+            ..config(Profile::Internal, Limits::default())
+        },
+    );
+    let public = server::app(
+        db.clone(),
+        EventBus::new(),
+        None,
+        &WebConfig {
+            cors_origins: vec![HeaderValue::from_static("https://blobs.example")],
+            ..config(Profile::Public, limits)
+        },
+    );
+
+    for uri in ["/grafana", "/admin/audit-log"] {
+        let (status, _, _) = get(&internal, uri, &[]).await?;
+        assert_ne!(status, 404, "{uri} isn't served internally");
+        let (status, _, _) = get(&public, uri, &[]).await?;
+        assert_eq!(status, 404, "{uri} is served publicly");
+    }
+
+    let (status, headers, _) = get(&public, "/api/stats", &[]).await?;
+    assert_eq!(status, 200);
+    assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=12");
+    let (_, headers, _) = get(&public, "/api/tail", &[]).await?;
+    assert!(!headers.contains_key(header::CACHE_CONTROL));
+    let (_, headers, _) = get(&internal, "/api/stats", &[]).await?;
+    assert!(!headers.contains_key(header::CACHE_CONTROL));
+
+    // Only listed origins may read cross-origin
+    let origin = |origin| [(header::ORIGIN, origin)];
+    let (_, headers, _) = get(&public, "/api/stats", &origin("https://blobs.example")).await?;
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://blobs.example"
+    );
+    let (_, headers, _) = get(&public, "/api/stats", &origin("https://other.example")).await?;
+    assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+    let (status, _, _) = get(&public, "/api/chart?blocks=2001", &[]).await?;
+    assert_eq!(status, 413);
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Snapshots taken while the database is written, and restoring them.
+
+use alloy_primitives::Address;
+use blob_exex::{db::NewBlock, snapshot, Database, DbError};
+use std::path::PathBuf;
+
+/// A database file unique to a test, removed again when dropped.
+struct TempDb(PathBuf);
+
+impl TempDb {
+    fn new(name: &str) -> Self {
+        let name = format!("blob-exex-{}-{name}.db", std::process::id());
+        Self(std::env::temp_dir().join(name))
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().expect("temp dir is valid UTF-8")
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", self.path()));
+        }
+    }
+}
+
+fn block(block_number: u64) -> NewBlock {
+    NewBlock {
+        block_number,
+        block_timestamp: 1_767_747_671 + block_number * 12,
+        tx_count: 0,
+        total_blobs: 3,
+        gas_used: 3 * 131_072,
+        gas_price: 1,
+        excess_blob_gas: 0,
+        base_fee_per_gas: 7,
+        priority_fees: None,
+        blob_target: 14,
+        blob_max: 21,
+        header_blob_gas_used: Some(3 * 131_072),
+        block_hash: format!("{block_number:#066x}"),
+        beneficiary: Address::repeat_byte(0x24),
+    }
+}
+
+#[test]
+fn snapshots_restore_what_was_written_before_them() -> eyre::Result<()> {
+    let file = TempDb::new("snapshot-source");
+    let db = Database::new(file.path())?;
+    // Still in the WAL, not checkpointed into the database file
+    for block_number in 1..=3 {
+        db.insert_block(&block(block_number))?;
+    }
+    db.record_admin_action("prune", "{}")?;
+
+    let snapshot = TempDb::new("snapshot");
+    db.snapshot(&snapshot.0)?;
+    db.insert_block(&block(4))?;
+    let err = db.snapshot(&snapshot.0).unwrap_err();
+    assert!(matches!(err, DbError::InvalidInput(_)), "{err:?}");
+
+    let restored = TempDb::new("snapshot-restored");
+    let target = Database::new(restored.path())?;
+    target.insert_block(&block(42))?;
+    target.restore(&snapshot.0)?;
+    assert_eq!(target.get_latest_block()?, Some(3));
+    assert_eq!(target.get_recent_blocks(10)?.len(), 3);
+    assert_eq!(target.get_admin_audit_log(10)?.len(), 1);
+    // Reopened from the file alone
+    drop(target);
+    assert_eq!(Database::new(restored.path())?.get_latest_block()?, Some(3));
+    Ok(())
+}
+
+#[test]
+fn only_sound_snapshots_are_restored() -> eyre::Result<()> {
+    let file = TempDb::new("snapshot-unsound");
+    let db = Database::new(file.path())?;
+    db.insert_block(&block(1))?;
+
+    let missing = TempDb::new("snapshot-missing");
+    let err = db.restore(&missing.0).unwrap_err();
+    assert!(matches!(err, DbError::NotFound(_)), "{err:?}");
+
+    let garbage = TempDb::new("snapshot-garbage");
+    std::fs::write(&garbage.0, [0x42; 4096])?;
+    let err = db.restore(&garbage.0).unwrap_err();
+    assert!(matches!(err, DbError::Corrupt(_)), "{err:?}");
+    assert_eq!(db.get_latest_block()?, Some(1));
+    Ok(())
+}
+
+#[test]
+fn scheduled_snapshots_keep_the_newest() -> eyre::Result<()> {
+    let file = TempDb::new("snapshot-rotate");
+    let db = Database::new(file.path())?;
+    let dir = std::env::temp_dir().join(format!("blob-exex-{}-snapshots", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    for name in ["blob_stats-1.db", "blob_stats-2.db", "manual.db"] {
+        std::fs::write(dir.join(name), "")?;
+    }
+
+    let path = snapshot::rotate(&db, &dir, 2)?;
+    let mut names: Vec<String> = std::fs::read_dir(&dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<std::io::Result<_>>()?;
+    let latest = path.file_name().unwrap().to_string_lossy().into_owned();
+    let mut kept = vec![
+        "blob_stats-2.db".to_string(),
+        latest,
+        "manual.db".to_string(),
+    ];
+    names.sort();
+    kept.sort();
+    assert_eq!(names, kept);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
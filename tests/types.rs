@@ -0,0 +1,195 @@
+//! API response types must read back what the server writes, and keep reading
+//! responses of newer servers that added fields.
+
+use blob_exex::types::{BlobFeeHistory, BlobTransaction, Block, ChainProfile, Health, Stats};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+/// Deserialize `body` into `T` and serialize it again, expecting the same JSON.
+fn round_trip<T: Serialize + DeserializeOwned>(body: Value) -> eyre::Result<T> {
+    let value: T = serde_json::from_value(body.clone())?;
+    assert_eq!(serde_json::to_value(&value)?, body);
+    Ok(value)
+}
+
+fn block(transactions: Option<Value>) -> Value {
+    let mut block = json!({
+        "block_number": 21_000_000,
+        "block_hash": format!("0x{:064x}", 21_000_000),
+        "url": format!("/api/blocks/0x{:064x}", 21_000_000),
+        "block_timestamp": 1_767_747_671,
+        "tx_count": 1,
+        "total_blobs": 2,
+        "total_blob_size": 132_072,
+        "gas_used": 262_144,
+        "gas_price": 1_000_000_000,
+        "excess_blob_gas": 0,
+        "base_fee_per_gas": 7,
+        "non_blob_tx_count": null,
+        "non_blob_gas_used": null,
+        "blob_target": 14,
+        "blob_max": 21,
+        "block_interval": 12,
+        "beneficiary": "0x4838B106FCe9647Bdf1E7877BF73cE8B0BAD5f97",
+        "by_chain": [{ "chain": "Base", "tx_count": 1, "blobs": 2, "blob_size": 132_072 }],
+        "target_utilization": 14.285714285714286,
+        "regime": "abundant",
+        "saturation_index": 9.523809523809524,
+        "blob_gas_used_ratio": 0.09523809523809523,
+        "consistency": {
+            "header_blob_gas_used": 262_144,
+            "computed_blob_gas_used": 262_144,
+            "matches": true,
+        },
+    });
+    if let Some(transactions) = transactions {
+        block["transactions"] = transactions;
+    }
+    block
+}
+
+#[test]
+fn stats_round_trip() -> eyre::Result<()> {
+    let stats: Stats = round_trip(json!({
+        "total_blocks": 180,
+        "total_blobs": 360,
+        "total_transactions": 180,
+        "avg_blobs_per_block": 2.0,
+        "latest_block": 180,
+        "earliest_block": 1,
+        "latest_gas_price": 1,
+        "total_slots": 181,
+        "missed_slots": 1,
+        "avg_blobs_per_slot": 1.988950276243094,
+        "throughput_windows": [{
+            "window": "1h",
+            "blocks": 180,
+            "slots": 181,
+            "missed_slots": 1,
+            "blobs_per_block": 2.0,
+            "blobs_per_slot": 1.988950276243094,
+            "block_intervals": {
+                "avg_secs": 12.067,
+                "p50_secs": 12,
+                "p90_secs": 12,
+                "p99_secs": 24,
+                "max_secs": 24,
+            },
+            "sustained_bytes_per_sec": 21727.35,
+            "peak_bytes_per_sec": 65536.0,
+        }],
+        "total_el_bytes": 36_000,
+        "total_da_bytes": 47_185_920,
+        "total_payload_bytes": 180_000,
+        "total_blob_refs": 360,
+        "distinct_daily_blobs": 358,
+        "regimes": [
+            { "name": "abundant", "from_pct": 0.0, "to_pct": 50.0 },
+            { "name": "normal", "from_pct": 50.0, "to_pct": 90.0 },
+            { "name": "pressured", "from_pct": 90.0, "to_pct": 100.0 },
+            { "name": "congested", "from_pct": 100.0, "to_pct": 150.0 },
+            { "name": "saturated", "from_pct": 150.0, "to_pct": null },
+        ],
+    }))?;
+    assert_eq!(stats.throughput_windows[0].window, "1h");
+    Ok(())
+}
+
+#[test]
+fn block_transactions_are_optional() -> eyre::Result<()> {
+    let block: Block = round_trip(block(None))?;
+    assert!(block.transactions.is_none());
+
+    let block: Block = round_trip(self::block(Some(json!([{
+        "tx_hash": format!("0x{:064x}", 1),
+        "sender": "0x5050F69a9786F081509234F1a7F4684b5E5b76C9",
+        "blob_count": 2,
+        "blob_size": 132_072,
+        "chain": "Base",
+        "attributed_entity": null,
+        "label_source": "registry",
+        "url": format!("/api/txs/0x{:064x}", 1),
+    }]))))?;
+    assert_eq!(block.transactions.map(|txs| txs.len()), Some(1));
+    Ok(())
+}
+
+#[test]
+fn nested_and_renamed_fields_round_trip() -> eyre::Result<()> {
+    round_trip::<BlobTransaction>(json!({
+        "tx_hash": format!("0x{:064x}", 1),
+        "block_number": 21_000_000,
+        "sender": "0x5050F69a9786F081509234F1a7F4684b5E5b76C9",
+        "blob_count": 2,
+        "blob_size": 132_072,
+        "gas_price": 1_000_000_000,
+        "chain": "Base",
+        "attributed_entity": "Base",
+        "label_source": "manual",
+        "tx_type": 3,
+        "blob_hashes": [format!("0x01{:062x}", 1), format!("0x01{:062x}", 2)],
+        "blob_sizes": [1000, null],
+        "url": format!("/api/txs/0x{:064x}", 1),
+    }))?;
+    round_trip::<ChainProfile>(json!({
+        "chain": "Base",
+        "total_transactions": 10,
+        "total_blobs": 60,
+        "percentage": 42.5,
+        "avg_blobs_per_tx": 6.0,
+        "avg_posting_interval_secs": 24.0,
+        "hourly_activity": vec![0.5; 24],
+        "price_sensitivity": {
+            "correlation": -0.25,
+            "lagged": [{ "lag_minutes": 10, "correlation": null }],
+        },
+    }))?;
+    round_trip::<Health>(json!({
+        "status": "ok",
+        "latest_block": 180,
+        "blocks_behind": 2,
+        "writer": {
+            "holder": "host:1234",
+            "acquired_at": 1_767_747_000,
+            "heartbeat_at": 1_767_747_671,
+            "heartbeat_age_secs": 3,
+            "active": true,
+            "node_tip": 182,
+        },
+        "db_size_bytes": 4_096_000,
+        "wal_size_bytes": 0,
+        "canonical": {
+            "checked_at": 1_767_747_600,
+            "blocks_checked": 320,
+            "mismatches": 0,
+            "total_mismatches": 1,
+            "last_mismatch_block": 150,
+            "last_mismatch_at": 1_767_747_000,
+        },
+        "senders": {
+            "checked_at": 1_767_747_000,
+            "drifted_senders": 2,
+            "tx_count_drift": 3,
+            "blob_drift": 9,
+            "repaired": true,
+            "total_repairs": 1,
+            "last_repair_at": 1_767_747_000,
+        },
+    }))?;
+    // Only present when reward percentiles are requested
+    round_trip::<BlobFeeHistory>(json!({
+        "oldestBlock": "0x1",
+        "baseFeePerBlobGas": ["0x1", "0x1"],
+        "blobGasUsedRatio": [0.5],
+    }))?;
+    Ok(())
+}
+
+#[test]
+fn unknown_fields_are_ignored() -> eyre::Result<()> {
+    let mut body = block(None);
+    body["added_in_a_later_version"] = json!({ "anything": [1, 2, 3] });
+    let block: Block = serde_json::from_value(body)?;
+    assert_eq!(block.block_number, 21_000_000);
+    Ok(())
+}
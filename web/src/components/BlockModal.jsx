@@ -11,6 +11,7 @@ import {
   BLOB_TARGET,
   BLOB_MAX,
   DATA_GAS_PER_BLOB,
+  getRegimeColor,
   getUtilizationColor,
   getSaturationColor,
 } from "../utils/protocol";
@@ -37,7 +38,9 @@ function BlockModal({ block, onClose }) {
   // Derived metrics
   const targetUtilization = (totalBlobs / BLOB_TARGET) * 100;
   const saturationIndex = (totalBlobs / BLOB_MAX) * 100;
-  const utilizationColor = getUtilizationColor(targetUtilization);
+  const utilizationColor = block.regime
+    ? getRegimeColor(block.regime)
+    : getUtilizationColor(targetUtilization);
   const saturationColor = getSaturationColor(saturationIndex);
 
   const handleOverlayClick = (e) => {
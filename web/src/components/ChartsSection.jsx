@@ -85,7 +85,9 @@ const BlobTooltip = ({ active, payload, label }) => {
   return null;
 };
 
-const formatGweiChart = (value) => value.toFixed(6);
+// Significant digits rather than decimals, so fees down to 1 wei don't read as 0
+const formatGweiChart = (value) =>
+  value >= 1 ? value.toFixed(2) : value.toPrecision(3);
 
 // Get bar color based on blob count relative to target/max
 // Uses blue-to-indigo gradient: light blue (low) -> blue -> indigo (90%+ = max)
@@ -144,6 +146,25 @@ const AllTimeTooltip = ({ active, payload, label }) => {
   return null;
 };
 
+// Vertical markers for operator annotations, at the block each falls at
+const annotationLines = (annotations) =>
+  (annotations || [])
+    .filter((annotation) => annotation.block_number != null)
+    .map((annotation) => (
+      <ReferenceLine
+        key={`annotation-${annotation.id}`}
+        x={annotation.block_number}
+        stroke="#a855f7"
+        strokeDasharray="2 4"
+        label={{
+          value: annotation.title,
+          position: "insideTopLeft",
+          fill: "#a855f7",
+          fontSize: 10,
+        }}
+      />
+    ));
+
 function ChartsSection({ chartData, allTimeChartData, onBlockClick }) {
   // Memoize processed chart data
   const blobsData = useMemo(() => {
@@ -158,7 +179,7 @@ function ChartsSection({ chartData, allTimeChartData, onBlockClick }) {
     if (!chartData?.labels) return [];
     return chartData.labels.map((label, index) => ({
       block: label,
-      price: chartData.gas_prices?.[index] || 0,
+      price: chartData.gas_prices?.[index] ?? null,
     }));
   }, [chartData?.labels, chartData?.gas_prices]);
 
@@ -294,6 +315,7 @@ function ChartsSection({ chartData, allTimeChartData, onBlockClick }) {
                       }}
                     />
                   )}
+                  {annotationLines(allTimeChartData?.annotations)}
                   {/* Dynamic max line */}
                   <Line
                     type="stepAfter"
@@ -401,6 +423,7 @@ function ChartsSection({ chartData, allTimeChartData, onBlockClick }) {
                       fontSize: 9,
                     }}
                   />
+                  {annotationLines(chartData.annotations)}
                   <Bar
                     dataKey="blobs"
                     radius={[2, 2, 0, 0]}
@@ -423,7 +446,9 @@ function ChartsSection({ chartData, allTimeChartData, onBlockClick }) {
           {/* Gas Price Chart */}
           <div className="chart-card fade-in">
             <div className="chart-header">
-              <h2 className="chart-title">Blob Gas Price Per Block (Gwei)</h2>
+              <h2 className="chart-title">
+                Blob Gas Price Per Block (Gwei, log scale)
+              </h2>
             </div>
             <div className="chart-body">
               <ResponsiveContainer width="100%" height={220}>
@@ -448,12 +473,15 @@ function ChartsSection({ chartData, allTimeChartData, onBlockClick }) {
                     tickLine={false}
                     tick={{ fill: "#71717a", fontSize: 11 }}
                     width={70}
+                    scale="log"
+                    domain={["auto", "auto"]}
                     tickFormatter={formatGweiChart}
                   />
                   <Tooltip
                     content={<CustomTooltip valueFormatter={formatGweiChart} />}
                     cursor={false}
                   />
+                  {annotationLines(chartData.annotations)}
                   <Line
                     type="monotone"
                     dataKey="price"
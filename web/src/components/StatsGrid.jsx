@@ -5,6 +5,8 @@ import {
   BLOB_TARGET,
   BLOB_MAX,
   BLOB_SIZE_BYTES,
+  DEFAULT_REGIMES,
+  getRegime,
   getUtilizationColor,
   getSaturationColor,
   getUtilizationColorName,
@@ -30,6 +32,7 @@ function StatsGrid({ stats, chainProfiles }) {
   const avgBlobs = stats.avg_blobs_per_block ?? 0;
   const targetUtilization = (avgBlobs / BLOB_TARGET) * 100;
   const saturationIndex = (avgBlobs / BLOB_MAX) * 100;
+  const regimes = stats.regimes ?? DEFAULT_REGIMES;
 
   // Process chain data for pie chart
   const { chainData, totalBlobs } = (() => {
@@ -163,9 +166,9 @@ function StatsGrid({ stats, chainProfiles }) {
     {
       title: "Target Utilization",
       value: `${targetUtilization.toFixed(1)}%`,
-      subtitle: `${avgBlobs.toFixed(1)} / ${BLOB_TARGET} blobs`,
-      color: getUtilizationColorName(targetUtilization),
-      customColor: getUtilizationColor(targetUtilization),
+      subtitle: `${avgBlobs.toFixed(1)} / ${BLOB_TARGET} blobs, ${getRegime(targetUtilization, regimes)}`,
+      color: getUtilizationColorName(targetUtilization, regimes),
+      customColor: getUtilizationColor(targetUtilization, regimes),
       hasBar: true,
       barValue: targetUtilization,
       barMax: 150,
@@ -11,6 +11,7 @@ import {
 import {
   BLOB_TARGET,
   BLOB_MAX,
+  getRegimeColor,
   getUtilizationColor,
 } from "../utils/protocol";
 
@@ -187,7 +188,9 @@ function TablesSection({ blocks, senders, blobTransactions, onBlockClick }) {
                     ((block.total_blobs || 0) / BLOB_TARGET) * 100;
                   const saturation =
                     ((block.total_blobs || 0) / BLOB_MAX) * 100;
-                  const utilizationColor = getUtilizationColor(utilization);
+                  const utilizationColor = block.regime
+                    ? getRegimeColor(block.regime)
+                    : getUtilizationColor(utilization);
 
                   return (
                     <tr
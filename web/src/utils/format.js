@@ -17,7 +17,9 @@ export function formatBytes(bytes) {
 export function formatGwei(wei) {
   if (!wei) return "0 Gwei";
   const gwei = parseFloat(wei) / 1e9;
-  if (gwei < 0.001) {
+  if (gwei < 0.000001) {
+    return wei.toString() + " wei";
+  } else if (gwei < 0.001) {
     return gwei.toFixed(6) + " Gwei";
   } else if (gwei < 0.01) {
     return gwei.toFixed(5) + " Gwei";
@@ -22,12 +22,40 @@ const COLOR_GRADIENT = {
 // Base blue color for non-percentage values
 export const BASE_BLUE = "#3b82f6";
 
+// Demand regimes by target utilization, as in /api/regimes. The API's
+// thresholds are configurable per network, these are only used until loaded
+export const DEFAULT_REGIMES = [
+  { name: "abundant", from_pct: 0, to_pct: 50 },
+  { name: "normal", from_pct: 50, to_pct: 90 },
+  { name: "pressured", from_pct: 90, to_pct: 100 },
+  { name: "congested", from_pct: 100, to_pct: 150 },
+  { name: "saturated", from_pct: 150, to_pct: null },
+];
+
+// Color name per regime, pressured and above all get indigo
+const REGIME_COLOR_NAMES = {
+  abundant: "lightBlue",
+  normal: "blue",
+  pressured: "indigo",
+  congested: "indigo",
+  saturated: "indigo",
+};
+
+// Get the regime name of a target utilization percentage
+export function getRegime(utilization, regimes = DEFAULT_REGIMES) {
+  const regime = regimes.find((r) => utilization < (r.to_pct ?? Infinity));
+  return (regime ?? regimes[regimes.length - 1]).name;
+}
+
+// Get the color of a regime name, e.g. a block's `regime`
+export function getRegimeColor(regime) {
+  return COLOR_GRADIENT[REGIME_COLOR_NAMES[regime] ?? "blue"];
+}
+
 // Get utilization color based on percentage (target-based, can exceed 100%)
-// Uses blue-to-indigo gradient where indigo indicates at or exceeding target
-export function getUtilizationColor(utilization) {
-  if (utilization < 50) return COLOR_GRADIENT.lightBlue;
-  if (utilization < 90) return COLOR_GRADIENT.blue;
-  return COLOR_GRADIENT.indigo; // 90%+ all get the same indigo
+// Uses blue-to-indigo gradient where indigo indicates pressured or above
+export function getUtilizationColor(utilization, regimes = DEFAULT_REGIMES) {
+  return getRegimeColor(getRegime(utilization, regimes));
 }
 
 // Get saturation color based on percentage (max-based, 0-100%)
@@ -39,10 +67,11 @@ export function getSaturationColor(saturation) {
 }
 
 // Get utilization color name for CSS class usage
-export function getUtilizationColorName(utilization) {
-  if (utilization < 50) return "lightBlue";
-  if (utilization < 90) return "blue";
-  return "indigo";
+export function getUtilizationColorName(
+  utilization,
+  regimes = DEFAULT_REGIMES,
+) {
+  return REGIME_COLOR_NAMES[getRegime(utilization, regimes)] ?? "blue";
 }
 
 // Get saturation color name for CSS class usage